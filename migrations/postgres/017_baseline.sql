@@ -0,0 +1,167 @@
+-- The whole schema as SQLite has it after `017_admin_audit.sql`.
+--
+-- PostgreSQL support arrived after the SQLite history was written, and
+-- most of that history rewrites chapter slugs in data no Postgres
+-- database ever held. So this set starts from one baseline numbered
+-- like the SQLite migration it catches up with; every later migration
+-- lands in both directories under the same number.
+--
+-- Types follow what the server decodes: `TIMESTAMPTZ` for timestamps,
+-- `BIGINT` for every integer, `BOOLEAN` for flags. See the SQLite files
+-- for the reasoning behind each table.
+CREATE TABLE participants (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    team_token TEXT,
+    passphrase_hash TEXT,
+    auth_required BOOLEAN NOT NULL DEFAULT FALSE,
+    auth_epoch BIGINT NOT NULL DEFAULT 0,
+    deleted_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_participants_team_token
+    ON participants(team_token)
+    WHERE team_token IS NOT NULL;
+
+CREATE INDEX idx_participants_deleted_at
+    ON participants(deleted_at)
+    WHERE deleted_at IS NOT NULL;
+
+CREATE TABLE submissions (
+    id TEXT PRIMARY KEY,
+    participant_id TEXT NOT NULL REFERENCES participants(id),
+    exercise_name TEXT NOT NULL,
+    source_code TEXT NOT NULL,
+    tests_passed BOOLEAN NOT NULL,
+    clippy_passed BOOLEAN DEFAULT FALSE,
+    fmt_passed BOOLEAN DEFAULT FALSE,
+    submitted_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    content_hash TEXT,
+    claimed_tests_passed BOOLEAN,
+    claimed_clippy_passed BOOLEAN,
+    claimed_fmt_passed BOOLEAN,
+    verification TEXT NOT NULL DEFAULT 'unverified'
+        CHECK(verification IN ('unverified', 'pending', 'verified', 'failed')),
+    verification_log TEXT,
+    verified_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_submissions_hash
+    ON submissions(participant_id, exercise_name, content_hash);
+
+CREATE INDEX idx_submissions_pending ON submissions(submitted_at)
+    WHERE verification = 'pending';
+
+CREATE TABLE course_events (
+    id TEXT PRIMARY KEY,
+    participant_id TEXT REFERENCES participants(id) ON DELETE CASCADE,
+    session_id TEXT NOT NULL CHECK(length(session_id) BETWEEN 1 AND 64),
+    event_type TEXT NOT NULL CHECK(event_type IN (
+        'chapter_view',
+        'editor_focus',
+        'hint_opened',
+        'solution_revealed',
+        'next_chapter_clicked',
+        'exercise_run'
+    )),
+    exercise_name TEXT,
+    result TEXT CHECK(result IS NULL OR result IN (
+        'passed',
+        'test_failed',
+        'compile_failed',
+        'no_tests',
+        'ran',
+        'upstream_failed'
+    )),
+    tests_passed BIGINT,
+    tests_total BIGINT,
+    duration_ms BIGINT CHECK(duration_ms IS NULL OR duration_ms >= 0),
+    diagnostic_code TEXT,
+    course_version TEXT NOT NULL,
+    git_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_course_events_created_at ON course_events(created_at);
+CREATE INDEX idx_course_events_participant ON course_events(participant_id, created_at);
+CREATE INDEX idx_course_events_exercise ON course_events(exercise_name, event_type, created_at);
+CREATE INDEX idx_course_events_session ON course_events(session_id, created_at);
+
+CREATE UNIQUE INDEX idx_course_events_ui_once_per_session
+    ON course_events(session_id, event_type, exercise_name)
+    WHERE event_type != 'exercise_run';
+
+CREATE TABLE course_event_diagnostics (
+    event_id TEXT NOT NULL REFERENCES course_events(id) ON DELETE CASCADE,
+    code TEXT NOT NULL CHECK(length(code) BETWEEN 1 AND 128),
+    level TEXT NOT NULL CHECK(level IN ('error', 'warning')),
+    occurrences BIGINT NOT NULL CHECK(occurrences > 0),
+    PRIMARY KEY (event_id, code)
+);
+
+CREATE INDEX idx_course_event_diagnostics_code ON course_event_diagnostics(code);
+
+CREATE TABLE participant_login_codes (
+    code_hash TEXT PRIMARY KEY,
+    participant_id TEXT NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at BIGINT NOT NULL,
+    used_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_participant_login_codes_participant
+    ON participant_login_codes(participant_id);
+
+CREATE TABLE participant_api_tokens (
+    id TEXT PRIMARY KEY,
+    participant_id TEXT NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    label TEXT NOT NULL CHECK(length(label) BETWEEN 1 AND 64),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_participant_api_tokens_participant
+    ON participant_api_tokens(participant_id);
+
+CREATE TABLE instructors (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL CHECK(length(name) BETWEEN 1 AND 64),
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_login_at TIMESTAMPTZ
+);
+
+CREATE TABLE instructor_teams (
+    instructor_id TEXT NOT NULL REFERENCES instructors(id) ON DELETE CASCADE,
+    team_token TEXT NOT NULL,
+    PRIMARY KEY (instructor_id, team_token)
+);
+
+CREATE TABLE admin_audit (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    actor TEXT NOT NULL,
+    actor_name TEXT NOT NULL,
+    action TEXT NOT NULL,
+    participant_id TEXT,
+    before TEXT,
+    after TEXT
+);
+
+CREATE INDEX idx_admin_audit_participant ON admin_audit(participant_id);
+CREATE INDEX idx_admin_audit_action ON admin_audit(action);
+
+CREATE FUNCTION admin_audit_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'admin_audit is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER admin_audit_no_update BEFORE UPDATE ON admin_audit
+    FOR EACH ROW EXECUTE FUNCTION admin_audit_append_only();
+
+CREATE TRIGGER admin_audit_no_delete BEFORE DELETE ON admin_audit
+    FOR EACH ROW EXECUTE FUNCTION admin_audit_append_only();
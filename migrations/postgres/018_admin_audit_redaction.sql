@@ -0,0 +1,24 @@
+-- A participant who deletes their account takes their data with them,
+-- including the snapshots the audit log kept of it. The only update the
+-- log accepts blanks `before` and `after`; see the SQLite migration of
+-- the same number.
+CREATE FUNCTION admin_audit_redact_only() RETURNS trigger AS $$
+BEGIN
+    IF NEW.before IS NULL AND NEW.after IS NULL
+        AND NEW.id = OLD.id
+        AND NEW.created_at = OLD.created_at
+        AND NEW.actor = OLD.actor
+        AND NEW.actor_name = OLD.actor_name
+        AND NEW.action = OLD.action
+        AND NEW.participant_id IS NOT DISTINCT FROM OLD.participant_id
+    THEN
+        RETURN NEW;
+    END IF;
+    RAISE EXCEPTION 'admin_audit is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER admin_audit_no_update ON admin_audit;
+
+CREATE TRIGGER admin_audit_no_update BEFORE UPDATE ON admin_audit
+    FOR EACH ROW EXECUTE FUNCTION admin_audit_redact_only();
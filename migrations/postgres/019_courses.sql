@@ -0,0 +1,29 @@
+-- One server, several courses. Everything recorded before belongs to
+-- `fundamentals`; see the SQLite migration of the same number.
+ALTER TABLE submissions ADD COLUMN course TEXT NOT NULL DEFAULT 'fundamentals';
+ALTER TABLE course_events ADD COLUMN course TEXT NOT NULL DEFAULT 'fundamentals';
+
+DROP INDEX idx_submissions_hash;
+CREATE INDEX idx_submissions_hash
+    ON submissions(participant_id, course, exercise_name, content_hash);
+
+DROP INDEX idx_course_events_exercise;
+CREATE INDEX idx_course_events_exercise
+    ON course_events(course, exercise_name, event_type, created_at);
+
+DROP INDEX idx_course_events_ui_once_per_session;
+CREATE UNIQUE INDEX idx_course_events_ui_once_per_session
+    ON course_events(session_id, event_type, course, exercise_name)
+    WHERE event_type != 'exercise_run';
+
+CREATE TABLE course_enrollments (
+    participant_id TEXT NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+    course TEXT NOT NULL,
+    enrolled_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (participant_id, course)
+);
+
+CREATE INDEX idx_course_enrollments_course ON course_enrollments(course);
+
+INSERT INTO course_enrollments (participant_id, course, enrolled_at)
+SELECT id, 'fundamentals', COALESCE(created_at, CURRENT_TIMESTAMP) FROM participants;
@@ -0,0 +1,8 @@
+-- Chapters an instructor has released; see the SQLite migration of the
+-- same number.
+CREATE TABLE chapter_releases (
+    course TEXT NOT NULL,
+    chapter TEXT NOT NULL,
+    released_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (course, chapter)
+);
@@ -0,0 +1,15 @@
+-- Answers to chapter quizzes, one row per question per attempt; see the
+-- SQLite migration of the same number.
+CREATE TABLE quiz_answers (
+    participant_id TEXT NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+    course TEXT NOT NULL,
+    chapter TEXT NOT NULL,
+    attempt TEXT NOT NULL CHECK(length(attempt) BETWEEN 1 AND 64),
+    question BIGINT NOT NULL CHECK(question >= 0),
+    answer TEXT NOT NULL,
+    correct BOOLEAN NOT NULL,
+    answered_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (participant_id, course, chapter, attempt, question)
+);
+
+CREATE INDEX idx_quiz_answers_question ON quiz_answers(course, chapter, question);
@@ -0,0 +1,10 @@
+-- Chapter tags mirrored from `.chapter.toml`; see the SQLite migration
+-- of the same number.
+CREATE TABLE chapter_tags (
+    course TEXT NOT NULL,
+    chapter TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (course, chapter, tag)
+);
+
+CREATE INDEX idx_chapter_tags_tag ON chapter_tags(course, tag);
@@ -0,0 +1,9 @@
+-- Admin session epochs; see the SQLite migration of the same number.
+ALTER TABLE instructors ADD COLUMN auth_epoch BIGINT NOT NULL DEFAULT 0;
+
+CREATE TABLE admin_auth (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    auth_epoch BIGINT NOT NULL DEFAULT 0
+);
+
+INSERT INTO admin_auth (id) VALUES (1);
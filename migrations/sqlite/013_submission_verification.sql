@@ -0,0 +1,29 @@
+-- Server-side verification of submissions.
+--
+-- `tests_passed` / `clippy_passed` / `fmt_passed` remain the columns every
+-- progress query reads, but they now hold the server's verdict. What the
+-- client reported is kept alongside in `claimed_*` so instructors can spot
+-- disagreements.
+--
+-- `verification` tracks where a row is:
+--   unverified  verification disabled; the verdict columns copy the claim
+--   pending     queued for the background verifier; verdict columns are 0
+--   verified    the server re-ran tests, rustfmt and clippy
+--   failed      the verifier couldn't judge the row (unknown exercise)
+ALTER TABLE submissions ADD COLUMN claimed_tests_passed BOOLEAN;
+ALTER TABLE submissions ADD COLUMN claimed_clippy_passed BOOLEAN;
+ALTER TABLE submissions ADD COLUMN claimed_fmt_passed BOOLEAN;
+ALTER TABLE submissions ADD COLUMN verification TEXT NOT NULL DEFAULT 'unverified'
+    CHECK(verification IN ('unverified', 'pending', 'verified', 'failed'));
+ALTER TABLE submissions ADD COLUMN verification_log TEXT;
+ALTER TABLE submissions ADD COLUMN verified_at TIMESTAMP;
+
+-- Every existing row was stored verbatim from the client.
+UPDATE submissions
+SET claimed_tests_passed = tests_passed,
+    claimed_clippy_passed = clippy_passed,
+    claimed_fmt_passed = fmt_passed;
+
+-- The verifier re-queues pending rows on startup.
+CREATE INDEX idx_submissions_pending ON submissions(submitted_at)
+    WHERE verification = 'pending';
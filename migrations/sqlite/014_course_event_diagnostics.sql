@@ -0,0 +1,17 @@
+-- Every diagnostic code a run produced, not just the first error.
+--
+-- `course_events.diagnostic_code` keeps holding the first `Exxxx` error so
+-- existing reports work unchanged. This table lists each distinct code
+-- from an `exercise_run` (error codes and lint names alike) with how often
+-- it fired in that run. Codes are validated server-side; no messages,
+-- spans or source text are stored.
+CREATE TABLE course_event_diagnostics (
+    event_id TEXT NOT NULL,
+    code TEXT NOT NULL CHECK(length(code) BETWEEN 1 AND 128),
+    level TEXT NOT NULL CHECK(level IN ('error', 'warning')),
+    occurrences INTEGER NOT NULL CHECK(occurrences > 0),
+    PRIMARY KEY (event_id, code),
+    FOREIGN KEY (event_id) REFERENCES course_events(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_course_event_diagnostics_code ON course_event_diagnostics(code);
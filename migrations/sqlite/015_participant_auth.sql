@@ -0,0 +1,39 @@
+-- Optional stronger sign-in for participants.
+--
+-- By default the ULID in a participant's login link is their whole
+-- identity. `auth_required` turns that off for one participant: it is set
+-- when they choose a passphrase or an instructor issues them a login code,
+-- and from then on their pages and API calls need a session cookie or an
+-- API token. Sessions are signed cookies carrying `auth_epoch`; bumping the
+-- epoch signs out every browser.
+ALTER TABLE participants ADD COLUMN passphrase_hash TEXT;
+ALTER TABLE participants ADD COLUMN auth_required BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE participants ADD COLUMN auth_epoch INTEGER NOT NULL DEFAULT 0;
+
+-- One-time codes handed out by an instructor. Only the SHA-256 of the
+-- code is stored; `expires_at` is unix seconds.
+CREATE TABLE participant_login_codes (
+    code_hash TEXT PRIMARY KEY,
+    participant_id TEXT NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at INTEGER NOT NULL,
+    used_at TIMESTAMP
+);
+
+CREATE INDEX idx_participant_login_codes_participant
+    ON participant_login_codes(participant_id);
+
+-- CLI credentials, created and revoked from the settings page. Revoked
+-- rows are kept so the settings page can show when a token stopped working.
+CREATE TABLE participant_api_tokens (
+    id TEXT PRIMARY KEY,
+    participant_id TEXT NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    label TEXT NOT NULL CHECK(length(label) BETWEEN 1 AND 64),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at TIMESTAMP,
+    revoked_at TIMESTAMP
+);
+
+CREATE INDEX idx_participant_api_tokens_participant
+    ON participant_api_tokens(participant_id);
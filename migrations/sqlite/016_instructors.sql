@@ -0,0 +1,20 @@
+-- Instructor accounts, scoped to one or more teams.
+--
+-- The super-admin (`CORRODE_ADMIN_TOKEN`) creates instructors and hands
+-- each one a sign-in token; only its SHA-256 is stored. An instructor
+-- sees and manages only participants whose `team_token` is listed for
+-- them in `instructor_teams`. Teams are still free-text labels (see
+-- migration 007), so there is no foreign key on `team_token`.
+CREATE TABLE instructors (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL CHECK(length(name) BETWEEN 1 AND 64),
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_login_at TIMESTAMP
+);
+
+CREATE TABLE instructor_teams (
+    instructor_id TEXT NOT NULL REFERENCES instructors(id) ON DELETE CASCADE,
+    team_token TEXT NOT NULL,
+    PRIMARY KEY (instructor_id, team_token)
+);
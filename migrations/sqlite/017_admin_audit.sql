@@ -0,0 +1,40 @@
+-- Who did what in the admin UI, and soft deletion of participants.
+--
+-- Every mutating admin route appends one row to `admin_audit` in the same
+-- transaction as the change. `actor` is `admin` for the super-admin or
+-- the instructor's id; `actor_name` is a snapshot so the log still
+-- reads after an instructor is deleted. `participant_id` has no foreign
+-- key for the same reason: the log outlives purged participants.
+-- `before` / `after` are JSON objects with the fields the action changed.
+CREATE TABLE admin_audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    actor TEXT NOT NULL,
+    actor_name TEXT NOT NULL,
+    action TEXT NOT NULL,
+    participant_id TEXT,
+    before TEXT,
+    after TEXT
+);
+
+CREATE INDEX idx_admin_audit_participant ON admin_audit(participant_id);
+CREATE INDEX idx_admin_audit_action ON admin_audit(action);
+
+CREATE TRIGGER admin_audit_no_update BEFORE UPDATE ON admin_audit
+BEGIN
+    SELECT RAISE(ABORT, 'admin_audit is append-only');
+END;
+
+CREATE TRIGGER admin_audit_no_delete BEFORE DELETE ON admin_audit
+BEGIN
+    SELECT RAISE(ABORT, 'admin_audit is append-only');
+END;
+
+-- Removing a participant now only stamps `deleted_at`; they disappear
+-- from every page and API at once and can be restored until the server
+-- purges them for good after the grace period.
+ALTER TABLE participants ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE INDEX idx_participants_deleted_at
+    ON participants(deleted_at)
+    WHERE deleted_at IS NOT NULL;
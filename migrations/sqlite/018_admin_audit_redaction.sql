@@ -0,0 +1,20 @@
+-- A participant who deletes their account takes their data with them,
+-- including the snapshots the audit log kept of it (`participant.remove`
+-- records the name, for one). The log stays append-only in every other
+-- respect: the only update it accepts blanks `before` and `after` and
+-- leaves who did what, when, and to which participant id untouched.
+DROP TRIGGER admin_audit_no_update;
+
+CREATE TRIGGER admin_audit_no_update BEFORE UPDATE ON admin_audit
+WHEN NOT (
+    NEW.before IS NULL AND NEW.after IS NULL
+    AND NEW.id = OLD.id
+    AND NEW.created_at = OLD.created_at
+    AND NEW.actor = OLD.actor
+    AND NEW.actor_name = OLD.actor_name
+    AND NEW.action = OLD.action
+    AND NEW.participant_id IS OLD.participant_id
+)
+BEGIN
+    SELECT RAISE(ABORT, 'admin_audit is append-only');
+END;
@@ -0,0 +1,11 @@
+-- Chapters with `unlock = "release"` in their `.chapter.toml` stay
+-- locked until an instructor releases them from `/admin`. A row here
+-- means the chapter is released in that course; withholding it again
+-- deletes the row. Chapters are keyed by their directory name, like
+-- `submissions.exercise_name`.
+CREATE TABLE chapter_releases (
+    course TEXT NOT NULL,
+    chapter TEXT NOT NULL,
+    released_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (course, chapter)
+);
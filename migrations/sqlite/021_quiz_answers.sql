@@ -0,0 +1,20 @@
+-- Answers to chapter quizzes (`quiz.toml`), one row per question per
+-- attempt. `attempt` is generated by the browser when the quiz loads and
+-- again on "Try again"; only the first answer to a question in an
+-- attempt is kept. `question` is the 0-based index into `questions`,
+-- and `answer` is what `Question::grade` was given (see
+-- `src/exercises.rs`). `correct` is the server's verdict, not the
+-- browser's.
+CREATE TABLE quiz_answers (
+    participant_id TEXT NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+    course TEXT NOT NULL,
+    chapter TEXT NOT NULL,
+    attempt TEXT NOT NULL CHECK(length(attempt) BETWEEN 1 AND 64),
+    question INTEGER NOT NULL CHECK(question >= 0),
+    answer TEXT NOT NULL,
+    correct BOOLEAN NOT NULL,
+    answered_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (participant_id, course, chapter, attempt, question)
+);
+
+CREATE INDEX idx_quiz_answers_question ON quiz_answers(course, chapter, question);
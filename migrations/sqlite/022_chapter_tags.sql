@@ -0,0 +1,14 @@
+-- The `tags` of every chapter's `.chapter.toml`, copied here so
+-- analytics reports can group `course_events` and `submissions` by
+-- topic with a join. The server rewrites a course's rows at startup
+-- and whenever its content reloads; nothing else writes to the table.
+-- Chapters are keyed by their directory name, like
+-- `submissions.exercise_name`.
+CREATE TABLE chapter_tags (
+    course TEXT NOT NULL,
+    chapter TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (course, chapter, tag)
+);
+
+CREATE INDEX idx_chapter_tags_tag ON chapter_tags(course, tag);
@@ -0,0 +1,16 @@
+-- Server-side sign-out for the admin UI.
+--
+-- Admin cookies are signed and stored nowhere, so clearing the cookie
+-- on sign-out left a copied cookie valid until it expired. They now
+-- carry an epoch like participant sessions (migration 015): the
+-- instructor's `auth_epoch`, or the super-admin's in the one-row
+-- `admin_auth` table. Signing out bumps it, which ends every session
+-- issued before.
+ALTER TABLE instructors ADD COLUMN auth_epoch INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE admin_auth (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    auth_epoch INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO admin_auth (id) VALUES (1);
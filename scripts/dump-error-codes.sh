@@ -0,0 +1,31 @@
+#!/usr/bin/env bash
+#
+# Dump the compiler's long-form error explanations for `/explain/{code}`.
+#
+# Writes one Markdown file per error code (`E0382.md`, ...) into the
+# given directory (default: `error_codes/`), using whatever `rustc` is
+# on PATH. Codes the toolchain doesn't know are skipped. The server
+# loads the directory once at startup (override with
+# CORRODE_ERROR_CODES_DIR).
+#
+# The Docker build runs this against the builder's toolchain, so the
+# image ships explanations that match a current stable rustc. Locally:
+#   ./scripts/dump-error-codes.sh
+#   make error-codes
+
+set -euo pipefail
+
+out="${1:-error_codes}"
+mkdir -p "$out"
+
+count=0
+for n in $(seq 1 999); do
+    code=$(printf 'E%04d' "$n")
+    if rustc --explain "$code" >"$out/$code.md" 2>/dev/null; then
+        count=$((count + 1))
+    else
+        rm -f "$out/$code.md"
+    fi
+done
+
+echo "Wrote $count explanations to $out/ ($(rustc --version))"
@@ -1,24 +1,60 @@
+use cargo_course::exercises::{self, CodeStep, Exercise, Note, Step};
+use cargo_course::lint;
 use cargo_course::types::{
-    Name, ProgressResponse, RegistrationRequest, RegistrationResponse, SubmissionRequest, Token,
+    AccountDeletionRequest, AccountExport, Name, ProgressResponse, RegistrationRequest,
+    RegistrationResponse, SubmissionRequest, Token,
 };
 
 use anyhow::{Result, anyhow};
 use clap::{Parser, Subcommand};
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fmt;
 use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
 use std::process::Command;
 use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
 
 const DEFAULT_SERVER_URL: &str = "https://course.corrode.dev";
 const TOKEN_FILE: &str = ".corrode/token";
+/// Submissions made while the server was unreachable, oldest first.
+/// `queue.lock` next to it is held locked while a command rewrites it,
+/// so a `watch` in one terminal and a `submit` in another don't lose
+/// each other's changes.
+const QUEUE_FILE: &str = ".corrode/queue.json";
+/// How long one queued submission may take to send before the rest of
+/// the queue waits for the next command.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often `cargo course watch` looks for saved exercise files.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+/// Failing tests named in a `watch` verdict before the rest are counted.
+const WATCH_FAILURES_SHOWN: usize = 3;
 
 /// Get the server URL from environment variable or use default
 fn get_server_url() -> String {
     env::var("CORRODE_SERVER_URL").unwrap_or_else(|_| DEFAULT_SERVER_URL.to_string())
 }
 
+/// The course these exercises belong to, from `CORRODE_COURSE`; `None`
+/// for the server's default course.
+fn get_course() -> Option<String> {
+    env::var("CORRODE_COURSE")
+        .ok()
+        .filter(|course| !course.trim().is_empty())
+}
+
+/// `exercise_name` as the server expects it: qualified with the course
+/// slug unless this is the default course.
+fn qualify_exercise_name(exercise_name: &str) -> String {
+    get_course().map_or_else(
+        || exercise_name.to_string(),
+        |course| format!("{course}:{exercise_name}"),
+    )
+}
+
 #[derive(Parser)]
 #[command(name = "cargo")]
 #[command(bin_name = "cargo")]
@@ -59,12 +95,74 @@ enum CourseCommands {
         #[arg(long)]
         all: bool,
     },
+    /// Re-test exercises as you save them and submit each one that passes
+    Watch,
+    /// Send the submissions queued while the server was unreachable
+    Sync,
     /// Show progress and available exercises
-    Status,
+    Status {
+        /// Only list chapters with this tag (e.g. `error-handling`)
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Reveal a step's hints one at a time
+    Hint {
+        /// The step's file (e.g. `examples/00_integers/3_add_health.rs`)
+        step: String,
+    },
+    /// Show a step's reference solution once you've submitted it
+    Solution {
+        /// The step's file (e.g. `examples/00_integers/3_add_health.rs`)
+        step: String,
+    },
+    /// Show the next unfinished step and open it in `$EDITOR`
+    Next,
+    /// Read a chapter's notes in the terminal
+    Read {
+        /// The chapter's directory or slug (e.g. `00_integers` or `integers`)
+        chapter: String,
+    },
     /// Open the course dashboard in the browser
     Open,
     /// Print the current token to stdout
     Token,
+    /// Download everything the server stores about you as JSON
+    Export {
+        /// Where to write the export
+        #[arg(short, long, default_value = "corrode-course-data.json")]
+        output: String,
+    },
+    /// Delete your account and all of its data from the server
+    DeleteAccount {
+        /// Skip the confirmation prompt
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Check the course content for problems (for course authors)
+    LintContent {
+        /// The course's `examples/` directory; `solutions/` is read next to it
+        #[arg(long, default_value = "examples")]
+        dir: String,
+    },
+}
+
+impl CourseCommands {
+    /// Whether to send queued submissions before running the command.
+    /// `token` output is read by scripts, and the others either sync
+    /// themselves or work without the server, so they shouldn't wait on
+    /// it.
+    const fn retries_queue(&self) -> bool {
+        !matches!(
+            self,
+            Self::Sync
+                | Self::Token
+                | Self::Hint { .. }
+                | Self::Read { .. }
+                | Self::Open
+                | Self::DeleteAccount { .. }
+                | Self::LintContent { .. }
+        )
+    }
 }
 
 #[tokio::main]
@@ -72,17 +170,36 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Course(args) => match args.command {
-            CourseCommands::Init { token } => handle_init(token).await,
-            CourseCommands::Submit {
-                file,
-                pedantic,
-                all,
-            } => handle_submit(file.as_deref(), pedantic, all).await,
-            CourseCommands::Status => handle_status().await,
-            CourseCommands::Open => handle_open(),
-            CourseCommands::Token => handle_token(),
-        },
+        Commands::Course(args) => {
+            if args.command.retries_queue() {
+                retry_queue().await;
+            }
+            run(args.command).await
+        }
+    }
+}
+
+/// Run one `cargo course` subcommand.
+async fn run(command: CourseCommands) -> Result<()> {
+    match command {
+        CourseCommands::Init { token } => handle_init(token).await,
+        CourseCommands::Submit {
+            file,
+            pedantic,
+            all,
+        } => handle_submit(file.as_deref(), pedantic, all).await,
+        CourseCommands::Watch => handle_watch().await,
+        CourseCommands::Sync => handle_sync().await,
+        CourseCommands::Status { tag } => handle_status(tag.as_deref()).await,
+        CourseCommands::Hint { step } => handle_hint(&step),
+        CourseCommands::Solution { step } => handle_solution(&step).await,
+        CourseCommands::Next => handle_next().await,
+        CourseCommands::Read { chapter } => handle_read(&chapter),
+        CourseCommands::Open => handle_open(),
+        CourseCommands::Token => handle_token(),
+        CourseCommands::Export { output } => handle_export(&output).await,
+        CourseCommands::DeleteAccount { yes } => handle_delete_account(yes).await,
+        CourseCommands::LintContent { dir } => handle_lint_content(Path::new(&dir)),
     }
 }
 
@@ -100,8 +217,14 @@ fn handle_open() -> Result<()> {
     // Read token from file
     let token = read_token()?;
 
-    // Construct the dashboard URL
-    let url = format!("{}/dashboard/{}", get_server_url(), token.as_str());
+    // Construct the dashboard URL. API tokens are CLI-only credentials
+    // and never go into a browser URL; `/me` finds the dashboard from
+    // the browser's own sign-in instead.
+    let url = if token.is_api_token() {
+        format!("{}/me", get_server_url())
+    } else {
+        format!("{}/dashboard/{}", get_server_url(), token.as_str())
+    };
 
     // Open the URL in the default browser
     if open::that(&url).is_err() {
@@ -187,16 +310,21 @@ async fn handle_submit(file: Option<&str>, pedantic: bool, all: bool) -> Result<
     // 5. Read token from file
     let token = read_token()?;
 
-    // 6. Submit to server
-    submit_to_server(SubmissionRequest {
+    // 6. Submit to server, or queue it while the server is unreachable
+    let delivery = submit_or_queue(SubmissionRequest {
         ulid: token.as_str().to_string(),
-        exercise_name: exercise_name.clone(),
+        exercise_name: qualify_exercise_name(&exercise_name),
         source_code,
         tests_passed,
         clippy_passed,
         fmt_passed,
+        submitted_at: None,
     })
     .await?;
+    if let Delivery::Queued(reason) = delivery {
+        println!("📦 {reason}. Your submission is saved in {QUEUE_FILE}");
+        println!("   and sent with your next cargo course command, or run: cargo course sync");
+    }
 
     // 7. Show result
     if tests_passed {
@@ -282,6 +410,13 @@ async fn handle_submit_all(pedantic: bool) -> Result<()> {
         println!("   {}", failed_exercises.join(", "));
     }
 
+    let queued = read_queue(Path::new(QUEUE_FILE))?.len();
+    if queued > 0 {
+        println!(
+            "📦 {queued} submission(s) queued until the server is reachable (cargo course sync)"
+        );
+    }
+
     if successful_submissions > 0 {
         println!("\n🎉 Use 'cargo course status' to see your updated progress!");
     }
@@ -336,28 +471,158 @@ async fn process_single_exercise(
         (false, false)
     };
 
-    // Submit to server
-    let submission_result = submit_to_server(SubmissionRequest {
+    // Submit to server, or queue it while the server is unreachable
+    let submission_result = submit_or_queue(SubmissionRequest {
         ulid: token.as_str().to_string(),
-        exercise_name: exercise_name.clone(),
+        exercise_name: qualify_exercise_name(&exercise_name),
         source_code,
         tests_passed,
         clippy_passed,
         fmt_passed,
+        submitted_at: None,
     })
     .await;
 
-    if submission_result.is_ok() {
-        if pedantic && fmt_passed && clippy_passed {
+    match submission_result {
+        Ok(Delivery::Sent) if pedantic && fmt_passed && clippy_passed => {
             print!("⭐ Perfected! ");
-        } else {
+            Ok(exercise_name)
+        }
+        Ok(Delivery::Sent) => {
             print!("✅ Submitted! ");
+            Ok(exercise_name)
         }
-        Ok(exercise_name)
-    } else {
-        println!("❌ Upload failed");
-        Err(exercise_name)
+        Ok(Delivery::Queued(_)) => {
+            print!("📦 Queued! ");
+            Ok(exercise_name)
+        }
+        Err(_) => {
+            println!("❌ Upload failed");
+            Err(exercise_name)
+        }
+    }
+}
+
+/// Watch `examples/` and re-test each exercise file when it's saved,
+/// submitting it once its tests pass. Polls modification times rather
+/// than subscribing to file events, like the server's content reload.
+///
+/// A step submitted since the watch started isn't submitted again, but
+/// its tests still run on every save so a regression shows up.
+async fn handle_watch() -> Result<()> {
+    let token = read_token()?;
+    let mut seen = modification_times()?;
+    let mut submitted: HashSet<String> = HashSet::new();
+    println!(
+        "👀 Watching {} exercise files in examples/. Save one to test it; Ctrl-C stops.",
+        seen.len()
+    );
+
+    let mut ticker = tokio::time::interval(WATCH_INTERVAL);
+    loop {
+        ticker.tick().await;
+        let now = modification_times()?;
+        let mut changed: Vec<&String> = now
+            .iter()
+            .filter(|(file, modified)| seen.get(*file) != Some(modified))
+            .map(|(file, _)| file)
+            .collect();
+        changed.sort();
+        for file in changed {
+            watch_step(file, &token, &mut submitted).await;
+        }
+        seen = now;
+    }
+}
+
+/// When each file [`find_exercise_files`] lists was last modified.
+/// Files that disappear between listing and `stat` are left out.
+fn modification_times() -> Result<HashMap<String, SystemTime>> {
+    Ok(find_exercise_files()?
+        .into_iter()
+        .filter_map(|file| {
+            let modified = fs::metadata(&file).and_then(|meta| meta.modified()).ok()?;
+            Some((file, modified))
+        })
+        .collect())
+}
+
+/// Test one saved exercise file and print a one-line verdict: the
+/// failing tests, the first compiler error, or that it passed. A pass
+/// is submitted unless `submitted` already holds the step.
+async fn watch_step(file: &str, token: &Token, submitted: &mut HashSet<String>) {
+    let Ok(target) = extract_submission_target(file) else {
+        return;
+    };
+    let exercise_name = target.exercise_key();
+    print!("🧪 {exercise_name}... ");
+    let _ = io::stdout().flush();
+
+    let (tests_passed, test_output) =
+        match run_cargo_test(&target.chapter, target.test_filter.as_deref()) {
+            Ok(result) => result,
+            Err(e) => {
+                println!("⚠️  couldn't run cargo test: {e}");
+                return;
+            }
+        };
+    if !tests_passed {
+        let failing = failing_tests(&test_output);
+        if failing.is_empty() {
+            let error = test_output
+                .lines()
+                .find(|line| line.starts_with("error"))
+                .unwrap_or("run `cargo test` for details");
+            println!("❌ doesn't compile: {error}");
+        } else {
+            let more = failing.len().saturating_sub(WATCH_FAILURES_SHOWN);
+            let shown = failing[..failing.len() - more].join(", ");
+            if more == 0 {
+                println!("❌ {} failing: {shown}", failing.len());
+            } else {
+                println!("❌ {} failing: {shown} and {more} more", failing.len());
+            }
+        }
+        return;
     }
+    if submitted.contains(&exercise_name) {
+        println!("✅ passed (already submitted)");
+        return;
+    }
+
+    let Ok(source_code) = fs::read_to_string(file) else {
+        println!("✅ passed, but {file} couldn't be read to submit it");
+        return;
+    };
+    let submission = submit_or_queue(SubmissionRequest {
+        ulid: token.as_str().to_string(),
+        exercise_name: qualify_exercise_name(&exercise_name),
+        source_code,
+        tests_passed,
+        clippy_passed: false,
+        fmt_passed: false,
+        submitted_at: None,
+    })
+    .await;
+    match submission {
+        Ok(Delivery::Sent) => {
+            println!("✅ passed and submitted");
+            submitted.insert(exercise_name);
+        }
+        Ok(Delivery::Queued(_)) => {
+            println!("✅ passed, queued until the server is reachable");
+            submitted.insert(exercise_name);
+        }
+        Err(e) => println!("✅ passed, but not submitted: {e}"),
+    }
+}
+
+/// Names of the tests `cargo test` reported as `FAILED` in `output`.
+fn failing_tests(output: &str) -> Vec<&str> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("test ")?.strip_suffix(" ... FAILED"))
+        .collect()
 }
 
 /// Find all exercise files in the examples directory.
@@ -436,12 +701,34 @@ fn find_exercise_files() -> Result<Vec<String>> {
 }
 
 /// Show participant progress and available exercises.
-async fn handle_status() -> Result<()> {
+async fn handle_status(tag: Option<&str>) -> Result<()> {
     let token = read_token()?;
     let progress = fetch_progress(&token).await?;
+    let exercises: Vec<_> = progress
+        .exercises
+        .iter()
+        .filter(|e| tag.is_none_or(|tag| e.tags.iter().any(|t| t == tag)))
+        .collect();
+
+    if let Some(tag) = tag
+        && exercises.is_empty()
+    {
+        let mut tags: Vec<&str> = progress
+            .exercises
+            .iter()
+            .flat_map(|e| e.tags.iter().map(String::as_str))
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+        println!("No chapters are tagged `{tag}`.");
+        if !tags.is_empty() {
+            println!("Tags in this course: {}", tags.join(", "));
+        }
+        return Ok(());
+    }
 
     println!("📚 corrode Rust Course Progress");
-    for exercise in &progress.exercises {
+    for exercise in &exercises {
         let status_icon = if exercise.perfected {
             "⭐"
         } else if exercise.completed {
@@ -449,16 +736,437 @@ async fn handle_status() -> Result<()> {
         } else {
             "⏳"
         };
-        println!("{status_icon} {}", exercise.name);
+        match exercise.difficulty {
+            Some(level) => println!("{status_icon} {} [{}]", exercise.name, level.as_str()),
+            None => println!("{status_icon} {}", exercise.name),
+        }
+    }
+
+    let completed = exercises.iter().filter(|e| e.completed).count();
+    let total = exercises.len();
+    match tag {
+        Some(tag) => println!("\nProgress: {completed}/{total} exercises tagged `{tag}`"),
+        None => println!("\nProgress: {completed}/{total} exercises"),
+    }
+    let queued = read_queue(Path::new(QUEUE_FILE))?.len();
+    if queued > 0 {
+        println!("📦 {queued} submission(s) not sent yet (cargo course sync)");
+    }
+
+    Ok(())
+}
+
+/// Print a step's hints one at a time, waiting for Enter between them.
+fn handle_hint(step_arg: &str) -> Result<()> {
+    let catalog = load_catalog()?;
+    let (key, chapter, step) = find_step(&catalog, step_arg)?;
+    // Single-step chapters keep their hints chapter-wide.
+    let hints = step
+        .hints_markdown
+        .as_deref()
+        .or_else(|| chapter.hints.as_ref().map(|note| note.markdown.as_str()))
+        .map(exercises::hint_items)
+        .unwrap_or_default();
+    if hints.is_empty() {
+        println!("No hints for {key}.");
+        println!(
+            "💡 Read the chapter with: cargo course read {}",
+            chapter.file_stem
+        );
+        return Ok(());
+    }
+
+    println!("💡 Hints for {key}");
+    for (i, hint) in hints.iter().enumerate() {
+        if i > 0 {
+            print!(
+                "\nPress Enter for hint {}/{} (q to stop) ",
+                i + 1,
+                hints.len()
+            );
+            io::stdout().flush()?;
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input)? == 0 || input.trim().eq_ignore_ascii_case("q") {
+                return Ok(());
+            }
+        }
+        println!("\nHint {}/{}:", i + 1, hints.len());
+        print!("{}", exercises::render_terminal(hint));
+    }
+    Ok(())
+}
+
+/// Print a step's reference solution, but only once the participant has
+/// submitted that step at least once.
+async fn handle_solution(step_arg: &str) -> Result<()> {
+    let catalog = load_catalog()?;
+    let (key, chapter, step) = find_step(&catalog, step_arg)?;
+    let Some(solution) = step.solution_code.as_deref() else {
+        println!("No reference solution for {key} in this course.");
+        return Ok(());
+    };
+
+    let token = read_token()?;
+    let progress = fetch_progress(&token).await?;
+    let submitted = progress
+        .exercises
+        .iter()
+        .any(|e| e.submitted_steps.contains(&key));
+    if !submitted {
+        println!("🔒 Submit {key} at least once to see its solution:");
+        println!("   cargo course submit {}", step_file(chapter, step));
+        return Ok(());
+    }
+
+    println!("📝 Reference solution for {key}\n");
+    println!("{solution}");
+    Ok(())
+}
+
+/// Print a chapter's notes as terminal text, with a pointer to each
+/// exercise file in between.
+fn handle_read(chapter_arg: &str) -> Result<()> {
+    let catalog = load_catalog()?;
+    let name = Path::new(chapter_arg)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(chapter_arg);
+    let Some(chapter) = catalog
+        .iter()
+        .find(|e| e.file_stem == name || e.slug == name)
+    else {
+        let chapters: Vec<&str> = catalog.iter().map(|e| e.file_stem.as_str()).collect();
+        return Err(anyhow!(
+            "No chapter `{chapter_arg}`. Chapters: {}",
+            chapters.join(", ")
+        ));
+    };
+
+    println!("📖 {}", chapter.title);
+    for step in &chapter.steps {
+        match step {
+            Step::Prose(note) => print_note(chapter, note),
+            Step::Code(code) => {
+                println!("\n✏️  Exercise: {}", step_file(chapter, code));
+            }
+            Step::Quiz(_) => println!("\n❓ Quiz: answer it in the browser (cargo course open)"),
+        }
+    }
+    Ok(())
+}
+
+/// Print a note as terminal text under its title (left out when it is
+/// the chapter's own title, already printed above it).
+fn print_note(chapter: &Exercise, note: &Note) {
+    if note.title != chapter.title {
+        println!(
+            "\n{}\n{}",
+            note.title,
+            "-".repeat(note.title.chars().count())
+        );
+    }
+    let text = exercises::render_terminal(&note.markdown);
+    if !text.is_empty() {
+        print!("\n{text}");
+    }
+}
+
+/// Where `cargo course next` sends the participant.
+enum NextStep<'a> {
+    Code(&'a Exercise, &'a CodeStep),
+    /// A quiz with a pass mark, answered in the browser.
+    Quiz(&'a Exercise),
+}
+
+/// Show the first unfinished step, in chapter order, of a chapter that is
+/// open, and open its file in `$EDITOR`. Without the server, `cargo test`
+/// decides what is finished.
+async fn handle_next() -> Result<()> {
+    let catalog = load_catalog()?;
+    let progress = match read_token() {
+        Ok(token) => fetch_progress(&token).await,
+        Err(e) => Err(e),
+    };
+    let next = match &progress {
+        Ok(progress) => next_from_progress(&catalog, progress),
+        Err(e) => {
+            let reason = e.to_string();
+            let reason = reason.lines().next().unwrap_or_default();
+            println!(
+                "⚠️  Couldn't load your progress: {}",
+                reason.trim_start_matches("❌ ")
+            );
+            println!("   Finding your next step with cargo test instead.");
+            next_from_local_tests(&catalog)?
+        }
+    };
+
+    let Some(next) = next else {
+        let locked: Vec<_> = progress
+            .iter()
+            .flat_map(|progress| &progress.exercises)
+            .filter_map(|e| Some((e.name.as_str(), e.locked.as_deref()?)))
+            .collect();
+        if locked.is_empty() {
+            println!("🎉 You've finished every chapter!");
+        } else {
+            println!("🔒 Everything open is finished. Still locked:");
+            for (name, note) in locked {
+                println!("   {name}: {note}");
+            }
+        }
+        return Ok(());
+    };
+
+    match next {
+        NextStep::Quiz(chapter) => {
+            println!("👉 Next: {}", chapter.title);
+            println!("❓ It's a quiz, so answer it in the browser (cargo course open)");
+        }
+        NextStep::Code(chapter, step) => {
+            let steps = chapter.code_steps();
+            match steps.iter().position(|s| std::ptr::eq(*s, step)) {
+                Some(i) if steps.len() > 1 => println!(
+                    "👉 Next: {}, step {} of {}",
+                    chapter.title,
+                    i + 1,
+                    steps.len()
+                ),
+                _ => println!("👉 Next: {}", chapter.title),
+            }
+            for note in intro_notes(chapter, step) {
+                print_note(chapter, note);
+            }
+
+            let file = step_file(chapter, step);
+            println!("\n✏️  {file}");
+            println!("💡 cargo course watch re-tests and submits it whenever you save");
+            open_in_editor(&file)?;
+        }
+    }
+    Ok(())
+}
+
+/// The first unfinished step according to the server: chapters it
+/// reports as completed or locked are skipped.
+fn next_from_progress<'a>(
+    catalog: &'a [Exercise],
+    progress: &ProgressResponse,
+) -> Option<NextStep<'a>> {
+    for chapter in catalog
+        .iter()
+        .filter(|e| !e.is_bonus() && e.is_completable())
+    {
+        let status = progress
+            .exercises
+            .iter()
+            .find(|e| e.name == chapter.file_stem);
+        if status.is_some_and(|s| s.completed || s.locked.is_some()) {
+            continue;
+        }
+        let passed = |step: &CodeStep| {
+            status.is_some_and(|s| s.passed_steps.contains(&exercise_name(chapter, step)))
+        };
+        if let Some(step) = chapter.code_steps().into_iter().find(|s| !passed(s)) {
+            return Some(NextStep::Code(chapter, step));
+        }
+        if chapter.quiz().is_some() {
+            return Some(NextStep::Quiz(chapter));
+        }
+    }
+    None
+}
+
+/// The first step whose tests fail locally. Only `requires` can lock a
+/// chapter here; whether an instructor has released one is only known
+/// to the server.
+fn next_from_local_tests(catalog: &[Exercise]) -> Result<Option<NextStep<'_>>> {
+    let mut finished: HashSet<&str> = HashSet::new();
+    for chapter in catalog.iter().filter(|e| !e.is_bonus()) {
+        if chapter
+            .lock(catalog, |stem| finished.contains(stem), true)
+            .is_some()
+        {
+            continue;
+        }
+        for step in chapter.code_steps() {
+            let filter = (!step.key().is_empty()).then(|| format!("_{}::", step.key()));
+            print!("🧪 Testing {}... ", exercise_name(chapter, step));
+            io::stdout().flush()?;
+            let (tests_passed, _) = run_cargo_test(&chapter.file_stem, filter.as_deref())?;
+            if !tests_passed {
+                println!("❌");
+                return Ok(Some(NextStep::Code(chapter, step)));
+            }
+            println!("✅");
+        }
+        finished.insert(&chapter.file_stem);
+    }
+    Ok(None)
+}
+
+/// The notes between the previous code step and `step`: the prose that
+/// introduces it.
+fn intro_notes<'a>(chapter: &'a Exercise, step: &CodeStep) -> Vec<&'a Note> {
+    let mut notes = Vec::new();
+    for s in &chapter.steps {
+        match s {
+            Step::Prose(note) => notes.push(note),
+            Step::Code(code) if std::ptr::eq(code, step) => break,
+            Step::Code(_) | Step::Quiz(_) => notes.clear(),
+        }
+    }
+    notes
+}
+
+/// Open `file` with the command in `$EDITOR`, if set.
+fn open_in_editor(file: &str) -> Result<()> {
+    let editor = env::var("EDITOR").unwrap_or_default();
+    let mut words = editor.split_whitespace();
+    let Some(program) = words.next() else {
+        println!("💡 Set $EDITOR to have cargo course next open the file for you");
+        return Ok(());
+    };
+    let status = Command::new(program)
+        .args(words)
+        .arg(file)
+        .status()
+        .map_err(|e| anyhow!("Failed to start $EDITOR ({editor}): {e}"))?;
+    if !status.success() {
+        return Err(anyhow!("$EDITOR ({editor}) exited with {status}"));
     }
+    Ok(())
+}
 
-    let completed = progress.exercises.iter().filter(|e| e.completed).count();
-    let total = progress.exercises.len();
-    println!("\nProgress: {completed}/{total} exercises");
+/// The course content in `examples/`, parsed the same way the server
+/// does (reference solutions come from the sibling `solutions/`).
+fn load_catalog() -> Result<Vec<Exercise>> {
+    let examples_dir = Path::new("examples");
+    if !examples_dir.exists() {
+        return Err(anyhow!("examples/ directory not found"));
+    }
+    exercises::scan_dir(examples_dir)
+}
+
+/// Find the code step `step_arg` names: a path as accepted by
+/// `cargo course submit`, or an exercise key like `00_integers/3_add_health`.
+fn find_step<'a>(
+    catalog: &'a [Exercise],
+    step_arg: &str,
+) -> Result<(String, &'a Exercise, &'a CodeStep)> {
+    let key = if step_arg.starts_with("examples/")
+        || Path::new(step_arg)
+            .extension()
+            .is_some_and(|ext| ext == "rs")
+    {
+        extract_submission_target(step_arg)?.exercise_key()
+    } else {
+        step_arg.trim_end_matches('/').to_string()
+    };
+    let chapter_stem = key
+        .split_once('/')
+        .map_or(key.as_str(), |(chapter, _)| chapter);
+    let chapter = catalog
+        .iter()
+        .find(|e| e.file_stem == chapter_stem)
+        .ok_or_else(|| anyhow!("No chapter `{chapter_stem}` in examples/"))?;
+    let Some(step) = exercises::find_code_step(catalog, &key) else {
+        let steps: Vec<String> = chapter
+            .code_steps()
+            .into_iter()
+            .map(|step| step_file(chapter, step))
+            .collect();
+        return Err(anyhow!(
+            "`{step_arg}` is not an exercise step. Steps in {chapter_stem}: {}",
+            steps.join(", ")
+        ));
+    };
+    Ok((key, chapter, step))
+}
+
+/// `submissions.exercise_name` of a step: `<chapter>` for single-step
+/// chapters, `<chapter>/<step_key>` otherwise.
+fn exercise_name(chapter: &Exercise, step: &CodeStep) -> String {
+    let key = step.key();
+    if key.is_empty() {
+        chapter.file_stem.clone()
+    } else {
+        format!("{}/{key}", chapter.file_stem)
+    }
+}
 
+/// Path of a step's exercise file, as `cargo course submit` takes it.
+fn step_file(chapter: &Exercise, step: &CodeStep) -> String {
+    let key = step.key();
+    if key.is_empty() {
+        format!("examples/{}/main.rs", chapter.file_stem)
+    } else {
+        format!("examples/{}/{key}.rs", chapter.file_stem)
+    }
+}
+
+/// Download the participant's data export and write it to `output`.
+async fn handle_export(output: &str) -> Result<()> {
+    let token = read_token()?;
+    let export = fetch_export(&token).await?;
+    fs::write(output, serde_json::to_string_pretty(&export)?)?;
+
+    println!(
+        "✅ Saved your data for {} to {output}",
+        export.participant.name
+    );
+    println!(
+        "   {} submission(s), {} course event(s), {} quiz answer(s)",
+        export.submissions.len(),
+        export.course_events.len(),
+        export.quiz_answers.len()
+    );
+    Ok(())
+}
+
+/// Delete the participant's account on the server, then forget the
+/// local token.
+async fn handle_delete_account(yes: bool) -> Result<()> {
+    let token = read_token()?;
+    if !yes {
+        println!("⚠️  This deletes your account, every submission and all recorded events.");
+        println!("   It cannot be undone. Run 'cargo course export' first to keep a copy.");
+        print!("Type 'delete' to confirm: ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("delete") {
+            println!("Nothing deleted.");
+            return Ok(());
+        }
+    }
+
+    delete_account_on_server(&token).await?;
+    fs::remove_file(TOKEN_FILE)?;
+    update_queue(Path::new(QUEUE_FILE), Vec::clear)?;
+    println!("✅ Your account and its data have been deleted.");
     Ok(())
 }
 
+/// Print every content problem as `path:line: message`. Fails (and
+/// so exits non-zero) when there are any, for gating content PRs.
+fn handle_lint_content(examples_dir: &Path) -> Result<()> {
+    let problems = lint::lint(examples_dir)?;
+    for problem in &problems {
+        println!("{problem}");
+    }
+    if problems.is_empty() {
+        println!("✅ No problems in {}", examples_dir.display());
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} problem(s) in the course content",
+            problems.len()
+        ))
+    }
+}
+
 /// A submission target derived from a file or chapter path.
 ///
 /// For legacy single-step chapters this is just a chapter name; for
@@ -557,7 +1265,9 @@ fn extract_submission_target(file_path: &str) -> Result<SubmissionTarget> {
     })
 }
 
-/// Run cargo test for an exercise and return success status and output.
+/// Run cargo test for an exercise and return success status and output:
+/// cargo's stderr (build errors) followed by the test harness's stdout
+/// (each test's result and the failures' panic messages).
 ///
 /// When `filter` is `Some`, it's passed as a `cargo test` test-name filter
 /// (e.g. `_2_fallback::`) so only one step's tests run.
@@ -571,8 +1281,9 @@ fn run_cargo_test(chapter: &str, filter: Option<&str>) -> Result<(bool, String)>
 
     let success = output.status.success();
     let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
 
-    Ok((success, stderr.to_string()))
+    Ok((success, format!("{stderr}{stdout}")))
 }
 
 /// Run cargo fmt --check and return success status.
@@ -596,7 +1307,10 @@ async fn register_with_server(name: &Name) -> Result<String> {
     let client = reqwest::Client::new();
     let response = client
         .post(format!("{}/api/register", get_server_url()))
-        .json(&RegistrationRequest { name: name.clone() })
+        .json(&RegistrationRequest {
+            name: name.clone(),
+            course: get_course(),
+        })
         .send()
         .await
         .map_err(|e| {
@@ -624,43 +1338,49 @@ async fn register_with_server(name: &Name) -> Result<String> {
 }
 
 /// Submit an exercise solution to the server.
-async fn submit_to_server(submission: SubmissionRequest) -> Result<()> {
+async fn submit_to_server(submission: &SubmissionRequest) -> Result<()> {
     let client = reqwest::Client::new();
     let response = client
         .post(format!("{}/api/submit", get_server_url()))
-        .json(&submission)
+        .json(submission)
         .send()
         .await
         .map_err(|e| {
             if e.is_connect() {
-                anyhow!(
-                    "❌ Cannot connect to the corrode course server at {}\n\n\
-                     💡 Your solution was tested locally but couldn't be submitted.\n\
-                     For offline practice, continue using: cargo test --example <exercise_name>",
+                Unreachable(format!(
+                    "Cannot connect to the corrode course server at {}",
                     get_server_url()
-                )
+                ))
             } else {
-                anyhow!("Network error: {e}")
+                Unreachable(format!("Network error: {e}"))
             }
         })?;
 
     if !response.status().is_success() {
         let error_msg = match response.status() {
             reqwest::StatusCode::UNAUTHORIZED => {
-                "Invalid token. Please run 'cargo course init' to register or check your token."
+                "Invalid token. If your account requires sign-in, create an API token on \
+                 your settings page and run 'cargo course init --token <TOKEN>'. \
+                 Otherwise run 'cargo course init' to register or check your token."
                     .to_string()
             }
             reqwest::StatusCode::BAD_REQUEST => {
-                "Invalid submission data. Please check your exercise file and try again."
-                    .to_string()
+                return Err(Rejected(
+                    "Invalid submission data. Please check your exercise file and try again."
+                        .to_string(),
+                )
+                .into());
             }
             reqwest::StatusCode::SERVICE_UNAVAILABLE => {
-                format!(
+                return Err(Unreachable(format!(
                     "Server at {} is up but reported 503 Service Unavailable. \
-                     The course server might be restarting or out of capacity. \
-                     Try again in a moment.",
+                     The course server might be restarting or out of capacity",
                     get_server_url()
-                )
+                ))
+                .into());
+            }
+            status if status.is_server_error() => {
+                return Err(Unreachable(format!("Submission failed: {status}")).into());
             }
             status => {
                 format!("Submission failed: {status}")
@@ -672,15 +1392,232 @@ async fn submit_to_server(submission: SubmissionRequest) -> Result<()> {
     Ok(())
 }
 
+/// A submission that didn't reach the server, or that the server
+/// failed to store: worth sending again later, unlike one it rejected.
+#[derive(Debug)]
+struct Unreachable(String);
+
+impl fmt::Display for Unreachable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Unreachable {}
+
+/// A submission the server refused as malformed (400): sending it
+/// again would fail the same way.
+#[derive(Debug)]
+struct Rejected(String);
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Rejected {}
+
+/// What became of a submission.
+enum Delivery {
+    Sent,
+    /// Saved to [`QUEUE_FILE`] for later, with why it wasn't sent.
+    Queued(String),
+}
+
+/// Send `submission`, or add it to [`QUEUE_FILE`], stamped with the
+/// current time, when the server can't take it right now.
+async fn submit_or_queue(mut submission: SubmissionRequest) -> Result<Delivery> {
+    match submit_to_server(&submission).await {
+        Ok(()) => Ok(Delivery::Sent),
+        Err(e) if e.is::<Unreachable>() => {
+            submission.submitted_at.get_or_insert_with(chrono::Utc::now);
+            update_queue(Path::new(QUEUE_FILE), |queue| queue.push(submission))?;
+            Ok(Delivery::Queued(e.to_string()))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Send the submissions queued while the server was unreachable.
+async fn handle_sync() -> Result<()> {
+    let flush = flush_queue().await?;
+    if flush.sent == 0 && flush.remaining == 0 {
+        println!("✅ No queued submissions");
+        return Ok(());
+    }
+    if flush.sent > 0 {
+        println!("📤 Sent {} queued submission(s)", flush.sent);
+    }
+    match flush.stopped {
+        Some(e) => Err(anyhow!(
+            "{} submission(s) still queued in {QUEUE_FILE}: {e}",
+            flush.remaining
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Send what an earlier command queued. Says nothing while the server
+/// is still unreachable; `cargo course sync` reports that.
+async fn retry_queue() {
+    match flush_queue().await {
+        Ok(flush) => {
+            if flush.sent > 0 {
+                println!("📤 Sent {} queued submission(s)", flush.sent);
+            }
+            if let Some(e) = flush.stopped.filter(|e| !e.is::<Unreachable>()) {
+                println!(
+                    "⚠️  {} queued submission(s) kept in {QUEUE_FILE}: {e}",
+                    flush.remaining
+                );
+            }
+        }
+        Err(e) => println!("⚠️  Couldn't send the queued submissions: {e}"),
+    }
+}
+
+/// What [`flush_queue`] did.
+#[derive(Default)]
+struct Flush {
+    sent: usize,
+    /// Still queued afterwards.
+    remaining: usize,
+    /// Why sending stopped early, if it did.
+    stopped: Option<anyhow::Error>,
+}
+
+/// Send the queued submissions oldest first, as the participant in
+/// [`TOKEN_FILE`] now, stopping at the first one the server can't take.
+/// That includes a 401: the token may have changed since (an account
+/// that now requires sign-in needs `cargo course init --token`), and
+/// the work is kept until it has. Only ones the server rejects as
+/// malformed are dropped, with a warning, since sending them again
+/// would fail the same way.
+async fn flush_queue() -> Result<Flush> {
+    let queue = Path::new(QUEUE_FILE);
+    let mut queued = read_queue(queue)?;
+    if let Ok(token) = read_token() {
+        for submission in &mut queued {
+            token.as_str().clone_into(&mut submission.ulid);
+        }
+    }
+    let mut flush = Flush::default();
+    let mut done = Vec::new();
+    for submission in &queued {
+        // One that did arrive after all is sent again next time, and the
+        // server drops it as a duplicate.
+        let sent = tokio::time::timeout(FLUSH_TIMEOUT, submit_to_server(submission))
+            .await
+            .unwrap_or_else(|_| {
+                Err(Unreachable(format!(
+                    "The server didn't answer within {} seconds",
+                    FLUSH_TIMEOUT.as_secs()
+                ))
+                .into())
+            });
+        match sent {
+            Ok(()) => flush.sent += 1,
+            Err(e) if e.is::<Rejected>() => println!(
+                "⚠️  Dropped the queued submission of {}: {e}",
+                submission.exercise_name
+            ),
+            Err(e) => {
+                flush.stopped = Some(e);
+                break;
+            }
+        }
+        done.push((submission.exercise_name.clone(), submission.submitted_at));
+    }
+    flush.remaining = if done.is_empty() {
+        queued.len()
+    } else {
+        update_queue(queue, |queue| forget_handled(queue, &done))?
+    };
+    Ok(flush)
+}
+
+/// Remove the submissions in `handled`, keyed by exercise and queue
+/// time. Another command (say, `watch` in a second terminal) may have
+/// queued more since they were read, so the rest stay.
+fn forget_handled(
+    queue: &mut Vec<SubmissionRequest>,
+    handled: &[(String, Option<chrono::DateTime<chrono::Utc>>)],
+) {
+    queue.retain(|s| {
+        !handled
+            .iter()
+            .any(|(name, at)| *name == s.exercise_name && *at == s.submitted_at)
+    });
+}
+
+/// The submissions queued in `queue`, oldest first.
+fn read_queue(queue: &Path) -> Result<Vec<SubmissionRequest>> {
+    match fs::read_to_string(queue) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Apply `change` to the submissions queued in `queue` and save them,
+/// removing the file once it is empty. Returns how many are left.
+fn update_queue(queue: &Path, change: impl FnOnce(&mut Vec<SubmissionRequest>)) -> Result<usize> {
+    // `submit --all` queues from several tasks at once; other `cargo
+    // course` processes are kept out by the lock file.
+    static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+    let _guard = QUEUE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(parent) = queue.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let lock = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(queue.with_extension("lock"))?;
+    lock.lock()?;
+
+    let mut queued = read_queue(queue)?;
+    change(&mut queued);
+    if queued.is_empty() {
+        match fs::remove_file(queue) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    } else {
+        // Readers don't take the lock; the rename means they see either
+        // the old queue or the new one, never half of it.
+        let staged = queue.with_extension("json.tmp");
+        fs::write(&staged, serde_json::to_string_pretty(&queued)?)?;
+        fs::rename(&staged, queue)?;
+    }
+    Ok(queued.len())
+}
+
+/// `GET /api/{endpoint}` as the participant. API tokens go in an
+/// `Authorization: Bearer` header so they stay out of URLs (and the
+/// access logs that record them); bare ULIDs stay in the path.
+fn participant_get(
+    client: &reqwest::Client,
+    endpoint: &str,
+    token: &Token,
+    query: &str,
+) -> reqwest::RequestBuilder {
+    let server = get_server_url();
+    if token.is_api_token() {
+        client
+            .get(format!("{server}/api/{endpoint}{query}"))
+            .bearer_auth(token.as_str())
+    } else {
+        client.get(format!("{server}/api/{endpoint}/{}{query}", token.as_str()))
+    }
+}
+
 /// Fetch participant progress from the server.
 async fn fetch_progress(token: &Token) -> Result<ProgressResponse> {
     let client = reqwest::Client::new();
-    let response = client
-        .get(format!(
-            "{}/api/status/{}",
-            get_server_url(),
-            token.as_str()
-        ))
+    let course = get_course().map_or_else(String::new, |course| format!("?course={course}"));
+    let response = participant_get(&client, "status", token, &course)
         .send()
         .await
         .map_err(|e| {
@@ -696,6 +1633,12 @@ async fn fetch_progress(token: &Token) -> Result<ProgressResponse> {
             }
         })?;
 
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(anyhow!(
+            "Failed to fetch progress: this account requires sign-in. Create an API token \
+             on your settings page and run 'cargo course init --token <TOKEN>'."
+        ));
+    }
     if !response.status().is_success() {
         return Err(anyhow!("Failed to fetch progress: {}", response.status()));
     }
@@ -704,6 +1647,64 @@ async fn fetch_progress(token: &Token) -> Result<ProgressResponse> {
     Ok(progress)
 }
 
+/// Fetch everything the server stores about the participant.
+async fn fetch_export(token: &Token) -> Result<AccountExport> {
+    let client = reqwest::Client::new();
+    let response = participant_get(&client, "export", token, "")
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_connect() {
+                anyhow!(
+                    "❌ Cannot connect to the corrode course server at {}",
+                    get_server_url()
+                )
+            } else {
+                anyhow!("Network error: {e}")
+            }
+        })?;
+
+    match response.status() {
+        reqwest::StatusCode::UNAUTHORIZED => Err(anyhow!(
+            "Export failed: invalid token. If your account requires sign-in, create an API \
+             token on your settings page and run 'cargo course init --token <TOKEN>'."
+        )),
+        status if !status.is_success() => Err(anyhow!("Export failed: {status}")),
+        _ => Ok(response.json().await?),
+    }
+}
+
+/// Ask the server to delete the participant's account.
+async fn delete_account_on_server(token: &Token) -> Result<()> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{}/api/delete-account", get_server_url()))
+        .json(&AccountDeletionRequest {
+            ulid: token.as_str().to_string(),
+        })
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_connect() {
+                anyhow!(
+                    "❌ Cannot connect to the corrode course server at {}",
+                    get_server_url()
+                )
+            } else {
+                anyhow!("Network error: {e}")
+            }
+        })?;
+
+    match response.status() {
+        reqwest::StatusCode::UNAUTHORIZED => Err(anyhow!(
+            "Deletion failed: invalid token. If your account requires sign-in, create an \
+             API token on your settings page and run 'cargo course init --token <TOKEN>'."
+        )),
+        status if !status.is_success() => Err(anyhow!("Deletion failed: {status}")),
+        _ => Ok(()),
+    }
+}
+
 /// Read the participant token from the local file.
 fn read_token() -> Result<Token> {
     let token_str = fs::read_to_string(TOKEN_FILE)
@@ -721,3 +1722,110 @@ fn save_token(token: &Token) -> Result<()> {
     fs::write(TOKEN_FILE, token.as_str())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A queue file in a fresh temp dir (the dir doesn't exist yet).
+    fn queue_path(name: &str) -> std::path::PathBuf {
+        let root = env::temp_dir().join(format!("corrode-queue-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        root.join(".corrode/queue.json")
+    }
+
+    fn queued(exercise_name: &str, minute: u32) -> SubmissionRequest {
+        SubmissionRequest {
+            ulid: "01ARZ3NDEKTSV4RRFFQ69G5FAV".to_string(),
+            exercise_name: exercise_name.to_string(),
+            source_code: String::new(),
+            tests_passed: true,
+            clippy_passed: false,
+            fmt_passed: false,
+            submitted_at: chrono::DateTime::from_timestamp(i64::from(minute) * 60, 0),
+        }
+    }
+
+    fn names(queue: &Path) -> Vec<(String, i64)> {
+        read_queue(queue)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.exercise_name, s.submitted_at.unwrap().timestamp() / 60))
+            .collect()
+    }
+
+    #[test]
+    fn update_queue_replaces_the_file_and_removes_it_once_empty() {
+        let queue = queue_path("update");
+        assert!(read_queue(&queue).unwrap().is_empty());
+
+        assert_eq!(
+            update_queue(&queue, |q| q.push(queued("00_a", 1))).unwrap(),
+            1
+        );
+        assert_eq!(
+            update_queue(&queue, |q| q.push(queued("01_b", 2))).unwrap(),
+            2
+        );
+        assert_eq!(
+            names(&queue),
+            [("00_a".to_string(), 1), ("01_b".to_string(), 2)]
+        );
+        assert!(queue.with_extension("lock").is_file());
+        assert!(!queue.with_extension("json.tmp").exists());
+
+        assert_eq!(update_queue(&queue, Vec::clear).unwrap(), 0);
+        assert!(!queue.exists());
+        // Clearing an already missing queue is fine too.
+        assert_eq!(update_queue(&queue, Vec::clear).unwrap(), 0);
+
+        fs::remove_dir_all(queue.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn update_queue_keeps_every_concurrent_change() {
+        let queue = queue_path("concurrent");
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let queue = &queue;
+                scope.spawn(move || {
+                    for minute in 0..10 {
+                        update_queue(queue, |q| {
+                            q.push(queued(&format!("0{thread}_t"), minute));
+                        })
+                        .unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(read_queue(&queue).unwrap().len(), 40);
+
+        fs::remove_dir_all(queue.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn forget_handled_keeps_submissions_queued_since() {
+        let queue = queue_path("forget");
+        update_queue(&queue, |q| {
+            q.extend([queued("00_a", 1), queued("01_b", 2), queued("00_a", 3)]);
+        })
+        .unwrap();
+        let handled: Vec<_> = read_queue(&queue)
+            .unwrap()
+            .into_iter()
+            .take(2)
+            .map(|s| (s.exercise_name, s.submitted_at))
+            .collect();
+        // Queued by another command between the read and the update.
+        update_queue(&queue, |q| q.push(queued("01_b", 4))).unwrap();
+
+        let left = update_queue(&queue, |q| forget_handled(q, &handled)).unwrap();
+        assert_eq!(left, 2);
+        assert_eq!(
+            names(&queue),
+            [("00_a".to_string(), 3), ("01_b".to_string(), 4)]
+        );
+
+        fs::remove_dir_all(queue.parent().unwrap().parent().unwrap()).unwrap();
+    }
+}
@@ -1,30 +1,58 @@
-use cargo_course::exercises::{self, Exercise, RenderItem, RenderKind, Step};
+use cargo_course::catalog::{self, CatalogStatus};
+use cargo_course::courses::{Course, Courses};
+use cargo_course::db::Db;
+use cargo_course::diagnostics::Diagnostic;
+use cargo_course::exercises::{
+    self, Difficulty, Exercise, Lock, RenderItem, RenderKind, Step, Unlock,
+};
+use cargo_course::explain::{self, Explanations};
+use cargo_course::runner::{
+    BackendKind, FormatBackend, LintBackend, LocalBackend, RunBackend, RunError, RunJob, RunOutput,
+    Sandbox,
+};
+use cargo_course::session::{
+    self, ADMIN_COOKIE, ADMIN_SESSION_TTL, AdminSession, LOGIN_CODE_TTL, LoginThrottle,
+    PARTICIPANT_COOKIE, PARTICIPANT_SESSION_TTL, ParticipantSession, SessionKey,
+};
 use cargo_course::types::{
-    ExerciseStatus, Name, ProgressResponse, RegistrationRequest, RegistrationResponse,
-    SubmissionRequest, TeamToken,
+    AccountDeletionRequest, AccountExport, ExerciseStatus, ExportedAdminAction, ExportedApiToken,
+    ExportedCourseEvent, ExportedDiagnostic, ExportedEnrollment, ExportedParticipant,
+    ExportedQuizAnswer, ExportedSubmission, Name, ProgressResponse, RegistrationRequest,
+    RegistrationResponse, SubmissionRequest, TeamToken, TeamTokenError,
 };
+use cargo_course::verify;
+use cargo_course::with_pool;
 
 use anyhow::Result;
 use askama::Template;
 use axum::{
-    Router, debug_handler,
-    extract::{Path as AxumPath, Query, State},
-    http::{StatusCode, header::CACHE_CONTROL},
+    Extension, Router, debug_handler,
+    extract::{ConnectInfo, OriginalUri, Path as AxumPath, Query, Request, State},
+    http::{
+        HeaderMap, HeaderValue, Method, StatusCode, Uri,
+        header::{
+            AUTHORIZATION, CACHE_CONTROL, CONTENT_DISPOSITION, COOKIE, RETRY_AFTER, SET_COOKIE,
+        },
+    },
+    middleware::Next,
     response::{Html, IntoResponse, Json, Response},
     routing::{delete, get, post},
 };
+use clap::{Parser, Subcommand};
 use dotenvy::dotenv;
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use sqlx::{Row, Sqlite, SqlitePool, migrate::MigrateDatabase, sqlite::SqlitePoolOptions};
 use std::env;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, LazyLock};
 use tower_http::services::ServeDir;
 use ulid::Ulid;
 
 /// Version of the course, sourced from the crate version in `Cargo.toml`.
-/// Surfaced in the site footer so users can tell which release they're on.
+/// Surfaced in the site footer so users can tell which release they're on,
+/// and recorded with the analytics of courses that don't set their own
+/// `version` in the course registry.
 const COURSE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Build-time git metadata baked in by `build.rs` via `cargo:rustc-env`.
@@ -83,14 +111,52 @@ fn git_hash() -> &'static str {
 /// Application state shared across all routes
 #[derive(Clone)]
 struct AppState {
-    pool: SqlitePool,
+    /// SQLite or Postgres, per `DATABASE_URL`.
+    db: Db,
+    /// Typed into `/admin/login`; never accepted from a URL.
     admin_token: String,
-    exercises: Arc<Vec<Exercise>>,
+    /// Signs admin session cookies and CSRF tokens.
+    sessions: SessionKey,
+    /// Whether session cookies get the `Secure` attribute
+    /// (`CORRODE_SECURE_COOKIES`, on unless set to `false`).
+    secure_cookies: bool,
+    /// Failed `/admin/login` attempts per client address.
+    login_throttle: Arc<LoginThrottle>,
+    /// Failed participant sign-ins per client address and per account.
+    participant_throttle: Arc<LoginThrottle<SignInKey>>,
+    /// Argon2 hashes allowed to run at once (see [`run_argon2`]).
+    argon2_permits: Arc<tokio::sync::Semaphore>,
+    /// Whether to take the client address from `X-Forwarded-For`
+    /// (`CORRODE_TRUST_FORWARDED_FOR`, off unless set to `true`).
+    trust_forwarded_for: bool,
+    /// Every course this server hosts. Course-scoped handlers get theirs
+    /// as an `Extension<Arc<Course>>` (see [`course_routes`]) and take
+    /// one `course.catalog.current()` snapshot per request.
+    courses: Arc<Courses>,
+    /// `rustc --explain` texts behind `/explain/{code}`.
+    explanations: Arc<Explanations>,
+    /// Where `/api/run` compiles and executes code.
+    runner: Arc<dyn RunBackend>,
+    /// Whether `/api/run` turns away requests without a participant
+    /// credential (`CORRODE_RUN_REQUIRE_PARTICIPANT`, off unless set to
+    /// `true`).
+    run_requires_participant: bool,
+    /// Where `/api/format` runs rustfmt.
+    formatter: Arc<dyn FormatBackend>,
+    /// Where `/api/clippy` lints code.
+    linter: Arc<dyn LintBackend>,
+    /// Queue of submission ids for the background verifier, or `None`
+    /// when `CORRODE_VERIFY_SUBMISSIONS` is off and client claims are
+    /// trusted.
+    verifier: Option<tokio::sync::mpsc::UnboundedSender<String>>,
 }
 
 /// A privacy-conscious analytics row. Deliberately excludes source code,
 /// participant names, URLs, user agents, and free-form client metadata.
 struct CourseEvent<'a> {
+    /// The course the page or exercise belongs to; its version is
+    /// recorded as `course_version`.
+    course: &'a Course,
     participant_id: Option<&'a str>,
     session_id: &'a str,
     event_type: &'a str,
@@ -100,33 +166,113 @@ struct CourseEvent<'a> {
     tests_total: Option<i64>,
     duration_ms: Option<i64>,
     diagnostic_code: Option<&'a str>,
+    /// Every distinct diagnostic code of a run, stored in
+    /// `course_event_diagnostics`. Empty for UI events.
+    diagnostics: &'a [DiagnosticCount<'a>],
 }
 
-async fn store_course_event(pool: &SqlitePool, event: CourseEvent<'_>) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r"
-        INSERT OR IGNORE INTO course_events (
-            id, participant_id, session_id, event_type, exercise_name, result,
-            tests_passed, tests_total, duration_ms, diagnostic_code,
-            course_version, git_hash
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-        ",
-    )
-    .bind(Ulid::new().to_string())
-    .bind(event.participant_id)
-    .bind(event.session_id)
-    .bind(event.event_type)
-    .bind(event.exercise_name)
-    .bind(event.result)
-    .bind(event.tests_passed)
-    .bind(event.tests_total)
-    .bind(event.duration_ms)
-    .bind(event.diagnostic_code)
-    .bind(COURSE_VERSION)
-    .bind(git_hash())
-    .execute(pool)
-    .await?;
-    Ok(())
+/// How often one compiler diagnostic code fired in a run.
+#[derive(Debug, PartialEq, Eq)]
+struct DiagnosticCount<'a> {
+    code: &'a str,
+    /// `error` or `warning`; `error` wins if the code fired at both.
+    level: &'a str,
+    occurrences: i64,
+}
+
+/// Distinct codes of the top-level errors and warnings in `diagnostics`,
+/// in the order they first appear. Codes that don't look like an
+/// `Exxxx` error code or a lint name are skipped: Playground output is
+/// parsed from text that also carries the program's own stderr, and
+/// analytics must not store anything a learner typed.
+fn diagnostic_counts(diagnostics: &[Diagnostic]) -> Vec<DiagnosticCount<'_>> {
+    let mut counts: Vec<DiagnosticCount<'_>> = Vec::new();
+    for diagnostic in diagnostics {
+        let level = diagnostic.level.as_str();
+        let Some(code) = diagnostic.code.as_deref() else {
+            continue;
+        };
+        if !matches!(level, "error" | "warning") || !valid_diagnostic_code(code) {
+            continue;
+        }
+        if let Some(count) = counts.iter_mut().find(|count| count.code == code) {
+            count.occurrences += 1;
+            if level == "error" {
+                count.level = level;
+            }
+        } else {
+            counts.push(DiagnosticCount {
+                code,
+                level,
+                occurrences: 1,
+            });
+        }
+    }
+    counts
+}
+
+/// `E0382`, or a lint name such as `unused_variables` /
+/// `clippy::needless_return`.
+fn valid_diagnostic_code(code: &str) -> bool {
+    is_rust_error_code(code)
+        || (code.len() <= 128
+            && code.starts_with(|c: char| c.is_ascii_lowercase())
+            && code.bytes().all(|byte| {
+                byte.is_ascii_lowercase() || byte.is_ascii_digit() || matches!(byte, b'_' | b':')
+            }))
+}
+
+fn is_rust_error_code(code: &str) -> bool {
+    code.len() == 5 && code.starts_with('E') && code[1..].bytes().all(|byte| byte.is_ascii_digit())
+}
+
+async fn store_course_event(db: &Db, event: CourseEvent<'_>) -> Result<(), sqlx::Error> {
+    let id = Ulid::new().to_string();
+    with_pool!(db, |pool| {
+        let mut tx = pool.begin().await?;
+        let inserted = sqlx::query(
+            r"
+            INSERT INTO course_events (
+                id, participant_id, session_id, event_type, course, exercise_name, result,
+                tests_passed, tests_total, duration_ms, diagnostic_code,
+                course_version, git_hash
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT DO NOTHING
+            ",
+        )
+        .bind(&id)
+        .bind(event.participant_id)
+        .bind(event.session_id)
+        .bind(event.event_type)
+        .bind(&event.course.slug)
+        .bind(event.exercise_name)
+        .bind(event.result)
+        .bind(event.tests_passed)
+        .bind(event.tests_total)
+        .bind(event.duration_ms)
+        .bind(event.diagnostic_code)
+        .bind(&event.course.version)
+        .bind(git_hash())
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        // A deduplicated UI event inserts nothing; it has no diagnostics anyway.
+        if inserted > 0 {
+            for diagnostic in event.diagnostics {
+                sqlx::query(
+                    "INSERT INTO course_event_diagnostics (event_id, code, level, occurrences) \
+                     VALUES ($1, $2, $3, $4)",
+                )
+                .bind(&id)
+                .bind(diagnostic.code)
+                .bind(diagnostic.level)
+                .bind(diagnostic.occurrences)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await
+    })
 }
 
 /// Database model for participants. Only the fields we actually read
@@ -154,6 +300,34 @@ impl DbParticipant {
     }
 }
 
+/// A participant's sign-in settings (migration
+/// `015_participant_auth.sql`).
+#[derive(sqlx::FromRow)]
+struct DbParticipantAuth {
+    /// `false`: the ULID in the login link is enough. `true`: pages and
+    /// API calls need a session cookie or an API token.
+    auth_required: bool,
+    /// Sessions issued under an older epoch are rejected.
+    auth_epoch: i64,
+    passphrase_hash: Option<String>,
+    /// Decides whether an instructor's session may open the pages.
+    team_token: Option<String>,
+}
+
+/// What a request may do as a given participant.
+enum ParticipantAccess {
+    /// No such participant.
+    Unknown,
+    /// The participant requires sign-in and the request isn't signed in.
+    SignInRequired,
+    /// Go ahead. `session` is the browser's session for this
+    /// participant, if it has one.
+    Granted {
+        auth: DbParticipantAuth,
+        session: Option<ParticipantSession>,
+    },
+}
+
 /// Database model for submissions. Mirrors the subset of the
 /// `submissions` table columns we actually read; the SQL queries below
 /// list these columns explicitly so adding a column to the schema
@@ -183,6 +357,11 @@ struct PlaygroundTemplate {
 #[derive(Template)]
 #[template(path = "tour.html")]
 struct TourTemplate {
+    /// [`Course::base`] of the course the page belongs to, prefixed to
+    /// its links.
+    base: String,
+    /// See [`ExerciseTemplate::key_prefix`].
+    key_prefix: String,
     /// The annotated tour source shown in the editor.
     starter: String,
     /// Always `None`: the tour is anonymous preamble with no participant
@@ -203,10 +382,43 @@ struct CheatsheetTemplate {
     html: String,
 }
 
+/// A chapter that teaches the concept behind an error code, linked
+/// from its explanation.
+struct ExplainChapter {
+    title: String,
+    href: String,
+}
+
+/// Template for `/explain/{code}`: the compiler's explanation of an
+/// error code plus links to the chapters that teach it.
+#[derive(Template)]
+#[template(path = "explain.html")]
+struct ExplainTemplate {
+    code: String,
+    html: String,
+    chapters: Vec<ExplainChapter>,
+}
+
+/// Bare-body variant of [`ExplainTemplate`], fetched by the editor to
+/// show an explanation inline under the run output.
+#[derive(Template)]
+#[template(path = "partials/explain_body.html")]
+struct ExplainFragmentTemplate {
+    code: String,
+    html: String,
+    chapters: Vec<ExplainChapter>,
+}
+
 /// Template for an exercise page
 #[derive(Template)]
 #[template(path = "exercise.html")]
 struct ExerciseTemplate {
+    /// [`Course::base`] of the course the page belongs to, prefixed to
+    /// its links.
+    base: String,
+    /// What [`Course::qualify`] puts in front of an exercise name:
+    /// the keys the editor submits and reports analytics under.
+    key_prefix: String,
     exercise: Exercise,
     /// `Some` when the page is rendered with participant context.
     ulid: Option<String>,
@@ -232,12 +444,31 @@ struct ExerciseTemplate {
     /// `partials/next_chapter_cta.html`.
     next_locked: bool,
     /// Number of completable chapters the participant has finished.
-    /// Quizzes and notes-only chapters don't count toward either total.
+    /// Notes-only chapters and practice quizzes don't count toward
+    /// either total.
     progress_done: usize,
     /// Number of completable chapters in the course (denominator).
     progress_total: usize,
 }
 
+/// What a chapter page shows instead of the chapter while its
+/// `.chapter.toml` keeps it locked for the viewer.
+#[derive(Template)]
+#[template(path = "locked.html")]
+struct LockedTemplate {
+    base: String,
+    ulid: Option<String>,
+    title: String,
+    /// See [`Exercise::number`]; `0` for bonus chapters.
+    number: u8,
+    /// The required chapters still to complete, as chapter-list rows.
+    missing: Vec<ProgressDot>,
+    /// The chapter also waits for an instructor to release it.
+    awaiting_release: bool,
+    dots: Vec<ProgressDot>,
+    chapter_rows: usize,
+}
+
 /// One row in the bottom chapter list.
 // reason: data-transfer struct for templates / JSON
 #[allow(clippy::struct_excessive_bools)]
@@ -254,8 +485,9 @@ struct ProgressDot {
     perfected: bool,
     current: bool,
     is_quiz: bool,
-    /// `false` for notes-only chapters (the appendix). Used to skip
-    /// them when building the "next chapter" CTA and progress totals.
+    /// `false` for notes-only chapters (the appendix) and practice
+    /// quizzes. Used to skip them when building the "next chapter" CTA
+    /// and progress totals.
     has_exercises: bool,
     /// `true` for optional bonus chapters: hidden from the TOC/picker and
     /// excluded from progress and the next-chapter flow.
@@ -265,14 +497,20 @@ struct ProgressDot {
     /// `/exercise/{slug}` URL. Used for non-exercise entries like the
     /// read-only "Quick Tour" preamble (`/tour`).
     href: Option<String>,
+    /// What the chapter still needs before it opens (see
+    /// [`lock_note`]); `None` when it's open.
+    locked: Option<String>,
+    /// See [`Exercise::difficulty`]; rendered as a badge on the row.
+    difficulty: Option<Difficulty>,
 }
 
 /// Synthetic chapter-list entry for the read-only "A Quick Tour of
 /// Rust" preamble. It isn't a real exercise (no code steps, no tests,
 /// no progress), so it carries an explicit `/tour` href and
 /// `has_exercises = false` to stay out of the progress totals while
-/// still showing as the first row of the table of contents.
-fn tour_dot() -> ProgressDot {
+/// still showing as the first row of the table of contents. `base` is
+/// the course's [`Course::base`].
+fn tour_dot(base: &str) -> ProgressDot {
     ProgressDot {
         slug: "tour".to_string(),
         number: 0,
@@ -284,7 +522,9 @@ fn tour_dot() -> ProgressDot {
         is_quiz: false,
         has_exercises: false,
         is_bonus: false,
-        href: Some("/tour".to_string()),
+        href: Some(format!("{base}/tour")),
+        locked: None,
+        difficulty: None,
     }
 }
 
@@ -313,6 +553,16 @@ struct UiExerciseStatus {
 #[derive(Template)]
 #[template(path = "dashboard.html")]
 struct DashboardTemplate {
+    /// [`Course::base`] of the course the page belongs to, prefixed to
+    /// its links.
+    base: String,
+    /// See [`ExerciseTemplate::key_prefix`].
+    key_prefix: String,
+    course_slug: String,
+    course_title: String,
+    /// Every course on the server, for the course switcher. Empty when
+    /// the server hosts just one.
+    courses: Vec<CourseLink>,
     participant_name: Option<String>,
     ulid: Option<String>,
     /// One entry per chapter, in display order. Renders the bottom
@@ -339,8 +589,8 @@ struct DashboardTemplate {
     next_chapter_number: u8,
     next_chapter_title: String,
     /// Number of completable chapters the participant has finished.
-    /// Always 0 in anonymous mode. Quizzes and notes-only chapters
-    /// don't count.
+    /// Always 0 in anonymous mode. Notes-only chapters and practice
+    /// quizzes don't count.
     progress_done: usize,
     /// Number of completable chapters in the course (denominator).
     progress_total: usize,
@@ -352,6 +602,23 @@ struct DashboardTemplate {
     /// "View your team" link on the dashboard. `None` for anonymous
     /// viewers and for participants who signed up via the public form.
     team_token: Option<TeamToken>,
+    /// Every tag in the course, for the filter chips above the chapter
+    /// list. Empty hides the filter.
+    tags: Vec<String>,
+    /// The tag `dots` is filtered by, if any.
+    tag: Option<String>,
+}
+
+/// One course in a course switcher.
+struct CourseLink {
+    title: String,
+    /// The course's dashboard for the viewer, or its landing page.
+    href: String,
+    /// The page being rendered belongs to this course.
+    current: bool,
+    /// The viewer is enrolled in it. Always `false` for anonymous
+    /// visitors and on admin pages.
+    enrolled: bool,
 }
 
 /// Template for the slim signup form.
@@ -362,20 +629,127 @@ struct DashboardTemplate {
 #[derive(Template)]
 #[template(path = "signup.html")]
 struct SignupTemplate {
+    /// [`Course::base`] of the course the page belongs to, prefixed to
+    /// its links.
+    base: String,
     team_slug: Option<String>,
 }
 
+/// Admin sign-in form (`/admin/login`).
+#[derive(Template)]
+#[template(path = "admin_login.html")]
+struct AdminLoginTemplate {
+    /// Shown above the form after a failed attempt.
+    error: Option<&'static str>,
+    /// Informational banner (legacy link, signed out).
+    notice: Option<&'static str>,
+    /// Round-tripped through a hidden input.
+    next: String,
+}
+
+/// Super-admin page for instructor accounts (`/admin/instructors`).
+#[derive(Template)]
+#[template(path = "admin_instructors.html")]
+struct AdminInstructorsTemplate {
+    csrf_token: String,
+    instructors: Vec<InstructorRow>,
+    /// Name and sign-in token of an instructor created by this very
+    /// request. Only the hash is stored, so this is the one chance to
+    /// copy it.
+    new_instructor: Option<(String, String)>,
+    error: Option<String>,
+}
+
+/// One instructor on the `/admin/instructors` page.
+#[derive(sqlx::FromRow)]
+struct InstructorRow {
+    id: String,
+    name: String,
+    /// Comma-separated team slugs; `None` when none are assigned.
+    teams: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    last_login_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Super-admin page for browsing `admin_audit` (`/admin/audit`).
+#[derive(Template)]
+#[template(path = "admin_audit.html")]
+struct AdminAuditTemplate {
+    entries: Vec<AuditRow>,
+    /// `true` when more than [`AUDIT_PAGE_LIMIT`] entries matched.
+    truncated: bool,
+    actions: &'static [&'static str],
+    /// The active filters, echoed back into the form.
+    action: String,
+    actor: String,
+    participant: String,
+}
+
+/// One `admin_audit` row, with the participant's name while they
+/// still exist.
+#[derive(sqlx::FromRow)]
+struct AuditRow {
+    created_at: chrono::DateTime<chrono::Utc>,
+    actor_name: String,
+    action: String,
+    participant_id: Option<String>,
+    participant_name: Option<String>,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+/// A soft-deleted participant in the dashboard's "Recently removed" list.
+#[derive(sqlx::FromRow)]
+struct RemovedParticipant {
+    id: String,
+    name: String,
+    team_token: Option<String>,
+    deleted_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl RemovedParticipant {
+    /// When [`purge_removed_participants`] will delete them for good.
+    fn purge_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.deleted_at + chrono::Duration::days(REMOVED_PARTICIPANT_GRACE_DAYS)
+    }
+}
+
+/// Participant sign-in form (`/login`): passphrase or login code.
+#[derive(Template)]
+#[template(path = "login.html")]
+struct LoginTemplate {
+    /// Participant ULID taken from `next`, so the passphrase form
+    /// doesn't have to ask for the login link again.
+    participant: Option<String>,
+    error: Option<&'static str>,
+    notice: Option<&'static str>,
+    /// Round-tripped through hidden inputs.
+    next: String,
+}
+
 /// Template for admin dashboard
 #[derive(Template)]
 #[template(path = "admin.html")]
 struct AdminTemplate {
+    /// [`Course::base`] of the course the page belongs to, prefixed to
+    /// its links.
+    base: String,
+    course_title: String,
+    /// Every course on the server, for switching between their admin
+    /// views. Empty when the server hosts just one.
+    courses: Vec<CourseLink>,
     participant_teams: Vec<ParticipantTeam>,
     recent_submissions: Vec<SubmissionSummary>,
     stats: AdminStats,
     exercises: Vec<String>,
-    /// Echoed back into every form action / link so the admin token
-    /// stays attached as the operator clicks around.
-    admin_token: String,
+    /// CSRF token of the operator's session, embedded in every
+    /// mutating form and read by `removeParticipant` in `base.html`.
+    csrf_token: String,
+    /// Set when an instructor is signed in; the page only shows their
+    /// teams. `None` for the super-admin, who gets the instructors link.
+    instructor: Option<InstructorBanner>,
+    /// Participants removed within the grace period, newest first.
+    removed: Vec<RemovedParticipant>,
     /// Default sort column / direction / filter baked into the initial
     /// render of every team table. Subsequent sorts and filters are
     /// served by `admin_team_members` (the htmx fragment endpoint),
@@ -383,6 +757,43 @@ struct AdminTemplate {
     sort: String,
     dir: String,
     filter: String,
+    /// How the last reload of `examples/` went; see [`Catalog::watch`].
+    content: CatalogStatus,
+    /// Chapters in the catalog being served.
+    content_chapters: usize,
+    /// Chapters with `unlock = "release"`, in catalog order.
+    releases: Vec<ChapterRelease>,
+    /// Every quiz question in the course, in catalog order.
+    quiz_questions: Vec<QuizQuestionStats>,
+}
+
+/// A row of the admin page's "Chapter releases" table.
+struct ChapterRelease {
+    /// `file_stem`, the key in `chapter_releases`.
+    chapter: String,
+    number: u8,
+    title: String,
+    released: bool,
+}
+
+/// A row of the admin page's "Quiz questions" table: how often the
+/// participants in scope got one question right.
+struct QuizQuestionStats {
+    chapter_title: String,
+    /// 1-based, as the quiz numbers it.
+    number: usize,
+    /// The prompt's first line, cut short.
+    prompt: String,
+    answers: i64,
+    /// Share of `answers` that were right, in whole percent; `None`
+    /// until someone answers.
+    correct_percent: Option<i64>,
+}
+
+/// Who the admin dashboard is scoped to, for its header.
+struct InstructorBanner {
+    name: String,
+    teams: Vec<String>,
 }
 
 /// Fragment template for one team's members table. Rendered both for
@@ -392,8 +803,11 @@ struct AdminTemplate {
 #[derive(Template)]
 #[template(path = "partials/team_members.html")]
 struct TeamMembersTemplate {
+    /// [`Course::base`] of the course the page belongs to, prefixed to
+    /// its links.
+    base: String,
     team: ParticipantTeam,
-    admin_token: String,
+    csrf_token: String,
     sort: String,
     dir: String,
     filter: String,
@@ -415,24 +829,59 @@ struct ExerciseProgress {
     /// Display number, 1-based (chapter 0 on disk -> `1.` in the TOC).
     number: u8,
     name: String,
+    /// Has a submission or, on a quiz, an answer.
+    attempted: bool,
     completed: bool,
     perfected: bool,
     title: String,
     description: String,
     submissions: Vec<ExerciseSubmission>,
     is_quiz: bool,
-    /// Notes-only chapters (appendix material) have no code steps and
-    /// can't be "completed". The dashboard skips them when choosing
-    /// the next chapter to resume.
+    /// See [`Exercise::is_completable`]: notes-only chapters (appendix
+    /// material) and practice quizzes can't be "completed". They stay
+    /// out of progress, and the dashboard skips them when choosing the
+    /// next chapter to resume.
     has_exercises: bool,
     /// `true` for optional bonus chapters (hidden from nav and progress).
     is_bonus: bool,
+    /// See [`ProgressDot::locked`]. Filled in by [`apply_locks`].
+    locked: Option<String>,
+    /// See [`Exercise::difficulty`].
+    difficulty: Option<Difficulty>,
+    /// See [`Exercise::tags`].
+    tags: Vec<String>,
+}
+
+impl ExerciseProgress {
+    /// The chapter's steps with at least one submission, sorted.
+    fn submitted_steps(&self) -> Vec<String> {
+        self.steps_where(|_| true)
+    }
+
+    /// The chapter's steps with a submission that passed its tests.
+    fn passed_steps(&self) -> Vec<String> {
+        self.steps_where(|s| s.tests_passed)
+    }
+
+    fn steps_where(&self, keep: impl Fn(&ExerciseSubmission) -> bool) -> Vec<String> {
+        let mut steps: Vec<String> = self
+            .submissions
+            .iter()
+            .filter(|s| keep(s))
+            .map(|s| s.exercise_name.clone())
+            .collect();
+        steps.sort_unstable();
+        steps.dedup();
+        steps
+    }
 }
 
 /// Individual submission for an exercise
 #[derive(Serialize, Clone)]
 struct ExerciseSubmission {
     id: String,
+    /// `<chapter>` or `<chapter>/<step_key>`; see `CodeStep::key`.
+    exercise_name: String,
     source_code: String,
     tests_passed: bool,
     clippy_passed: bool,
@@ -485,8 +934,13 @@ impl ParticipantTeam {
 }
 
 /// Submission summary for admin view
+// reason: data-transfer struct for templates / JSON
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Serialize, Clone)]
 struct SubmissionSummary {
+    participant_id: String,
+    /// Slug of the course the submission belongs to.
+    course: String,
     participant_name: String,
     exercise_name: String,
     /// Human-readable version of `exercise_name`. The DB value is
@@ -500,8 +954,75 @@ struct SubmissionSummary {
     perfected: bool,
     submitted_at: chrono::DateTime<chrono::Utc>,
     source_code: String,
+    /// The server is still re-running this submission in the background
+    /// (`submissions.verification = 'pending'`).
+    verification_pending: bool,
+    /// The server re-ran the submission and rejected at least one check
+    /// the client claimed had passed.
+    claim_rejected: bool,
+}
+
+/// A row carrying `participant_name` plus the `submissions` columns
+/// listed in [`SUBMISSION_SUMMARY_COLUMNS`].
+#[derive(sqlx::FromRow)]
+struct SubmissionSummaryRow {
+    participant_id: String,
+    course: String,
+    participant_name: String,
+    exercise_name: String,
+    tests_passed: bool,
+    fmt_passed: bool,
+    clippy_passed: bool,
+    claimed_tests_passed: Option<bool>,
+    claimed_fmt_passed: Option<bool>,
+    claimed_clippy_passed: Option<bool>,
+    verification: String,
+    submitted_at: chrono::DateTime<chrono::Utc>,
+    source_code: String,
+}
+
+impl From<SubmissionSummaryRow> for SubmissionSummary {
+    fn from(row: SubmissionSummaryRow) -> Self {
+        let claimed = |claim: Option<bool>| claim.unwrap_or(false);
+        let claim_rejected = row.verification == "verified"
+            && ((claimed(row.claimed_tests_passed) && !row.tests_passed)
+                || (claimed(row.claimed_fmt_passed) && !row.fmt_passed)
+                || (claimed(row.claimed_clippy_passed) && !row.clippy_passed));
+        let exercise_label = prettify_exercise_name(&row.exercise_name);
+        Self {
+            participant_id: row.participant_id,
+            course: row.course,
+            participant_name: row.participant_name,
+            exercise_name: row.exercise_name,
+            exercise_label,
+            tests_passed: row.tests_passed,
+            perfected: row.fmt_passed && row.clippy_passed,
+            submitted_at: row.submitted_at,
+            source_code: row.source_code,
+            verification_pending: row.verification == "pending",
+            claim_rejected,
+        }
+    }
 }
 
+/// `submissions` columns (aliased `s`) that [`SubmissionSummaryRow`]
+/// reads, for splicing into the admin and team queries.
+const SUBMISSION_SUMMARY_COLUMNS: &str = "s.participant_id, s.course, s.exercise_name, s.tests_passed, \
+    s.fmt_passed, s.clippy_passed, s.claimed_tests_passed, s.claimed_fmt_passed, \
+    s.claimed_clippy_passed, s.verification, s.submitted_at, s.source_code";
+
+/// Condition on `participants` (aliased `p`) that keeps an admin query
+/// to the signed-in instructor's teams. Bind [`Admin::instructor_id`]
+/// as the first parameter; `NULL` (the super-admin) matches everyone.
+const ADMIN_SCOPE_FILTER: &str = "($1 IS NULL OR p.team_token IN \
+    (SELECT team_token FROM instructor_teams WHERE instructor_id = $1))";
+
+/// Condition on `participants` (aliased `p`) that keeps an admin query
+/// to one course's participants. Bind the course slug as the second
+/// parameter.
+const COURSE_ENROLLED_FILTER: &str =
+    "p.id IN (SELECT participant_id FROM course_enrollments WHERE course = $2)";
+
 /// Strip the `NN_` ordering prefix and turn underscores into spaces.
 /// `01_strings_and_chars` -> `Strings and chars`,
 /// `4_shout` -> `Shout`.
@@ -531,15 +1052,19 @@ fn prettify_exercise_name(name: &str) -> String {
 ///
 /// Two viewer modes share this template:
 ///
-/// - **Admin** (`is_admin = true`): `admin_token` is `Some` so links
-///   back to `/admin` carry the token, member rows show ULIDs and a
-///   "View dashboard" link, and the "Unassigned" bucket is reachable.
+/// - **Admin** (`is_admin = true`): `csrf_token` is `Some` so the
+///   "move to team" and "remove" actions can post, member rows show
+///   ULIDs and a "View dashboard" link, and the "Unassigned" bucket is
+///   reachable.
 /// - **Participant** (`is_admin = false`): no admin chrome, the
 ///   member matching `viewer_ulid` is highlighted as `is_self`, and
 ///   `back_href` returns the user to their own dashboard.
 #[derive(Template)]
 #[template(path = "team.html")]
 struct TeamPageTemplate {
+    /// [`Course::base`] of the course the page belongs to, prefixed to
+    /// its links.
+    base: String,
     /// Display label shown in the page heading. The team slug for
     /// a real team, or the literal string "Unassigned" for the
     /// no-team bucket.
@@ -552,12 +1077,9 @@ struct TeamPageTemplate {
     /// admin-only chrome (ULIDs, dashboard links, the back-to-admin
     /// link) is rendered.
     is_admin: bool,
-    /// Admin token to thread back into `/admin` and `/dashboard/{ulid}`
-    /// links so the operator stays authenticated as they click
-    /// around. `None` in participant mode. Threaded into the inline
-    /// "move to team" form action so the form posts back with the
-    /// admin token still attached.
-    admin_token: Option<String>,
+    /// CSRF token of the admin session, embedded in the inline "move
+    /// to team" form and the remove button. `None` in participant mode.
+    csrf_token: Option<String>,
     /// ULID of the participant viewing the page, when this is the
     /// participant view. Used to deep-link the topbar Settings icon
     /// straight at `/settings/{ulid}`.
@@ -601,6 +1123,72 @@ struct TeamMemberView {
 /// into individual dashboards for the full history.
 const TEAM_SUBMISSIONS_LIMIT: i64 = 60;
 
+/// Every attempt a participant made at one code step
+/// (`/dashboard/{ulid}/history/{exercise_name}`), with diffs.
+///
+/// Reached from the step's "History" link on the exercise page, and by
+/// admins and instructors from the submission cards on `/admin` and the
+/// team pages: [`participant_access`] lets their session through.
+#[derive(Template)]
+#[template(path = "history.html")]
+struct HistoryTemplate {
+    /// [`Course::base`] of the course the page belongs to, prefixed to
+    /// its links.
+    base: String,
+    ulid: String,
+    participant_name: String,
+    /// Full `submissions.exercise_name` of the step.
+    exercise_name: String,
+    chapter_title: String,
+    /// Display form of `exercise_name`, as on the submission cards.
+    step_label: String,
+    /// The step's chapter under the participant's ULID.
+    exercise_href: String,
+    /// Newest first; `number` counts up from the oldest attempt.
+    attempts: Vec<HistoryAttempt>,
+    /// Ids of the attempts picked in the compare form.
+    from_id: String,
+    to_id: String,
+    /// Diff between the two picked attempts; `None` with fewer than two.
+    comparison: Option<AttemptComparison>,
+    /// Diff from the latest attempt to `CodeStep::solution_code`; `None`
+    /// without attempts or without a reference solution.
+    solution_diff: Option<Vec<DiffLine>>,
+}
+
+/// One row of the history table.
+// reason: data-transfer struct for templates
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone)]
+struct HistoryAttempt {
+    id: String,
+    number: usize,
+    submitted_at: chrono::DateTime<chrono::Utc>,
+    tests_passed: bool,
+    fmt_passed: bool,
+    clippy_passed: bool,
+    verification_pending: bool,
+    source_code: String,
+}
+
+/// Unified diff between two attempts, labelled by attempt number.
+struct AttemptComparison {
+    from_number: usize,
+    to_number: usize,
+    lines: Vec<DiffLine>,
+}
+
+/// One line of a unified diff. `kind` doubles as the CSS modifier:
+/// `hunk` for the `@@` header, then `equal`, `insert` or `delete`.
+/// Line numbers are 1-based and `None` on the side a line is missing from.
+#[derive(Debug, PartialEq, Eq)]
+struct DiffLine {
+    kind: &'static str,
+    old_line: Option<usize>,
+    new_line: Option<usize>,
+    text: String,
+}
+
 /// Settings page (`/settings`, `/settings/{ulid}`). Renders
 /// editor/UI preferences (Vim mode, font size, draft data) plus, when
 /// a participant ULID is supplied, a snapshot of their team: the
@@ -631,24 +1219,160 @@ struct SettingsTemplate {
     /// Distinct exercise names that show up in the feed; powers the
     /// per-exercise filter dropdown.
     exercises: Vec<String>,
+    /// Sign-in settings and API tokens. `None` for anonymous visitors.
+    account: Option<AccountView>,
+}
+
+/// The "Account" section of `/settings/{ulid}`.
+// reason: data-transfer struct for templates
+#[allow(clippy::struct_excessive_bools)]
+struct AccountView {
+    /// `/settings/{ulid}`, the prefix of every account form action.
+    form_base: String,
+    /// CSRF token for every account form (see
+    /// [`SessionKey::participant_csrf_token`]).
+    csrf_token: String,
+    /// The login link alone no longer opens the account.
+    auth_required: bool,
+    has_passphrase: bool,
+    /// This browser holds a session cookie for the account.
+    signed_in: bool,
+    api_tokens: Vec<ApiTokenView>,
+    /// A token created by this very request. Shown once, never again.
+    new_api_token: Option<String>,
+    /// Token for the `cargo course init` snippet: the fresh API token,
+    /// else the ULID while it still works as a credential.
+    cli_token: Option<String>,
+    notice: Option<&'static str>,
+    error: Option<&'static str>,
+}
+
+/// One row of the API token list on the settings page.
+#[derive(sqlx::FromRow)]
+struct ApiTokenView {
+    id: String,
+    label: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Query parameters on `/admin/login`.
+///
+/// `next` is the admin page to return to after signing in (checked by
+/// [`safe_admin_next`]). `reason` picks a notice: `legacy-token` when an
+/// old `?token=` link was turned away, `signed-out` after logout.
+#[derive(Deserialize, Default)]
+struct AdminLoginQuery {
+    #[serde(default)]
+    next: Option<String>,
+    #[serde(default)]
+    reason: Option<String>,
 }
 
-/// Query parameters for admin access
+/// Form data for `POST /admin/login`.
 #[derive(Deserialize)]
-struct AdminQuery {
+struct AdminLoginForm {
     token: String,
+    #[serde(default)]
+    next: Option<String>,
+}
+
+/// Query parameters on `/login`.
+///
+/// `next` is the participant page to return to (checked by
+/// [`safe_participant_next`]). `reason=signed-out` shows a notice.
+#[derive(Deserialize, Default)]
+struct LoginQuery {
+    #[serde(default)]
+    next: Option<String>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Form data for `POST /login/passphrase`. `participant` is the ULID or
+/// the whole login link.
+#[derive(Deserialize)]
+struct PassphraseLoginForm {
+    participant: String,
+    passphrase: String,
+    #[serde(default)]
+    next: Option<String>,
+}
+
+/// Form data for `POST /login/code`.
+#[derive(Deserialize)]
+struct CodeLoginForm {
+    code: String,
+    #[serde(default)]
+    next: Option<String>,
+}
+
+/// Optional query parameters on `/settings/{ulid}`. `notice` is a short
+/// code set by the account forms after they redirect back.
+#[derive(Deserialize, Default)]
+struct SettingsQuery {
+    #[serde(default)]
+    notice: Option<String>,
+}
+
+/// Form data for `POST /settings/{ulid}/passphrase`.
+#[derive(Deserialize)]
+struct PassphraseForm {
+    #[serde(default)]
+    csrf: String,
+    passphrase: String,
+    confirm: String,
+}
+
+/// Form data for `POST /settings/{ulid}/api-tokens`.
+#[derive(Deserialize)]
+struct ApiTokenForm {
+    #[serde(default)]
+    csrf: String,
+    #[serde(default)]
+    label: String,
+}
+
+/// Form data for `POST /settings/{ulid}/delete`. `confirm` must be the
+/// word `delete`, typed out.
+#[derive(Deserialize)]
+struct DeleteAccountForm {
+    #[serde(default)]
+    csrf: String,
+    #[serde(default)]
+    confirm: String,
+}
+
+/// Form data for admin and account forms that carry nothing but the
+/// CSRF token (`POST /admin/logout`, `POST /logout`, ...), and for
+/// admin forms, the page to return to.
+#[derive(Deserialize)]
+struct CsrfForm {
+    #[serde(default)]
+    csrf: String,
+    /// The admin page the form was on (`/admin` or a course's), checked
+    /// with [`safe_admin_next`]. Only the admin forms send it.
+    #[serde(default)]
+    next: Option<String>,
 }
 
-/// Optional query parameters on the anonymous dashboard at `/`.
+/// Optional query parameters on the dashboards.
+///
+/// `reason` carries a short machine-readable code that the anonymous
+/// dashboard turns into a toast: `unknown-token` (set when
+/// `/dashboard/{ulid}` redirects here because the ULID has no matching
+/// participant) or `account-deleted` (after a participant deleted their
+/// account). Anything else renders as no toast.
 ///
-/// `reason` carries a short machine-readable code that the template
-/// turns into a toast. Only `unknown-token` is recognized today (set
-/// when `/dashboard/{ulid}` redirects here because the ULID has no
-/// matching participant). Anything else renders as no toast.
+/// `tag` narrows the chapter list to the chapters carrying that tag; a
+/// tag no chapter uses is ignored.
 #[derive(Deserialize, Default)]
 struct DashboardQuery {
     #[serde(default)]
     reason: Option<String>,
+    #[serde(default)]
+    tag: Option<String>,
 }
 
 /// Form data for web registration.
@@ -708,15 +1432,15 @@ fn bucket_participants_by_team(participants: Vec<ParticipantSummary>) -> Vec<Par
 }
 
 /// Number of *completable* chapters in the catalog: the denominator
-/// every participant's progress is measured against. Quizzes and
-/// notes-only chapters have no code steps and never count, matching
-/// `participant_dashboard` / `render_exercise_page`.
-fn completable_total(state: &AppState) -> i64 {
+/// every participant's progress is measured against. Notes-only
+/// chapters and practice quizzes never count (see
+/// [`Exercise::is_completable`]), matching `participant_dashboard` /
+/// `render_exercise_page`.
+fn completable_total(catalog: &[Exercise]) -> i64 {
     i64::try_from(
-        state
-            .exercises
+        catalog
             .iter()
-            .filter(|e| !e.is_quiz() && !e.is_bonus() && !e.code_steps().is_empty())
+            .filter(|e| !e.is_bonus() && e.is_completable())
             .count(),
     )
     .unwrap_or(i64::MAX)
@@ -728,18 +1452,20 @@ fn completable_total(state: &AppState) -> i64 {
 /// to `0` rather than taking the admin page down.
 async fn build_participant_summary(
     state: &AppState,
+    course: &Course,
+    catalog: &[Exercise],
     id: String,
     name: String,
     team_token: Option<TeamToken>,
     last_activity: Option<chrono::DateTime<chrono::Utc>>,
-    total_exercises: i64,
 ) -> ParticipantSummary {
+    let total_exercises = completable_total(catalog);
     let completed_count =
-        match get_exercise_progress(&state.pool, Some(&id), &state.exercises).await {
+        match get_exercise_progress(&state.db, Some(&id), &course.slug, catalog).await {
             Ok(exercises) => i64::try_from(
                 exercises
                     .iter()
-                    .filter(|e| !e.is_quiz && e.has_exercises && !e.is_bonus && e.completed)
+                    .filter(|e| e.has_exercises && !e.is_bonus && e.completed)
                     .count(),
             )
             .unwrap_or(i64::MAX),
@@ -760,11 +1486,13 @@ async fn build_participant_summary(
 }
 
 /// Load the member summaries for a single team bucket. `team` is the
-/// validated [`TeamToken`], or `None` for the Unassigned bucket. Used
-/// by the `admin_team_members` fragment endpoint so a sort/filter only
-/// recomputes progress for the team being viewed instead of everyone.
+/// validated [`TeamToken`], or `None` for the Unassigned bucket. Only
+/// members enrolled in `course` are listed, with their progress in it.
+/// Used by the `admin_team_members` fragment endpoint so a sort/filter
+/// only recomputes progress for the team being viewed instead of everyone.
 async fn load_team_member_summaries(
     state: &AppState,
+    course: &Course,
     team: Option<&TeamToken>,
 ) -> Result<Vec<ParticipantSummary>, sqlx::Error> {
     let base = r"
@@ -774,40 +1502,71 @@ async fn load_team_member_summaries(
             p.team_token,
             MAX(s.submitted_at) as last_activity
         FROM participants p
-        LEFT JOIN submissions s ON p.id = s.participant_id AND s.tests_passed = 1
+        JOIN course_enrollments e ON e.participant_id = p.id AND e.course = $1
+        LEFT JOIN submissions s
+            ON p.id = s.participant_id AND s.course = $1 AND s.tests_passed = TRUE
     ";
-    let rows = if let Some(token) = team {
-        sqlx::query(&format!(
-            "{base} WHERE p.team_token = ? GROUP BY p.id, p.name, p.team_token"
+    let rows: Vec<ParticipantActivityRow> = if let Some(token) = team {
+        with_pool!(&state.db, |pool| sqlx::query_as(&format!(
+            "{base} WHERE p.team_token = $2 AND p.deleted_at IS NULL
+             GROUP BY p.id, p.name, p.team_token"
         ))
+        .bind(&course.slug)
         .bind(token.as_str())
-        .fetch_all(&state.pool)
-        .await?
+        .fetch_all(pool)
+        .await)?
     } else {
-        sqlx::query(&format!(
-            "{base} WHERE p.team_token IS NULL GROUP BY p.id, p.name, p.team_token"
+        with_pool!(&state.db, |pool| sqlx::query_as(&format!(
+            "{base} WHERE p.team_token IS NULL AND p.deleted_at IS NULL
+             GROUP BY p.id, p.name, p.team_token"
         ))
-        .fetch_all(&state.pool)
-        .await?
+        .bind(&course.slug)
+        .fetch_all(pool)
+        .await)?
     };
 
-    let total = completable_total(state);
+    let catalog = course.catalog.current();
     let mut members = Vec::with_capacity(rows.len());
     for row in rows {
-        let raw_token: Option<String> = row.get("team_token");
-        let team_token = raw_token
+        let team_token = row
+            .team_token
             .as_deref()
             .and_then(|s| TeamToken::try_from(s).ok());
-        let id: String = row.get("id");
-        let name: String = row.get("name");
-        let last_activity = row.get("last_activity");
         members.push(
-            build_participant_summary(state, id, name, team_token, last_activity, total).await,
+            build_participant_summary(
+                state,
+                course,
+                &catalog,
+                row.id,
+                row.name,
+                team_token,
+                row.last_activity,
+            )
+            .await,
         );
     }
     Ok(members)
 }
 
+/// One member of a team page roster.
+#[derive(sqlx::FromRow)]
+struct TeamRosterRow {
+    id: String,
+    name: String,
+    completed_count: i64,
+    last_activity: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// A participant with the time of their latest passing submission, as
+/// selected by the admin dashboard and team-members queries.
+#[derive(sqlx::FromRow)]
+struct ParticipantActivityRow {
+    id: String,
+    name: String,
+    team_token: Option<String>,
+    last_activity: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 /// Filter a team's members by a case-insensitive substring of the name,
 /// then sort by the requested column and direction in place. `sort` is
 /// one of `name` | `progress` | `activity` (anything else falls back to
@@ -843,10 +1602,63 @@ fn apply_member_filter_sort(
     }
 }
 
+/// The course server. Without a subcommand it serves the course; the
+/// subcommands maintain the database named by `DATABASE_URL`.
+#[derive(Parser)]
+#[command(name = "server", about = "corrode course server")]
+struct ServerCli {
+    #[command(subcommand)]
+    command: Option<ServerCommand>,
+}
+
+#[derive(Subcommand)]
+enum ServerCommand {
+    /// Serve the course (the default)
+    Serve,
+    /// Write a consistent backup of the database; safe while serving
+    Backup {
+        /// Where to write it (default: `backups/course-<timestamp>.db`,
+        /// or `.dump` for PostgreSQL)
+        output: Option<std::path::PathBuf>,
+    },
+    /// Replace the database with a backup. Stop the server first; the
+    /// current contents are backed up next to the one being restored
+    Restore {
+        /// A file written by `server backup`
+        backup: std::path::PathBuf,
+    },
+    /// Delete old analytics, surplus submissions and inactive
+    /// participants
+    Prune(PruneArgs),
+}
+
+/// `server prune` options; each one turns on one kind of pruning.
+#[derive(clap::Args)]
+#[command(group(clap::ArgGroup::new("policy").required(true).multiple(true)))]
+struct PruneArgs {
+    /// Delete course events older than this many days
+    #[arg(long, value_name = "DAYS", group = "policy",
+          value_parser = clap::value_parser!(i64).range(1..))]
+    events_older_than: Option<i64>,
+    /// Keep at most this many submissions per step for each participant.
+    /// The newest passing and newest perfected ones are always kept
+    #[arg(long, value_name = "N", group = "policy",
+          value_parser = clap::value_parser!(i64).range(1..))]
+    max_submissions_per_step: Option<i64>,
+    /// Delete participants with no activity for this many days, with
+    /// everything they submitted
+    #[arg(long, value_name = "DAYS", group = "policy",
+          value_parser = clap::value_parser!(i64).range(1..))]
+    inactive_for: Option<i64>,
+    /// Report what would be deleted without deleting it
+    #[arg(long)]
+    dry_run: bool,
+}
+
 #[tokio::main]
-// reason: top-level server bootstrap; splitting would only add indirection
-#[allow(clippy::too_many_lines)]
 async fn main() -> Result<()> {
+    let cli = ServerCli::parse();
+
     // Initialize logging with info level by default
     if env::var("RUST_LOG").is_err() {
         unsafe {
@@ -858,10 +1670,6 @@ async fn main() -> Result<()> {
     // Load environment variables
     dotenv().ok();
 
-    info!("Starting corrode course server...");
-
-    let admin_token =
-        env::var("CORRODE_ADMIN_TOKEN").expect("CORRODE_ADMIN_TOKEN must be set in .env file");
     let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
         // Create the database in the current working directory
         let current_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
@@ -869,94 +1677,231 @@ async fn main() -> Result<()> {
         info!("📁 Database will be created at: {}", db_path.display());
         format!("sqlite:{}", db_path.display())
     });
+    let migrations = std::path::Path::new("./migrations");
+
+    match cli.command.unwrap_or(ServerCommand::Serve) {
+        ServerCommand::Serve => serve(database_url).await,
+        ServerCommand::Backup { output } => {
+            let output = output.unwrap_or_else(|| {
+                let extension = match cargo_course::db::Backend::from_url(&database_url) {
+                    Some(cargo_course::db::Backend::Postgres) => "dump",
+                    _ => "db",
+                };
+                let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+                std::path::PathBuf::from(format!("backups/course-{stamp}.{extension}"))
+            });
+            cargo_course::db::backup(&database_url, &output).await?;
+            println!("✅ Backed up {database_url} to {}", output.display());
+            Ok(())
+        }
+        ServerCommand::Restore { backup } => {
+            let saved = cargo_course::db::restore(&database_url, &backup, migrations).await?;
+            println!("✅ Restored {database_url} from {}", backup.display());
+            if let Some(saved) = saved {
+                println!("   The previous contents are in {}", saved.display());
+            }
+            Ok(())
+        }
+        ServerCommand::Prune(args) => {
+            let db = Db::connect(&database_url, 1).await?;
+            db.migrate(migrations).await?;
+            let policy = RetentionPolicy {
+                events_older_than_days: args.events_older_than,
+                max_submissions_per_step: args.max_submissions_per_step,
+                inactive_for_days: args.inactive_for,
+            };
+            let report = apply_retention(&db, &policy, args.dry_run).await;
+            db.close().await;
+            let report = report?;
+            println!(
+                "{} {} participant(s), {} submission(s), {} course event(s)",
+                if args.dry_run {
+                    "Would delete"
+                } else {
+                    "Deleted"
+                },
+                report.participants,
+                report.submissions,
+                report.course_events
+            );
+            Ok(())
+        }
+    }
+}
+
+// reason: top-level server bootstrap; splitting would only add indirection
+#[allow(clippy::too_many_lines)]
+async fn serve(database_url: String) -> Result<()> {
+    info!("Starting corrode course server...");
+
+    let admin_token =
+        env::var("CORRODE_ADMIN_TOKEN").expect("CORRODE_ADMIN_TOKEN must be set in .env file");
     let port: u16 = env::var("PORT")
         .unwrap_or_else(|_| "3000".to_string())
         .parse()
         .expect("PORT must be a valid number");
 
     // Set up database
-    info!("Checking if database exists: {database_url}");
-    if Sqlite::database_exists(&database_url)
-        .await
-        .unwrap_or(false)
-    {
-        info!("Database already exists");
-    } else {
-        info!("Creating database {database_url}");
-        match Sqlite::create_database(&database_url).await {
-            Ok(()) => {
-                println!("✅ Database created successfully");
-                info!("Database created successfully");
-            }
-            Err(error) => {
-                error!("Error creating database: {error}");
-                panic!("❌ Error creating database: {error}");
-            }
-        }
-    }
-
-    info!("Connecting to database...");
-    let pool = SqlitePoolOptions::new()
-        .max_connections(20)
-        .connect(&database_url)
-        .await
-        .map_err(|e| {
-            eprintln!("❌ Failed to connect to database: {e}");
-            eprintln!("   Database URL: {database_url}");
-            error!("Failed to connect to database: {e}");
-            error!("Database URL: {database_url}");
-            e
-        })?;
+    let backend =
+        cargo_course::db::Backend::from_url(&database_url).map_or("unknown", |b| b.name());
+    info!("Connecting to {backend} database {database_url}");
+    let db = Db::connect(&database_url, 20).await.map_err(|e| {
+        eprintln!("❌ Failed to connect to database: {e}");
+        eprintln!("   Database URL: {database_url}");
+        error!("Failed to connect to database: {e}");
+        error!("Database URL: {database_url}");
+        e
+    })?;
 
     info!("Database connection established");
 
     // Run migrations
-    let migrations = std::path::Path::new("./migrations");
-    let migrator = sqlx::migrate::Migrator::new(migrations).await?;
-    migrator.run(&pool).await?;
+    db.migrate(std::path::Path::new("./migrations")).await?;
     info!("Database migrations completed");
 
-    // Scan exercises once at startup.
-    let exercises = exercises::load(std::path::Path::new("examples")).map_err(|e| {
-        error!("Failed to scan exercises: {e:#}");
+    tokio::spawn(purge_removed_participants_hourly(db.clone()));
+
+    // Scan every course at startup; `Catalog::watch` picks up later edits.
+    let courses = Arc::new(Courses::from_env(COURSE_VERSION).map_err(|e| {
+        error!("Failed to load the courses: {e:#}");
         e
-    })?;
-    info!("Loaded {} exercises", exercises.len());
+    })?);
+    let poll_interval = catalog::poll_interval_from_env();
+    for course in courses.iter() {
+        let chapters = course.catalog.current();
+        info!(
+            "Loaded {} exercises for course `{}` at {}/",
+            chapters.len(),
+            course.slug,
+            course.base()
+        );
+        sync_chapter_tags(&db, &course.slug, &chapters).await;
+        if let Some(interval) = poll_interval {
+            let db = db.clone();
+            let slug = course.slug.clone();
+            tokio::spawn(
+                Arc::clone(&course.catalog).watch(interval, move |chapters| {
+                    let db = db.clone();
+                    let slug = slug.clone();
+                    async move { sync_chapter_tags(&db, &slug, &chapters).await }
+                }),
+            );
+        }
+    }
+    if poll_interval.is_none() {
+        info!("Course content reload is off (CORRODE_CONTENT_POLL_SECONDS=0)");
+    }
+
+    let explain_dir =
+        env::var("CORRODE_ERROR_CODES_DIR").unwrap_or_else(|_| explain::DEFAULT_DIR.to_string());
+    let explanations = Explanations::load(std::path::Path::new(&explain_dir))?;
+    if explanations.is_empty() {
+        warn!(
+            "No error code explanations in {explain_dir}/; run scripts/dump-error-codes.sh to enable /explain"
+        );
+    } else {
+        info!("Loaded {} error code explanations", explanations.len());
+    }
+
+    let run_kind = backend_kind(BackendKind::RUN_ENV_VAR, "rustc")?;
+    let run_requires_participant = matches!(
+        env::var("CORRODE_RUN_REQUIRE_PARTICIPANT").as_deref(),
+        Ok("true" | "1" | "on")
+    );
+    if run_kind == BackendKind::Local && !run_requires_participant && Sandbox::from_env()?.is_none()
+    {
+        anyhow::bail!(
+            "{}=local would run anyone's code as the server's user: set {} to sandbox it, \
+             or CORRODE_RUN_REQUIRE_PARTICIPANT=true to limit /api/run to known participants",
+            BackendKind::RUN_ENV_VAR,
+            Sandbox::ENV_VAR
+        );
+    }
+    let runner = run_kind.run_backend()?;
+    info!("Running learner code on the {} backend", runner.name());
+    if run_requires_participant {
+        info!("/api/run only runs code for known participants");
+    }
+    let formatter = backend_kind(BackendKind::FORMAT_ENV_VAR, "rustfmt")?.format_backend()?;
+    info!(
+        "Formatting learner code on the {} backend",
+        formatter.name()
+    );
+    let linter = backend_kind(BackendKind::CLIPPY_ENV_VAR, "clippy-driver")?.lint_backend()?;
+    info!("Linting learner code on the {} backend", linter.name());
+
+    let verify_locally = match env::var("CORRODE_VERIFY_SUBMISSIONS").as_deref() {
+        Ok("local") => true,
+        Ok("" | "off") | Err(_) => false,
+        Ok(other) => anyhow::bail!(
+            "CORRODE_VERIFY_SUBMISSIONS: unknown mode `{other}` (expected `off` or `local`)"
+        ),
+    };
+    let verifier = if verify_locally {
+        let backend = LocalBackend::from_env()?;
+        for tool in ["rustc", "rustfmt", "clippy-driver"] {
+            backend.require_tool(tool, "CORRODE_VERIFY_SUBMISSIONS=local")?;
+        }
+        Some(spawn_verifier(db.clone(), Arc::clone(&courses), backend).await?)
+    } else {
+        None
+    };
+
+    let secure_cookies = !matches!(
+        env::var("CORRODE_SECURE_COOKIES").as_deref(),
+        Ok("false" | "0" | "off")
+    );
+    if !secure_cookies {
+        warn!("CORRODE_SECURE_COOKIES is off: admin session cookies will be sent over plain HTTP");
+    }
+    let trust_forwarded_for = matches!(
+        env::var("CORRODE_TRUST_FORWARDED_FOR").as_deref(),
+        Ok("true" | "1" | "on")
+    );
 
     let app_state = AppState {
-        pool,
-        admin_token: admin_token.clone(),
-        exercises,
+        db,
+        sessions: SessionKey::from_admin_token(&admin_token),
+        admin_token,
+        secure_cookies,
+        login_throttle: Arc::default(),
+        participant_throttle: Arc::default(),
+        argon2_permits: Arc::new(tokio::sync::Semaphore::new(
+            std::thread::available_parallelism().map_or(1, |cores| cores.get().div_ceil(2)),
+        )),
+        trust_forwarded_for,
+        courses,
+        explanations: Arc::new(explanations),
+        runner,
+        run_requires_participant,
+        formatter,
+        linter,
+        verifier,
     };
 
     // Build API routes
     let api_routes = Router::new()
         .route("/register", post(api_register))
         .route("/submit", post(api_submit))
+        .route("/status", get(api_status))
         .route("/status/{ulid}", get(api_status))
+        .route("/export", get(api_export))
+        .route("/export/{ulid}", get(api_export))
+        .route("/delete-account", post(api_delete_account))
         .route("/events", post(api_course_event))
+        .route("/quiz-answer", post(api_quiz_answer))
         .route("/run", post(api_run))
         .route("/format", post(api_format))
+        .route("/clippy", post(api_clippy))
         .with_state(app_state.clone());
 
-    // Build main routes
-    let app = Router::new()
-        .route("/", get(anonymous_dashboard))
-        .route("/signup", get(signup_page))
-        .route("/signup/{team_slug}", get(signup_page_with_team))
-        .route("/register", post(web_register))
-        .route("/dashboard/{ulid}", get(participant_dashboard))
-        .route("/exercise/{slug}", get(public_exercise_page))
-        .route("/exercise/{ulid}/{slug}", get(participant_exercise_page))
-        .route("/playground", get(playground_page))
-        .route("/tour", get(tour_page))
-        .route("/tour/{ulid}", get(tour_page_with_ulid))
-        .route("/settings", get(settings_page))
-        .route("/settings/{ulid}", get(participant_settings_page))
-        .route("/cheatsheet", get(cheatsheet_page))
-        .route("/cheatsheet/fragment", get(cheatsheet_fragment))
-        .route("/health", get(health_check))
-        .route("/admin", get(admin_dashboard))
+    // Admin routes authenticate with the session cookie set by
+    // `/admin/login`. Old `?token=` bookmarks are turned away before
+    // they reach a handler (see `reject_legacy_admin_token`). The
+    // per-course admin views live in `course_routes`.
+    let admin_routes = Router::new()
+        .route("/admin/login", get(admin_login_page).post(admin_login))
+        .route("/admin/logout", post(admin_logout))
         .route(
             "/admin/remove-participant/{ulid}",
             delete(admin_remove_participant),
@@ -965,11 +1910,80 @@ async fn main() -> Result<()> {
             "/admin/participants/{ulid}/team-token",
             post(admin_set_team_token),
         )
-        .route("/admin/team-members", get(admin_team_members))
-        .route("/admin/team/{slug}", get(admin_team_page))
-        .route("/admin/team-unassigned", get(admin_team_unassigned_page))
-        .route("/dashboard/{ulid}/team", get(participant_team_page))
-        .nest("/api", api_routes)
+        .route(
+            "/admin/participants/{ulid}/login-code",
+            post(admin_issue_login_code),
+        )
+        .route(
+            "/admin/participants/{ulid}/restore",
+            post(admin_restore_participant),
+        )
+        .route("/admin/audit", get(admin_audit_page))
+        .route(
+            "/admin/instructors",
+            get(admin_instructors_page).post(admin_create_instructor),
+        )
+        .route(
+            "/admin/instructors/{id}/teams",
+            post(admin_set_instructor_teams),
+        )
+        .route(
+            "/admin/instructors/{id}/delete",
+            post(admin_delete_instructor),
+        )
+        .route_layer(axum::middleware::from_fn(reject_legacy_admin_token));
+
+    // Build main routes: the ones every course shares. Accounts,
+    // settings and the reference pages aren't tied to a course.
+    let mut app = Router::new()
+        .route("/playground", get(playground_page))
+        .route("/settings", get(settings_page))
+        .route("/settings/{ulid}", get(participant_settings_page))
+        .route("/settings/{ulid}/passphrase", post(settings_set_passphrase))
+        .route(
+            "/settings/{ulid}/sign-out-elsewhere",
+            post(settings_sign_out_elsewhere),
+        )
+        .route(
+            "/settings/{ulid}/api-tokens",
+            post(settings_create_api_token),
+        )
+        .route(
+            "/settings/{ulid}/api-tokens/{id}/revoke",
+            post(settings_revoke_api_token),
+        )
+        .route("/settings/{ulid}/export", get(settings_export))
+        .route("/settings/{ulid}/delete", post(settings_delete_account))
+        .route("/login", get(login_page))
+        .route("/login/passphrase", post(login_with_passphrase))
+        .route("/login/code", post(login_with_code))
+        .route("/logout", post(logout))
+        .route("/me", get(my_dashboard))
+        .route("/cheatsheet", get(cheatsheet_page))
+        .route("/cheatsheet/fragment", get(cheatsheet_fragment))
+        .route("/explain/{code}", get(explain_page))
+        .route("/explain/{code}/fragment", get(explain_fragment))
+        .route("/health", get(health_check))
+        .merge(admin_routes)
+        .nest("/api", api_routes);
+    // Every course under `/courses/<slug>`, and the default one at the
+    // root too, where the single course used to live.
+    for course in app_state.courses.iter() {
+        let routes = course_routes().layer(Extension(Arc::clone(course)));
+        if course.is_default() {
+            app = app.merge(routes.clone());
+        }
+        let base = format!("/courses/{}", course.slug);
+        // A nested `/` only matches `/courses/<slug>`; pages link to the
+        // course home as `{base}/`.
+        app = app
+            .route(
+                &format!("{base}/"),
+                get(anonymous_dashboard).layer(Extension(Arc::clone(course))),
+            )
+            .nest(&base, routes);
+    }
+    let app = app
         // Every route above renders per-participant state (progress
         // checkmarks, submitted code) keyed only by the ulid in the
         // URL. Without an explicit policy these HTML pages are freely
@@ -985,13 +1999,55 @@ async fn main() -> Result<()> {
 
     let listener = tokio::net::TcpListener::bind(&format!("0.0.0.0:{port}")).await?;
     info!("🚀 Server listening on 0.0.0.0:{port} (open http://localhost:{port} locally)");
-    info!("📊 Admin dashboard path: /admin?token={admin_token}");
+    info!("📊 Admin dashboard: /admin (sign in with CORRODE_ADMIN_TOKEN)");
     info!("🗃️  Database: {database_url}");
 
-    axum::serve(listener, app).await?;
+    // Peer addresses feed the admin sign-in throttle.
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
 
+/// The pages of one course. Mounted once per course with the course as
+/// an `Extension`, so the handlers below read their chapters, progress
+/// and admin views from it; links they render are prefixed with
+/// [`Course::base`].
+fn course_routes() -> Router<AppState> {
+    let admin_routes = Router::new()
+        .route("/admin", get(admin_dashboard))
+        .route("/admin/team-members", get(admin_team_members))
+        .route("/admin/team/{slug}", get(admin_team_page))
+        .route("/admin/team-unassigned", get(admin_team_unassigned_page))
+        .route(
+            "/admin/chapters/{chapter}/release",
+            post(admin_release_chapter),
+        )
+        .route(
+            "/admin/chapters/{chapter}/withhold",
+            post(admin_withhold_chapter),
+        )
+        .route_layer(axum::middleware::from_fn(reject_legacy_admin_token));
+    Router::new()
+        .route("/", get(anonymous_dashboard))
+        .route("/signup", get(signup_page))
+        .route("/signup/{team_slug}", get(signup_page_with_team))
+        .route("/register", post(web_register))
+        .route("/dashboard/{ulid}", get(participant_dashboard))
+        .route("/exercise/{slug}", get(public_exercise_page))
+        .route("/exercise/{ulid}/{slug}", get(participant_exercise_page))
+        .route("/tour", get(tour_page))
+        .route("/tour/{ulid}", get(tour_page_with_ulid))
+        .route("/dashboard/{ulid}/team", get(participant_team_page))
+        .route(
+            "/dashboard/{ulid}/history/{*exercise_name}",
+            get(participant_history_page),
+        )
+        .merge(admin_routes)
+}
+
 /// Stamp `Cache-Control: no-store` on every dynamic HTML/API response.
 ///
 /// These pages embed per-participant state (progress, submitted code)
@@ -1012,7 +2068,11 @@ async fn no_store(mut response: Response) -> Response {
 /// trivial query, 503 otherwise. Cheap enough to hit every few seconds
 /// from uptime monitors.
 async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
-    match sqlx::query("SELECT 1").execute(&state.pool).await {
+    match with_pool!(&state.db, |pool| sqlx::query("SELECT 1")
+        .execute(pool)
+        .await
+        .map(|done| done.rows_affected()))
+    {
         Ok(_) => (StatusCode::OK, "ok"),
         Err(e) => {
             error!("health check failed: {e}");
@@ -1024,25 +2084,161 @@ async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
 /// Build chapter-list rows for the homepage from per-exercise
 /// progress. Mirrors the dot construction in `render_exercise_page`
 /// so both pages feed the same `partials/chapter_list.html` partial.
-/// `current` is always `false` on the homepage.
-fn dots_from_exercises(exercises: &[ExerciseProgress]) -> Vec<ProgressDot> {
-    std::iter::once(tour_dot())
-        .chain(exercises.iter().map(|e| ProgressDot {
-            slug: e.name.clone(),
-            number: e.number,
-            title: e.title.clone(),
-            attempted: !e.submissions.is_empty(),
-            completed: e.completed,
-            perfected: e.perfected,
-            current: false,
-            is_quiz: e.is_quiz,
-            has_exercises: e.has_exercises,
-            is_bonus: e.is_bonus,
-            href: None,
-        }))
+/// `current` is always `false` on the homepage. With a `tag`, only the
+/// chapters carrying it are listed, and the tour (which has no tags)
+/// is left out.
+fn dots_from_exercises(
+    base: &str,
+    exercises: &[ExerciseProgress],
+    tag: Option<&str>,
+) -> Vec<ProgressDot> {
+    tag.is_none()
+        .then(|| tour_dot(base))
+        .into_iter()
+        .chain(
+            exercises
+                .iter()
+                .filter(|e| tag.is_none_or(|tag| e.tags.iter().any(|t| t == tag)))
+                .map(|e| ProgressDot {
+                    slug: e.name.clone(),
+                    number: e.number,
+                    title: e.title.clone(),
+                    attempted: e.attempted,
+                    completed: e.completed,
+                    perfected: e.perfected,
+                    current: false,
+                    is_quiz: e.is_quiz,
+                    has_exercises: e.has_exercises,
+                    is_bonus: e.is_bonus,
+                    href: None,
+                    locked: e.locked.clone(),
+                    difficulty: e.difficulty,
+                }),
+        )
         .collect()
 }
 
+/// Every tag in `catalog`, and the requested `tag` if a chapter
+/// carries it.
+fn tag_filter(catalog: &[Exercise], tag: Option<String>) -> (Vec<String>, Option<String>) {
+    let tags: Vec<String> = exercises::catalog_tags(catalog)
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let tag = tag.filter(|tag| tags.contains(tag));
+    (tags, tag)
+}
+
+/// Chapters of `catalog` that are locked for a viewer, keyed by
+/// `file_stem`. `completed` and `attempted` say how far the viewer got
+/// with a chapter (nowhere, for anonymous visitors). A chapter the
+/// viewer has already worked on stays open, so withholding a release
+/// or adding a requirement mid-course doesn't lock anyone out of their
+/// own code.
+fn chapter_locks(
+    catalog: &[Exercise],
+    released: &std::collections::HashSet<String>,
+    completed: impl Fn(&str) -> bool,
+    attempted: impl Fn(&str) -> bool,
+) -> std::collections::HashMap<String, Lock> {
+    catalog
+        .iter()
+        .filter(|e| !attempted(&e.file_stem))
+        .filter_map(|e| {
+            e.lock(catalog, &completed, released.contains(&e.file_stem))
+                .map(|lock| (e.file_stem.clone(), lock))
+        })
+        .collect()
+}
+
+/// One line saying what a locked chapter still needs, for the chapter
+/// list's tooltip.
+fn lock_note(lock: &Lock, catalog: &[Exercise]) -> String {
+    let mut parts = Vec::new();
+    if !lock.missing.is_empty() {
+        let titles: Vec<&str> = lock
+            .missing
+            .iter()
+            .map(|stem| {
+                catalog
+                    .iter()
+                    .find(|e| &e.file_stem == stem)
+                    .map_or(stem.as_str(), |e| e.title.as_str())
+            })
+            .collect();
+        parts.push(format!("Complete {} first", titles.join(", ")));
+    }
+    if lock.awaiting_release {
+        parts.push("Opens when your instructor releases it".to_string());
+    }
+    parts.join(". ")
+}
+
+/// Fill in [`ExerciseProgress::locked`] for one viewer's progress
+/// through `catalog` (which `exercises` was built from, in order).
+fn apply_locks(
+    exercises: &mut [ExerciseProgress],
+    catalog: &[Exercise],
+    released: &std::collections::HashSet<String>,
+) {
+    let locks = chapter_locks(
+        catalog,
+        released,
+        |stem| exercises.iter().any(|e| e.name == stem && e.completed),
+        |stem| exercises.iter().any(|e| e.name == stem && e.attempted),
+    );
+    for progress in exercises.iter_mut() {
+        progress.locked = locks
+            .get(&progress.name)
+            .map(|lock| lock_note(lock, catalog));
+    }
+}
+
+/// Rewrite `course`'s rows in `chapter_tags` to match `catalog`, so
+/// analytics reports can group by tag (see `docs/analytics.md`). A
+/// failure is logged and leaves the previous rows in place.
+async fn sync_chapter_tags(db: &Db, course: &str, catalog: &[Exercise]) {
+    let result = with_pool!(db, |pool| async {
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM chapter_tags WHERE course = $1")
+            .bind(course)
+            .execute(&mut *tx)
+            .await?;
+        for exercise in catalog {
+            for tag in exercise.tags() {
+                sqlx::query(
+                    "INSERT INTO chapter_tags (course, chapter, tag) VALUES ($1, $2, $3)
+                     ON CONFLICT DO NOTHING",
+                )
+                .bind(course)
+                .bind(&exercise.file_stem)
+                .bind(tag)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await
+    }
+    .await);
+    if let Err(e) = result {
+        error!("Failed to record the chapter tags of course `{course}`: {e}");
+    }
+}
+
+/// Chapters released in `course`; see [`Unlock::Release`].
+async fn load_releases(
+    db: &Db,
+    course: &str,
+) -> Result<std::collections::HashSet<String>, sqlx::Error> {
+    let chapters: Vec<String> = with_pool!(db, |pool| sqlx::query_scalar(
+        "SELECT chapter FROM chapter_releases WHERE course = $1"
+    )
+    .bind(course)
+    .fetch_all(pool)
+    .await)?;
+    Ok(chapters.into_iter().collect())
+}
+
 /// Number of rows in the first column of the two-column table of
 /// contents (`partials/chapter_list.html`). The list uses
 /// `grid-auto-flow: column` with `--chapter-rows` rows, filling the
@@ -1065,15 +2261,21 @@ fn chapter_rows(dots: &[ProgressDot]) -> usize {
 /// toast (e.g. when a participant landed on a missing-ULID dashboard).
 async fn anonymous_dashboard(
     State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
     Query(query): Query<DashboardQuery>,
 ) -> impl IntoResponse {
-    let Ok(exercises) = get_exercise_progress(&state.pool, None, &state.exercises).await else {
+    let catalog = course.catalog.current();
+    let (Ok(mut exercises), Ok(released)) = (
+        get_exercise_progress(&state.db, None, &course.slug, &catalog).await,
+        load_releases(&state.db, &course.slug).await,
+    ) else {
         return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build catalog").into_response();
     };
+    apply_locks(&mut exercises, &catalog, &released);
 
     let first = exercises
         .iter()
-        .find(|e| !e.is_quiz && e.has_exercises && !e.is_bonus)
+        .find(|e| e.has_exercises && !e.is_bonus && e.locked.is_none())
         .or_else(|| exercises.first());
     let (next_slug, next_label, next_chapter_number, next_chapter_title) = first.map_or_else(
         || {
@@ -1095,11 +2297,17 @@ async fn anonymous_dashboard(
     );
     let progress_total = exercises
         .iter()
-        .filter(|e| !e.is_quiz && e.has_exercises && !e.is_bonus)
+        .filter(|e| e.has_exercises && !e.is_bonus)
         .count();
 
-    let dots = dots_from_exercises(&exercises);
+    let (tags, tag) = tag_filter(&catalog, query.tag);
+    let dots = dots_from_exercises(&course.base(), &exercises, tag.as_deref());
     let template = DashboardTemplate {
+        base: course.base(),
+        key_prefix: course.qualify(""),
+        course_slug: course.slug.clone(),
+        course_title: course.title.clone(),
+        courses: course_links(&state.courses, &course, "", &[]),
         participant_name: None,
         ulid: None,
         chapter_rows: chapter_rows(&dots),
@@ -1112,6 +2320,8 @@ async fn anonymous_dashboard(
         progress_total,
         reason: query.reason,
         team_token: None,
+        tags,
+        tag,
     };
     template.render().map_or_else(
         |_| {
@@ -1126,8 +2336,8 @@ async fn anonymous_dashboard(
 }
 
 /// Public signup form at `/signup` (no team).
-async fn signup_page() -> impl IntoResponse {
-    render_signup(None)
+async fn signup_page(Extension(course): Extension<Arc<Course>>) -> impl IntoResponse {
+    render_signup(&course, None)
 }
 
 /// Workshop signup form at `/signup/{team_slug}`.
@@ -1135,7 +2345,10 @@ async fn signup_page() -> impl IntoResponse {
 /// The slug is captured server-side and surfaced both as a banner and
 /// as a hidden `team_token` input on the form so it round-trips back
 /// to `/register` without the user typing anything.
-async fn signup_page_with_team(AxumPath(team_slug): AxumPath<String>) -> impl IntoResponse {
+async fn signup_page_with_team(
+    Extension(course): Extension<Arc<Course>>,
+    AxumPath(team_slug): AxumPath<String>,
+) -> impl IntoResponse {
     // Defensive trim. Empty slugs degrade to the public form rather
     // than rendering a banner that says "Signing up with **(blank)**".
     let trimmed = team_slug.trim();
@@ -1144,11 +2357,14 @@ async fn signup_page_with_team(AxumPath(team_slug): AxumPath<String>) -> impl In
     } else {
         Some(trimmed.to_string())
     };
-    render_signup(team)
+    render_signup(&course, team)
 }
 
-fn render_signup(team_slug: Option<String>) -> axum::response::Response {
-    let template = SignupTemplate { team_slug };
+fn render_signup(course: &Course, team_slug: Option<String>) -> axum::response::Response {
+    let template = SignupTemplate {
+        base: course.base(),
+        team_slug,
+    };
     template.render().map_or_else(
         |_| {
             (
@@ -1167,8 +2383,8 @@ fn render_signup(team_slug: Option<String>) -> axum::response::Response {
 /// into one editable, runnable code box. Like the playground, edits
 /// live in `localStorage` and "Run" proxies to play.rust-lang.org; the
 /// page adds concept-class hover explanations on top.
-async fn tour_page(State(state): State<AppState>) -> impl IntoResponse {
-    render_tour(&state, None)
+async fn tour_page(Extension(course): Extension<Arc<Course>>) -> impl IntoResponse {
+    render_tour(&course, None)
 }
 
 /// Same tour page, but reached with a participant ULID (e.g. straight
@@ -1177,18 +2393,18 @@ async fn tour_page(State(state): State<AppState>) -> impl IntoResponse {
 /// progress context when they move on to chapter 1.
 async fn tour_page_with_ulid(
     AxumPath(ulid): AxumPath<String>,
-    State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
 ) -> impl IntoResponse {
-    render_tour(&state, Some(ulid))
+    render_tour(&course, Some(ulid))
 }
 
-fn render_tour(state: &AppState, ulid: Option<String>) -> axum::response::Html<String> {
+fn render_tour(course: &Course, ulid: Option<String>) -> axum::response::Html<String> {
     const STARTER: &str = include_str!("../../static/tour_starter.rs");
     // Derive the first real chapter the same way the dashboard does, so
     // the closing CTA keeps pointing at the right place even if chapters
     // are renamed or reordered.
-    let first = state
-        .exercises
+    let catalog = course.catalog.current();
+    let first = catalog
         .iter()
         .find(|e| !e.is_quiz() && !e.is_bonus() && !e.code_steps().is_empty());
     let next_dot = first.map(|e| ProgressDot {
@@ -1203,8 +2419,12 @@ fn render_tour(state: &AppState, ulid: Option<String>) -> axum::response::Html<S
         has_exercises: !e.code_steps().is_empty(),
         is_bonus: e.is_bonus(),
         href: None,
+        locked: None,
+        difficulty: e.difficulty(),
     });
     let template = TourTemplate {
+        base: course.base(),
+        key_prefix: course.qualify(""),
         starter: STARTER.to_string(),
         ulid,
         next_dot,
@@ -1280,9 +2500,76 @@ async fn cheatsheet_fragment() -> impl IntoResponse {
     Html(exercises::render_markdown(&md))
 }
 
+/// Renders the offline `rustc --explain` text for one error code.
+async fn explain_page(State(state): State<AppState>, AxumPath(code): AxumPath<String>) -> Response {
+    explain_response(&state, &code, false)
+}
+
+/// Same as [`explain_page`] without the page chrome, for the inline
+/// panel under the editor's run output.
+async fn explain_fragment(
+    State(state): State<AppState>,
+    AxumPath(code): AxumPath<String>,
+) -> Response {
+    explain_response(&state, &code, true)
+}
+
+fn explain_response(state: &AppState, raw: &str, fragment: bool) -> Response {
+    let suffix = if fragment { "/fragment" } else { "" };
+    let Some(code) = explain::normalize_code(raw) else {
+        return (StatusCode::NOT_FOUND, "Not an error code").into_response();
+    };
+    // `/explain/e382` and friends redirect to the canonical URL.
+    if code != raw {
+        return axum::response::Redirect::permanent(&format!("/explain/{code}{suffix}"))
+            .into_response();
+    }
+    let Some(markdown) = state.explanations.get(&code) else {
+        return (StatusCode::NOT_FOUND, format!("No explanation for {code}")).into_response();
+    };
+    let html = exercises::render_markdown(markdown);
+    let mut chapters = Vec::new();
+    for course in state.courses.iter() {
+        let catalog = course.catalog.current();
+        chapters.extend(explain::chapters_teaching(&catalog, &code).map(|exercise| {
+            ExplainChapter {
+                title: if course.is_default() {
+                    exercise.title.clone()
+                } else {
+                    format!("{} ({})", exercise.title, course.title)
+                },
+                href: format!("{}/exercise/{}", course.base(), exercise.file_stem),
+            }
+        }));
+    }
+    let rendered = if fragment {
+        ExplainFragmentTemplate {
+            code,
+            html,
+            chapters,
+        }
+        .render()
+    } else {
+        ExplainTemplate {
+            code,
+            html,
+            chapters,
+        }
+        .render()
+    };
+    match rendered {
+        Ok(html) => Html(html).into_response(),
+        Err(e) => {
+            error!("explain template render failed: {e}");
+            Html("Error rendering template".to_string()).into_response()
+        }
+    }
+}
+
 /// Web registration handler
 async fn web_register(
     State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
     axum::Form(form): axum::Form<WebRegistrationForm>,
 ) -> Result<axum::response::Redirect, StatusCode> {
     let name = Name::try_from(form.name).map_err(|_| StatusCode::BAD_REQUEST)?;
@@ -1299,11 +2586,17 @@ async fn web_register(
 
     let ulid = Ulid::new().to_string();
 
-    sqlx::query("INSERT INTO participants (id, name, team_token) VALUES (?, ?, ?)")
-        .bind(&ulid)
-        .bind(name.as_str())
-        .bind(team_token.as_ref().map(TeamToken::as_str))
-        .execute(&state.pool)
+    with_pool!(&state.db, |pool| sqlx::query(
+        "INSERT INTO participants (id, name, team_token) VALUES ($1, $2, $3)"
+    )
+    .bind(&ulid)
+    .bind(name.as_str())
+    .bind(team_token.as_ref().map(TeamToken::as_str))
+    .execute(pool)
+    .await
+    .map(|done| done.rows_affected()))
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    enroll(&state.db, &ulid, &course.slug)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -1313,20 +2606,34 @@ async fn web_register(
     // looking at, not the dashboard.
     let target = resolve_register_next(form.next.as_deref(), &ulid)
         .unwrap_or_else(|| format!("/dashboard/{ulid}"));
-    Ok(axum::response::Redirect::to(&target))
+    Ok(axum::response::Redirect::to(&format!(
+        "{}{target}",
+        course.base()
+    )))
 }
 
 /// Participant dashboard handler
+// reason: top-level request handler; splitting purely for line count adds indirection without value
+#[allow(clippy::too_many_lines)]
 async fn participant_dashboard(
     AxumPath(ulid): AxumPath<String>,
+    Query(query): Query<DashboardQuery>,
+    headers: HeaderMap,
+    OriginalUri(uri): OriginalUri,
     State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
 ) -> impl IntoResponse {
+    if let Some(response) = require_participant_page(&state, &headers, &uri, &ulid).await {
+        return response;
+    }
+
     // Get participant info
-    let participant_result =
-        sqlx::query_as("SELECT name, team_token FROM participants WHERE id = ?")
-            .bind(&ulid)
-            .fetch_one(&state.pool)
-            .await;
+    let participant_result = with_pool!(&state.db, |pool| sqlx::query_as(
+        "SELECT name, team_token FROM participants WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(&ulid)
+    .fetch_one(pool)
+    .await);
 
     let participant: DbParticipant = match participant_result {
         Ok(p) => p,
@@ -1340,22 +2647,39 @@ async fn participant_dashboard(
         }
     };
 
+    // Opening a course's dashboard is what enrolls someone in it.
+    let enrolled = match enroll(&state.db, &ulid, &course.slug).await {
+        Ok(()) => enrolled_courses(&state.db, &ulid).await,
+        Err(err) => Err(err),
+    };
+    let enrolled = match enrolled {
+        Ok(enrolled) => enrolled,
+        Err(err) => {
+            error!("Failed to enroll {ulid} in {}: {err}", course.slug);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
     // Get exercise progress
-    let Ok(exercises) = get_exercise_progress(&state.pool, Some(&ulid), &state.exercises).await
-    else {
+    let catalog = course.catalog.current();
+    let (Ok(mut exercises), Ok(released)) = (
+        get_exercise_progress(&state.db, Some(&ulid), &course.slug, &catalog).await,
+        load_releases(&state.db, &course.slug).await,
+    ) else {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to get exercise progress",
         )
             .into_response();
     };
+    apply_locks(&mut exercises, &catalog, &released);
 
     // CTA: jump to the first unfinished, non-quiz chapter that actually
-    // has exercises. If everything is done, point back to chapter 1 as
-    // a graceful default.
+    // has exercises and is open. If everything is done, point back to
+    // chapter 1 as a graceful default.
     let first_unfinished = exercises
         .iter()
-        .find(|e| !e.completed && !e.is_quiz && e.has_exercises && !e.is_bonus)
+        .find(|e| !e.completed && e.has_exercises && !e.is_bonus && e.locked.is_none())
         .or_else(|| exercises.first());
     let any_completed = exercises.iter().any(|e| e.completed);
     let (next_slug, next_label, next_chapter_number, next_chapter_title) = match first_unfinished {
@@ -1380,16 +2704,27 @@ async fn participant_dashboard(
     };
     let progress_total = exercises
         .iter()
-        .filter(|e| !e.is_quiz && e.has_exercises && !e.is_bonus)
+        .filter(|e| e.has_exercises && !e.is_bonus)
         .count();
     let progress_done = exercises
         .iter()
-        .filter(|e| !e.is_quiz && e.has_exercises && !e.is_bonus && e.completed)
+        .filter(|e| e.has_exercises && !e.is_bonus && e.completed)
         .count();
 
     let team_token = participant.parsed_team_token();
-    let dots = dots_from_exercises(&exercises);
+    let (tags, tag) = tag_filter(&catalog, query.tag);
+    let dots = dots_from_exercises(&course.base(), &exercises, tag.as_deref());
     let template = DashboardTemplate {
+        base: course.base(),
+        key_prefix: course.qualify(""),
+        course_slug: course.slug.clone(),
+        course_title: course.title.clone(),
+        courses: course_links(
+            &state.courses,
+            &course,
+            &format!("/dashboard/{ulid}"),
+            &enrolled,
+        ),
         participant_name: Some(participant.name),
         ulid: Some(ulid.clone()),
         chapter_rows: chapter_rows(&dots),
@@ -1402,6 +2737,8 @@ async fn participant_dashboard(
         progress_total,
         reason: None,
         team_token,
+        tags,
+        tag,
     };
 
     template.render().map_or_else(
@@ -1420,27 +2757,36 @@ async fn participant_dashboard(
 async fn public_exercise_page(
     AxumPath(slug): AxumPath<String>,
     State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
 ) -> impl IntoResponse {
-    render_exercise_page(&state, &slug, None).await
+    render_exercise_page(&state, &course, &slug, None).await
 }
 
 /// Exercise page with participant context.
 async fn participant_exercise_page(
     AxumPath((ulid, slug)): AxumPath<(String, String)>,
+    headers: HeaderMap,
+    OriginalUri(uri): OriginalUri,
     State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
 ) -> impl IntoResponse {
+    if let Some(response) = require_participant_page(&state, &headers, &uri, &ulid).await {
+        return response;
+    }
     // Verify the participant exists; if not, fall back to the public page so
     // the URL still resolves to something useful.
-    let exists = sqlx::query("SELECT 1 FROM participants WHERE id = ?")
-        .bind(&ulid)
-        .fetch_optional(&state.pool)
-        .await
-        .ok()
-        .flatten()
-        .is_some();
+    let exists = with_pool!(&state.db, |pool| sqlx::query_scalar::<_, i32>(
+        "SELECT 1 FROM participants WHERE id = $1 AND deleted_at IS NULL"
+    )
+    .bind(&ulid)
+    .fetch_optional(pool)
+    .await)
+    .ok()
+    .flatten()
+    .is_some();
 
     let ulid = if exists { Some(ulid) } else { None };
-    render_exercise_page(&state, &slug, ulid).await
+    render_exercise_page(&state, &course, &slug, ulid).await
 }
 
 /// Minimal HTML escape for arbitrary text we splice into a template.
@@ -1463,30 +2809,31 @@ fn html_escape(s: &str) -> String {
 #[allow(clippy::too_many_lines)]
 async fn render_exercise_page(
     state: &AppState,
+    course: &Course,
     slug: &str,
     ulid: Option<String>,
 ) -> axum::response::Response {
     // Look up by slug or by file_stem so both `/exercise/strings_and_chars`
     // and `/exercise/02_strings_and_chars` resolve.
-    let Some(idx) = state
-        .exercises
+    let catalog = course.catalog.current();
+    let Some(idx) = catalog
         .iter()
         .position(|e| e.slug == slug || e.file_stem == slug)
     else {
         return (StatusCode::NOT_FOUND, "Exercise not found").into_response();
     };
-    let exercise = state.exercises[idx].clone();
+    let exercise = catalog[idx].clone();
 
     // Per-chapter rollup status for the header badge and chapter list.
     let chapter_progress: std::collections::HashMap<String, UiExerciseStatus> = match &ulid {
-        Some(u) => match get_exercise_progress(&state.pool, Some(u), &state.exercises).await {
+        Some(u) => match get_exercise_progress(&state.db, Some(u), &course.slug, &catalog).await {
             Ok(rows) => rows
                 .into_iter()
                 .map(|r| {
                     (
                         r.name,
                         UiExerciseStatus {
-                            attempted: !r.submissions.is_empty(),
+                            attempted: r.attempted,
                             completed: r.completed,
                             perfected: r.perfected,
                             // Chapter rollup is only used for the header
@@ -1508,7 +2855,7 @@ async fn render_exercise_page(
     // Per-step status (`<chapter>/<step_key>`). For legacy single-step
     // chapters the step status is just the chapter status.
     let step_progress: std::collections::HashMap<String, UiExerciseStatus> = match &ulid {
-        Some(u) => load_step_progress(&state.pool, u)
+        Some(u) => load_step_progress(&state.db, u, &course.slug)
             .await
             .unwrap_or_else(|e| {
                 warn!("Failed to load per-step progress: {e}");
@@ -1522,8 +2869,21 @@ async fn render_exercise_page(
         .cloned()
         .unwrap_or_default();
 
-    let dots: Vec<ProgressDot> = std::iter::once(tour_dot())
-        .chain(state.exercises.iter().enumerate().map(|(i, e)| {
+    let released = load_releases(&state.db, &course.slug)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load chapter releases: {e}");
+            std::collections::HashSet::new()
+        });
+    let locks = chapter_locks(
+        &catalog,
+        &released,
+        |stem| chapter_progress.get(stem).is_some_and(|s| s.completed),
+        |stem| chapter_progress.get(stem).is_some_and(|s| s.attempted),
+    );
+
+    let dots: Vec<ProgressDot> = std::iter::once(tour_dot(&course.base()))
+        .chain(catalog.iter().enumerate().map(|(i, e)| {
             let s = chapter_progress
                 .get(&e.file_stem)
                 .cloned()
@@ -1537,13 +2897,48 @@ async fn render_exercise_page(
                 perfected: s.perfected,
                 current: i == idx,
                 is_quiz: e.is_quiz(),
-                has_exercises: !e.code_steps().is_empty(),
+                has_exercises: e.is_completable(),
                 is_bonus: e.is_bonus(),
                 href: None,
+                locked: locks
+                    .get(&e.file_stem)
+                    .map(|lock| lock_note(lock, &catalog)),
+                difficulty: e.difficulty(),
             }
         }))
         .collect();
 
+    if let Some(lock) = locks.get(&exercise.file_stem) {
+        // `dots` starts with the tour, so chapter `i` is `dots[i + 1]`.
+        let missing = lock
+            .missing
+            .iter()
+            .filter_map(|stem| catalog.iter().position(|e| &e.file_stem == stem))
+            .map(|i| dots[i + 1].clone())
+            .collect();
+        let template = LockedTemplate {
+            base: course.base(),
+            ulid,
+            title: exercise.title,
+            number: exercise.number,
+            missing,
+            awaiting_release: lock.awaiting_release,
+            chapter_rows: chapter_rows(&dots),
+            dots,
+        };
+        return match template.render() {
+            Ok(html) => (StatusCode::FORBIDDEN, Html(html)).into_response(),
+            Err(e) => {
+                error!("Failed to render locked chapter template: {e}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to render template",
+                )
+                    .into_response()
+            }
+        };
+    }
+
     // Build the ordered render plan from the chapter's steps.
     let total_code_steps = exercise.code_steps().len();
     let mut current_code_index: usize = 0;
@@ -1646,19 +3041,24 @@ async fn render_exercise_page(
         .cloned();
 
     // Progress: how many completable chapters has the participant
-    // finished? Quizzes and notes-only chapters never "complete", so
-    // they're excluded from both numerator and denominator.
+    // finished? Notes-only chapters and practice quizzes never
+    // "complete", so they're excluded from both numerator and
+    // denominator.
     let progress_total = dots
         .iter()
-        .filter(|d| !d.is_quiz && d.has_exercises && !d.is_bonus)
+        .filter(|d| d.has_exercises && !d.is_bonus)
         .count();
     let progress_done = dots
         .iter()
-        .filter(|d| !d.is_quiz && d.has_exercises && !d.is_bonus && d.completed)
+        .filter(|d| d.has_exercises && !d.is_bonus && d.completed)
         .count();
 
-    let next_locked = ulid.is_some() && !current_status.completed;
+    // Chapters that can't be completed have nothing to reveal the
+    // button, so it shows from the start.
+    let next_locked = ulid.is_some() && exercise.is_completable() && !current_status.completed;
     let template = ExerciseTemplate {
+        base: course.base(),
+        key_prefix: course.qualify(""),
         exercise,
         ulid,
         current_status,
@@ -1683,20 +3083,23 @@ async fn render_exercise_page(
     }
 }
 
-/// Load per-step submission status for a participant.
+/// Load per-step submission status for a participant in `course`.
 ///
 /// Keys are the full `submissions.exercise_name` value (`<chapter>` for
 /// legacy single-step chapters or `<chapter>/<step_key>` for multi-step).
 async fn load_step_progress(
-    pool: &SqlitePool,
+    db: &Db,
     ulid: &str,
+    course: &str,
 ) -> Result<std::collections::HashMap<String, UiExerciseStatus>> {
-    let rows: Vec<DbSubmission> = sqlx::query_as(
-        "SELECT * FROM submissions WHERE participant_id = ? ORDER BY exercise_name, submitted_at DESC",
+    let rows: Vec<DbSubmission> = with_pool!(db, |pool| sqlx::query_as(
+        "SELECT * FROM submissions WHERE participant_id = $1 AND course = $2
+         ORDER BY exercise_name, submitted_at DESC",
     )
     .bind(ulid)
+    .bind(course)
     .fetch_all(pool)
-    .await?;
+    .await)?;
 
     let mut by_key: std::collections::HashMap<String, UiExerciseStatus> =
         std::collections::HashMap::new();
@@ -1718,538 +3121,2433 @@ async fn load_step_progress(
     }
     Ok(by_key)
 }
-// reason: top-level request handler; splitting purely for line count adds indirection without value
-#[allow(clippy::too_many_lines)]
-async fn admin_dashboard(
-    Query(query): Query<AdminQuery>,
-    State(state): State<AppState>,
-) -> impl IntoResponse {
-    // Verify admin token
-    if query.token != state.admin_token {
-        return (StatusCode::FORBIDDEN, "Invalid admin token").into_response();
-    }
 
-    // Get participant summaries. We only pull identity + last-activity
-    // from SQL here; the completed/total counts are computed below with
-    // `get_exercise_progress` so they match exactly what each
-    // participant sees on their own dashboard.
-    let participant_rows_result = sqlx::query(
-        r"
-        SELECT 
-            p.id,
-            p.name,
-            p.team_token,
-            MAX(s.submitted_at) as last_activity
-        FROM participants p
-        LEFT JOIN submissions s ON p.id = s.participant_id AND s.tests_passed = 1
-        GROUP BY p.id, p.name, p.team_token
-        ORDER BY
-            CASE WHEN p.team_token IS NULL THEN 1 ELSE 0 END,
-            p.team_token COLLATE NOCASE,
-            last_activity DESC NULLS LAST
-        ",
-    )
-    .fetch_all(&state.pool)
-    .await;
-
-    let Ok(participant_rows) = participant_rows_result else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to fetch participants",
-        )
-            .into_response();
-    };
+/// Which two attempts the history page compares. Both are submission
+/// ids; missing ones default to the latest attempt and the one before.
+#[derive(Deserialize, Default)]
+struct HistoryQuery {
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    to: Option<String>,
+}
 
-    // Denominator: the number of *completable* chapters in the catalog.
-    // Quizzes and notes-only chapters have no code steps and never count
-    // toward progress, so they're excluded here just like in
-    // `participant_dashboard` / `render_exercise_page`.
-    let total_exercises = completable_total(&state);
+/// A `submissions` row as the history page reads it.
+#[derive(sqlx::FromRow)]
+struct HistoryRow {
+    id: String,
+    source_code: String,
+    tests_passed: bool,
+    fmt_passed: bool,
+    clippy_passed: bool,
+    verification: String,
+    submitted_at: chrono::DateTime<chrono::Utc>,
+}
 
-    let mut participants = Vec::new();
-    for row in participant_rows {
-        let raw_token: Option<String> = row.get("team_token");
-        // Rows with a column value the validator rejects degrade to
-        // `None` (the participant lands in the "Unassigned" bucket).
-        // That can't happen via our own write paths, but it keeps a
-        // hand-edited DB row from taking the page down.
-        let team_token = raw_token
-            .as_deref()
-            .and_then(|s| TeamToken::try_from(s).ok());
-        let id: String = row.get("id");
-        let name: String = row.get("name");
-        let last_activity = row.get("last_activity");
+/// Every attempt `ulid` made at `exercise_name`, newest first.
+async fn load_step_history(
+    db: &Db,
+    ulid: &str,
+    course: &str,
+    exercise_name: &str,
+) -> Result<Vec<HistoryAttempt>, sqlx::Error> {
+    let rows: Vec<HistoryRow> = with_pool!(db, |pool| sqlx::query_as(
+        "SELECT id, source_code, tests_passed, fmt_passed, clippy_passed, verification,
+                submitted_at
+         FROM submissions WHERE participant_id = $1 AND course = $2 AND exercise_name = $3
+         ORDER BY submitted_at DESC, id DESC",
+    )
+    .bind(ulid)
+    .bind(course)
+    .bind(exercise_name)
+    .fetch_all(pool)
+    .await)?;
+    let total = rows.len();
+    Ok(rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| HistoryAttempt {
+            id: row.id,
+            number: total - i,
+            submitted_at: row.submitted_at,
+            tests_passed: row.tests_passed,
+            fmt_passed: row.fmt_passed,
+            clippy_passed: row.clippy_passed,
+            verification_pending: row.verification == "pending",
+            source_code: row.source_code,
+        })
+        .collect())
+}
 
-        // Numerator (inside `build_participant_summary`): distinct
-        // completable chapters this participant has finished. Counting
-        // passing *submissions* badly overcounts, since multiple
-        // submissions per step are allowed and multi-step chapters
-        // store one row per step. A per-participant query is fine on
-        // this low-traffic admin page.
-        participants.push(
-            build_participant_summary(&state, id, name, team_token, last_activity, total_exercises)
-                .await,
-        );
+/// Unified diff of `old` against `new` with three lines of context per
+/// hunk. Empty when the two are identical.
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let diff = similar::TextDiff::from_lines(old, new);
+    let mut lines = Vec::new();
+    for group in diff.grouped_ops(3) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let old_range = first.old_range().start..last.old_range().end;
+        let new_range = first.new_range().start..last.new_range().end;
+        lines.push(DiffLine {
+            kind: "hunk",
+            old_line: None,
+            new_line: None,
+            text: format!(
+                "@@ -{},{} +{},{} @@",
+                old_range.start + 1,
+                old_range.len(),
+                new_range.start + 1,
+                new_range.len()
+            ),
+        });
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                lines.push(DiffLine {
+                    kind: match change.tag() {
+                        similar::ChangeTag::Equal => "equal",
+                        similar::ChangeTag::Insert => "insert",
+                        similar::ChangeTag::Delete => "delete",
+                    },
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                    text: change.value().trim_end_matches(['\n', '\r']).to_string(),
+                });
+            }
+        }
     }
+    lines
+}
 
-    // Bucket the flat list into teams. The query already sorted
-    // by team_token (with NULLs last) so a single linear pass is
-    // enough to produce one bucket per distinct value.
-    let mut participant_teams = bucket_participants_by_team(participants);
-
-    // Order each bucket by the default sort the template advertises
-    // (name, ascending) so the column header arrow matches what the
-    // operator first sees. Later sorts/filters are handled by
-    // `admin_team_members` over htmx.
-    for team in &mut participant_teams {
-        apply_member_filter_sort(&mut team.members, "name", "asc", "");
+/// Submission history for one code step, with a diff between any two
+/// attempts and from the latest one to the reference solution.
+// reason: top-level request handler; the steps read top to bottom
+#[allow(clippy::too_many_lines)]
+async fn participant_history_page(
+    AxumPath((ulid, exercise_name)): AxumPath<(String, String)>,
+    Query(query): Query<HistoryQuery>,
+    headers: HeaderMap,
+    OriginalUri(uri): OriginalUri,
+    State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
+) -> Response {
+    if let Some(response) = require_participant_page(&state, &headers, &uri, &ulid).await {
+        return response;
     }
-
-    // Get recent submissions
-    let submission_rows_result = sqlx::query(
-        r"
-        SELECT 
-            p.name as participant_name,
-            s.exercise_name,
-            s.tests_passed,
-            s.fmt_passed,
-            s.clippy_passed,
-            s.submitted_at,
-            s.source_code
-        FROM submissions s
-        JOIN participants p ON s.participant_id = p.id
-        ORDER BY s.submitted_at DESC
-        LIMIT 20
-        ",
-    )
-    .fetch_all(&state.pool)
-    .await;
-
-    let Ok(submission_rows) = submission_rows_result else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to fetch submissions",
-        )
-            .into_response();
+    let (chapter, _) = exercise_name
+        .split_once('/')
+        .unwrap_or((exercise_name.as_str(), ""));
+    let catalog = course.catalog.current();
+    let (Some(exercise), Some(step)) = (
+        catalog.iter().find(|ex| ex.file_stem == chapter),
+        exercises::find_code_step(&catalog, &exercise_name),
+    ) else {
+        return (StatusCode::NOT_FOUND, "Exercise not found").into_response();
     };
 
-    let mut recent_submissions = Vec::new();
-    for row in submission_rows {
-        let fmt_passed: bool = row.get("fmt_passed");
-        let clippy_passed: bool = row.get("clippy_passed");
-        let exercise_name: String = row.get("exercise_name");
-        let exercise_label = prettify_exercise_name(&exercise_name);
-        recent_submissions.push(SubmissionSummary {
-            participant_name: row.get("participant_name"),
-            exercise_name,
-            exercise_label,
-            tests_passed: row.get("tests_passed"),
-            perfected: fmt_passed && clippy_passed,
-            submitted_at: row.get("submitted_at"),
-            source_code: row.get("source_code"),
-        });
-    }
+    let participant_name: Option<String> = match with_pool!(&state.db, |pool| {
+        sqlx::query_scalar("SELECT name FROM participants WHERE id = $1 AND deleted_at IS NULL")
+            .bind(&ulid)
+            .fetch_optional(pool)
+            .await
+    }) {
+        Ok(name) => name,
+        Err(err) => {
+            error!("Failed to load participant {ulid}: {err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    let Some(participant_name) = participant_name else {
+        return axum::response::Redirect::to("/?reason=unknown-token").into_response();
+    };
 
-    // Get admin statistics with proper SQL queries
-    let Ok(admin_stats) = get_admin_stats(&state.pool).await else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to get admin statistics",
+    let attempts = match load_step_history(&state.db, &ulid, &course.slug, &exercise_name).await {
+        Ok(attempts) => attempts,
+        Err(err) => {
+            error!("Failed to load history of {exercise_name} for {ulid}: {err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    // An id that isn't one of this step's attempts is a stale or
+    // hand-edited link; say so rather than silently comparing others.
+    let pick = |id: Option<&str>, default: usize| {
+        id.map_or_else(
+            || Ok(attempts.get(default)),
+            |id| {
+                attempts
+                    .iter()
+                    .find(|attempt| attempt.id == id)
+                    .map(Some)
+                    .ok_or(())
+            },
         )
-            .into_response();
     };
-
-    // Get unique exercises from submissions
-    let exercise_rows_result =
-        sqlx::query("SELECT DISTINCT exercise_name FROM submissions ORDER BY exercise_name")
-            .fetch_all(&state.pool)
-            .await;
-
-    let exercises = exercise_rows_result.map_or_else(
-        |_| Vec::new(),
-        |rows| {
-            rows.iter()
-                .map(|row| row.get::<String, _>("exercise_name"))
-                .collect()
-        },
-    );
-
-    let template = AdminTemplate {
-        participant_teams,
-        recent_submissions,
-        stats: admin_stats,
-        exercises,
-        admin_token: state.admin_token.clone(),
-        sort: "name".to_string(),
-        dir: "asc".to_string(),
-        filter: String::new(),
+    let (Ok(to), Ok(from)) = (pick(query.to.as_deref(), 0), pick(query.from.as_deref(), 1)) else {
+        return (StatusCode::NOT_FOUND, "Attempt not found").into_response();
     };
-
-    template.render().map_or_else(
-        |_| {
+    let comparison = from.zip(to).map(|(from, to)| AttemptComparison {
+        from_number: from.number,
+        to_number: to.number,
+        lines: diff_lines(&from.source_code, &to.source_code),
+    });
+    let from_id = from.map(|attempt| attempt.id.clone()).unwrap_or_default();
+    let to_id = to.map(|attempt| attempt.id.clone()).unwrap_or_default();
+    let solution_diff = attempts
+        .first()
+        .zip(step.solution_code.as_deref())
+        .map(|(latest, solution)| diff_lines(&latest.source_code, solution));
+
+    let template = HistoryTemplate {
+        base: course.base(),
+        step_label: prettify_exercise_name(&exercise_name),
+        exercise_href: format!("{}/exercise/{ulid}/{}", course.base(), exercise.slug),
+        ulid,
+        participant_name,
+        exercise_name,
+        chapter_title: exercise.title.clone(),
+        from_id,
+        to_id,
+        attempts,
+        comparison,
+        solution_diff,
+    };
+    match template.render() {
+        Ok(html) => Html(html).into_response(),
+        Err(err) => {
+            error!("history template render failed: {err}");
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Failed to render template",
             )
                 .into_response()
-        },
-        |html| Html(html).into_response(),
-    )
+        }
+    }
+}
+/// Value of cookie `name` in the request's `Cookie` headers.
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| {
+            let (key, value) = pair.trim().split_once('=')?;
+            (key == name).then_some(value)
+        })
 }
 
-/// Query parameters for the `admin_team_members` htmx fragment. `team`
-/// is the slug (empty string = Unassigned bucket); `sort`/`dir`/`filter`
-/// carry the current table state forwarded by the column-header sort
-/// buttons and the per-team filter box.
-#[derive(Deserialize)]
-struct TeamMembersQuery {
-    token: String,
-    #[serde(default)]
-    team: String,
-    #[serde(default)]
-    sort: String,
-    #[serde(default)]
-    dir: String,
-    #[serde(default)]
-    filter: String,
+/// The admin session carried by the request, if its cookie is valid
+/// and unexpired.
+fn admin_session(state: &AppState, headers: &HeaderMap) -> Option<AdminSession> {
+    let cookie = cookie_value(headers, ADMIN_COOKIE)?;
+    state
+        .sessions
+        .verify(cookie, chrono::Utc::now().timestamp())
 }
 
-/// htmx fragment endpoint backing per-team sorting and filtering on the
-/// admin dashboard. Returns just the `partials/team_members.html`
-/// table (the swap target), re-rendered with the requested sort column,
-/// direction, and name filter applied.
-async fn admin_team_members(
-    Query(query): Query<TeamMembersQuery>,
-    State(state): State<AppState>,
-) -> impl IntoResponse {
-    if query.token != state.admin_token {
-        return (StatusCode::FORBIDDEN, "Invalid admin token").into_response();
+/// Whoever is signed in to the admin UI: the super-admin, or an
+/// instructor with the teams they were assigned.
+struct Admin {
+    session: AdminSession,
+    /// `None` for the super-admin.
+    instructor: Option<InstructorScope>,
+}
+
+/// An instructor's teams, loaded fresh on every request so changes by
+/// the super-admin apply immediately.
+struct InstructorScope {
+    id: String,
+    name: String,
+    teams: Vec<TeamToken>,
+}
+
+impl Admin {
+    const fn is_super_admin(&self) -> bool {
+        self.instructor.is_none()
     }
 
-    let team_token = if query.team.trim().is_empty() {
-        None
-    } else {
-        match TeamToken::try_from(query.team.as_str()) {
-            Ok(token) => Some(token),
-            Err(_) => return (StatusCode::BAD_REQUEST, "Invalid team slug").into_response(),
-        }
-    };
+    fn instructor_id(&self) -> Option<&str> {
+        self.instructor.as_ref().map(|scope| scope.id.as_str())
+    }
 
-    let Ok(mut members) = load_team_member_summaries(&state, team_token.as_ref()).await else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to fetch team members",
-        )
-            .into_response();
-    };
+    /// Whether this admin may see and manage the members of `team`.
+    /// `None` is the Unassigned bucket, which only the super-admin sees.
+    fn covers(&self, team: Option<&TeamToken>) -> bool {
+        self.instructor
+            .as_ref()
+            .is_none_or(|scope| team.is_some_and(|team| scope.teams.contains(team)))
+    }
+}
 
-    // Normalise the incoming state so the template's arrows and the
-    // hx-get URLs it bakes back out stay in the {name|progress|activity}
-    // x {asc|desc} space regardless of what arrived on the query string.
-    let sort = if matches!(query.sort.as_str(), "progress" | "activity") {
-        query.sort
-    } else {
-        "name".to_string()
-    };
-    let dir = if query.dir == "desc" {
-        "desc".to_string()
-    } else {
-        "asc".to_string()
+/// The request's admin, with the instructor's scope loaded. `None`
+/// without a valid session, when the instructor has been deleted, or
+/// when the cookie predates a sign-out (its epoch is behind).
+async fn current_admin(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<Option<Admin>, sqlx::Error> {
+    let Some(session) = admin_session(state, headers) else {
+        return Ok(None);
     };
-    apply_member_filter_sort(&mut members, &sort, &dir, &query.filter);
-
-    let team = ParticipantTeam {
-        team_token,
-        members,
+    let Some(instructor_id) = session.instructor_id.clone() else {
+        let epoch = admin_epoch(&state.db).await?;
+        return Ok((session.epoch == epoch).then_some(Admin {
+            session,
+            instructor: None,
+        }));
     };
-    let template = TeamMembersTemplate {
-        team,
-        admin_token: state.admin_token.clone(),
-        sort,
-        dir,
-        filter: query.filter,
+    let Some((name, epoch)) = with_pool!(&state.db, |pool| sqlx::query_as::<_, (String, i64)>(
+        "SELECT name, auth_epoch FROM instructors WHERE id = $1"
+    )
+    .bind(&instructor_id)
+    .fetch_optional(pool)
+    .await)?
+    else {
+        return Ok(None);
     };
-
-    template.render().map_or_else(
-        |_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to render template",
-            )
-                .into_response()
-        },
-        |html| Html(html).into_response(),
+    if epoch != session.epoch {
+        return Ok(None);
+    }
+    let teams: Vec<String> = with_pool!(&state.db, |pool| sqlx::query_scalar(
+        "SELECT team_token FROM instructor_teams WHERE instructor_id = $1 ORDER BY team_token",
     )
+    .bind(&instructor_id)
+    .fetch_all(pool)
+    .await)?;
+    Ok(Some(Admin {
+        session,
+        instructor: Some(InstructorScope {
+            id: instructor_id,
+            name,
+            teams: teams
+                .iter()
+                .filter_map(|team| TeamToken::try_from(team.as_str()).ok())
+                .collect(),
+        }),
+    }))
 }
 
-/// Form data for `POST /admin/participants/{ulid}/team-token`.
-#[derive(Deserialize)]
-struct TeamTokenForm {
-    team_token: String,
-}
-
-/// Move a participant into a different team bucket (or out of any).
-///
-/// Submitted by the inline form on the admin participants table. The
-/// `team_token` field is normalised via `normalize_team_token`:
-/// blank values clear the column (sending the participant to the
-/// "Unassigned" bucket), anything else has to be a short slug.
-///
-/// Always redirects back to `/admin?token=...` on success so the
-/// admin sees the regrouped table immediately.
-async fn admin_set_team_token(
-    AxumPath(participant_id): AxumPath<String>,
-    Query(query): Query<AdminQuery>,
-    State(state): State<AppState>,
-    axum::Form(form): axum::Form<TeamTokenForm>,
-) -> impl IntoResponse {
-    if query.token != state.admin_token {
-        return (StatusCode::FORBIDDEN, "Invalid admin token").into_response();
+/// Session guard for admin pages: without a session, send the browser
+/// to the login form and come back to `uri` afterwards.
+// reason: the error is the response handed straight back to axum
+#[allow(clippy::result_large_err)]
+async fn require_admin_page(
+    state: &AppState,
+    headers: &HeaderMap,
+    uri: &Uri,
+) -> Result<Admin, Response> {
+    match current_admin(state, headers).await {
+        Ok(Some(admin)) => Ok(admin),
+        Ok(None) => {
+            let next = safe_admin_next(Some(uri.path()));
+            Err(axum::response::Redirect::to(&format!("/admin/login?next={next}")).into_response())
+        }
+        Err(err) => {
+            error!("Failed to load admin session: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())
+        }
     }
+}
 
-    let new_token = match TeamToken::parse_form_input(&form.team_token) {
-        Ok(value) => value,
+/// Session guard for admin requests made by scripts (htmx fragments,
+/// `fetch`), which can't follow a redirect to a login form.
+// reason: the error is the response handed straight back to axum
+#[allow(clippy::result_large_err)]
+async fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<Admin, Response> {
+    match current_admin(state, headers).await {
+        Ok(Some(admin)) => Ok(admin),
+        Ok(None) => Err((
+            StatusCode::UNAUTHORIZED,
+            "Admin session expired. Sign in again at /admin/login.",
+        )
+            .into_response()),
         Err(err) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                format!("Invalid team_token: {err}"),
-            )
-                .into_response();
+            error!("Failed to load admin session: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())
         }
-    };
+    }
+}
 
-    let result = sqlx::query("UPDATE participants SET team_token = ? WHERE id = ?")
-        .bind(new_token.as_ref().map(TeamToken::as_str))
-        .bind(&participant_id)
-        .execute(&state.pool)
-        .await;
+/// Session guard for mutating admin requests: a valid session and its
+/// CSRF token, from a form field or the `X-CSRF-Token` header.
+// reason: the error is the response handed straight back to axum
+#[allow(clippy::result_large_err)]
+async fn require_admin_csrf(
+    state: &AppState,
+    headers: &HeaderMap,
+    form_token: Option<&str>,
+) -> Result<Admin, Response> {
+    let admin = require_admin(state, headers).await?;
+    let presented = form_token.or_else(|| {
+        headers
+            .get("x-csrf-token")
+            .and_then(|value| value.to_str().ok())
+    });
+    if presented.is_some_and(|token| state.sessions.check_csrf(&admin.session, token)) {
+        Ok(admin)
+    } else {
+        warn!("Rejected admin request with a missing or invalid CSRF token");
+        Err((
+            StatusCode::FORBIDDEN,
+            "Invalid CSRF token. Reload the page and retry.",
+        )
+            .into_response())
+    }
+}
+
+/// Guard for the pages and actions only the super-admin has: managing
+/// instructors and the Unassigned bucket.
+// reason: the error is the response handed straight back to axum
+#[allow(clippy::result_large_err)]
+fn require_super_admin(admin: &Admin) -> Result<(), Response> {
+    if admin.is_super_admin() {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "Only the course admin can do this.").into_response())
+    }
+}
+
+/// Guard for admin actions on one participant: instructors may only
+/// touch members of their own teams. A missing participant is left to
+/// the handler's own 404 for the super-admin.
+// reason: the error is the response handed straight back to axum
+#[allow(clippy::result_large_err)]
+async fn require_participant_in_scope(
+    state: &AppState,
+    admin: &Admin,
+    participant_id: &str,
+) -> Result<(), Response> {
+    if admin.is_super_admin() {
+        return Ok(());
+    }
+    let team: Option<Option<String>> = with_pool!(&state.db, |pool| sqlx::query_scalar(
+        "SELECT team_token FROM participants WHERE id = $1"
+    )
+    .bind(participant_id)
+    .fetch_optional(pool)
+    .await)
+    .map_err(|err| {
+        error!("Failed to look up participant {participant_id}: {err}");
+        (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+    })?;
+    let Some(team) = team else {
+        return Err((StatusCode::NOT_FOUND, "Participant not found").into_response());
+    };
+    let team = team
+        .as_deref()
+        .and_then(|team| TeamToken::try_from(team).ok());
+    if admin.covers(team.as_ref()) {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::FORBIDDEN,
+            "This participant is not in one of your teams.",
+        )
+            .into_response())
+    }
+}
+
+/// `Set-Cookie` value for a session cookie. `max_age = 0` clears it.
+///
+/// The admin cookie is site-wide too, so an operator can open a
+/// participant's dashboard even when it requires sign-in.
+fn session_cookie(state: &AppState, name: &str, value: &str, max_age: i64) -> HeaderValue {
+    let secure = if state.secure_cookies { "; Secure" } else { "" };
+    let cookie =
+        format!("{name}={value}; Path=/; Max-Age={max_age}; HttpOnly; SameSite=Lax{secure}");
+    HeaderValue::from_str(&cookie).expect("session cookies are plain ASCII")
+}
+
+/// Where to go after signing in. Only admin pages are allowed, so the
+/// login form can't be turned into an open redirect; anything else
+/// falls back to the dashboard.
+fn safe_admin_next(next: Option<&str>) -> String {
+    next.map(str::trim)
+        .filter(|next| {
+            let page = course_relative(next);
+            page == "/admin" || page.starts_with("/admin/")
+        })
+        .filter(|next| {
+            next.bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'/' | b'-' | b'_'))
+        })
+        .filter(|next| !next.contains("//"))
+        .filter(|next| !matches!(*next, "/admin/login" | "/admin/logout"))
+        .map_or_else(|| "/admin".to_string(), str::to_string)
+}
+
+/// Admin routes used to authenticate with `?token=` in the URL. Such
+/// requests are refused (never authenticated, even with the right
+/// token) and page loads are sent to the login form, whose URL doesn't
+/// repeat the token, so an old bookmark fails closed.
+async fn reject_legacy_admin_token(request: Request, next: Next) -> Response {
+    let has_token = request.uri().query().is_some_and(|query| {
+        query
+            .split('&')
+            .any(|pair| pair.split('=').next() == Some("token"))
+    });
+    if !has_token {
+        return next.run(request).await;
+    }
+    warn!(
+        "Refused {} {} carrying a legacy ?token= parameter",
+        request.method(),
+        request.uri().path()
+    );
+    if request.method() == Method::GET {
+        axum::response::Redirect::to("/admin/login?reason=legacy-token").into_response()
+    } else {
+        (
+            StatusCode::FORBIDDEN,
+            "Admin tokens in URLs are no longer accepted. Sign in at /admin/login.",
+        )
+            .into_response()
+    }
+}
+
+fn render_admin_login(template: &AdminLoginTemplate) -> Response {
+    match template.render() {
+        Ok(html) => Html(html).into_response(),
+        Err(err) => {
+            error!("admin login template render failed: {err}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to render template",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Admin sign-in form. Already signed-in operators go straight on.
+async fn admin_login_page(
+    Query(query): Query<AdminLoginQuery>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Response {
+    let next = safe_admin_next(query.next.as_deref());
+    if matches!(current_admin(&state, &headers).await, Ok(Some(_))) {
+        return axum::response::Redirect::to(&next).into_response();
+    }
+    let notice = match query.reason.as_deref() {
+        Some("legacy-token") => Some(
+            "Admin links with ?token= no longer work. Sign in below, then bookmark /admin instead.",
+        ),
+        Some("signed-out") => Some("You have been signed out."),
+        _ => None,
+    };
+    render_admin_login(&AdminLoginTemplate {
+        error: None,
+        notice,
+        next,
+    })
+}
+
+/// Turn a sign-in page into a 429 telling the client to come back in
+/// `wait` seconds.
+fn retry_after(mut response: Response, wait: i64) -> Response {
+    *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(wait));
+    response
+}
+
+/// The super-admin's current `auth_epoch` (migration `023`).
+async fn admin_epoch(db: &Db) -> Result<i64, sqlx::Error> {
+    with_pool!(db, |pool| sqlx::query_scalar(
+        "SELECT auth_epoch FROM admin_auth WHERE id = 1"
+    )
+    .fetch_one(pool)
+    .await)
+}
+
+/// The address to throttle admin sign-ins by: the TCP peer, or with
+/// `CORRODE_TRUST_FORWARDED_FOR` the last `X-Forwarded-For` entry,
+/// which the reverse proxy in front of the server appended.
+fn client_ip(state: &AppState, headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+    state
+        .trust_forwarded_for
+        .then(|| {
+            headers
+                .get_all("x-forwarded-for")
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .next_back()?
+                .trim()
+                .parse()
+                .ok()
+        })
+        .flatten()
+        .unwrap_or_else(|| peer.ip())
+}
+
+/// Check the token from the login form and start a session: the admin
+/// token for the super-admin, a `ci_…` token for an instructor. After
+/// a few wrong tokens from one address it has to wait before the next
+/// is even checked (see [`LoginThrottle`]).
+async fn admin_login(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<AdminLoginForm>,
+) -> Response {
+    let next = safe_admin_next(form.next.as_deref());
+    let now = chrono::Utc::now().timestamp();
+    let ip = client_ip(&state, &headers, peer);
+    let wait = state.login_throttle.wait(&ip, now);
+    if wait > 0 {
+        warn!("Throttled admin login attempt from {ip}");
+        let response = render_admin_login(&AdminLoginTemplate {
+            error: Some("Too many wrong tokens. Wait a moment, then try again."),
+            notice: None,
+            next,
+        });
+        return retry_after(response, wait);
+    }
+
+    let token = form.token.trim();
+    let issued = if token.starts_with("ci_") {
+        let instructor: Result<Option<(String, i64)>, sqlx::Error> =
+            with_pool!(&state.db, |pool| sqlx::query_as(
+                "UPDATE instructors SET last_login_at = CURRENT_TIMESTAMP
+                 WHERE token_hash = $1 RETURNING id, auth_epoch",
+            )
+            .bind(session::secret_hash(token))
+            .fetch_optional(pool)
+            .await);
+        match instructor {
+            Ok(instructor) => instructor.map(|(id, epoch)| {
+                info!("Instructor {id} signed in");
+                state.sessions.issue_instructor(&id, epoch, now)
+            }),
+            Err(err) => {
+                error!("Failed to look up instructor token: {err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        }
+    } else if SessionKey::admin_token_matches(&state.admin_token, &form.token) {
+        match admin_epoch(&state.db).await {
+            Ok(epoch) => {
+                info!("Admin signed in");
+                Some(state.sessions.issue(epoch, now))
+            }
+            Err(err) => {
+                error!("Failed to load the admin epoch: {err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        }
+    } else {
+        None
+    };
+    let Some((_, cookie)) = issued else {
+        warn!("Failed admin login attempt from {ip}");
+        state.login_throttle.failed(ip, now);
+        let mut response = render_admin_login(&AdminLoginTemplate {
+            error: Some("That token is not correct."),
+            notice: None,
+            next,
+        });
+        *response.status_mut() = StatusCode::UNAUTHORIZED;
+        return response;
+    };
+    state.login_throttle.succeeded(&ip);
+
+    let mut response = axum::response::Redirect::to(&next).into_response();
+    response.headers_mut().insert(
+        SET_COOKIE,
+        session_cookie(&state, ADMIN_COOKIE, &cookie, ADMIN_SESSION_TTL),
+    );
+    response
+}
+
+/// Invalidate every admin cookie issued so far to the super-admin
+/// (`instructor_id` `None`) or to one instructor.
+async fn bump_admin_epoch(db: &Db, instructor_id: Option<&str>) -> Result<u64, sqlx::Error> {
+    match instructor_id {
+        None => with_pool!(db, |pool| sqlx::query(
+            "UPDATE admin_auth SET auth_epoch = auth_epoch + 1 WHERE id = 1"
+        )
+        .execute(pool)
+        .await
+        .map(|done| done.rows_affected())),
+        Some(id) => with_pool!(db, |pool| sqlx::query(
+            "UPDATE instructors SET auth_epoch = auth_epoch + 1 WHERE id = $1"
+        )
+        .bind(id)
+        .execute(pool)
+        .await
+        .map(|done| done.rows_affected())),
+    }
+}
+
+/// End the admin session. Needs the CSRF token so another site can't
+/// sign the operator out. Bumping the admin's `auth_epoch` ends every
+/// copy of the cookie, not just the one this browser drops.
+async fn admin_logout(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<CsrfForm>,
+) -> Response {
+    let admin = match require_admin_csrf(&state, &headers, Some(&form.csrf)).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    if let Err(err) = bump_admin_epoch(&state.db, admin.instructor_id()).await {
+        error!("Failed to end the admin's sessions: {err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+    }
+    info!("Admin signed out");
+    let mut response =
+        axum::response::Redirect::to("/admin/login?reason=signed-out").into_response();
+    response
+        .headers_mut()
+        .insert(SET_COOKIE, session_cookie(&state, ADMIN_COOKIE, "", 0));
+    response
+}
+
+/// The participant session carried by the request, if its cookie is
+/// valid and unexpired. Whether its epoch is still current is up to
+/// [`participant_access`].
+fn participant_session(state: &AppState, headers: &HeaderMap) -> Option<ParticipantSession> {
+    let cookie = cookie_value(headers, PARTICIPANT_COOKIE)?;
+    state
+        .sessions
+        .verify_participant(cookie, chrono::Utc::now().timestamp())
+}
+
+async fn load_participant_auth(
+    db: &Db,
+    participant_id: &str,
+) -> Result<Option<DbParticipantAuth>, sqlx::Error> {
+    with_pool!(db, |pool| sqlx::query_as(
+        "SELECT auth_required, auth_epoch, passphrase_hash, team_token
+         FROM participants WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(participant_id)
+    .fetch_optional(pool)
+    .await)
+}
+
+/// Decide whether this request may act as participant `ulid`: always
+/// for participants who haven't opted into sign-in, otherwise only
+/// with a current session for them, or an admin session that covers
+/// their team.
+async fn participant_access(
+    state: &AppState,
+    headers: &HeaderMap,
+    ulid: &str,
+) -> Result<ParticipantAccess, sqlx::Error> {
+    let Some(auth) = load_participant_auth(&state.db, ulid).await? else {
+        return Ok(ParticipantAccess::Unknown);
+    };
+    let session = participant_session(state, headers)
+        .filter(|session| session.participant_id == ulid && session.epoch == auth.auth_epoch);
+    let granted = !auth.auth_required || session.is_some() || {
+        let team = auth
+            .team_token
+            .as_deref()
+            .and_then(|team| TeamToken::try_from(team).ok());
+        current_admin(state, headers)
+            .await?
+            .is_some_and(|admin| admin.covers(team.as_ref()))
+    };
+    if granted {
+        Ok(ParticipantAccess::Granted { auth, session })
+    } else {
+        Ok(ParticipantAccess::SignInRequired)
+    }
+}
+
+/// Guard for the pages under a participant's ULID. Returns the
+/// response to send instead (a redirect to `/login`) when the
+/// participant requires sign-in. Unknown ULIDs pass through so each
+/// page keeps its own fallback.
+async fn require_participant_page(
+    state: &AppState,
+    headers: &HeaderMap,
+    uri: &Uri,
+    ulid: &str,
+) -> Option<Response> {
+    match participant_access(state, headers, ulid).await {
+        Ok(ParticipantAccess::SignInRequired) => {
+            let next = safe_participant_next(Some(uri.path()), ulid).unwrap_or_default();
+            Some(axum::response::Redirect::to(&format!("/login?next={next}")).into_response())
+        }
+        Ok(ParticipantAccess::Granted { .. } | ParticipantAccess::Unknown) => None,
+        Err(err) => {
+            error!("Failed to check sign-in for {ulid}: {err}");
+            Some((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())
+        }
+    }
+}
+
+/// The API token in an `Authorization: Bearer cc_…` header, if any.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+/// Resolve the credential the CLI (or the exercise page) sends with a
+/// submission or status request to a participant id.
+///
+/// An `Authorization: Bearer` header wins over `credential`, which is
+/// how the CLI sends API tokens to the `GET` endpoints. `cc_…` values
+/// are API tokens from the settings page. Anything else is taken as a
+/// participant ULID, which only counts for participants who haven't
+/// opted into sign-in, or with their session cookie.
+async fn authenticate_participant(
+    state: &AppState,
+    headers: &HeaderMap,
+    credential: Option<&str>,
+) -> Result<Option<String>, sqlx::Error> {
+    let Some(credential) = bearer_token(headers).or(credential) else {
+        return Ok(None);
+    };
+    if credential.starts_with("cc_") {
+        return with_pool!(&state.db, |pool| sqlx::query_scalar(
+            "UPDATE participant_api_tokens SET last_used_at = CURRENT_TIMESTAMP
+             WHERE token_hash = $1 AND revoked_at IS NULL
+               AND participant_id IN (SELECT id FROM participants WHERE deleted_at IS NULL)
+             RETURNING participant_id",
+        )
+        .bind(session::secret_hash(credential))
+        .fetch_optional(pool)
+        .await);
+    }
+    Ok(
+        match participant_access(state, headers, credential).await? {
+            ParticipantAccess::Granted { .. } => Some(credential.to_string()),
+            ParticipantAccess::SignInRequired | ParticipantAccess::Unknown => None,
+        },
+    )
+}
+
+/// Guard for the account forms on `/settings/{ulid}`: the request must
+/// be allowed to act as `ulid` and carry the page's CSRF token.
+// reason: the error is the response handed straight back to axum
+#[allow(clippy::result_large_err)]
+async fn require_participant_form(
+    state: &AppState,
+    headers: &HeaderMap,
+    ulid: &str,
+    csrf: &str,
+) -> Result<(DbParticipantAuth, Option<ParticipantSession>), Response> {
+    match participant_access(state, headers, ulid).await {
+        Ok(ParticipantAccess::Granted { auth, session }) => {
+            if state
+                .sessions
+                .check_participant_csrf(ulid, session.as_ref(), csrf)
+            {
+                Ok((auth, session))
+            } else {
+                warn!("Rejected account form for {ulid} with an invalid CSRF token");
+                Err((
+                    StatusCode::FORBIDDEN,
+                    "Invalid CSRF token. Reload the page and retry.",
+                )
+                    .into_response())
+            }
+        }
+        Ok(ParticipantAccess::SignInRequired) => Err((
+            StatusCode::UNAUTHORIZED,
+            "Sign in at /login to change this account.",
+        )
+            .into_response()),
+        Ok(ParticipantAccess::Unknown) => {
+            Err((StatusCode::NOT_FOUND, "Participant not found").into_response())
+        }
+        Err(err) => {
+            error!("Failed to check sign-in for {ulid}: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response())
+        }
+    }
+}
+
+/// Where to go after a participant signs in: a page under their own
+/// ULID, or `None` for anything else (including open redirects).
+fn safe_participant_next(next: Option<&str>, ulid: &str) -> Option<String> {
+    let next = next?.trim();
+    let page = course_relative(next);
+    let allowed_prefix = ["/dashboard/", "/exercise/", "/settings/"]
+        .iter()
+        .any(|prefix| page.starts_with(prefix));
+    let own_page = page.split('/').nth(2) == Some(ulid);
+    let plain = next
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'/' | b'-' | b'_'))
+        && !next.contains("//");
+    (allowed_prefix && own_page && plain).then(|| next.to_string())
+}
+
+/// `path` without a leading `/courses/<slug>`, i.e. the page within
+/// whichever course it belongs to.
+fn course_relative(path: &str) -> &str {
+    path.strip_prefix("/courses/")
+        .and_then(|rest| rest.find('/').map(|slash| &rest[slash..]))
+        .unwrap_or(path)
+}
+
+/// The participant ULID in a typed-in login link (or a bare ULID).
+fn participant_id_from_input(raw: &str) -> Option<String> {
+    raw.trim()
+        .split(['/', '?', '#'])
+        .find_map(|segment| Ulid::from_string(segment).ok())
+        .map(|ulid| ulid.to_string())
+}
+
+/// Where a sign-in lands: `next` if it's one of the participant's own
+/// pages, else their dashboard.
+fn participant_landing(next: Option<&str>, participant_id: &str) -> String {
+    safe_participant_next(next, participant_id)
+        .unwrap_or_else(|| format!("/dashboard/{participant_id}"))
+}
+
+/// Redirect to `target` with a fresh session cookie for
+/// `participant_id` under `epoch`.
+fn participant_signed_in(
+    state: &AppState,
+    participant_id: &str,
+    epoch: i64,
+    target: &str,
+) -> Response {
+    let (_, cookie) =
+        state
+            .sessions
+            .issue_participant(participant_id, epoch, chrono::Utc::now().timestamp());
+    let mut response = axum::response::Redirect::to(target).into_response();
+    response.headers_mut().insert(
+        SET_COOKIE,
+        session_cookie(state, PARTICIPANT_COOKIE, &cookie, PARTICIPANT_SESSION_TTL),
+    );
+    response
+}
+
+fn render_login(template: &LoginTemplate, status: StatusCode) -> Response {
+    match template.render() {
+        Ok(html) => (status, Html(html)).into_response(),
+        Err(err) => {
+            error!("login template render failed: {err}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to render template",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Participant sign-in form.
+async fn login_page(Query(query): Query<LoginQuery>) -> Response {
+    let participant = query.next.as_deref().and_then(participant_id_from_input);
+    let next = participant
+        .as_deref()
+        .and_then(|ulid| safe_participant_next(query.next.as_deref(), ulid))
+        .unwrap_or_default();
+    let notice = match query.reason.as_deref() {
+        Some("signed-out") => Some("You have been signed out on this browser."),
+        _ if participant.is_some() => Some("This account needs a passphrase or a login code."),
+        _ => None,
+    };
+    render_login(
+        &LoginTemplate {
+            participant,
+            error: None,
+            notice,
+            next,
+        },
+        StatusCode::OK,
+    )
+}
+
+/// What a failed participant sign-in counts against: the client
+/// address always, and the account when the form named one, so
+/// guesses spread over many addresses still slow down.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum SignInKey {
+    Address(IpAddr),
+    Account(String),
+}
+
+/// Seconds until every one of `keys` may try again.
+fn sign_in_wait(throttle: &LoginThrottle<SignInKey>, keys: &[SignInKey], now: i64) -> i64 {
+    keys.iter()
+        .map(|key| throttle.wait(key, now))
+        .max()
+        .unwrap_or(0)
+}
+
+/// The login page, asking the client to come back in `wait` seconds.
+fn throttled_sign_in(participant: Option<String>, next: String, wait: i64) -> Response {
+    warn!("Throttled participant sign-in");
+    let response = render_login(
+        &LoginTemplate {
+            participant,
+            error: Some("Too many failed sign-ins. Wait a moment, then try again."),
+            notice: None,
+            next,
+        },
+        StatusCode::TOO_MANY_REQUESTS,
+    );
+    retry_after(response, wait)
+}
+
+/// Run Argon2 work, slow on purpose, off the async workers. At most
+/// `argon2_permits` run at once, so a flood of sign-ins queues up
+/// instead of taking every core.
+async fn run_argon2<T: Send + 'static>(
+    state: &AppState,
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, tokio::task::JoinError> {
+    // The semaphore is never closed, so this always gets a permit.
+    let permit = Arc::clone(&state.argon2_permits).acquire_owned().await;
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        work()
+    })
+    .await
+}
+
+/// Sign in with the login link plus passphrase.
+async fn login_with_passphrase(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<PassphraseLoginForm>,
+) -> Response {
+    let participant = participant_id_from_input(&form.participant);
+    let now = chrono::Utc::now().timestamp();
+    let mut keys = vec![SignInKey::Address(client_ip(&state, &headers, peer))];
+    keys.extend(participant.clone().map(SignInKey::Account));
+    let wait = sign_in_wait(&state.participant_throttle, &keys, now);
+    if wait > 0 {
+        let next = participant
+            .as_deref()
+            .and_then(|ulid| safe_participant_next(form.next.as_deref(), ulid))
+            .unwrap_or_default();
+        return throttled_sign_in(participant, next, wait);
+    }
+
+    let auth = match &participant {
+        Some(ulid) => match load_participant_auth(&state.db, ulid).await {
+            Ok(auth) => auth,
+            Err(err) => {
+                error!("Failed to load sign-in settings for {ulid}: {err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        },
+        None => None,
+    };
+
+    let hash = auth.as_ref().and_then(|auth| auth.passphrase_hash.clone());
+    let passphrase = form.passphrase;
+    let verified = match hash {
+        Some(hash) => run_argon2(&state, move || {
+            session::verify_passphrase(&passphrase, &hash)
+        })
+        .await
+        .unwrap_or_else(|err| {
+            error!("Passphrase check failed: {err}");
+            false
+        }),
+        None => false,
+    };
+
+    // Unknown participant, no passphrase set and a wrong passphrase all
+    // get the same answer.
+    match (participant, auth) {
+        (Some(ulid), Some(auth)) if verified => {
+            info!("Participant {ulid} signed in with a passphrase");
+            for key in &keys {
+                state.participant_throttle.succeeded(key);
+            }
+            let target = participant_landing(form.next.as_deref(), &ulid);
+            participant_signed_in(&state, &ulid, auth.auth_epoch, &target)
+        }
+        (participant, _) => {
+            warn!("Failed passphrase sign-in");
+            for key in keys {
+                state.participant_throttle.failed(key, now);
+            }
+            let next = participant
+                .as_deref()
+                .and_then(|ulid| safe_participant_next(form.next.as_deref(), ulid))
+                .unwrap_or_default();
+            render_login(
+                &LoginTemplate {
+                    participant,
+                    error: Some("That login link and passphrase don't match."),
+                    notice: None,
+                    next,
+                },
+                StatusCode::UNAUTHORIZED,
+            )
+        }
+    }
+}
+
+/// Sign in with a one-time code from an instructor. The code is spent
+/// even if the redirect never arrives. Codes name no account, so wrong
+/// ones only count against the client address.
+async fn login_with_code(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<CodeLoginForm>,
+) -> Response {
+    let now = chrono::Utc::now().timestamp();
+    let key = SignInKey::Address(client_ip(&state, &headers, peer));
+    let wait = state.participant_throttle.wait(&key, now);
+    if wait > 0 {
+        return throttled_sign_in(None, String::new(), wait);
+    }
+    let code_hash = session::secret_hash(&session::normalize_login_code(&form.code));
+    let redeemed: Result<Option<(String, i64)>, sqlx::Error> =
+        with_pool!(&state.db, |pool| sqlx::query_as(
+            "UPDATE participant_login_codes SET used_at = CURRENT_TIMESTAMP
+             WHERE code_hash = $1 AND used_at IS NULL AND expires_at > $2
+               AND participant_id IN (SELECT id FROM participants WHERE deleted_at IS NULL)
+             RETURNING participant_id,
+                 (SELECT auth_epoch FROM participants p WHERE p.id = participant_id)",
+        )
+        .bind(&code_hash)
+        .bind(now)
+        .fetch_optional(pool)
+        .await);
+
+    match redeemed {
+        Ok(Some((ulid, epoch))) => {
+            info!("Participant {ulid} signed in with a login code");
+            state.participant_throttle.succeeded(&key);
+            let target = participant_landing(form.next.as_deref(), &ulid);
+            participant_signed_in(&state, &ulid, epoch, &target)
+        }
+        Ok(None) => {
+            warn!("Failed login code sign-in");
+            state.participant_throttle.failed(key, now);
+            render_login(
+                &LoginTemplate {
+                    participant: None,
+                    error: Some("That login code is unknown, used or expired."),
+                    notice: None,
+                    next: String::new(),
+                },
+                StatusCode::UNAUTHORIZED,
+            )
+        }
+        Err(err) => {
+            error!("Failed to redeem login code: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// Forget the participant session on this browser.
+async fn logout(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<CsrfForm>,
+) -> Response {
+    if let Some(session) = participant_session(&state, &headers) {
+        if !state.sessions.check_participant_csrf(
+            &session.participant_id,
+            Some(&session),
+            &form.csrf,
+        ) {
+            return (
+                StatusCode::FORBIDDEN,
+                "Invalid CSRF token. Reload the page and retry.",
+            )
+                .into_response();
+        }
+        info!("Participant {} signed out", session.participant_id);
+    }
+    let mut response = axum::response::Redirect::to("/login?reason=signed-out").into_response();
+    response.headers_mut().insert(
+        SET_COOKIE,
+        session_cookie(&state, PARTICIPANT_COOKIE, "", 0),
+    );
+    response
+}
+
+/// A link without a ULID in it: the signed-in participant's dashboard.
+async fn my_dashboard(headers: HeaderMap, State(state): State<AppState>) -> Response {
+    let target = participant_session(&state, &headers).map_or_else(
+        || "/login".to_string(),
+        |session| format!("/dashboard/{}", session.participant_id),
+    );
+    axum::response::Redirect::to(&target).into_response()
+}
+
+/// Admin: issue a one-time login code for a participant. From then on
+/// the participant needs it (or a passphrase) to open their pages.
+async fn admin_issue_login_code(
+    AxumPath(participant_id): AxumPath<String>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Response {
+    let admin = match require_admin_csrf(&state, &headers, None).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    if let Err(response) = require_participant_in_scope(&state, &admin, &participant_id).await {
+        return response;
+    }
+
+    let code = session::new_login_code();
+    let expires_at = chrono::Utc::now().timestamp() + LOGIN_CODE_TTL;
+    let result = with_pool!(&state.db, |pool| async {
+        let mut tx = pool.begin().await?;
+        let Some(auth_required): Option<bool> = sqlx::query_scalar(
+            "SELECT auth_required FROM participants WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(&participant_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(false);
+        };
+        sqlx::query("UPDATE participants SET auth_required = TRUE WHERE id = $1")
+            .bind(&participant_id)
+            .execute(&mut *tx)
+            .await?;
+        // The code itself is a secret; its expiry is enough to tell
+        // codes apart in the log.
+        admin_audit_insert(
+            &admin,
+            "participant.login_code",
+            Some(&participant_id),
+            Some(serde_json::json!({ "auth_required": auth_required })),
+            Some(serde_json::json!({ "auth_required": true, "code_expires_at": expires_at })),
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO participant_login_codes (code_hash, participant_id, expires_at)
+             VALUES ($1, $2, $3)",
+        )
+        .bind(session::secret_hash(&session::normalize_login_code(&code)))
+        .bind(&participant_id)
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(true)
+    }
+    .await);
+
+    match result {
+        Ok(true) => {
+            info!("Admin issued a login code for participant {participant_id}");
+            Json(serde_json::json!({ "code": code, "expires_at": expires_at })).into_response()
+        }
+        Ok(false) => (StatusCode::NOT_FOUND, "Participant not found").into_response(),
+        Err(err) => {
+            error!("Failed to issue login code for {participant_id}: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+// reason: top-level request handler; splitting purely for line count adds indirection without value
+#[allow(clippy::too_many_lines)]
+async fn admin_dashboard(
+    headers: HeaderMap,
+    OriginalUri(uri): OriginalUri,
+    State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
+) -> impl IntoResponse {
+    let admin = match require_admin_page(&state, &headers, &uri).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    let scope = admin.instructor_id();
+
+    // Get participant summaries. We only pull identity + last-activity
+    // from SQL here; the completed/total counts are computed below with
+    // `get_exercise_progress` so they match exactly what each
+    // participant sees on their own dashboard.
+    let participant_rows_result: Result<Vec<ParticipantActivityRow>, _> =
+        with_pool!(&state.db, |pool| sqlx::query_as(&format!(
+            r"
+            SELECT
+                p.id,
+                p.name,
+                p.team_token,
+                MAX(s.submitted_at) as last_activity
+            FROM participants p
+            LEFT JOIN submissions s
+                ON p.id = s.participant_id AND s.tests_passed = TRUE AND s.course = $2
+            WHERE p.deleted_at IS NULL AND {ADMIN_SCOPE_FILTER} AND {COURSE_ENROLLED_FILTER}
+            GROUP BY p.id, p.name, p.team_token
+            ORDER BY
+                CASE WHEN p.team_token IS NULL THEN 1 ELSE 0 END,
+                lower(p.team_token),
+                last_activity DESC NULLS LAST
+            "
+        ))
+        .bind(scope)
+        .bind(&course.slug)
+        .fetch_all(pool)
+        .await);
+
+    let Ok(participant_rows) = participant_rows_result else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to fetch participants",
+        )
+            .into_response();
+    };
+
+    // Denominator: the number of *completable* chapters in the catalog.
+    // Notes-only chapters and practice quizzes never count toward
+    // progress, so they're excluded here just like in
+    // `participant_dashboard` / `render_exercise_page`.
+    let catalog = course.catalog.current();
+
+    let mut participants = Vec::new();
+    for row in participant_rows {
+        // Rows with a column value the validator rejects degrade to
+        // `None` (the participant lands in the "Unassigned" bucket).
+        // That can't happen via our own write paths, but it keeps a
+        // hand-edited DB row from taking the page down.
+        let team_token = row
+            .team_token
+            .as_deref()
+            .and_then(|s| TeamToken::try_from(s).ok());
+
+        // Numerator (inside `build_participant_summary`): distinct
+        // completable chapters this participant has finished. Counting
+        // passing *submissions* badly overcounts, since multiple
+        // submissions per step are allowed and multi-step chapters
+        // store one row per step. A per-participant query is fine on
+        // this low-traffic admin page.
+        participants.push(
+            build_participant_summary(
+                &state,
+                &course,
+                &catalog,
+                row.id,
+                row.name,
+                team_token,
+                row.last_activity,
+            )
+            .await,
+        );
+    }
+
+    // Bucket the flat list into teams. The query already sorted
+    // by team_token (with NULLs last) so a single linear pass is
+    // enough to produce one bucket per distinct value.
+    let mut participant_teams = bucket_participants_by_team(participants);
+
+    // Order each bucket by the default sort the template advertises
+    // (name, ascending) so the column header arrow matches what the
+    // operator first sees. Later sorts/filters are handled by
+    // `admin_team_members` over htmx.
+    for team in &mut participant_teams {
+        apply_member_filter_sort(&mut team.members, "name", "asc", "");
+    }
+
+    // Get recent submissions
+    let submission_rows_result: Result<Vec<SubmissionSummaryRow>, _> =
+        with_pool!(&state.db, |pool| sqlx::query_as(&format!(
+            r"
+            SELECT p.name AS participant_name, {SUBMISSION_SUMMARY_COLUMNS}
+            FROM submissions s
+            JOIN participants p ON s.participant_id = p.id
+            WHERE p.deleted_at IS NULL AND {ADMIN_SCOPE_FILTER} AND s.course = $2
+            ORDER BY s.submitted_at DESC
+            LIMIT 20
+            "
+        ))
+        .bind(scope)
+        .bind(&course.slug)
+        .fetch_all(pool)
+        .await);
+
+    let Ok(submission_rows) = submission_rows_result else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to fetch submissions",
+        )
+            .into_response();
+    };
+
+    let recent_submissions: Vec<SubmissionSummary> = submission_rows
+        .into_iter()
+        .map(SubmissionSummary::from)
+        .collect();
+
+    let removed_result: Result<Vec<RemovedParticipant>, sqlx::Error> =
+        with_pool!(&state.db, |pool| sqlx::query_as(&format!(
+            "SELECT p.id, p.name, p.team_token, p.deleted_at
+             FROM participants p
+             WHERE p.deleted_at IS NOT NULL AND {ADMIN_SCOPE_FILTER} AND {COURSE_ENROLLED_FILTER}
+             ORDER BY p.deleted_at DESC"
+        ))
+        .bind(scope)
+        .bind(&course.slug)
+        .fetch_all(pool)
+        .await);
+    let Ok(removed) = removed_result else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to fetch removed participants",
+        )
+            .into_response();
+    };
+
+    // Get admin statistics with proper SQL queries
+    let Ok(admin_stats) = get_admin_stats(&state.db, scope, &course.slug).await else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to get admin statistics",
+        )
+            .into_response();
+    };
+
+    // Get unique exercises from submissions
+    let exercise_rows_result: Result<Vec<String>, _> =
+        with_pool!(&state.db, |pool| sqlx::query_scalar(&format!(
+            "SELECT DISTINCT s.exercise_name FROM submissions s
+             JOIN participants p ON s.participant_id = p.id
+             WHERE p.deleted_at IS NULL AND {ADMIN_SCOPE_FILTER} AND s.course = $2
+             ORDER BY s.exercise_name"
+        ))
+        .bind(scope)
+        .bind(&course.slug)
+        .fetch_all(pool)
+        .await);
+
+    let exercises = exercise_rows_result.unwrap_or_default();
+
+    let Ok(released_chapters) = load_releases(&state.db, &course.slug).await else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to fetch chapter releases",
+        )
+            .into_response();
+    };
+    let releases = catalog
+        .iter()
+        .filter(|e| e.unlock() == Unlock::Release)
+        .map(|e| ChapterRelease {
+            chapter: e.file_stem.clone(),
+            number: e.number,
+            title: e.title.clone(),
+            released: released_chapters.contains(&e.file_stem),
+        })
+        .collect();
+
+    let Ok(quiz_questions) =
+        load_quiz_question_stats(&state.db, scope, &course.slug, &catalog).await
+    else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to fetch quiz answers",
+        )
+            .into_response();
+    };
+
+    let template = AdminTemplate {
+        base: course.base(),
+        course_title: course.title.clone(),
+        courses: course_links(&state.courses, &course, "/admin", &[]),
+        participant_teams,
+        recent_submissions,
+        stats: admin_stats,
+        exercises,
+        csrf_token: state.sessions.csrf_token(&admin.session),
+        instructor: admin.instructor.map(|scope| InstructorBanner {
+            name: scope.name,
+            teams: scope
+                .teams
+                .iter()
+                .map(|team| team.as_str().to_string())
+                .collect(),
+        }),
+        removed,
+        content: course.catalog.status(),
+        content_chapters: catalog.len(),
+        releases,
+        quiz_questions,
+        sort: "name".to_string(),
+        dir: "asc".to_string(),
+        filter: String::new(),
+    };
+
+    template.render().map_or_else(
+        |_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to render template",
+            )
+                .into_response()
+        },
+        |html| Html(html).into_response(),
+    )
+}
+
+/// Query parameters for the `admin_team_members` htmx fragment. `team`
+/// is the slug (empty string = Unassigned bucket); `sort`/`dir`/`filter`
+/// carry the current table state forwarded by the column-header sort
+/// buttons and the per-team filter box.
+#[derive(Deserialize)]
+struct TeamMembersQuery {
+    #[serde(default)]
+    team: String,
+    #[serde(default)]
+    sort: String,
+    #[serde(default)]
+    dir: String,
+    #[serde(default)]
+    filter: String,
+}
+
+/// htmx fragment endpoint backing per-team sorting and filtering on the
+/// admin dashboard. Returns just the `partials/team_members.html`
+/// table (the swap target), re-rendered with the requested sort column,
+/// direction, and name filter applied.
+async fn admin_team_members(
+    Query(query): Query<TeamMembersQuery>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
+) -> impl IntoResponse {
+    let admin = match require_admin(&state, &headers).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    let team_token = if query.team.trim().is_empty() {
+        None
+    } else {
+        match TeamToken::try_from(query.team.as_str()) {
+            Ok(token) => Some(token),
+            Err(_) => return (StatusCode::BAD_REQUEST, "Invalid team slug").into_response(),
+        }
+    };
+    if !admin.covers(team_token.as_ref()) {
+        return (StatusCode::FORBIDDEN, "Not one of your teams").into_response();
+    }
+
+    let Ok(mut members) = load_team_member_summaries(&state, &course, team_token.as_ref()).await
+    else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to fetch team members",
+        )
+            .into_response();
+    };
+
+    // Normalise the incoming state so the template's arrows and the
+    // hx-get URLs it bakes back out stay in the {name|progress|activity}
+    // x {asc|desc} space regardless of what arrived on the query string.
+    let sort = if matches!(query.sort.as_str(), "progress" | "activity") {
+        query.sort
+    } else {
+        "name".to_string()
+    };
+    let dir = if query.dir == "desc" {
+        "desc".to_string()
+    } else {
+        "asc".to_string()
+    };
+    apply_member_filter_sort(&mut members, &sort, &dir, &query.filter);
+
+    let team = ParticipantTeam {
+        team_token,
+        members,
+    };
+    let template = TeamMembersTemplate {
+        base: course.base(),
+        team,
+        csrf_token: state.sessions.csrf_token(&admin.session),
+        sort,
+        dir,
+        filter: query.filter,
+    };
+
+    template.render().map_or_else(
+        |_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to render template",
+            )
+                .into_response()
+        },
+        |html| Html(html).into_response(),
+    )
+}
+
+/// Form data for `POST /admin/instructors`.
+#[derive(Deserialize)]
+struct InstructorForm {
+    #[serde(default)]
+    csrf: String,
+    name: String,
+    #[serde(default)]
+    teams: String,
+}
+
+/// Form data for `POST /admin/instructors/{id}/teams`.
+#[derive(Deserialize)]
+struct InstructorTeamsForm {
+    #[serde(default)]
+    csrf: String,
+    #[serde(default)]
+    teams: String,
+}
+
+/// Form data for `POST /admin/participants/{ulid}/team-token`.
+#[derive(Deserialize)]
+struct TeamTokenForm {
+    team_token: String,
+    #[serde(default)]
+    csrf: String,
+    /// See [`CsrfForm::next`].
+    #[serde(default)]
+    next: Option<String>,
+}
+
+/// Move a participant into a different team bucket (or out of any).
+///
+/// Submitted by the inline form on the admin participants table. The
+/// `team_token` field is normalised via `normalize_team_token`:
+/// blank values clear the column (sending the participant to the
+/// "Unassigned" bucket), anything else has to be a short slug.
+///
+/// Always redirects back to `/admin` on success so the admin sees the
+/// regrouped table immediately.
+async fn admin_set_team_token(
+    AxumPath(participant_id): AxumPath<String>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<TeamTokenForm>,
+) -> impl IntoResponse {
+    let admin = match require_admin_csrf(&state, &headers, Some(&form.csrf)).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    if let Err(response) = require_participant_in_scope(&state, &admin, &participant_id).await {
+        return response;
+    }
+
+    let new_token = match TeamToken::parse_form_input(&form.team_token) {
+        Ok(value) => value,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid team_token: {err}"),
+            )
+                .into_response();
+        }
+    };
+    // Instructors can move people between their own teams, but not out
+    // of them (that includes Unassigned).
+    if !admin.covers(new_token.as_ref()) {
+        return (
+            StatusCode::FORBIDDEN,
+            "You can only move participants into one of your teams.",
+        )
+            .into_response();
+    }
+
+    let new_slug = new_token.as_ref().map(TeamToken::as_str);
+    let result = with_pool!(&state.db, |pool| async {
+        let mut tx = pool.begin().await?;
+        let Some(old_slug): Option<Option<String>> = sqlx::query_scalar(
+            "SELECT team_token FROM participants WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(&participant_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(false);
+        };
+        sqlx::query("UPDATE participants SET team_token = $1 WHERE id = $2")
+            .bind(new_slug)
+            .bind(&participant_id)
+            .execute(&mut *tx)
+            .await?;
+        admin_audit_insert(
+            &admin,
+            "participant.move",
+            Some(&participant_id),
+            Some(serde_json::json!({ "team_token": old_slug })),
+            Some(serde_json::json!({ "team_token": new_slug })),
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(true)
+    }
+    .await);
+
+    match result {
+        Ok(false) => (StatusCode::NOT_FOUND, "Participant not found").into_response(),
+        Ok(true) => {
+            info!("Admin moved participant {participant_id} to team {new_token:?}");
+            // 303 so the browser follows up with a GET.
+            axum::response::Redirect::to(&safe_admin_next(form.next.as_deref())).into_response()
+        }
+        Err(err) => {
+            error!("Failed to update team_token for {participant_id}: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// Builds the per-team page (`team.html`) for a given team label.
+///
+/// `team_token = None` selects the synthetic Unassigned bucket
+/// (every participant whose `participants.team_token` column is
+/// NULL). `viewer_ulid` highlights the matching member row in
+/// participant mode and is ignored in admin mode. Returns the
+/// rendered HTML response, or a 500 if any of the supporting queries
+/// fail.
+// reason: top-level request handler; splitting purely for line count adds indirection without value
+/// Snapshot of a single team (or the unassigned bucket): roster,
+/// recent submissions, truncation flag, and the distinct exercises
+/// present in the feed. Shared between [`render_team_page`] and the
+/// settings page so both pages see the same data and respect the same
+/// `TEAM_SUBMISSIONS_LIMIT`.
+#[allow(clippy::too_many_lines)]
+async fn load_team_view(
+    state: &AppState,
+    course: &Course,
+    team_token: Option<&TeamToken>,
+    viewer_ulid: Option<&str>,
+) -> Result<
+    (
+        Vec<TeamMemberView>,
+        Vec<SubmissionSummary>,
+        bool,
+        Vec<String>,
+    ),
+    axum::response::Response,
+> {
+    let total_exercises: i64 = i64::try_from(course.catalog.current().len()).unwrap_or(i64::MAX);
+
+    // Roster: every participant in this team enrolled in the course,
+    // with a count of their passing submissions to it and the timestamp
+    // of their most recent one.
+    // We branch on the SQL because SQLite has no portable way to bind
+    // an Option<&str> against `IS NULL` in a single statement.
+    let roster_query = match team_token {
+        Some(_) => {
+            r"
+            SELECT p.id, p.name,
+                   COUNT(s.id) AS completed_count,
+                   MAX(s.submitted_at) AS last_activity
+            FROM participants p
+            JOIN course_enrollments e ON e.participant_id = p.id AND e.course = $1
+            LEFT JOIN submissions s
+                ON p.id = s.participant_id AND s.tests_passed = TRUE AND s.course = $1
+            WHERE p.team_token = $2 AND p.deleted_at IS NULL
+            GROUP BY p.id, p.name
+            ORDER BY last_activity DESC NULLS LAST, lower(p.name)
+            "
+        }
+        None => {
+            r"
+            SELECT p.id, p.name,
+                   COUNT(s.id) AS completed_count,
+                   MAX(s.submitted_at) AS last_activity
+            FROM participants p
+            JOIN course_enrollments e ON e.participant_id = p.id AND e.course = $1
+            LEFT JOIN submissions s
+                ON p.id = s.participant_id AND s.tests_passed = TRUE AND s.course = $1
+            WHERE p.team_token IS NULL AND p.deleted_at IS NULL
+            GROUP BY p.id, p.name
+            ORDER BY last_activity DESC NULLS LAST, lower(p.name)
+            "
+        }
+    };
+    let roster_result: Result<Vec<TeamRosterRow>, _> = with_pool!(&state.db, |pool| {
+        let roster_q = sqlx::query_as(roster_query).bind(&course.slug);
+        let roster_q = match team_token {
+            Some(t) => roster_q.bind(t.as_str()),
+            None => roster_q,
+        };
+        roster_q.fetch_all(pool).await
+    });
+    let roster_rows = match roster_result {
+        Ok(rows) => rows,
+        Err(err) => {
+            error!("team page roster query failed: {err}");
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response());
+        }
+    };
+
+    // Submissions feed: every member's latest activity, capped so a
+    // very busy team doesn't render thousands of editor instances.
+    let member_ids: Vec<String> = roster_rows.iter().map(|row| row.id.clone()).collect();
+
+    let mut members = Vec::with_capacity(roster_rows.len());
+    for row in roster_rows {
+        let is_self = viewer_ulid.is_some_and(|u| u == row.id.as_str());
+        members.push(TeamMemberView {
+            id: row.id,
+            name: row.name,
+            completed_count: row.completed_count,
+            total_exercises,
+            last_activity: row.last_activity,
+            is_self,
+        });
+    }
+
+    let (submissions, submissions_truncated) = if member_ids.is_empty() {
+        (Vec::new(), false)
+    } else {
+        // Hand-rolled IN list. Member IDs are ULIDs we just read out
+        // of the same DB so there's no untrusted input to escape;
+        // sqlx's prepared-statement placeholders go in via `bind`.
+        let placeholders = (2..=member_ids.len() + 1)
+            .map(|n| format!("${n}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let limit_param = member_ids.len() + 2;
+        let sql = format!(
+            r"
+            SELECT p.name AS participant_name, {SUBMISSION_SUMMARY_COLUMNS}
+            FROM submissions s
+            JOIN participants p ON s.participant_id = p.id
+            WHERE s.course = $1 AND s.participant_id IN ({placeholders})
+            ORDER BY s.submitted_at DESC
+            LIMIT ${limit_param}
+            "
+        );
+        let result: Result<Vec<SubmissionSummaryRow>, _> = with_pool!(&state.db, |pool| {
+            let mut q = sqlx::query_as(&sql).bind(&course.slug);
+            for id in &member_ids {
+                q = q.bind(id);
+            }
+            // +1 so we can detect truncation without a second query.
+            q = q.bind(TEAM_SUBMISSIONS_LIMIT + 1);
+            q.fetch_all(pool).await
+        });
+        let rows = match result {
+            Ok(rows) => rows,
+            Err(err) => {
+                error!("team page submissions query failed: {err}");
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response());
+            }
+        };
+        let truncated = i64::try_from(rows.len()).unwrap_or(i64::MAX) > TEAM_SUBMISSIONS_LIMIT;
+        let limit = usize::try_from(TEAM_SUBMISSIONS_LIMIT).unwrap_or(0);
+        let subs = rows
+            .into_iter()
+            .take(limit)
+            .map(SubmissionSummary::from)
+            .collect();
+        (subs, truncated)
+    };
+
+    // Distinct exercise names from the submissions we're about to
+    // render. Sorted for a stable, alphabetic dropdown.
+    let mut exercises: Vec<String> = submissions
+        .iter()
+        .map(|s| s.exercise_name.clone())
+        .collect();
+    exercises.sort();
+    exercises.dedup();
+
+    Ok((members, submissions, submissions_truncated, exercises))
+}
+
+/// The back link leads to the viewer's dashboard, or for admins
+/// (`viewer_ulid` is `None`) to the course's admin page.
+async fn render_team_page(
+    state: &AppState,
+    course: &Course,
+    team_token: Option<&TeamToken>,
+    is_admin: bool,
+    csrf_token: Option<String>,
+    viewer_ulid: Option<&str>,
+) -> axum::response::Response {
+    let (members, submissions, submissions_truncated, exercises) =
+        match load_team_view(state, course, team_token, viewer_ulid).await {
+            Ok(view) => view,
+            Err(response) => return response,
+        };
+
+    let (team_label, is_unassigned) = team_token.map_or_else(
+        || ("Unassigned".to_string(), true),
+        |t| (t.as_str().to_string(), false),
+    );
+
+    let (back_href, back_label) = viewer_ulid.map_or_else(
+        || (format!("{}/admin", course.base()), "Back to admin"),
+        |ulid| {
+            (
+                format!("{}/dashboard/{ulid}", course.base()),
+                "Back to your dashboard",
+            )
+        },
+    );
+    let template = TeamPageTemplate {
+        base: course.base(),
+        team_label,
+        is_unassigned,
+        is_admin,
+        csrf_token,
+        viewer_ulid: viewer_ulid.map(str::to_string),
+        members,
+        submissions,
+        submissions_truncated,
+        exercises,
+        back_href,
+        back_label: back_label.to_string(),
+    };
+
+    match template.render() {
+        Ok(html) => Html(html).into_response(),
+        Err(err) => {
+            error!("team page template render failed: {err}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to render template",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Admin: per-team page for a real team slug.
+async fn admin_team_page(
+    AxumPath(slug): AxumPath<String>,
+    headers: HeaderMap,
+    OriginalUri(uri): OriginalUri,
+    State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
+) -> impl IntoResponse {
+    let admin = match require_admin_page(&state, &headers, &uri).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    let Ok(token) = TeamToken::try_from(slug.as_str()) else {
+        return (StatusCode::BAD_REQUEST, "Invalid team slug").into_response();
+    };
+    if !admin.covers(Some(&token)) {
+        return (StatusCode::FORBIDDEN, "Not one of your teams").into_response();
+    }
+    render_team_page(
+        &state,
+        &course,
+        Some(&token),
+        true,
+        Some(state.sessions.csrf_token(&admin.session)),
+        None,
+    )
+    .await
+}
+
+/// Admin: per-team page for the synthetic Unassigned bucket.
+async fn admin_team_unassigned_page(
+    headers: HeaderMap,
+    OriginalUri(uri): OriginalUri,
+    State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
+) -> impl IntoResponse {
+    let admin = match require_admin_page(&state, &headers, &uri).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    if let Err(response) = require_super_admin(&admin) {
+        return response;
+    }
+    render_team_page(
+        &state,
+        &course,
+        None,
+        true,
+        Some(state.sessions.csrf_token(&admin.session)),
+        None,
+    )
+    .await
+}
+
+/// Team slugs typed into the instructor forms, separated by commas or
+/// whitespace. Duplicates are dropped; order is kept.
+fn parse_team_list(raw: &str) -> Result<Vec<TeamToken>, TeamTokenError> {
+    let mut teams: Vec<TeamToken> = Vec::new();
+    for slug in raw
+        .split([',', ' ', '\t', '\n', '\r'])
+        .filter(|s| !s.is_empty())
+    {
+        let team = TeamToken::try_from(slug)?;
+        if !teams.contains(&team) {
+            teams.push(team);
+        }
+    }
+    Ok(teams)
+}
+
+async fn render_admin_instructors(
+    state: &AppState,
+    admin: &Admin,
+    new_instructor: Option<(String, String)>,
+    error: Option<String>,
+) -> Response {
+    let instructors: Vec<InstructorRow> = match with_pool!(&state.db, |pool| sqlx::query_as(
+        "SELECT i.id, i.name, i.created_at, i.last_login_at,
+             string_agg(t.team_token, ', ') AS teams
+         FROM instructors i
+         LEFT JOIN instructor_teams t ON t.instructor_id = i.id
+         GROUP BY i.id
+         ORDER BY lower(i.name)",
+    )
+    .fetch_all(pool)
+    .await)
+    {
+        Ok(rows) => rows,
+        Err(err) => {
+            error!("Failed to load instructors: {err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    let status = if error.is_some() {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::OK
+    };
+    let template = AdminInstructorsTemplate {
+        csrf_token: state.sessions.csrf_token(&admin.session),
+        instructors,
+        new_instructor,
+        error,
+    };
+    match template.render() {
+        Ok(html) => (status, Html(html)).into_response(),
+        Err(err) => {
+            error!("instructors template render failed: {err}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to render template",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Super-admin: list instructor accounts and their teams.
+async fn admin_instructors_page(
+    headers: HeaderMap,
+    uri: Uri,
+    State(state): State<AppState>,
+) -> Response {
+    let admin = match require_admin_page(&state, &headers, &uri).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    if let Err(response) = require_super_admin(&admin) {
+        return response;
+    }
+    render_admin_instructors(&state, &admin, None, None).await
+}
+
+/// Super-admin: create an instructor and show their sign-in token once.
+async fn admin_create_instructor(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<InstructorForm>,
+) -> Response {
+    let admin = match require_admin_csrf(&state, &headers, Some(&form.csrf)).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    if let Err(response) = require_super_admin(&admin) {
+        return response;
+    }
+
+    let name = form.name.trim();
+    if name.is_empty() || name.chars().count() > 64 {
+        let error = "Give the instructor a name of at most 64 characters.".to_string();
+        return render_admin_instructors(&state, &admin, None, Some(error)).await;
+    }
+    let teams = match parse_team_list(&form.teams) {
+        Ok(teams) => teams,
+        Err(err) => {
+            let error = format!("Invalid team list: {err}.");
+            return render_admin_instructors(&state, &admin, None, Some(error)).await;
+        }
+    };
+
+    let id = Ulid::new().to_string();
+    let token = session::new_instructor_token();
+    let result = with_pool!(&state.db, |pool| async {
+        let mut tx = pool.begin().await?;
+        sqlx::query("INSERT INTO instructors (id, name, token_hash) VALUES ($1, $2, $3)")
+            .bind(&id)
+            .bind(name)
+            .bind(session::secret_hash(&token))
+            .execute(&mut *tx)
+            .await?;
+        for team in &teams {
+            sqlx::query("INSERT INTO instructor_teams (instructor_id, team_token) VALUES ($1, $2)")
+                .bind(&id)
+                .bind(team.as_str())
+                .execute(&mut *tx)
+                .await?;
+        }
+        admin_audit_insert(
+            &admin,
+            "instructor.create",
+            None,
+            None,
+            Some(serde_json::json!({ "instructor_id": id, "name": name, "teams": teams })),
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+    .await);
+    if let Err(err) = result {
+        error!("Failed to create instructor: {err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+    }
+
+    info!("Admin created instructor {id} for teams {teams:?}");
+    render_admin_instructors(&state, &admin, Some((name.to_string(), token)), None).await
+}
+
+/// Super-admin: replace an instructor's team assignments.
+async fn admin_set_instructor_teams(
+    AxumPath(instructor_id): AxumPath<String>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<InstructorTeamsForm>,
+) -> Response {
+    let admin = match require_admin_csrf(&state, &headers, Some(&form.csrf)).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    if let Err(response) = require_super_admin(&admin) {
+        return response;
+    }
+    let teams = match parse_team_list(&form.teams) {
+        Ok(teams) => teams,
+        Err(err) => {
+            let error = format!("Invalid team list: {err}.");
+            return render_admin_instructors(&state, &admin, None, Some(error)).await;
+        }
+    };
+
+    let result = with_pool!(&state.db, |pool| async {
+        let mut tx = pool.begin().await?;
+        let Some(name): Option<String> =
+            sqlx::query_scalar("SELECT name FROM instructors WHERE id = $1")
+                .bind(&instructor_id)
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            return Ok(false);
+        };
+        let old_teams: Vec<String> = sqlx::query_scalar(
+            "SELECT team_token FROM instructor_teams WHERE instructor_id = $1 ORDER BY team_token",
+        )
+        .bind(&instructor_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let before =
+            serde_json::json!({ "instructor_id": instructor_id, "name": name, "teams": old_teams });
+        admin_audit_insert(
+            &admin,
+            "instructor.teams",
+            None,
+            Some(before),
+            Some(serde_json::json!({ "teams": teams })),
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM instructor_teams WHERE instructor_id = $1")
+            .bind(&instructor_id)
+            .execute(&mut *tx)
+            .await?;
+        for team in &teams {
+            sqlx::query("INSERT INTO instructor_teams (instructor_id, team_token) VALUES ($1, $2)")
+                .bind(&instructor_id)
+                .bind(team.as_str())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(true)
+    }
+    .await);
 
     match result {
-        Ok(res) if res.rows_affected() == 0 => {
-            (StatusCode::NOT_FOUND, "Participant not found").into_response()
+        Ok(true) => {
+            info!("Admin assigned instructor {instructor_id} to teams {teams:?}");
+            axum::response::Redirect::to("/admin/instructors").into_response()
+        }
+        Ok(false) => (StatusCode::NOT_FOUND, "Instructor not found").into_response(),
+        Err(err) => {
+            error!("Failed to update teams of instructor {instructor_id}: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
         }
+    }
+}
+
+/// Super-admin: delete an instructor. Their sessions stop working on
+/// the next request, since [`current_admin`] no longer finds them.
+async fn admin_delete_instructor(
+    AxumPath(instructor_id): AxumPath<String>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<CsrfForm>,
+) -> Response {
+    let admin = match require_admin_csrf(&state, &headers, Some(&form.csrf)).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    if let Err(response) = require_super_admin(&admin) {
+        return response;
+    }
+
+    let result = with_pool!(&state.db, |pool| async {
+        let mut tx = pool.begin().await?;
+        let Some(name): Option<String> =
+            sqlx::query_scalar("SELECT name FROM instructors WHERE id = $1")
+                .bind(&instructor_id)
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            return Ok(0);
+        };
+        let teams: Vec<String> = sqlx::query_scalar(
+            "DELETE FROM instructor_teams WHERE instructor_id = $1 RETURNING team_token",
+        )
+        .bind(&instructor_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let deleted = sqlx::query("DELETE FROM instructors WHERE id = $1")
+            .bind(&instructor_id)
+            .execute(&mut *tx)
+            .await?;
+        admin_audit_insert(
+            &admin,
+            "instructor.delete",
+            None,
+            Some(
+                serde_json::json!({ "instructor_id": instructor_id, "name": name, "teams": teams }),
+            ),
+            None,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(deleted.rows_affected())
+    }
+    .await);
+
+    match result {
+        Ok(0) => (StatusCode::NOT_FOUND, "Instructor not found").into_response(),
         Ok(_) => {
-            info!("Admin moved participant {participant_id} to team {new_token:?}");
-            // The admin token is already taken from the request URL,
-            // which means the operator's browser was OK with it as-is;
-            // echo it straight back, the same way the rest of the
-            // admin UI does (see /admin/remove-participant).
-            axum::response::Redirect::to(&format!("/admin?token={}", state.admin_token))
-                .into_response()
+            info!("Admin deleted instructor {instructor_id}");
+            axum::response::Redirect::to("/admin/instructors").into_response()
         }
         Err(err) => {
-            error!("Failed to update team_token for {participant_id}: {err}");
+            error!("Failed to delete instructor {instructor_id}: {err}");
             (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
         }
     }
 }
 
-/// Builds the per-team page (`team.html`) for a given team label.
+/// Participant: read-only view of their own team's submissions.
 ///
-/// `team_token = None` selects the synthetic Unassigned bucket
-/// (every participant whose `participants.team_token` column is
-/// NULL). `viewer_ulid` highlights the matching member row in
-/// participant mode and is ignored in admin mode. Returns the
-/// rendered HTML response, or a 500 if any of the supporting queries
-/// fail.
-// reason: top-level request handler; splitting purely for line count adds indirection without value
-/// Snapshot of a single team (or the unassigned bucket): roster,
-/// recent submissions, truncation flag, and the distinct exercises
-/// present in the feed. Shared between [`render_team_page`] and the
-/// settings page so both pages see the same data and respect the same
-/// `TEAM_SUBMISSIONS_LIMIT`.
-#[allow(clippy::too_many_lines)]
-async fn load_team_view(
-    state: &AppState,
-    team_token: Option<&TeamToken>,
-    viewer_ulid: Option<&str>,
-) -> Result<
-    (
-        Vec<TeamMemberView>,
-        Vec<SubmissionSummary>,
-        bool,
-        Vec<String>,
-    ),
-    axum::response::Response,
-> {
-    let total_exercises: i64 = i64::try_from(state.exercises.len()).unwrap_or(i64::MAX);
-
-    // Roster: every participant in this team, with a count of their
-    // passing submissions and the timestamp of their most recent one.
-    // We branch on the SQL because SQLite has no portable way to bind
-    // an Option<&str> against `IS NULL` in a single statement.
-    let roster_query = match team_token {
-        Some(_) => {
-            r"
-            SELECT p.id, p.name,
-                   COUNT(s.id) AS completed_count,
-                   MAX(s.submitted_at) AS last_activity
-            FROM participants p
-            LEFT JOIN submissions s
-                ON p.id = s.participant_id AND s.tests_passed = 1
-            WHERE p.team_token = ?
-            GROUP BY p.id, p.name
-            ORDER BY last_activity DESC NULLS LAST, p.name COLLATE NOCASE
-            "
-        }
-        None => {
-            r"
-            SELECT p.id, p.name,
-                   COUNT(s.id) AS completed_count,
-                   MAX(s.submitted_at) AS last_activity
-            FROM participants p
-            LEFT JOIN submissions s
-                ON p.id = s.participant_id AND s.tests_passed = 1
-            WHERE p.team_token IS NULL
-            GROUP BY p.id, p.name
-            ORDER BY last_activity DESC NULLS LAST, p.name COLLATE NOCASE
-            "
+/// Returns 404 if the ULID is unknown; redirects to the dashboard
+/// with a one-shot toast if the participant has no `team_token` (no
+/// team means there's nothing meaningful to show on this page).
+async fn participant_team_page(
+    AxumPath(ulid): AxumPath<String>,
+    headers: HeaderMap,
+    OriginalUri(uri): OriginalUri,
+    State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
+) -> impl IntoResponse {
+    if let Some(response) = require_participant_page(&state, &headers, &uri, &ulid).await {
+        return response;
+    }
+    let participant: DbParticipant = match with_pool!(&state.db, |pool| sqlx::query_as(
+        "SELECT name, team_token FROM participants WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(&ulid)
+    .fetch_one(pool)
+    .await)
+    {
+        Ok(p) => p,
+        Err(_) => {
+            return axum::response::Redirect::to("/?reason=unknown-token").into_response();
         }
     };
-    let roster_q = sqlx::query(roster_query);
-    let roster_q = match team_token {
-        Some(t) => roster_q.bind(t.as_str()),
-        None => roster_q,
+
+    let Some(token) = participant.parsed_team_token() else {
+        // No team to show; bounce back to the personal dashboard.
+        return axum::response::Redirect::to(&format!("{}/dashboard/{ulid}", course.base()))
+            .into_response();
     };
-    let roster_rows = match roster_q.fetch_all(&state.pool).await {
-        Ok(rows) => rows,
+
+    render_team_page(&state, &course, Some(&token), false, None, Some(&ulid)).await
+}
+
+/// Settings page for anonymous visitors: editor preferences only, no
+/// team data. Routed at `/settings`.
+async fn settings_page() -> impl IntoResponse {
+    let template = SettingsTemplate {
+        participant_name: None,
+        ulid: None,
+        team_token: None,
+        members: Vec::new(),
+        submissions: Vec::new(),
+        submissions_truncated: false,
+        exercises: Vec::new(),
+        account: None,
+    };
+    match template.render() {
+        Ok(html) => Html(html).into_response(),
         Err(err) => {
-            error!("team page roster query failed: {err}");
-            return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response());
+            error!("settings template render failed: {err}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to render template",
+            )
+                .into_response()
         }
-    };
-
-    let mut members = Vec::with_capacity(roster_rows.len());
-    for row in &roster_rows {
-        let id: String = row.get("id");
-        let is_self = viewer_ulid.is_some_and(|u| u == id.as_str());
-        members.push(TeamMemberView {
-            id,
-            name: row.get("name"),
-            completed_count: row.get("completed_count"),
-            total_exercises,
-            last_activity: row.get("last_activity"),
-            is_self,
-        });
     }
+}
 
-    // Submissions feed: every member's latest activity, capped so a
-    // very busy team doesn't render thousands of editor instances.
-    let member_ids: Vec<String> = roster_rows
-        .iter()
-        .map(|r| r.get::<String, _>("id"))
-        .collect();
-
-    let (submissions, submissions_truncated) = if member_ids.is_empty() {
-        (Vec::new(), false)
-    } else {
-        // Hand-rolled IN list. Member IDs are ULIDs we just read out
-        // of the same DB so there's no untrusted input to escape;
-        // sqlx's prepared-statement placeholders go in via `bind`.
-        let placeholders = std::iter::repeat_n("?", member_ids.len())
-            .collect::<Vec<_>>()
-            .join(", ");
-        let sql = format!(
-            r"
-            SELECT p.name AS participant_name,
-                   s.exercise_name,
-                   s.tests_passed,
-                   s.fmt_passed,
-                   s.clippy_passed,
-                   s.submitted_at,
-                   s.source_code
-            FROM submissions s
-            JOIN participants p ON s.participant_id = p.id
-            WHERE s.participant_id IN ({placeholders})
-            ORDER BY s.submitted_at DESC
-            LIMIT ?
-            "
-        );
-        let mut q = sqlx::query(&sql);
-        for id in &member_ids {
-            q = q.bind(id);
+/// Settings page for a known participant: editor preferences, their
+/// account (passphrase, API tokens) plus their team snapshot (roster +
+/// recent submissions). Routed at `/settings/{ulid}`. Unknown ULIDs
+/// redirect to the anonymous dashboard with the standard one-shot toast.
+async fn participant_settings_page(
+    AxumPath(ulid): AxumPath<String>,
+    Query(query): Query<SettingsQuery>,
+    headers: HeaderMap,
+    uri: Uri,
+    State(state): State<AppState>,
+) -> Response {
+    let (auth, session) = match participant_access(&state, &headers, &ulid).await {
+        Ok(ParticipantAccess::Granted { auth, session }) => (auth, session),
+        Ok(ParticipantAccess::Unknown) => {
+            return axum::response::Redirect::to("/?reason=unknown-token").into_response();
         }
-        // +1 so we can detect truncation without a second query.
-        q = q.bind(TEAM_SUBMISSIONS_LIMIT + 1);
-        let rows = match q.fetch_all(&state.pool).await {
-            Ok(rows) => rows,
-            Err(err) => {
-                error!("team page submissions query failed: {err}");
-                return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response());
-            }
-        };
-        let truncated = i64::try_from(rows.len()).unwrap_or(i64::MAX) > TEAM_SUBMISSIONS_LIMIT;
-        let limit = usize::try_from(TEAM_SUBMISSIONS_LIMIT).unwrap_or(0);
-        let mut subs = Vec::with_capacity(rows.len().min(limit));
-        for row in rows.into_iter().take(limit) {
-            let fmt_passed: bool = row.get("fmt_passed");
-            let clippy_passed: bool = row.get("clippy_passed");
-            let exercise_name: String = row.get("exercise_name");
-            let exercise_label = prettify_exercise_name(&exercise_name);
-            subs.push(SubmissionSummary {
-                participant_name: row.get("participant_name"),
-                exercise_name,
-                exercise_label,
-                tests_passed: row.get("tests_passed"),
-                perfected: fmt_passed && clippy_passed,
-                submitted_at: row.get("submitted_at"),
-                source_code: row.get("source_code"),
-            });
+        Ok(ParticipantAccess::SignInRequired) => {
+            return require_participant_page(&state, &headers, &uri, &ulid)
+                .await
+                .unwrap_or_else(|| StatusCode::UNAUTHORIZED.into_response());
+        }
+        Err(err) => {
+            error!("Failed to check sign-in for {ulid}: {err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
         }
-        (subs, truncated)
     };
+    let notice = match query.notice.as_deref() {
+        Some("passphrase-set") => {
+            Some("Passphrase saved. Your login link alone no longer opens this account.")
+        }
+        Some("token-revoked") => Some("API token revoked. The CLI using it is signed out."),
+        Some("signed-out-elsewhere") => Some("Every other browser has been signed out."),
+        _ => None,
+    };
+    render_participant_settings(&state, &ulid, &auth, session.as_ref(), notice, None, None).await
+}
 
-    // Distinct exercise names from the submissions we're about to
-    // render. Sorted for a stable, alphabetic dropdown.
-    let mut exercises: Vec<String> = submissions
-        .iter()
-        .map(|s| s.exercise_name.clone())
-        .collect();
-    exercises.sort();
-    exercises.dedup();
+/// Renders `/settings/{ulid}` for a participant the caller has already
+/// let in. `new_api_token` is shown once, right after it was created.
+async fn render_participant_settings(
+    state: &AppState,
+    ulid: &str,
+    auth: &DbParticipantAuth,
+    session: Option<&ParticipantSession>,
+    notice: Option<&'static str>,
+    error: Option<&'static str>,
+    new_api_token: Option<String>,
+) -> Response {
+    let participant: DbParticipant = match with_pool!(&state.db, |pool| sqlx::query_as(
+        "SELECT name, team_token FROM participants WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(ulid)
+    .fetch_one(pool)
+    .await)
+    {
+        Ok(p) => p,
+        Err(_) => {
+            return axum::response::Redirect::to("/?reason=unknown-token").into_response();
+        }
+    };
 
-    Ok((members, submissions, submissions_truncated, exercises))
-}
+    let api_tokens: Vec<ApiTokenView> = match with_pool!(&state.db, |pool| sqlx::query_as(
+        "SELECT id, label, created_at, last_used_at, revoked_at
+         FROM participant_api_tokens WHERE participant_id = $1
+         ORDER BY revoked_at IS NOT NULL, created_at DESC",
+    )
+    .bind(ulid)
+    .fetch_all(pool)
+    .await)
+    {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            error!("Failed to load API tokens for {ulid}: {err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
 
-#[allow(clippy::needless_pass_by_value)]
-async fn render_team_page(
-    state: &AppState,
-    team_token: Option<&TeamToken>,
-    is_admin: bool,
-    admin_token: Option<String>,
-    viewer_ulid: Option<&str>,
-    back_href: String,
-    back_label: String,
-) -> axum::response::Response {
-    let (members, submissions, submissions_truncated, exercises) =
-        match load_team_view(state, team_token, viewer_ulid).await {
+    let team_token_parsed = participant.parsed_team_token();
+    let (members, submissions, submissions_truncated, exercises) = match team_token_parsed {
+        Some(ref token) => match load_team_view(
+            state,
+            state.courses.default_course(),
+            Some(token),
+            Some(ulid),
+        )
+        .await
+        {
             Ok(view) => view,
             Err(response) => return response,
-        };
+        },
+        None => (Vec::new(), Vec::new(), false, Vec::new()),
+    };
 
-    let (team_label, is_unassigned) = team_token.map_or_else(
-        || ("Unassigned".to_string(), true),
-        |t| (t.as_str().to_string(), false),
-    );
+    let cli_token = new_api_token
+        .clone()
+        .or_else(|| (!auth.auth_required).then(|| ulid.to_string()));
+    let account = AccountView {
+        form_base: format!("/settings/{ulid}"),
+        csrf_token: state.sessions.participant_csrf_token(ulid, session),
+        auth_required: auth.auth_required,
+        has_passphrase: auth.passphrase_hash.is_some(),
+        signed_in: session.is_some(),
+        api_tokens,
+        new_api_token,
+        cli_token,
+        notice,
+        error,
+    };
 
-    let template = TeamPageTemplate {
-        team_label,
-        is_unassigned,
-        is_admin,
-        admin_token,
-        viewer_ulid: viewer_ulid.map(str::to_string),
+    let template = SettingsTemplate {
+        participant_name: Some(participant.name),
+        ulid: Some(ulid.to_string()),
+        team_token: team_token_parsed.map(|t| t.as_str().to_string()),
         members,
         submissions,
         submissions_truncated,
         exercises,
-        back_href,
-        back_label,
+        account: Some(account),
     };
-
     match template.render() {
         Ok(html) => Html(html).into_response(),
         Err(err) => {
-            error!("team page template render failed: {err}");
+            error!("settings template render failed: {err}");
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Failed to render template",
@@ -2259,160 +5557,778 @@ async fn render_team_page(
     }
 }
 
-/// Admin: per-team page for a real team slug.
-async fn admin_team_page(
-    AxumPath(slug): AxumPath<String>,
-    Query(query): Query<AdminQuery>,
+/// Set or change the participant's passphrase. This turns on sign-in
+/// for the account, signs out every other browser and keeps this one
+/// signed in.
+async fn settings_set_passphrase(
+    AxumPath(ulid): AxumPath<String>,
+    headers: HeaderMap,
     State(state): State<AppState>,
-) -> impl IntoResponse {
-    if query.token != state.admin_token {
-        return (StatusCode::FORBIDDEN, "Invalid admin token").into_response();
+    axum::Form(form): axum::Form<PassphraseForm>,
+) -> Response {
+    let (auth, session) = match require_participant_form(&state, &headers, &ulid, &form.csrf).await
+    {
+        Ok(access) => access,
+        Err(response) => return response,
+    };
+
+    let problem = if form.passphrase == form.confirm {
+        session::check_passphrase(&form.passphrase).err()
+    } else {
+        Some("The two passphrases don't match.")
+    };
+    if let Some(problem) = problem {
+        return render_participant_settings(
+            &state,
+            &ulid,
+            &auth,
+            session.as_ref(),
+            None,
+            Some(problem),
+            None,
+        )
+        .await;
     }
-    let Ok(token) = TeamToken::try_from(slug.as_str()) else {
-        return (StatusCode::BAD_REQUEST, "Invalid team slug").into_response();
+
+    let passphrase = form.passphrase;
+    let hashed = run_argon2(&state, move || session::hash_passphrase(&passphrase))
+        .await
+        .map_err(|err| err.to_string())
+        .and_then(|hashed| hashed.map_err(|err| err.to_string()));
+    let hash = match hashed {
+        Ok(hash) => hash,
+        Err(err) => {
+            error!("Failed to hash passphrase: {err}");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save passphrase",
+            )
+                .into_response();
+        }
     };
-    let admin_token = state.admin_token.clone();
-    let back_href = format!("/admin?token={admin_token}");
-    render_team_page(
+    let epoch: i64 = match with_pool!(&state.db, |pool| sqlx::query_scalar(
+        "UPDATE participants
+         SET passphrase_hash = $1, auth_required = TRUE, auth_epoch = auth_epoch + 1
+         WHERE id = $2
+         RETURNING auth_epoch",
+    )
+    .bind(&hash)
+    .bind(&ulid)
+    .fetch_one(pool)
+    .await)
+    {
+        Ok(epoch) => epoch,
+        Err(err) => {
+            error!("Failed to store passphrase for {ulid}: {err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    info!("Participant {ulid} set a passphrase");
+    participant_signed_in(
         &state,
-        Some(&token),
-        true,
-        Some(admin_token),
-        None,
-        back_href,
-        "Back to admin".to_string(),
+        &ulid,
+        epoch,
+        &format!("/settings/{ulid}?notice=passphrase-set"),
     )
-    .await
 }
 
-/// Admin: per-team page for the synthetic Unassigned bucket.
-async fn admin_team_unassigned_page(
-    Query(query): Query<AdminQuery>,
+/// Sign out every other browser by bumping the epoch; this one gets a
+/// fresh session.
+async fn settings_sign_out_elsewhere(
+    AxumPath(ulid): AxumPath<String>,
+    headers: HeaderMap,
     State(state): State<AppState>,
-) -> impl IntoResponse {
-    if query.token != state.admin_token {
-        return (StatusCode::FORBIDDEN, "Invalid admin token").into_response();
+    axum::Form(form): axum::Form<CsrfForm>,
+) -> Response {
+    if let Err(response) = require_participant_form(&state, &headers, &ulid, &form.csrf).await {
+        return response;
     }
-    let admin_token = state.admin_token.clone();
-    let back_href = format!("/admin?token={admin_token}");
-    render_team_page(
+    let epoch: i64 = match with_pool!(&state.db, |pool| sqlx::query_scalar(
+        "UPDATE participants SET auth_epoch = auth_epoch + 1 WHERE id = $1
+         RETURNING auth_epoch",
+    )
+    .bind(&ulid)
+    .fetch_one(pool)
+    .await)
+    {
+        Ok(epoch) => epoch,
+        Err(err) => {
+            error!("Failed to bump auth epoch for {ulid}: {err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    info!("Participant {ulid} signed out every other browser");
+    participant_signed_in(
         &state,
+        &ulid,
+        epoch,
+        &format!("/settings/{ulid}?notice=signed-out-elsewhere"),
+    )
+}
+
+/// Create an API token for the CLI. The token is shown on the page this
+/// returns and stored only as a hash.
+async fn settings_create_api_token(
+    AxumPath(ulid): AxumPath<String>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<ApiTokenForm>,
+) -> Response {
+    let (auth, session) = match require_participant_form(&state, &headers, &ulid, &form.csrf).await
+    {
+        Ok(access) => access,
+        Err(response) => return response,
+    };
+
+    let label = form.label.trim();
+    let label = if label.is_empty() {
+        "cargo course"
+    } else {
+        label
+    };
+    if label.chars().count() > 64 {
+        return render_participant_settings(
+            &state,
+            &ulid,
+            &auth,
+            session.as_ref(),
+            None,
+            Some("Keep the token label under 64 characters."),
+            None,
+        )
+        .await;
+    }
+
+    let token = session::new_api_token();
+    if let Err(err) = with_pool!(&state.db, |pool| sqlx::query(
+        "INSERT INTO participant_api_tokens (id, participant_id, token_hash, label)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(Ulid::new().to_string())
+    .bind(&ulid)
+    .bind(session::secret_hash(&token))
+    .bind(label)
+    .execute(pool)
+    .await
+    .map(|done| done.rows_affected()))
+    {
+        error!("Failed to create API token for {ulid}: {err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+    }
+
+    info!("Participant {ulid} created an API token");
+    render_participant_settings(
+        &state,
+        &ulid,
+        &auth,
+        session.as_ref(),
+        Some("API token created. Copy it now: it won't be shown again."),
         None,
-        true,
-        Some(admin_token),
-        None,
-        back_href,
-        "Back to admin".to_string(),
+        Some(token),
     )
     .await
 }
 
-/// Participant: read-only view of their own team's submissions.
-///
-/// Returns 404 if the ULID is unknown; redirects to the dashboard
-/// with a one-shot toast if the participant has no `team_token` (no
-/// team means there's nothing meaningful to show on this page).
-async fn participant_team_page(
+/// Revoke one of the participant's API tokens.
+async fn settings_revoke_api_token(
+    AxumPath((ulid, token_id)): AxumPath<(String, String)>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<CsrfForm>,
+) -> Response {
+    if let Err(response) = require_participant_form(&state, &headers, &ulid, &form.csrf).await {
+        return response;
+    }
+    let result = with_pool!(&state.db, |pool| sqlx::query(
+        "UPDATE participant_api_tokens SET revoked_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND participant_id = $2 AND revoked_at IS NULL",
+    )
+    .bind(&token_id)
+    .bind(&ulid)
+    .execute(pool)
+    .await
+    .map(|done| done.rows_affected()));
+    match result {
+        Ok(0) => (StatusCode::NOT_FOUND, "API token not found").into_response(),
+        Ok(_) => {
+            info!("Participant {ulid} revoked API token {token_id}");
+            axum::response::Redirect::to(&format!("/settings/{ulid}?notice=token-revoked"))
+                .into_response()
+        }
+        Err(err) => {
+            error!("Failed to revoke API token {token_id}: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// "Download my data": everything stored about the participant, as a
+/// JSON file.
+async fn settings_export(
     AxumPath(ulid): AxumPath<String>,
+    headers: HeaderMap,
+    uri: Uri,
     State(state): State<AppState>,
-) -> impl IntoResponse {
-    let participant: DbParticipant =
-        match sqlx::query_as("SELECT name, team_token FROM participants WHERE id = ?")
-            .bind(&ulid)
-            .fetch_one(&state.pool)
-            .await
-        {
-            Ok(p) => p,
-            Err(_) => {
-                return axum::response::Redirect::to("/?reason=unknown-token").into_response();
+) -> Response {
+    if let Some(response) = require_participant_page(&state, &headers, &uri, &ulid).await {
+        return response;
+    }
+    account_export_response(&state, &ulid).await
+}
+
+/// Delete the participant's account and everything stored with it, for
+/// good. Unlike an admin removal there is no grace period.
+async fn settings_delete_account(
+    AxumPath(ulid): AxumPath<String>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<DeleteAccountForm>,
+) -> Response {
+    let (auth, session) = match require_participant_form(&state, &headers, &ulid, &form.csrf).await
+    {
+        Ok(access) => access,
+        Err(response) => return response,
+    };
+    // An instructor viewing a signed-in account can't delete it on the
+    // participant's behalf; they remove participants from /admin, which
+    // is audited and can be undone.
+    if auth.auth_required && session.is_none() {
+        return (
+            StatusCode::FORBIDDEN,
+            "Only the participant can delete their own account.",
+        )
+            .into_response();
+    }
+    if !form.confirm.trim().eq_ignore_ascii_case("delete") {
+        return render_participant_settings(
+            &state,
+            &ulid,
+            &auth,
+            session.as_ref(),
+            None,
+            Some("Type delete to confirm deleting your account."),
+            None,
+        )
+        .await;
+    }
+
+    match delete_account(&state.db, &ulid).await {
+        Ok(true) => {
+            info!("Participant {ulid} deleted their account");
+            let mut response =
+                axum::response::Redirect::to("/?reason=account-deleted").into_response();
+            if session.is_some() {
+                response.headers_mut().insert(
+                    SET_COOKIE,
+                    session_cookie(&state, PARTICIPANT_COOKIE, "", 0),
+                );
             }
-        };
+            response
+        }
+        Ok(false) => (StatusCode::NOT_FOUND, "Participant not found").into_response(),
+        Err(err) => {
+            error!("Failed to delete account {ulid}: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
 
-    let Some(token) = participant.parsed_team_token() else {
-        // No team to show; bounce back to the personal dashboard.
-        return axum::response::Redirect::to(&format!("/dashboard/{ulid}")).into_response();
+/// The data export for `ulid` as a JSON attachment, shared by the
+/// settings page and the API.
+async fn account_export_response(state: &AppState, ulid: &str) -> Response {
+    let export = match load_account_export(&state.db, ulid).await {
+        Ok(Some(export)) => export,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Participant not found").into_response(),
+        Err(err) => {
+            error!("Failed to export data for {ulid}: {err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
     };
+    info!("Exported data for participant {ulid}");
+    let disposition = format!("attachment; filename=\"corrode-course-{ulid}.json\"");
+    match HeaderValue::from_str(&disposition) {
+        Ok(disposition) => ([(CONTENT_DISPOSITION, disposition)], Json(export)).into_response(),
+        Err(_) => Json(export).into_response(),
+    }
+}
 
-    let back_href = format!("/dashboard/{ulid}");
-    render_team_page(
-        &state,
-        Some(&token),
-        false,
-        None,
-        Some(&ulid),
-        back_href,
-        "Back to your dashboard".to_string(),
+/// Everything stored about participant `ulid`, or `None` when there is
+/// no such participant (or they were removed by an admin).
+// reason: one query per exported table, read top to bottom
+#[allow(clippy::too_many_lines)]
+async fn load_account_export(db: &Db, ulid: &str) -> Result<Option<AccountExport>, sqlx::Error> {
+    let participant: Option<ExportedParticipant> = with_pool!(db, |pool| sqlx::query_as(
+        "SELECT id, name, team_token, created_at, auth_required,
+                passphrase_hash IS NOT NULL AS has_passphrase
+         FROM participants WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(ulid)
+    .fetch_optional(pool)
+    .await)?;
+    let Some(participant) = participant else {
+        return Ok(None);
+    };
+
+    let enrollments: Vec<ExportedEnrollment> = with_pool!(db, |pool| sqlx::query_as(
+        "SELECT course, enrolled_at FROM course_enrollments WHERE participant_id = $1
+         ORDER BY enrolled_at, course",
+    )
+    .bind(ulid)
+    .fetch_all(pool)
+    .await)?;
+
+    let submissions: Vec<ExportedSubmission> = with_pool!(db, |pool| {
+        sqlx::query_as(
+        "SELECT id, course, exercise_name, source_code, tests_passed, clippy_passed, fmt_passed,
+                submitted_at, verification, verification_log
+         FROM submissions WHERE participant_id = $1
+         ORDER BY submitted_at, id",
     )
+    .bind(ulid)
+    .fetch_all(pool)
+    .await
+    })?;
+
+    let mut course_events: Vec<ExportedCourseEvent> = with_pool!(db, |pool| {
+        sqlx::query_as(
+        "SELECT id, course, session_id, event_type, exercise_name, result, tests_passed, tests_total,
+                duration_ms, diagnostic_code, course_version, git_hash, created_at
+         FROM course_events WHERE participant_id = $1
+         ORDER BY created_at, id",
+    )
+    .bind(ulid)
+    .fetch_all(pool)
     .await
+    })?;
+    let diagnostics: Vec<(String, String, String, i64)> = with_pool!(db, |pool| sqlx::query_as(
+        "SELECT d.event_id, d.code, d.level, d.occurrences
+         FROM course_event_diagnostics d
+         JOIN course_events e ON e.id = d.event_id
+         WHERE e.participant_id = $1
+         ORDER BY d.event_id, d.code",
+    )
+    .bind(ulid)
+    .fetch_all(pool)
+    .await)?;
+    let mut diagnostics_by_event: std::collections::HashMap<String, Vec<ExportedDiagnostic>> =
+        std::collections::HashMap::new();
+    for (event_id, code, level, occurrences) in diagnostics {
+        diagnostics_by_event
+            .entry(event_id)
+            .or_default()
+            .push(ExportedDiagnostic {
+                code,
+                level,
+                occurrences,
+            });
+    }
+    for event in &mut course_events {
+        event.diagnostics = diagnostics_by_event.remove(&event.id).unwrap_or_default();
+    }
+
+    let quiz_answers: Vec<ExportedQuizAnswer> = with_pool!(db, |pool| sqlx::query_as(
+        "SELECT course, chapter, attempt, question, answer, correct, answered_at
+         FROM quiz_answers WHERE participant_id = $1
+         ORDER BY answered_at, course, chapter, attempt, question",
+    )
+    .bind(ulid)
+    .fetch_all(pool)
+    .await)?;
+
+    let api_tokens: Vec<ExportedApiToken> = with_pool!(db, |pool| sqlx::query_as(
+        "SELECT label, created_at, last_used_at, revoked_at
+         FROM participant_api_tokens WHERE participant_id = $1
+         ORDER BY created_at",
+    )
+    .bind(ulid)
+    .fetch_all(pool)
+    .await)?;
+
+    let audit: Vec<AuditSnapshotRow> = with_pool!(db, |pool| sqlx::query_as(
+        "SELECT created_at, action, before, after
+         FROM admin_audit WHERE participant_id = $1
+         ORDER BY id",
+    )
+    .bind(ulid)
+    .fetch_all(pool)
+    .await)?;
+    let json = |raw: Option<String>| raw.and_then(|raw| serde_json::from_str(&raw).ok());
+    let admin_actions = audit
+        .into_iter()
+        .map(|row| ExportedAdminAction {
+            created_at: row.created_at,
+            action: row.action,
+            before: json(row.before),
+            after: json(row.after),
+        })
+        .collect();
+
+    Ok(Some(AccountExport {
+        exported_at: chrono::Utc::now(),
+        participant,
+        enrollments,
+        submissions,
+        course_events,
+        quiz_answers,
+        api_tokens,
+        admin_actions,
+    }))
 }
 
-/// Settings page for anonymous visitors: editor preferences only, no
-/// team data. Routed at `/settings`.
-async fn settings_page() -> impl IntoResponse {
-    let template = SettingsTemplate {
-        participant_name: None,
-        ulid: None,
-        team_token: None,
-        members: Vec::new(),
-        submissions: Vec::new(),
-        submissions_truncated: false,
-        exercises: Vec::new(),
+/// An `admin_audit` row as it goes into a data export.
+#[derive(sqlx::FromRow)]
+struct AuditSnapshotRow {
+    created_at: chrono::DateTime<chrono::Utc>,
+    action: String,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+/// Hard-delete participant `ulid` and all of their data. Submissions go
+/// first (they have no cascade); sign-in codes, API tokens, course
+/// enrollments, course events and quiz answers cascade with the participant. Audit log rows about them stay,
+/// with their `before` / `after` snapshots blanked (migration
+/// `018_admin_audit_redaction.sql`). Returns `false` when there is no
+/// such participant.
+async fn delete_account(db: &Db, ulid: &str) -> Result<bool, sqlx::Error> {
+    with_pool!(db, |pool| async {
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM submissions WHERE participant_id = $1")
+            .bind(ulid)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&redact_admin_audit("$1"))
+            .bind(ulid)
+            .execute(&mut *tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM participants WHERE id = $1 AND deleted_at IS NULL")
+            .bind(ulid)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if deleted == 0 {
+            return Ok(false);
+        }
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(true)
+    }
+    .await)
+}
+
+/// Admin participant removal endpoint. Removal is a soft delete: the
+/// participant vanishes from every page and API at once, and can be
+/// restored until [`purge_removed_participants`] runs after
+/// [`REMOVED_PARTICIPANT_GRACE_DAYS`].
+async fn admin_remove_participant(
+    AxumPath(participant_id): AxumPath<String>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    // `fetch` from `removeParticipant` sends the CSRF token as a header.
+    let admin = match require_admin_csrf(&state, &headers, None).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
     };
-    match template.render() {
-        Ok(html) => Html(html).into_response(),
+    if let Err(response) = require_participant_in_scope(&state, &admin, &participant_id).await {
+        return response;
+    }
+
+    info!("Admin removing participant: {participant_id}");
+
+    let result = with_pool!(&state.db, |pool| async {
+        let mut tx = pool.begin().await?;
+        let Some((name, team_token)): Option<(String, Option<String>)> = sqlx::query_as(
+            "SELECT name, team_token FROM participants WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(&participant_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(false);
+        };
+        let deleted_at: chrono::DateTime<chrono::Utc> = sqlx::query_scalar(
+            "UPDATE participants SET deleted_at = CURRENT_TIMESTAMP WHERE id = $1
+             RETURNING deleted_at",
+        )
+        .bind(&participant_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        admin_audit_insert(
+            &admin,
+            "participant.remove",
+            Some(&participant_id),
+            Some(serde_json::json!({ "name": name, "team_token": team_token, "deleted_at": null })),
+            Some(serde_json::json!({ "deleted_at": deleted_at })),
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(true)
+    }
+    .await);
+
+    match result {
+        Ok(true) => {
+            info!("Successfully removed participant: {participant_id}");
+            (StatusCode::OK, "Participant removed successfully").into_response()
+        }
+        Ok(false) => (StatusCode::NOT_FOUND, "Participant not found").into_response(),
         Err(err) => {
-            error!("settings template render failed: {err}");
+            error!("Failed to remove participant {participant_id}: {err}");
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to render template",
+                "Failed to remove participant",
             )
                 .into_response()
         }
     }
 }
 
-/// Settings page for a known participant: editor preferences plus
-/// their team snapshot (roster + recent submissions). Routed at
-/// `/settings/{ulid}`. Unknown ULIDs redirect to the anonymous
-/// dashboard with the standard one-shot toast.
-async fn participant_settings_page(
-    AxumPath(ulid): AxumPath<String>,
+/// Admin: undo a removal during the grace period.
+async fn admin_restore_participant(
+    AxumPath(participant_id): AxumPath<String>,
+    headers: HeaderMap,
     State(state): State<AppState>,
-) -> impl IntoResponse {
-    let participant: DbParticipant =
-        match sqlx::query_as("SELECT name, team_token FROM participants WHERE id = ?")
-            .bind(&ulid)
-            .fetch_one(&state.pool)
-            .await
-        {
-            Ok(p) => p,
-            Err(_) => {
-                return axum::response::Redirect::to("/?reason=unknown-token").into_response();
-            }
+    axum::Form(form): axum::Form<CsrfForm>,
+) -> Response {
+    let admin = match require_admin_csrf(&state, &headers, Some(&form.csrf)).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    if let Err(response) = require_participant_in_scope(&state, &admin, &participant_id).await {
+        return response;
+    }
+
+    let result = with_pool!(&state.db, |pool| async {
+        let mut tx = pool.begin().await?;
+        let Some(deleted_at): Option<chrono::DateTime<chrono::Utc>> = sqlx::query_scalar(
+            "SELECT deleted_at FROM participants WHERE id = $1 AND deleted_at IS NOT NULL",
+        )
+        .bind(&participant_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(false);
         };
+        sqlx::query("UPDATE participants SET deleted_at = NULL WHERE id = $1")
+            .bind(&participant_id)
+            .execute(&mut *tx)
+            .await?;
+        admin_audit_insert(
+            &admin,
+            "participant.restore",
+            Some(&participant_id),
+            Some(serde_json::json!({ "deleted_at": deleted_at })),
+            Some(serde_json::json!({ "deleted_at": null })),
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(true)
+    }
+    .await);
 
-    let team_token_parsed = participant.parsed_team_token();
-    let (members, submissions, submissions_truncated, exercises) = match team_token_parsed {
-        Some(ref token) => match load_team_view(&state, Some(token), Some(&ulid)).await {
-            Ok(view) => view,
-            Err(response) => return response,
-        },
-        None => (Vec::new(), Vec::new(), false, Vec::new()),
+    match result {
+        Ok(true) => {
+            info!("Admin restored participant {participant_id}");
+            axum::response::Redirect::to(&safe_admin_next(form.next.as_deref())).into_response()
+        }
+        Ok(false) => (StatusCode::NOT_FOUND, "No removed participant with that id").into_response(),
+        Err(err) => {
+            error!("Failed to restore participant {participant_id}: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// Admin: open a chapter with `unlock = "release"` for everyone in the
+/// course. Instructors can do this too; releases are course-wide.
+async fn admin_release_chapter(
+    AxumPath(chapter): AxumPath<String>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
+    axum::Form(form): axum::Form<CsrfForm>,
+) -> Response {
+    set_chapter_release(&state, &course, &headers, &chapter, &form, true).await
+}
+
+/// Admin: lock a released chapter again. Participants who already
+/// started it keep access.
+async fn admin_withhold_chapter(
+    AxumPath(chapter): AxumPath<String>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Extension(course): Extension<Arc<Course>>,
+    axum::Form(form): axum::Form<CsrfForm>,
+) -> Response {
+    set_chapter_release(&state, &course, &headers, &chapter, &form, false).await
+}
+
+async fn set_chapter_release(
+    state: &AppState,
+    course: &Course,
+    headers: &HeaderMap,
+    chapter: &str,
+    form: &CsrfForm,
+    released: bool,
+) -> Response {
+    let admin = match require_admin_csrf(state, headers, Some(&form.csrf)).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
     };
+    if !course
+        .catalog
+        .current()
+        .iter()
+        .any(|e| e.file_stem == chapter && e.unlock() == Unlock::Release)
+    {
+        return (StatusCode::NOT_FOUND, "No chapter waits for a release").into_response();
+    }
 
-    let template = SettingsTemplate {
-        participant_name: Some(participant.name),
-        ulid: Some(ulid),
-        team_token: team_token_parsed.map(|t| t.as_str().to_string()),
-        members,
-        submissions,
-        submissions_truncated,
-        exercises,
+    let (action, sql) = if released {
+        (
+            "chapter.release",
+            "INSERT INTO chapter_releases (course, chapter) VALUES ($1, $2)
+             ON CONFLICT DO NOTHING",
+        )
+    } else {
+        (
+            "chapter.withhold",
+            "DELETE FROM chapter_releases WHERE course = $1 AND chapter = $2",
+        )
+    };
+    let result = with_pool!(&state.db, |pool| {
+        async {
+        let mut tx = pool.begin().await?;
+        let changed = sqlx::query(sql)
+            .bind(&course.slug)
+            .bind(chapter)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if changed > 0 {
+            admin_audit_insert(
+                &admin,
+                action,
+                None,
+                Some(serde_json::json!({ "course": course.slug, "chapter": chapter, "released": !released })),
+                Some(serde_json::json!({ "course": course.slug, "chapter": chapter, "released": released })),
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(())
+    }
+    .await
+    });
+
+    match result {
+        Ok(()) => {
+            info!("{action} {chapter} in {}", course.slug);
+            axum::response::Redirect::to(&format!("{}/admin", course.base())).into_response()
+        }
+        Err(err) => {
+            error!("Failed to {action} {chapter} in {}: {err}", course.slug);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// Every `action` written by [`admin_audit_insert`], for the audit
+/// page's filter.
+const AUDIT_ACTIONS: &[&str] = &[
+    "participant.move",
+    "participant.login_code",
+    "participant.remove",
+    "participant.restore",
+    "chapter.release",
+    "chapter.withhold",
+    "instructor.create",
+    "instructor.teams",
+    "instructor.delete",
+];
+
+/// Most entries `/admin/audit` shows at once.
+const AUDIT_PAGE_LIMIT: i64 = 200;
+
+/// Filters on `/admin/audit`. Empty strings match everything.
+#[derive(Deserialize, Default)]
+struct AuditQuery {
+    #[serde(default)]
+    action: String,
+    /// Matches the actor's name or id.
+    #[serde(default)]
+    actor: String,
+    /// A participant ULID.
+    #[serde(default)]
+    participant: String,
+}
+
+/// Super-admin: browse the audit log, newest first.
+async fn admin_audit_page(
+    Query(query): Query<AuditQuery>,
+    headers: HeaderMap,
+    uri: Uri,
+    State(state): State<AppState>,
+) -> Response {
+    let admin = match require_admin_page(&state, &headers, &uri).await {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    if let Err(response) = require_super_admin(&admin) {
+        return response;
+    }
+
+    let action = query.action.trim();
+    let actor = query.actor.trim();
+    let participant = query.participant.trim();
+    let rows: Result<Vec<AuditRow>, sqlx::Error> = with_pool!(&state.db, |pool| sqlx::query_as(
+        "SELECT a.created_at, a.actor_name, a.action, a.participant_id,
+             p.name AS participant_name, a.before, a.after
+         FROM admin_audit a
+         LEFT JOIN participants p ON p.id = a.participant_id
+         WHERE ($1 = '' OR a.action = $2)
+           AND ($3 = '' OR a.actor = $4 OR a.actor_name LIKE '%' || $5 || '%')
+           AND ($6 = '' OR a.participant_id = $7)
+         ORDER BY a.id DESC
+         LIMIT $8",
+    )
+    .bind(action)
+    .bind(action)
+    .bind(actor)
+    .bind(actor)
+    .bind(actor)
+    .bind(participant)
+    .bind(participant)
+    .bind(AUDIT_PAGE_LIMIT + 1)
+    .fetch_all(pool)
+    .await);
+    let mut entries = match rows {
+        Ok(rows) => rows,
+        Err(err) => {
+            error!("Failed to load audit log: {err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    let limit = usize::try_from(AUDIT_PAGE_LIMIT).unwrap_or(usize::MAX);
+    let truncated = entries.len() > limit;
+    entries.truncate(limit);
+
+    let template = AdminAuditTemplate {
+        entries,
+        truncated,
+        actions: AUDIT_ACTIONS,
+        action: action.to_string(),
+        actor: actor.to_string(),
+        participant: participant.to_string(),
     };
     match template.render() {
         Ok(html) => Html(html).into_response(),
         Err(err) => {
-            error!("settings template render failed: {err}");
+            error!("audit template render failed: {err}");
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Failed to render template",
@@ -2422,71 +6338,232 @@ async fn participant_settings_page(
     }
 }
 
-/// Admin participant removal endpoint
-async fn admin_remove_participant(
-    AxumPath(participant_id): AxumPath<String>,
-    Query(query): Query<AdminQuery>,
-    State(state): State<AppState>,
-) -> impl IntoResponse {
-    // Verify admin token
-    if query.token != state.admin_token {
-        return (StatusCode::FORBIDDEN, "Invalid admin token").into_response();
+/// How long a removed participant can be restored before
+/// [`purge_removed_participants`] deletes them and their submissions.
+const REMOVED_PARTICIPANT_GRACE_DAYS: i64 = 14;
+
+/// Hard-delete participants removed more than
+/// [`REMOVED_PARTICIPANT_GRACE_DAYS`] ago. Their sign-in codes, API
+/// tokens and course events go with them (`ON DELETE CASCADE`);
+/// submissions have no cascade and are deleted first. Audit log rows
+/// about them are kept but redacted, as in [`delete_account`].
+async fn purge_removed_participants(db: &Db) -> Result<u64, sqlx::Error> {
+    // Naive UTC so SQLite compares it as text against its
+    // `CURRENT_TIMESTAMP` values; Postgres sessions run in UTC.
+    let cutoff =
+        (chrono::Utc::now() - chrono::Duration::days(REMOVED_PARTICIPANT_GRACE_DAYS)).naive_utc();
+    with_pool!(db, |pool| async {
+        let mut tx = pool.begin().await?;
+        sqlx::query(&redact_admin_audit(
+            "SELECT id FROM participants WHERE deleted_at < $1",
+        ))
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "DELETE FROM submissions WHERE participant_id IN
+             (SELECT id FROM participants WHERE deleted_at < $1)",
+        )
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?;
+        let purged = sqlx::query("DELETE FROM participants WHERE deleted_at < $1")
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(purged)
     }
+    .await)
+}
 
-    info!("Admin removing participant: {participant_id}");
+/// Run [`purge_removed_participants`] at startup and then hourly.
+async fn purge_removed_participants_hourly(db: Db) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_hours(1));
+    loop {
+        interval.tick().await;
+        match purge_removed_participants(&db).await {
+            Ok(0) => {}
+            Ok(purged) => info!(
+                "Purged {purged} participant(s) removed over {REMOVED_PARTICIPANT_GRACE_DAYS} days ago"
+            ),
+            Err(err) => error!("Failed to purge removed participants: {err}"),
+        }
+    }
+}
 
-    // Start a transaction to ensure atomicity
-    let mut tx = match state.pool.begin().await {
-        Ok(tx) => tx,
-        Err(err) => {
-            error!("Failed to start transaction: {err}");
-            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+/// What `server prune` deletes. Each `None` leaves that data alone.
+struct RetentionPolicy {
+    /// Course events older than this many days.
+    events_older_than_days: Option<i64>,
+    /// Surplus submissions per (participant, step), oldest first.
+    max_submissions_per_step: Option<i64>,
+    /// Participants with no activity for this many days.
+    inactive_for_days: Option<i64>,
+}
+
+/// How many rows [`apply_retention`] deleted (or, in a dry run, would
+/// have).
+#[derive(Debug, Default, PartialEq, Eq)]
+struct RetentionReport {
+    participants: u64,
+    submissions: u64,
+    course_events: u64,
+}
+
+/// Participants with no sign of life since `$1`: created before it, and
+/// no submission, course event or API token use after it.
+const INACTIVE_PARTICIPANTS: &str = "SELECT p.id FROM participants p
+     WHERE (p.created_at IS NULL OR p.created_at < $1)
+       AND NOT EXISTS (SELECT 1 FROM submissions s
+                       WHERE s.participant_id = p.id AND s.submitted_at >= $1)
+       AND NOT EXISTS (SELECT 1 FROM course_events e
+                       WHERE e.participant_id = p.id AND e.created_at >= $1)
+       AND NOT EXISTS (SELECT 1 FROM participant_api_tokens t
+                       WHERE t.participant_id = p.id AND t.last_used_at >= $1)";
+
+/// Submissions beyond the newest `$1` for their participant and step.
+/// Progress counts a step as done (or perfected) if any submission was,
+/// so the newest passing and the newest perfected submission stay even
+/// when they are older; so do submissions still being verified.
+const SURPLUS_SUBMISSIONS: &str = "SELECT id FROM (
+         SELECT id, kind,
+                ROW_NUMBER() OVER (PARTITION BY participant_id, course, exercise_name
+                                   ORDER BY submitted_at DESC, id DESC) AS newest,
+                ROW_NUMBER() OVER (PARTITION BY participant_id, course, exercise_name, kind
+                                   ORDER BY submitted_at DESC, id DESC) AS newest_of_kind
+         FROM (SELECT id, participant_id, course, exercise_name, submitted_at,
+                      CASE WHEN tests_passed AND COALESCE(clippy_passed, FALSE)
+                                AND COALESCE(fmt_passed, FALSE) THEN 2
+                           WHEN tests_passed THEN 1
+                           ELSE 0 END AS kind
+               FROM submissions WHERE verification != 'pending') classified
+     ) ranked
+     WHERE newest > $1 AND (kind = 0 OR newest_of_kind > 1)";
+
+/// Apply `policy` in one transaction. A dry run executes the same
+/// deletes and rolls them back, so its counts are exactly what a real
+/// run would remove.
+///
+/// Inactive participants go first, since pruning events could make an
+/// active participant look idle. They leave the way
+/// [`purge_removed_participants`] removes them: submissions and events
+/// explicitly (so they're counted), the rest by cascade, with their
+/// audit log snapshots blanked.
+async fn apply_retention(
+    db: &Db,
+    policy: &RetentionPolicy,
+    dry_run: bool,
+) -> Result<RetentionReport, sqlx::Error> {
+    // Naive UTC, as in `purge_removed_participants`.
+    let days_ago = |days: i64| (chrono::Utc::now() - chrono::Duration::days(days)).naive_utc();
+    with_pool!(db, |pool| async {
+        let mut tx = pool.begin().await?;
+        let mut report = RetentionReport::default();
+
+        if let Some(days) = policy.inactive_for_days {
+            let cutoff = days_ago(days);
+            sqlx::query(&redact_admin_audit(INACTIVE_PARTICIPANTS))
+                .bind(cutoff)
+                .execute(&mut *tx)
+                .await?;
+            report.submissions += sqlx::query(&format!(
+                "DELETE FROM submissions WHERE participant_id IN ({INACTIVE_PARTICIPANTS})"
+            ))
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            report.course_events += sqlx::query(&format!(
+                "DELETE FROM course_events WHERE participant_id IN ({INACTIVE_PARTICIPANTS})"
+            ))
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            report.participants += sqlx::query(&format!(
+                "DELETE FROM participants WHERE id IN ({INACTIVE_PARTICIPANTS})"
+            ))
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
         }
-    };
 
-    // First, delete all submissions for this participant
-    if let Err(err) = sqlx::query("DELETE FROM submissions WHERE participant_id = ?")
-        .bind(&participant_id)
-        .execute(&mut *tx)
-        .await
-    {
-        error!("Failed to delete submissions for participant {participant_id}: {err}");
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to delete submissions",
-        )
-            .into_response();
-    }
+        if let Some(keep) = policy.max_submissions_per_step {
+            report.submissions += sqlx::query(&format!(
+                "DELETE FROM submissions WHERE id IN ({SURPLUS_SUBMISSIONS})"
+            ))
+            .bind(keep)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
 
-    // Then, delete the participant
-    match sqlx::query("DELETE FROM participants WHERE id = ?")
-        .bind(&participant_id)
-        .execute(&mut *tx)
-        .await
-    {
-        Ok(result) => {
-            if result.rows_affected() == 0 {
-                return (StatusCode::NOT_FOUND, "Participant not found").into_response();
-            }
+        if let Some(days) = policy.events_older_than_days {
+            report.course_events += sqlx::query("DELETE FROM course_events WHERE created_at < $1")
+                .bind(days_ago(days))
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
         }
-        Err(err) => {
-            error!("Failed to delete participant {participant_id}: {err}");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to delete participant",
-            )
-                .into_response();
+
+        if dry_run {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
         }
+        Ok(report)
     }
+    .await)
+}
 
-    // Commit the transaction
-    if let Err(err) = tx.commit().await {
-        error!("Failed to commit transaction: {err}");
-        return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
-    }
+/// The `INSERT` that appends one row to `admin_audit`. Execute it in the
+/// transaction of the change it describes, so the two commit or roll
+/// back together.
+fn admin_audit_insert<'q, DB>(
+    admin: &'q Admin,
+    action: &'q str,
+    participant_id: Option<&'q str>,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+) -> sqlx::query::Query<'q, DB, DB::Arguments<'q>>
+where
+    DB: sqlx::Database,
+    &'q str: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    Option<&'q str>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    Option<String>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+{
+    let (actor, actor_name) = admin
+        .instructor
+        .as_ref()
+        .map_or(("admin", "admin"), |scope| {
+            (scope.id.as_str(), scope.name.as_str())
+        });
+    sqlx::query(
+        "INSERT INTO admin_audit (actor, actor_name, action, participant_id, before, after)
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(actor)
+    .bind(actor_name)
+    .bind(action)
+    .bind(participant_id)
+    .bind(before.map(|value| value.to_string()))
+    .bind(after.map(|value| value.to_string()))
+}
 
-    info!("Successfully removed participant: {participant_id}");
-    (StatusCode::OK, "Participant removed successfully").into_response()
+/// The `UPDATE` that blanks the `before` / `after` snapshots of every
+/// `admin_audit` row about the participants `participants` selects (a
+/// subquery, or a single `$1`). Every path that hard-deletes participants
+/// runs it in the same transaction; the rows themselves stay (migration
+/// `018_admin_audit_redaction.sql`).
+fn redact_admin_audit(participants: &str) -> String {
+    format!(
+        "UPDATE admin_audit SET before = NULL, after = NULL
+         WHERE participant_id IN ({participants})
+           AND (before IS NOT NULL OR after IS NOT NULL)"
+    )
 }
 
 /// API registration endpoint
@@ -2496,20 +6573,32 @@ async fn api_register(
     Json(request): Json<RegistrationRequest>,
 ) -> Result<Json<RegistrationResponse>, StatusCode> {
     let ulid = Ulid::new().to_string();
+    let course = match request.course.as_deref() {
+        Some(slug) => state.courses.get(slug).ok_or(StatusCode::NOT_FOUND)?,
+        None => state.courses.default_course(),
+    };
 
     info!(
-        "New participant registration: name='{}', ulid='{}'",
+        "New participant registration: name='{}', ulid='{}', course='{}'",
         request.name.as_str(),
-        ulid
+        ulid,
+        course.slug
     );
 
-    match sqlx::query("INSERT INTO participants (id, name) VALUES (?, ?)")
-        .bind(&ulid)
-        .bind(request.name.as_str())
-        .execute(&state.pool)
-        .await
+    match with_pool!(&state.db, |pool| sqlx::query(
+        "INSERT INTO participants (id, name) VALUES ($1, $2)"
+    )
+    .bind(&ulid)
+    .bind(request.name.as_str())
+    .execute(pool)
+    .await
+    .map(|done| done.rows_affected()))
     {
         Ok(_) => {
+            if let Err(e) = enroll(&state.db, &ulid, &course.slug).await {
+                error!("Failed to enroll new participant {ulid}: {e}");
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
             info!("Participant registered successfully: {ulid}");
             Ok(Json(RegistrationResponse { ulid }))
         }
@@ -2532,24 +6621,30 @@ struct SubmitResponse {
     /// `completed` flag in `get_exercise_progress`.
     chapter_completed: bool,
     /// Number of completable chapters the participant has finished
-    /// (excludes quizzes and notes-only chapters), matching the
+    /// (excludes notes-only chapters and practice quizzes), matching the
     /// computation in `render_exercise_page` / `participant_dashboard`.
     progress_done: usize,
     /// Number of completable chapters in the course (denominator).
     progress_total: usize,
+    /// The stored row's `submissions.verification` state: `"pending"`
+    /// while the server re-verifies it in the background (progress above
+    /// doesn't count it yet), `"unverified"` when verification is off and
+    /// the client's claim was stored as-is.
+    verification: String,
 }
 
 /// Compute the participant's chapter-level progress for the chapter that
 /// owns `exercise_name`. Returns `(chapter_completed, progress_done,
-/// progress_total)`, using the same `!is_quiz && has_exercises` filter
+/// progress_total)`, using the same `has_exercises` filter
 /// as the page renderers so the numbers stay consistent.
 async fn compute_submit_progress(
-    pool: &SqlitePool,
-    catalog: &[Exercise],
+    db: &Db,
+    course: &Course,
     ulid: &str,
     exercise_name: &str,
 ) -> Result<(bool, usize, usize)> {
-    let exercises = get_exercise_progress(pool, Some(ulid), catalog).await?;
+    let exercises =
+        get_exercise_progress(db, Some(ulid), &course.slug, &course.catalog.current()).await?;
     // `exercise_name` is either `<chapter>` (legacy) or `<chapter>/<step>`
     // (multi-step); the chapter slug is whatever's before the first `/`.
     let chapter_slug = exercise_name.split('/').next().unwrap_or(exercise_name);
@@ -2557,13 +6652,10 @@ async fn compute_submit_progress(
         .iter()
         .find(|e| e.name == chapter_slug)
         .is_some_and(|e| e.completed);
-    let progress_total = exercises
-        .iter()
-        .filter(|e| !e.is_quiz && e.has_exercises)
-        .count();
+    let progress_total = exercises.iter().filter(|e| e.has_exercises).count();
     let progress_done = exercises
         .iter()
-        .filter(|e| !e.is_quiz && e.has_exercises && e.completed)
+        .filter(|e| e.has_exercises && e.completed)
         .count();
     Ok((chapter_completed, progress_done, progress_total))
 }
@@ -2573,29 +6665,19 @@ async fn compute_submit_progress(
 // reason: top-level request handler; splitting purely for line count adds indirection without value
 #[allow(clippy::too_many_lines)]
 async fn api_submit(
+    headers: HeaderMap,
     State(state): State<AppState>,
     Json(request): Json<SubmissionRequest>,
 ) -> Result<Json<SubmitResponse>, StatusCode> {
-    info!(
-        "Submission attempt: participant_id='{}', exercise='{}'",
-        request.ulid, request.exercise_name
-    );
-
-    // First, check if the participant exists
-    let participant_exists = sqlx::query("SELECT 1 FROM participants WHERE id = ?")
-        .bind(&request.ulid)
-        .fetch_optional(&state.pool)
-        .await;
-
-    match participant_exists {
-        Ok(Some(_)) => {
-            // Participant exists, proceed with submission
-            info!("Valid participant found for submission: {}", request.ulid);
-        }
+    // `request.ulid` is a participant ULID or an API token; never log it
+    // before it has been resolved to a participant.
+    let participant_id = match authenticate_participant(&state, &headers, Some(&request.ulid)).await
+    {
+        Ok(Some(participant_id)) => participant_id,
         Ok(None) => {
             warn!(
-                "Submission attempt with invalid participant ID: {}",
-                request.ulid
+                "Submission attempt with an invalid credential for exercise '{}'",
+                request.exercise_name
             );
             return Err(StatusCode::UNAUTHORIZED);
         }
@@ -2603,46 +6685,80 @@ async fn api_submit(
             error!("Database error while checking participant: {e}");
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
+    };
+    info!(
+        "Submission attempt: participant_id='{participant_id}', exercise='{}'",
+        request.exercise_name
+    );
+    let (course, exercise_name) = match state.courses.resolve(&request.exercise_name) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            warn!(
+                "Rejecting submission for '{}': {err}",
+                request.exercise_name
+            );
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+    if let Err(e) = enroll(&state.db, &participant_id, &course.slug).await {
+        error!("Failed to enroll {participant_id} in {}: {e}", course.slug);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
     }
 
     // Calculate content hash for deduplication
     let content_hash =
-        calculate_submission_hash(&request.ulid, &request.exercise_name, &request.source_code);
+        calculate_submission_hash(&participant_id, exercise_name, &request.source_code);
 
     // Check if identical submission already exists
-    let existing_submission = sqlx::query(
-        "SELECT id FROM submissions WHERE participant_id = ? AND exercise_name = ? AND content_hash = ?"
-    )
-    .bind(&request.ulid)
-    .bind(&request.exercise_name)
-    .bind(&content_hash)
-    .fetch_optional(&state.pool)
-    .await;
+    let existing_submission: Result<Option<(String, String)>, _> = with_pool!(&state.db, |pool| {
+        sqlx::query_as(
+            "SELECT id, verification FROM submissions
+             WHERE participant_id = $1 AND course = $2 AND exercise_name = $3
+               AND content_hash = $4",
+        )
+        .bind(&participant_id)
+        .bind(&course.slug)
+        .bind(exercise_name)
+        .bind(&content_hash)
+        .fetch_optional(pool)
+        .await
+    });
 
     match existing_submission {
-        Ok(Some(_)) => {
+        Ok(Some((submission_id, verification))) => {
             info!(
                 "Duplicate submission detected for participant='{}', exercise='{}'; skipping",
-                request.ulid, request.exercise_name
+                participant_id, request.exercise_name
             );
+            // `watch` submits without fmt and clippy; a later
+            // `submit --pedantic` of the same file still adds them.
+            if (request.clippy_passed || request.fmt_passed)
+                && let Err(e) = upgrade_claims(
+                    &state.db,
+                    &submission_id,
+                    request.clippy_passed,
+                    request.fmt_passed,
+                )
+                .await
+            {
+                error!("Failed to update claims of duplicate submission: {e}");
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
             // Return success but don't store duplicate. Still recompute
             // progress so the client can reconcile its UI even when the
             // user re-submits an already-saved solution.
-            let (chapter_completed, progress_done, progress_total) = compute_submit_progress(
-                &state.pool,
-                &state.exercises,
-                &request.ulid,
-                &request.exercise_name,
-            )
-            .await
-            .map_err(|e| {
-                error!("Failed to compute progress after duplicate submit: {e}");
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
+            let (chapter_completed, progress_done, progress_total) =
+                compute_submit_progress(&state.db, course, &participant_id, exercise_name)
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to compute progress after duplicate submit: {e}");
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
             return Ok(Json(SubmitResponse {
                 chapter_completed,
                 progress_done,
                 progress_total,
+                verification,
             }));
         }
         Ok(None) => {
@@ -2654,61 +6770,310 @@ async fn api_submit(
         }
     }
 
-    // Insert new submission with hash
-    match sqlx::query(
+    // Insert new submission with hash. The client's booleans are always
+    // kept as the claim; with verification on, the verdict columns start
+    // out false and the background verifier fills them in.
+    let submission_id = Ulid::new().to_string();
+    let joined = match request.submitted_at {
+        Some(_) => with_pool!(&state.db, |pool| sqlx::query_scalar(
+            "SELECT created_at FROM participants WHERE id = $1"
+        )
+        .bind(&participant_id)
+        .fetch_one(pool)
+        .await)
+        .map_err(|e| {
+            error!("Failed to load when {participant_id} joined: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?,
+        None => None,
+    };
+    let submitted_at = submission_time(request.submitted_at, joined, chrono::Utc::now());
+    let verifying = state.verifier.is_some();
+    let verification = if verifying { "pending" } else { "unverified" };
+    match with_pool!(&state.db, |pool| sqlx::query(
         r"
-        INSERT INTO submissions (id, participant_id, exercise_name, source_code, tests_passed, clippy_passed, fmt_passed, content_hash)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO submissions (id, participant_id, course, exercise_name, source_code,
+                                 tests_passed, clippy_passed, fmt_passed, content_hash,
+                                 claimed_tests_passed, claimed_clippy_passed, claimed_fmt_passed,
+                                 verification, submitted_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
         "
     )
-    .bind(Ulid::new().to_string())
-    .bind(&request.ulid)
-    .bind(&request.exercise_name)
+    .bind(&submission_id)
+    .bind(&participant_id)
+    .bind(&course.slug)
+    .bind(exercise_name)
     .bind(&request.source_code)
+    .bind(request.tests_passed && !verifying)
+    .bind(request.clippy_passed && !verifying)
+    .bind(request.fmt_passed && !verifying)
+    .bind(&content_hash)
     .bind(request.tests_passed)
     .bind(request.clippy_passed)
     .bind(request.fmt_passed)
-    .bind(&content_hash)
-    .execute(&state.pool)
+    .bind(verification)
+    .bind(submitted_at)
+    .execute(pool)
     .await
+    .map(|done| done.rows_affected()))
     {
         Ok(_) => {
-            info!("Submission successful: participant='{}', exercise='{}', tests_passed={}", 
-                  request.ulid, request.exercise_name, request.tests_passed);
-            let (chapter_completed, progress_done, progress_total) = compute_submit_progress(
-                &state.pool,
-                &state.exercises,
-                &request.ulid,
-                &request.exercise_name,
-            )
-            .await
-            .map_err(|e| {
-                error!("Failed to compute progress after submit: {e}");
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
+            info!(
+                "Submission successful: participant='{}', exercise='{}', tests_passed={}, verification={verification}",
+                participant_id, request.exercise_name, request.tests_passed
+            );
+            if let Some(queue) = &state.verifier
+                && queue.send(submission_id).is_err()
+            {
+                // Stays `pending` and is picked up again on restart.
+                error!("Verification worker is gone; submission left pending");
+            }
+            let (chapter_completed, progress_done, progress_total) =
+                compute_submit_progress(&state.db, course, &participant_id, exercise_name)
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to compute progress after submit: {e}");
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
             Ok(Json(SubmitResponse {
                 chapter_completed,
                 progress_done,
                 progress_total,
+                verification: verification.to_string(),
             }))
-        },
+        }
         Err(e) => {
             error!("Failed to save submission: {e}");
             Err(StatusCode::INTERNAL_SERVER_ERROR)
-        },
+        }
+    }
+}
+
+/// Add the fmt / clippy passes a duplicate of submission `id` claims.
+/// Claims only ever go from false to true. Without verification they
+/// are the verdict too; with it the verifier has already checked fmt
+/// and clippy itself, so its verdict stays.
+async fn upgrade_claims(
+    db: &Db,
+    id: &str,
+    clippy_passed: bool,
+    fmt_passed: bool,
+) -> Result<u64, sqlx::Error> {
+    with_pool!(db, |pool| sqlx::query(
+        "UPDATE submissions
+         SET claimed_clippy_passed = COALESCE(claimed_clippy_passed, FALSE) OR $2,
+             claimed_fmt_passed = COALESCE(claimed_fmt_passed, FALSE) OR $3,
+             clippy_passed = CASE WHEN verification = 'unverified'
+                                  THEN COALESCE(clippy_passed, FALSE) OR $2
+                                  ELSE clippy_passed END,
+             fmt_passed = CASE WHEN verification = 'unverified'
+                               THEN COALESCE(fmt_passed, FALSE) OR $3
+                               ELSE fmt_passed END
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(clippy_passed)
+    .bind(fmt_passed)
+    .execute(pool)
+    .await
+    .map(|done| done.rows_affected()))
+}
+
+/// The `submitted_at` to store for a submission: the time the CLI
+/// claims for a queued one, never later than `now`, so a fast clock
+/// can't put it in the future, and never before the participant
+/// `joined`, so the timeline can't be backdated. Naive UTC, as in
+/// `purge_removed_participants`.
+fn submission_time(
+    claimed: Option<chrono::DateTime<chrono::Utc>>,
+    joined: Option<chrono::DateTime<chrono::Utc>>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> chrono::NaiveDateTime {
+    claimed
+        .map_or(now, |at| at.max(joined.unwrap_or(at)).min(now))
+        .naive_utc()
+}
+
+/// Read the backend choice in `key`. A [`BackendKind::Local`] choice is
+/// checked for `tool` up front (inside the sandbox, if one is set), so a
+/// host without a usable toolchain (the runtime Docker image has none)
+/// fails at startup rather than on every request.
+fn backend_kind(key: &str, tool: &str) -> anyhow::Result<BackendKind> {
+    let kind = BackendKind::from_env(key)?;
+    if kind == BackendKind::Local {
+        LocalBackend::from_env()?.require_tool(tool, &format!("{key}=local"))?;
+    }
+    Ok(kind)
+}
+
+/// Start the background submission verifier on `backend` and return its
+/// queue.
+///
+/// Rows left `pending` by a previous run (crash, restart, toolchain
+/// hiccup) are queued again before new submissions arrive. The worker
+/// handles one submission at a time so a burst of submits can't fork a
+/// compiler per request.
+async fn spawn_verifier(
+    db: Db,
+    courses: Arc<Courses>,
+    backend: LocalBackend,
+) -> Result<tokio::sync::mpsc::UnboundedSender<String>> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+
+    let pending: Vec<String> = with_pool!(&db, |pool| sqlx::query_scalar(
+        "SELECT id FROM submissions WHERE verification = 'pending' ORDER BY submitted_at",
+    )
+    .fetch_all(pool)
+    .await)?;
+    if !pending.is_empty() {
+        info!(
+            "Re-queueing {} pending submission(s) for verification",
+            pending.len()
+        );
+    }
+    for id in pending {
+        // The receiver is alive until the worker below exits, so this
+        // can't fail yet.
+        let _ = tx.send(id);
+    }
+
+    tokio::spawn(async move {
+        while let Some(id) = rx.recv().await {
+            if let Err(e) = verify_submission(&db, &courses, &backend, &id).await {
+                error!("Verifying submission {id} failed: {e:#}");
+            }
+        }
+    });
+    info!("Verifying submissions server-side with the local toolchain");
+    Ok(tx)
+}
+
+/// Re-run one pending submission and store the server's verdict.
+///
+/// A toolchain failure leaves the row `pending` (it is retried on the
+/// next start) rather than recording a verdict the learner didn't earn.
+async fn verify_submission(
+    db: &Db,
+    courses: &Courses,
+    backend: &LocalBackend,
+    id: &str,
+) -> Result<()> {
+    let Some((course, exercise_name, source_code)): Option<(String, String, String)> =
+        with_pool!(db, |pool| {
+            sqlx::query_as(
+                "SELECT course, exercise_name, source_code FROM submissions
+                 WHERE id = $1 AND verification = 'pending'",
+            )
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+        })?
+    else {
+        return Ok(());
+    };
+
+    let catalog = courses
+        .get(&course)
+        .map(|course| course.catalog.current())
+        .unwrap_or_default();
+    let Some(step) = exercises::find_code_step(&catalog, &exercise_name) else {
+        warn!("Can't verify submission {id}: unknown exercise `{exercise_name}`");
+        with_pool!(db, |pool| sqlx::query(
+            "UPDATE submissions SET verification = 'failed', verification_log = $1,
+                 verified_at = CURRENT_TIMESTAMP
+             WHERE id = $2",
+        )
+        .bind(format!("unknown exercise `{exercise_name}`"))
+        .bind(id)
+        .execute(pool)
+        .await
+        .map(|done| done.rows_affected()))?;
+        return Ok(());
+    };
+
+    let verdict = verify::verify(backend, &source_code, &step.starter_code).await?;
+    info!(
+        "Verified submission {id} ({exercise_name}): tests={} fmt={} clippy={}",
+        verdict.tests_passed, verdict.fmt_passed, verdict.clippy_passed
+    );
+    with_pool!(db, |pool| sqlx::query(
+        r"
+        UPDATE submissions
+        SET tests_passed = $1, clippy_passed = $2, fmt_passed = $3,
+            verification = 'verified', verification_log = $4, verified_at = CURRENT_TIMESTAMP
+        WHERE id = $5
+        ",
+    )
+    .bind(verdict.tests_passed)
+    .bind(verdict.clippy_passed)
+    .bind(verdict.fmt_passed)
+    .bind((!verdict.log.is_empty()).then_some(verdict.log))
+    .bind(id)
+    .execute(pool)
+    .await
+    .map(|done| done.rows_affected()))?;
+    Ok(())
+}
+
+/// `?course=` on the status endpoint; the default course when absent.
+#[derive(Deserialize)]
+struct StatusQuery {
+    #[serde(default)]
+    course: Option<String>,
+}
+
+/// The participant ULID in the path of `/api/status/{ulid}` and
+/// `/api/export/{ulid}`. API tokens belong in an `Authorization: Bearer`
+/// header (`GET /api/status`, `GET /api/export`), where they stay out of
+/// access logs; one in the path is refused.
+fn path_ulid(path: Option<AxumPath<String>>) -> Result<Option<String>, StatusCode> {
+    match path {
+        Some(AxumPath(credential)) if credential.starts_with("cc_") => Err(StatusCode::BAD_REQUEST),
+        Some(AxumPath(ulid)) => Ok(Some(ulid)),
+        None => Ok(None),
     }
 }
 
 /// API status endpoint
 #[debug_handler]
 async fn api_status(
-    AxumPath(ulid): AxumPath<String>,
+    path: Option<AxumPath<String>>,
+    Query(query): Query<StatusQuery>,
+    headers: HeaderMap,
     State(state): State<AppState>,
 ) -> Result<Json<ProgressResponse>, StatusCode> {
+    let course = match query.course.as_deref() {
+        Some(slug) => state.courses.get(slug).ok_or(StatusCode::NOT_FOUND)?,
+        None => state.courses.default_course(),
+    };
+    // Unknown ULIDs keep getting an empty progress list, as before;
+    // only revoked tokens and participants behind sign-in are refused.
+    let ulid = match path_ulid(path)? {
+        Some(ulid) if bearer_token(&headers).is_none() => {
+            participant_access(&state, &headers, &ulid)
+                .await
+                .map(|access| match access {
+                    ParticipantAccess::SignInRequired => None,
+                    ParticipantAccess::Granted { .. } | ParticipantAccess::Unknown => Some(ulid),
+                })
+        }
+        _ => authenticate_participant(&state, &headers, None).await,
+    }
+    .map_err(|e| {
+        error!("Database error while checking participant: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::UNAUTHORIZED)?;
     info!("Status request for participant: {ulid}");
 
-    match get_exercise_progress(&state.pool, Some(&ulid), &state.exercises).await {
-        Ok(exercises) => {
+    let catalog = course.catalog.current();
+    let released = load_releases(&state.db, &course.slug).await.map_err(|e| {
+        error!("Failed to load chapter releases: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    match get_exercise_progress(&state.db, Some(&ulid), &course.slug, &catalog).await {
+        Ok(mut exercises) => {
+            apply_locks(&mut exercises, &catalog, &released);
             let completed_count = exercises.iter().filter(|e| e.completed).count();
             let perfected_count = exercises.iter().filter(|e| e.perfected).count();
             info!(
@@ -2718,9 +7083,14 @@ async fn api_status(
             let exercise_statuses = exercises
                 .into_iter()
                 .map(|e| ExerciseStatus {
+                    submitted_steps: e.submitted_steps(),
+                    passed_steps: e.passed_steps(),
                     name: e.name,
                     completed: e.completed,
                     perfected: e.perfected,
+                    locked: e.locked,
+                    difficulty: e.difficulty,
+                    tags: e.tags,
                 })
                 .collect();
 
@@ -2735,6 +7105,54 @@ async fn api_status(
     }
 }
 
+/// API data export endpoint (`cargo course export`)
+async fn api_export(
+    path: Option<AxumPath<String>>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Response {
+    let ulid = match path_ulid(path) {
+        Ok(ulid) => ulid,
+        Err(status) => return status.into_response(),
+    };
+    match authenticate_participant(&state, &headers, ulid.as_deref()).await {
+        Ok(Some(participant_id)) => account_export_response(&state, &participant_id).await,
+        Ok(None) => StatusCode::UNAUTHORIZED.into_response(),
+        Err(e) => {
+            error!("Database error while checking participant: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// API account deletion endpoint (`cargo course delete-account`)
+async fn api_delete_account(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Json(request): Json<AccountDeletionRequest>,
+) -> StatusCode {
+    let participant_id = match authenticate_participant(&state, &headers, Some(&request.ulid)).await
+    {
+        Ok(Some(participant_id)) => participant_id,
+        Ok(None) => return StatusCode::UNAUTHORIZED,
+        Err(e) => {
+            error!("Database error while checking participant: {e}");
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+    match delete_account(&state.db, &participant_id).await {
+        Ok(true) => {
+            info!("Participant {participant_id} deleted their account from the CLI");
+            StatusCode::NO_CONTENT
+        }
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(err) => {
+            error!("Failed to delete account {participant_id}: {err}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct CourseEventRequest {
     #[serde(default)]
@@ -2766,9 +7184,16 @@ async fn api_course_event(
     {
         return StatusCode::BAD_REQUEST;
     }
+    let (course, exercise_name) = match request.exercise_name.as_deref() {
+        Some(name) => match state.courses.resolve(name) {
+            Ok((course, name)) => (course, Some(name)),
+            Err(_) => return StatusCode::BAD_REQUEST,
+        },
+        None => (state.courses.default_course(), None),
+    };
 
     if let Some(participant_id) = request.participant_id.as_deref() {
-        match participant_exists(&state.pool, participant_id).await {
+        match participant_exists(&state.db, participant_id).await {
             Ok(true) => {}
             Ok(false) => return StatusCode::UNAUTHORIZED,
             Err(error) => {
@@ -2779,17 +7204,19 @@ async fn api_course_event(
     }
 
     let event = CourseEvent {
+        course,
         participant_id: request.participant_id.as_deref(),
         session_id: &request.session_id,
         event_type: &request.event_type,
-        exercise_name: request.exercise_name.as_deref(),
+        exercise_name,
         result: None,
         tests_passed: None,
         tests_total: None,
         duration_ms: None,
         diagnostic_code: None,
+        diagnostics: &[],
     };
-    if let Err(error) = store_course_event(&state.pool, event).await {
+    if let Err(error) = store_course_event(&state.db, event).await {
         error!("Failed to store course event: {error}");
         return StatusCode::INTERNAL_SERVER_ERROR;
     }
@@ -2809,14 +7236,127 @@ fn valid_exercise_name(value: &str) -> bool {
         && !value.is_empty()
         && value
             .bytes()
-            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'/'))
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'/' | b':'))
 }
 
-async fn participant_exists(pool: &SqlitePool, participant_id: &str) -> Result<bool, sqlx::Error> {
-    sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM participants WHERE id = ?)")
-        .bind(participant_id)
-        .fetch_one(pool)
-        .await
+/// Request body for `/api/quiz-answer`, posted by `static/js/quiz.js`
+/// each time the learner answers a question.
+#[derive(Deserialize)]
+struct QuizAnswerRequest {
+    /// The participant's ULID, or an API token (`cc_…`)
+    ulid: String,
+    /// The page's `data-course-chapter`: the chapter's directory name,
+    /// qualified with the course slug outside the default course.
+    chapter: String,
+    /// Generated by the page per run through the quiz.
+    attempt: String,
+    /// 0-based position of the question in `quiz.toml`.
+    question: usize,
+    /// See [`exercises::Question::grade`].
+    answer: String,
+}
+
+/// Longest `answer` accepted; "what does this print" answers are the
+/// only long ones, and no quiz program prints pages.
+const MAX_QUIZ_ANSWER_BYTES: usize = 4096;
+
+/// The server's verdict on a quiz answer and, as for `/api/submit`,
+/// where the participant's progress stands now.
+#[derive(Serialize)]
+struct QuizAnswerResponse {
+    correct: bool,
+    chapter_completed: bool,
+    progress_done: usize,
+    progress_total: usize,
+}
+
+/// Record one quiz answer. It's graded against `quiz.toml` here rather
+/// than taken from the browser, and only the first answer to a question
+/// in an attempt is kept, so "Try again" starts a new attempt instead
+/// of overwriting the last one.
+async fn api_quiz_answer(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Json(request): Json<QuizAnswerRequest>,
+) -> Result<Json<QuizAnswerResponse>, StatusCode> {
+    let participant_id = match authenticate_participant(&state, &headers, Some(&request.ulid)).await
+    {
+        Ok(Some(participant_id)) => participant_id,
+        Ok(None) => return Err(StatusCode::UNAUTHORIZED),
+        Err(e) => {
+            error!("Database error while checking participant: {e}");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    if !valid_analytics_identifier(&request.attempt, 64)
+        || request.answer.len() > MAX_QUIZ_ANSWER_BYTES
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let Ok((course, chapter)) = state.courses.resolve(&request.chapter) else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    let catalog = course.catalog.current();
+    let Some(question) = catalog
+        .iter()
+        .find(|e| e.file_stem == chapter)
+        .and_then(Exercise::quiz)
+        .and_then(|quiz| quiz.questions.get(request.question))
+    else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let (Some(correct), Ok(index)) = (
+        question.grade(&request.answer),
+        i64::try_from(request.question),
+    ) else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    if let Err(e) = enroll(&state.db, &participant_id, &course.slug).await {
+        error!("Failed to enroll {participant_id} in {}: {e}", course.slug);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    if let Err(e) = with_pool!(&state.db, |pool| {
+        sqlx::query(
+        "INSERT INTO quiz_answers (participant_id, course, chapter, attempt, question, answer, correct)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(&participant_id)
+    .bind(&course.slug)
+    .bind(chapter)
+    .bind(&request.attempt)
+    .bind(index)
+    .bind(&request.answer)
+    .bind(correct)
+    .execute(pool)
+    .await
+    .map(|done| done.rows_affected())
+    }) {
+        error!("Failed to store quiz answer for {participant_id}: {e}");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    let (chapter_completed, progress_done, progress_total) =
+        compute_submit_progress(&state.db, course, &participant_id, chapter)
+            .await
+            .map_err(|e| {
+                error!("Failed to compute progress after quiz answer: {e}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    Ok(Json(QuizAnswerResponse {
+        correct,
+        chapter_completed,
+        progress_done,
+        progress_total,
+    }))
+}
+
+async fn participant_exists(db: &Db, participant_id: &str) -> Result<bool, sqlx::Error> {
+    with_pool!(db, |pool| sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM participants WHERE id = $1 AND deleted_at IS NULL)",
+    )
+    .bind(participant_id)
+    .fetch_one(pool)
+    .await)
 }
 
 /// Request body for `/api/run`. We accept any source code; the slug is
@@ -2851,6 +7391,8 @@ struct RunResponse {
     stdout: String,
     stderr: String,
     test_results: Vec<TestResult>,
+    /// Compiler errors and warnings with spans, for editor squiggles.
+    diagnostics: Vec<Diagnostic>,
 }
 
 #[derive(Serialize)]
@@ -2859,91 +7401,64 @@ struct TestResult {
     passed: bool,
 }
 
-/// Proxy handler: forwards the editor's source to play.rust-lang.org and
-/// returns the JSON. We intentionally keep this thin: the upstream
-/// already runs untrusted code in a sandbox and enforces its own rate
-/// limits, so we just pass status codes back through.
-#[derive(Deserialize)]
-struct PlaygroundResp {
-    success: bool,
-    stdout: String,
-    stderr: String,
-}
-
+/// Run handler: compiles and runs the editor's source through the
+/// configured [`RunBackend`] (play.rust-lang.org by default, or the local
+/// toolchain when `CORRODE_RUN_BACKEND=local`). Backend failures map onto
+/// status codes the browser already understands: 429 is forwarded so the
+/// editor can show a "rate limited" hint, anything else collapses to 502.
+/// With `CORRODE_RUN_REQUIRE_PARTICIPANT`, requests whose
+/// `participant_id` (or bearer token) doesn't resolve to a participant
+/// get 401.
 async fn api_run(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(req): Json<RunRequest>,
 ) -> Result<Json<RunResponse>, StatusCode> {
     let started_at = std::time::Instant::now();
+    if state.run_requires_participant {
+        authenticate_participant(&state, &headers, req.participant_id.as_deref())
+            .await
+            .map_err(|e| {
+                error!("Database error while checking participant: {e}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+    }
     let slug = req.slug.as_deref().unwrap_or("<unknown>");
-    info!("/api/run: forwarding {} bytes for {slug}", req.code.len());
-
-    let body = serde_json::json!({
-        "channel": "stable",
-        "mode": "debug",
-        "edition": "2024",
-        "crateType": "bin",
-        "tests": req.tests,
-        "backtrace": false,
-        "code": req.code,
-    });
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(20))
-        .build()
-        .map_err(|e| {
-            error!("reqwest client build failed: {e}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    info!(
+        "/api/run: running {} bytes for {slug} on {}",
+        req.code.len(),
+        state.runner.name()
+    );
 
-    let resp = match client
-        .post("https://play.rust-lang.org/execute")
-        .json(&body)
-        .send()
-        .await
-    {
-        Ok(response) => response,
-        Err(error) => {
-            error!("Playground request failed: {error:?}");
-            record_upstream_failure(&state.pool, &req, started_at).await;
-            return Err(StatusCode::BAD_GATEWAY);
-        }
+    let job = RunJob {
+        code: &req.code,
+        tests: req.tests,
     };
-
-    let status = resp.status();
-    if !status.is_success() {
-        warn!("Playground returned non-2xx: {status}");
-        // Forward 429 so the browser can show a 'rate limited' hint;
-        // collapse anything else to 502.
-        let mapped = if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
-            StatusCode::TOO_MANY_REQUESTS
-        } else {
-            StatusCode::BAD_GATEWAY
-        };
-        record_upstream_failure(&state.pool, &req, started_at).await;
-        return Err(mapped);
-    }
-
-    let parsed: PlaygroundResp = match resp.json().await {
-        Ok(response) => response,
+    let output = match state.runner.run(job).await {
+        Ok(output) => output,
         Err(error) => {
-            error!("Failed to parse Playground response: {error}");
-            record_upstream_failure(&state.pool, &req, started_at).await;
-            return Err(StatusCode::BAD_GATEWAY);
+            warn!("/api/run: {} backend failed: {error}", state.runner.name());
+            record_upstream_failure(&state.db, &state.courses, &req, started_at).await;
+            return Err(match error {
+                RunError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+                RunError::Upstream(_) | RunError::Toolchain(_) => StatusCode::BAD_GATEWAY,
+            });
         }
     };
 
-    let test_results = parse_test_results(&parsed.stdout);
+    let test_results = parse_test_results(&output.stdout);
     info!(
         "/api/run: {} test result(s) parsed (success={})",
         test_results.len(),
-        parsed.success
+        output.success
     );
 
     record_run_event(
-        &state.pool,
+        &state.db,
+        &state.courses,
         &req,
-        Some(&parsed),
+        Some(&output),
         &test_results,
         started_at,
         None,
@@ -2951,20 +7466,23 @@ async fn api_run(
     .await;
 
     Ok(Json(RunResponse {
-        success: parsed.success,
-        stdout: parsed.stdout,
-        stderr: parsed.stderr,
+        success: output.success,
+        stdout: output.stdout,
+        stderr: output.stderr,
         test_results,
+        diagnostics: output.diagnostics,
     }))
 }
 
 async fn record_upstream_failure(
-    pool: &SqlitePool,
+    db: &Db,
+    courses: &Courses,
     request: &RunRequest,
     started_at: std::time::Instant,
 ) {
     record_run_event(
-        pool,
+        db,
+        courses,
         request,
         None,
         &[],
@@ -2975,9 +7493,10 @@ async fn record_upstream_failure(
 }
 
 async fn record_run_event(
-    pool: &SqlitePool,
+    db: &Db,
+    courses: &Courses,
     request: &RunRequest,
-    response: Option<&PlaygroundResp>,
+    response: Option<&RunOutput>,
     test_results: &[TestResult],
     started_at: std::time::Instant,
     result_override: Option<&str>,
@@ -2990,7 +7509,7 @@ async fn record_run_event(
         return;
     };
     let participant_id = if let Some(id) = request.participant_id.as_deref() {
-        match participant_exists(pool, id).await {
+        match participant_exists(db, id).await {
             Ok(true) => Some(id),
             Ok(false) => None,
             Err(error) => {
@@ -3014,23 +7533,37 @@ async fn record_run_event(
             )
         })
     });
-    let diagnostic = response.and_then(|value| first_rust_error_code(&value.stderr));
+    let diagnostics = response.map_or_else(Vec::new, |value| diagnostic_counts(&value.diagnostics));
+    let first_error = diagnostics
+        .iter()
+        .find(|count| count.level == "error" && is_rust_error_code(count.code))
+        .map(|count| count.code);
     let duration_ms = i64::try_from(started_at.elapsed().as_millis()).unwrap_or(i64::MAX);
+    // Runs from a course nobody hosts (a stale tab after a registry
+    // change) still count, just without an exercise.
+    let (course, exercise_name) = request
+        .slug
+        .as_deref()
+        .filter(|name| valid_exercise_name(name))
+        .and_then(|name| courses.resolve(name).ok())
+        .map_or_else(
+            || (courses.default_course(), None),
+            |(course, name)| (course, Some(name)),
+        );
     let event = CourseEvent {
+        course,
         participant_id,
         session_id,
         event_type: "exercise_run",
-        exercise_name: request
-            .slug
-            .as_deref()
-            .filter(|name| valid_exercise_name(name)),
+        exercise_name,
         result: Some(result),
         tests_passed: Some(tests_passed_count),
         tests_total: Some(tests_total),
         duration_ms: Some(duration_ms),
-        diagnostic_code: diagnostic.as_deref(),
+        diagnostic_code: first_error,
+        diagnostics: &diagnostics,
     };
-    if let Err(error) = store_course_event(pool, event).await {
+    if let Err(error) = store_course_event(db, event).await {
         // Analytics is best-effort and must never prevent a learner from
         // receiving their compiler/test result.
         warn!("Failed to store run analytics: {error}");
@@ -3056,14 +7589,6 @@ const fn classify_run_result(
     }
 }
 
-fn first_rust_error_code(stderr: &str) -> Option<String> {
-    let marker = "error[E";
-    let start = stderr.find(marker)? + "error[".len();
-    let code = stderr.get(start..start + 5)?;
-    (code.starts_with('E') && code[1..].bytes().all(|byte| byte.is_ascii_digit()))
-        .then(|| code.to_string())
-}
-
 /// Request body for `/api/format`. Same request as `/api/run` minus the
 /// fields the formatter doesn't care about.
 #[derive(Deserialize)]
@@ -3084,68 +7609,85 @@ struct FormatResponse {
     stderr: String,
 }
 
-/// Proxy handler for play.rust-lang.org's `/format` endpoint. Takes the
-/// editor's source, runs it through `rustfmt` upstream, and returns the
+/// Format handler: runs the editor's source through the configured
+/// [`FormatBackend`] (play.rust-lang.org by default, or the local
+/// `rustfmt` when `CORRODE_FORMAT_BACKEND=local`) and returns the
 /// reformatted code so the client can replace its buffer.
-#[derive(Deserialize)]
-struct PlaygroundFormatResp {
+async fn api_format(
+    State(state): State<AppState>,
+    Json(req): Json<FormatRequest>,
+) -> Result<Json<FormatResponse>, StatusCode> {
+    let slug = req.slug.as_deref().unwrap_or("<unknown>");
+    info!(
+        "/api/format: formatting {} bytes for {slug} on {}",
+        req.code.len(),
+        state.formatter.name()
+    );
+
+    let output = state.formatter.format(&req.code).await.map_err(|error| {
+        warn!(
+            "/api/format: {} backend failed: {error}",
+            state.formatter.name()
+        );
+        match error {
+            RunError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            RunError::Upstream(_) | RunError::Toolchain(_) => StatusCode::BAD_GATEWAY,
+        }
+    })?;
+
+    info!("/api/format: success={}", output.success);
+    Ok(Json(FormatResponse {
+        success: output.success,
+        code: output.code,
+        stderr: output.stderr,
+    }))
+}
+
+/// Response to `/api/clippy`. `success = false` means the code didn't
+/// compile (the errors are among `diagnostics`); lint warnings alone
+/// don't fail it.
+#[derive(Serialize)]
+struct ClippyResponse {
     success: bool,
-    code: String,
+    diagnostics: Vec<Diagnostic>,
     stderr: String,
 }
 
-async fn api_format(Json(req): Json<FormatRequest>) -> Result<Json<FormatResponse>, StatusCode> {
+/// Clippy handler: lints the editor's source through the configured
+/// [`LintBackend`] and returns structured diagnostics (lint name,
+/// level, message, spans, suggested replacements) for the editor to
+/// underline. Takes the same body as `/api/format`.
+async fn api_clippy(
+    State(state): State<AppState>,
+    Json(req): Json<FormatRequest>,
+) -> Result<Json<ClippyResponse>, StatusCode> {
     let slug = req.slug.as_deref().unwrap_or("<unknown>");
     info!(
-        "/api/format: forwarding {} bytes for {slug}",
-        req.code.len()
+        "/api/clippy: linting {} bytes for {slug} on {}",
+        req.code.len(),
+        state.linter.name()
     );
 
-    let body = serde_json::json!({
-        "channel": "stable",
-        "edition": "2024",
-        "code": req.code,
-    });
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(15))
-        .build()
-        .map_err(|e| {
-            error!("reqwest client build failed: {e}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-    let resp = client
-        .post("https://play.rust-lang.org/format")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| {
-            error!("Playground format request failed: {e:?}");
-            StatusCode::BAD_GATEWAY
-        })?;
-
-    let status = resp.status();
-    if !status.is_success() {
-        warn!("Playground /format returned non-2xx: {status}");
-        let mapped = if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
-            StatusCode::TOO_MANY_REQUESTS
-        } else {
-            StatusCode::BAD_GATEWAY
-        };
-        return Err(mapped);
-    }
-
-    let parsed: PlaygroundFormatResp = resp.json().await.map_err(|e| {
-        error!("Failed to parse Playground /format response: {e}");
-        StatusCode::BAD_GATEWAY
+    let output = state.linter.clippy(&req.code).await.map_err(|error| {
+        warn!(
+            "/api/clippy: {} backend failed: {error}",
+            state.linter.name()
+        );
+        match error {
+            RunError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            RunError::Upstream(_) | RunError::Toolchain(_) => StatusCode::BAD_GATEWAY,
+        }
     })?;
 
-    info!("/api/format: success={}", parsed.success);
-    Ok(Json(FormatResponse {
-        success: parsed.success,
-        code: parsed.code,
-        stderr: parsed.stderr,
+    info!(
+        "/api/clippy: success={} diagnostics={}",
+        output.success,
+        output.diagnostics.len()
+    );
+    Ok(Json(ClippyResponse {
+        success: output.success,
+        diagnostics: output.diagnostics,
+        stderr: output.stderr,
     }))
 }
 
@@ -3182,24 +7724,95 @@ fn parse_test_results(stdout: &str) -> Vec<TestResult> {
 }
 
 /// Get admin statistics
-async fn get_admin_stats(pool: &SqlitePool) -> Result<AdminStats> {
+/// Headline counts for the admin dashboard, limited to an instructor's
+/// teams when `instructor_id` is set.
+/// Per-question correctness for every quiz in `catalog`, counting each
+/// answer the participants in scope gave (every attempt, not just
+/// their first). A question most people miss is often a misleading one.
+async fn load_quiz_question_stats(
+    db: &Db,
+    instructor_id: Option<&str>,
+    course: &str,
+    catalog: &[Exercise],
+) -> Result<Vec<QuizQuestionStats>, sqlx::Error> {
+    let rows: Vec<(String, i64, i64, i64)> = with_pool!(db, |pool| sqlx::query_as(&format!(
+        "SELECT q.chapter, q.question, COUNT(*),
+                SUM(CASE WHEN q.correct THEN 1 ELSE 0 END)
+         FROM quiz_answers q JOIN participants p ON q.participant_id = p.id
+         WHERE p.deleted_at IS NULL AND {ADMIN_SCOPE_FILTER} AND q.course = $2
+         GROUP BY q.chapter, q.question"
+    ))
+    .bind(instructor_id)
+    .bind(course)
+    .fetch_all(pool)
+    .await)?;
+    let counts: std::collections::HashMap<(&str, i64), (i64, i64)> = rows
+        .iter()
+        .map(|(chapter, question, answers, correct)| {
+            ((chapter.as_str(), *question), (*answers, *correct))
+        })
+        .collect();
+
+    let mut stats = Vec::new();
+    for exercise in catalog {
+        let Some(quiz) = exercise.quiz() else {
+            continue;
+        };
+        for (i, question) in quiz.questions.iter().enumerate() {
+            let (answers, correct) = i64::try_from(i)
+                .ok()
+                .and_then(|i| counts.get(&(exercise.file_stem.as_str(), i)))
+                .copied()
+                .unwrap_or_default();
+            let first_line = question.prompt.lines().next().unwrap_or_default();
+            let mut prompt: String = first_line.chars().take(100).collect();
+            if prompt.len() < first_line.len() {
+                prompt.push('…');
+            }
+            stats.push(QuizQuestionStats {
+                chapter_title: exercise.title.clone(),
+                number: i + 1,
+                prompt,
+                answers,
+                correct_percent: (answers > 0).then(|| (correct * 100 + answers / 2) / answers),
+            });
+        }
+    }
+    Ok(stats)
+}
+
+async fn get_admin_stats(db: &Db, instructor_id: Option<&str>, course: &str) -> Result<AdminStats> {
     // Get total participants
-    let total_participants: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM participants")
-        .fetch_one(pool)
-        .await?;
+    let total_participants: i64 = with_pool!(db, |pool| sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM participants p
+         WHERE p.deleted_at IS NULL AND {ADMIN_SCOPE_FILTER} AND {COURSE_ENROLLED_FILTER}"
+    ))
+    .bind(instructor_id)
+    .bind(course)
+    .fetch_one(pool)
+    .await)?;
 
     // Get total submissions across all participants
-    let total_submissions: i64 =
-        sqlx::query_scalar("SELECT COUNT(*) FROM submissions WHERE tests_passed = 1")
-            .fetch_one(pool)
-            .await?;
+    let total_submissions: i64 = with_pool!(db, |pool| sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM submissions s JOIN participants p ON s.participant_id = p.id
+         WHERE s.tests_passed = TRUE AND p.deleted_at IS NULL AND {ADMIN_SCOPE_FILTER}
+           AND s.course = $2"
+    ))
+    .bind(instructor_id)
+    .bind(course)
+    .fetch_one(pool)
+    .await)?;
 
     // Get total perfected submissions (successful + fmt + clippy)
-    let total_perfected: i64 = sqlx::query_scalar(
-        "SELECT COUNT(*) FROM submissions WHERE tests_passed = 1 AND fmt_passed = 1 AND clippy_passed = 1"
-    )
+    let total_perfected: i64 = with_pool!(db, |pool| sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM submissions s JOIN participants p ON s.participant_id = p.id
+         WHERE s.tests_passed = TRUE AND s.fmt_passed = TRUE AND s.clippy_passed = TRUE
+           AND p.deleted_at IS NULL AND {ADMIN_SCOPE_FILTER} AND s.course = $2"
+    ))
+    .bind(instructor_id)
+    .bind(course)
     .fetch_one(pool)
-    .await?;
+    .await)?;
 
     Ok(AdminStats {
         participants: total_participants,
@@ -3208,16 +7821,72 @@ async fn get_admin_stats(pool: &SqlitePool) -> Result<AdminStats> {
     })
 }
 
-/// Build the per-chapter progress vector used by the dashboard.
+/// Enroll `participant_id` in `course` unless they already are.
+async fn enroll(db: &Db, participant_id: &str, course: &str) -> Result<(), sqlx::Error> {
+    with_pool!(db, |pool| sqlx::query(
+        "INSERT INTO course_enrollments (participant_id, course) VALUES ($1, $2)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(participant_id)
+    .bind(course)
+    .execute(pool)
+    .await
+    .map(|_| ()))
+}
+
+/// Slugs of the courses `participant_id` is enrolled in.
+async fn enrolled_courses(db: &Db, participant_id: &str) -> Result<Vec<String>, sqlx::Error> {
+    with_pool!(db, |pool| sqlx::query_scalar(
+        "SELECT course FROM course_enrollments WHERE participant_id = $1 ORDER BY enrolled_at",
+    )
+    .bind(participant_id)
+    .fetch_all(pool)
+    .await)
+}
+
+/// The course switcher for a page of `current`: every course, linking
+/// to `page` (e.g. `/dashboard/<ulid>`, or `""` for the landing page)
+/// within it. Empty when the server hosts a single course, so the
+/// switcher doesn't render.
+fn course_links(
+    courses: &Courses,
+    current: &Course,
+    page: &str,
+    enrolled: &[String],
+) -> Vec<CourseLink> {
+    if courses.len() < 2 {
+        return Vec::new();
+    }
+    courses
+        .iter()
+        .map(|course| {
+            let href = format!("{}{page}", course.base());
+            CourseLink {
+                title: course.title.clone(),
+                href: if href.is_empty() {
+                    "/".to_string()
+                } else {
+                    href
+                },
+                current: course.slug == current.slug,
+                enrolled: enrolled.contains(&course.slug),
+            }
+        })
+        .collect()
+}
+
+/// Build the per-chapter progress vector used by the dashboard, for the
+/// course whose slug is `course` and whose chapters are `catalog`.
 ///
 /// Pass `Some(ulid)` for an authenticated participant view; submissions
-/// belonging to that participant are aggregated into per-chapter
-/// `completed` / `perfected` flags. Pass `None` for the anonymous
-/// dashboard at `/`; the database is skipped entirely and every
-/// chapter comes back with both flags `false`.
+/// and quiz answers belonging to that participant are aggregated into
+/// per-chapter `completed` / `perfected` flags. Pass `None` for the
+/// anonymous dashboard at `/`; the database is skipped entirely and
+/// every chapter comes back with both flags `false`.
 async fn get_exercise_progress<'a>(
-    pool: &'a SqlitePool,
+    db: &'a Db,
     ulid: Option<&'a str>,
+    course: &'a str,
     catalog: &'a [Exercise],
 ) -> Result<Vec<ExerciseProgress>> {
     // Anonymous mode: skip the SQL round-trip entirely. The loop below
@@ -3225,12 +7894,25 @@ async fn get_exercise_progress<'a>(
     // match against every chapter falls into the "not yet attempted"
     // branch.
     let all_submissions: Vec<DbSubmission> = match ulid {
-        Some(ulid) => sqlx::query_as(
-            "SELECT * FROM submissions WHERE participant_id = ? ORDER BY exercise_name, submitted_at DESC",
+        Some(ulid) => with_pool!(db, |pool| sqlx::query_as(
+            "SELECT * FROM submissions WHERE participant_id = $1 AND course = $2
+             ORDER BY exercise_name, submitted_at DESC",
         )
         .bind(ulid)
+        .bind(course)
         .fetch_all(pool)
-        .await?,
+        .await)?,
+        None => Vec::new(),
+    };
+    let quiz_answers: Vec<QuizAnswerRow> = match ulid {
+        Some(ulid) => with_pool!(db, |pool| sqlx::query_as(
+            "SELECT chapter, attempt, question, correct FROM quiz_answers
+             WHERE participant_id = $1 AND course = $2",
+        )
+        .bind(ulid)
+        .bind(course)
+        .fetch_all(pool)
+        .await)?,
         None => Vec::new(),
     };
 
@@ -3247,6 +7929,7 @@ async fn get_exercise_progress<'a>(
             })
             .map(|s| ExerciseSubmission {
                 id: s.id.clone(),
+                exercise_name: s.exercise_name.clone(),
                 source_code: s.source_code.clone(),
                 tests_passed: s.tests_passed,
                 clippy_passed: s.clippy_passed,
@@ -3256,56 +7939,98 @@ async fn get_exercise_progress<'a>(
             .collect();
 
         let is_quiz = ex.is_quiz();
+        let answers: Vec<&QuizAnswerRow> = quiz_answers
+            .iter()
+            .filter(|a| a.chapter == ex.file_stem)
+            .collect();
 
         // Chapter is "completed" when every code step has at least one
         // passing submission. "Perfected" requires tests + fmt + clippy
-        // green on the same submission for every step.
+        // green on the same submission for every step. Quizzes are
+        // scored by `quiz_progress` instead.
         let code_steps = ex.code_steps();
-        let (completed, perfected) = if is_quiz || code_steps.is_empty() {
-            (false, false)
-        } else {
-            let mut all_done = true;
-            let mut all_perfect = true;
-            for step in &code_steps {
-                let step_key = step.key();
-                let key = if step_key.is_empty() {
-                    ex.file_stem.clone()
-                } else {
-                    format!("{}/{}", ex.file_stem, step_key)
-                };
-                let step_done = all_submissions
-                    .iter()
-                    .any(|s| s.exercise_name == key && s.tests_passed);
-                let step_perfect = all_submissions.iter().any(|s| {
-                    s.exercise_name == key && s.tests_passed && s.fmt_passed && s.clippy_passed
-                });
-                if !step_done {
-                    all_done = false;
-                }
-                if !step_perfect {
-                    all_perfect = false;
+        let (completed, perfected) = match ex.quiz() {
+            Some(quiz) => quiz_progress(quiz, &answers),
+            None if is_quiz || code_steps.is_empty() => (false, false),
+            None => {
+                let mut all_done = true;
+                let mut all_perfect = true;
+                for step in &code_steps {
+                    let step_key = step.key();
+                    let key = if step_key.is_empty() {
+                        ex.file_stem.clone()
+                    } else {
+                        format!("{}/{}", ex.file_stem, step_key)
+                    };
+                    let step_done = all_submissions
+                        .iter()
+                        .any(|s| s.exercise_name == key && s.tests_passed);
+                    let step_perfect = all_submissions.iter().any(|s| {
+                        s.exercise_name == key && s.tests_passed && s.fmt_passed && s.clippy_passed
+                    });
+                    if !step_done {
+                        all_done = false;
+                    }
+                    if !step_perfect {
+                        all_perfect = false;
+                    }
                 }
+                (all_done, all_perfect)
             }
-            (all_done, all_perfect)
         };
 
         exercises.push(ExerciseProgress {
             number: ex.number,
             name: ex.file_stem.clone(),
+            attempted: !exercise_submissions.is_empty() || !answers.is_empty(),
             completed,
             perfected,
             title: ex.title.clone(),
             description: String::new(),
             submissions: exercise_submissions,
             is_quiz,
-            has_exercises: !code_steps.is_empty(),
+            has_exercises: ex.is_completable(),
             is_bonus: ex.is_bonus(),
+            locked: None,
+            difficulty: ex.difficulty(),
+            tags: ex.tags().to_vec(),
         });
     }
 
     Ok(exercises)
 }
 
+/// A participant's answer to one quiz question, as
+/// [`get_exercise_progress`] needs it.
+#[derive(sqlx::FromRow)]
+struct QuizAnswerRow {
+    chapter: String,
+    attempt: String,
+    question: i64,
+    correct: bool,
+}
+
+/// `(completed, perfected)` for a chapter quiz from the participant's
+/// answers to it. Every attempt is scored on its own and the best one
+/// counts, so retaking a quiz never takes the chapter away again. A
+/// quiz without a [`pass_mark`](exercises::Quiz::pass_mark) is never
+/// completed.
+fn quiz_progress(quiz: &exercises::Quiz, answers: &[&QuizAnswerRow]) -> (bool, bool) {
+    let Some(pass_mark) = quiz.pass_mark else {
+        return (false, false);
+    };
+    let total = quiz.questions.len();
+    let mut scores: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+    for answer in answers {
+        // Answers to questions the quiz no longer has don't count.
+        if answer.correct && usize::try_from(answer.question).is_ok_and(|q| q < total) {
+            *scores.entry(&answer.attempt).or_default() += 1;
+        }
+    }
+    let best = scores.into_values().max().unwrap_or(0);
+    (best * 100 >= usize::from(pass_mark) * total, best == total)
+}
+
 /// Calculate a hash for submission content to detect duplicates
 fn calculate_submission_hash(
     participant_id: &str,
@@ -3321,9 +8046,199 @@ fn calculate_submission_hash(
     format!("{:x}", hasher.finalize())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_admin_next_only_returns_admin_pages() {
+        assert_eq!(
+            safe_admin_next(Some("/admin/team/rustfest")),
+            "/admin/team/rustfest"
+        );
+        assert_eq!(safe_admin_next(Some("/admin")), "/admin");
+        assert_eq!(
+            safe_admin_next(Some("/courses/async/admin")),
+            "/courses/async/admin"
+        );
+        for bad in [
+            None,
+            Some(""),
+            Some("/administrator"),
+            Some("/admin/login"),
+            Some("/admin//evil.com"),
+            Some("https://evil.com/admin"),
+            Some("/admin/team/x?token=secret"),
+            Some("/dashboard/U"),
+            Some("/courses/async/dashboard/U"),
+        ] {
+            assert_eq!(safe_admin_next(bad), "/admin", "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn cookie_value_finds_the_named_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            COOKIE,
+            HeaderValue::from_static("theme=dark; corrode_admin=1.ab.cd"),
+        );
+        headers.append(COOKIE, HeaderValue::from_static("other=x"));
+        assert_eq!(cookie_value(&headers, ADMIN_COOKIE), Some("1.ab.cd"));
+        assert_eq!(cookie_value(&headers, "other"), Some("x"));
+        assert_eq!(cookie_value(&headers, "missing"), None);
+    }
+
+    #[test]
+    fn bearer_token_reads_the_authorization_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Basic abc"));
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer cc_abc"));
+        assert_eq!(bearer_token(&headers), Some("cc_abc"));
+        assert_eq!(
+            path_ulid(Some(AxumPath("cc_abc".to_string()))),
+            Err(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    #[test]
+    fn parse_team_list_splits_and_dedups() {
+        let teams = parse_team_list(" veo, rustfest\nveo  acme ").unwrap();
+        let slugs: Vec<&str> = teams.iter().map(TeamToken::as_str).collect();
+        assert_eq!(slugs, ["veo", "rustfest", "acme"]);
+        assert!(parse_team_list("").unwrap().is_empty());
+        assert!(parse_team_list("veo, <script>").is_err());
+    }
+
+    #[test]
+    fn participant_sign_ins_back_off_per_address_and_account() {
+        let throttle = LoginThrottle::default();
+        let guesser = SignInKey::Address([192, 0, 2, 1].into());
+        let botnet: Vec<SignInKey> = (10..20)
+            .map(|host| SignInKey::Address([198, 51, 100, host].into()))
+            .collect();
+        let victim = SignInKey::Account("01ARZ3NDEKTSV4RRFFQ69G5FAV".to_string());
+        let other = SignInKey::Account("01BX5ZZKBKACTAV9WEVGEMMVRZ".to_string());
+
+        // One address guessing at many accounts.
+        for _ in 0..session::FREE_LOGIN_ATTEMPTS {
+            throttle.failed(guesser.clone(), 100);
+        }
+        assert!(sign_in_wait(&throttle, &[guesser, other.clone()], 100) > 0);
+
+        // Many addresses guessing at one account.
+        for address in &botnet[..session::FREE_LOGIN_ATTEMPTS as usize] {
+            assert_eq!(
+                sign_in_wait(&throttle, &[address.clone(), victim.clone()], 100),
+                0
+            );
+            throttle.failed(address.clone(), 100);
+            throttle.failed(victim.clone(), 100);
+        }
+        assert!(sign_in_wait(&throttle, &[botnet[9].clone(), victim.clone()], 100) > 0);
+        assert_eq!(sign_in_wait(&throttle, &[botnet[9].clone(), other], 100), 0);
+
+        throttle.succeeded(&victim);
+        assert_eq!(
+            sign_in_wait(&throttle, &[botnet[9].clone(), victim], 100),
+            0
+        );
+    }
+
+    #[test]
+    fn instructors_only_cover_their_teams() {
+        let key = SessionKey::from_admin_token("secret");
+        let veo = TeamToken::try_from("veo").unwrap();
+        let acme = TeamToken::try_from("acme").unwrap();
+        let instructor = Admin {
+            session: key.issue_instructor("I", 0, 0).0,
+            instructor: Some(InstructorScope {
+                id: "I".to_string(),
+                name: "Ida".to_string(),
+                teams: vec![veo.clone()],
+            }),
+        };
+        assert!(instructor.covers(Some(&veo)));
+        assert!(!instructor.covers(Some(&acme)));
+        assert!(!instructor.covers(None));
+        assert_eq!(instructor.instructor_id(), Some("I"));
+
+        let super_admin = Admin {
+            session: key.issue(0, 0).0,
+            instructor: None,
+        };
+        assert!(super_admin.covers(Some(&acme)));
+        assert!(super_admin.covers(None));
+    }
+
+    #[test]
+    fn diff_lines_numbers_both_sides_of_a_hunk() {
+        let lines = diff_lines("fn main() {\n    todo!()\n}\n", "fn main() {\n    42\n}\n");
+        let summary: Vec<_> = lines
+            .iter()
+            .map(|line| (line.kind, line.old_line, line.new_line, line.text.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("hunk", None, None, "@@ -1,3 +1,3 @@"),
+                ("equal", Some(1), Some(1), "fn main() {"),
+                ("delete", Some(2), None, "    todo!()"),
+                ("insert", None, Some(2), "    42"),
+                ("equal", Some(3), Some(3), "}"),
+            ]
+        );
+        assert!(diff_lines("same\n", "same\n").is_empty());
+    }
+
+    #[test]
+    fn safe_participant_next_stays_on_own_pages() {
+        let ulid = "01ARZ3NDEKTSV4RRFFQ69G5FAV";
+        for good in [
+            format!("/dashboard/{ulid}"),
+            format!("/dashboard/{ulid}/team"),
+            format!("/exercise/{ulid}/01_numbers"),
+            format!("/settings/{ulid}"),
+            format!("/courses/async/dashboard/{ulid}"),
+        ] {
+            assert_eq!(safe_participant_next(Some(&good), ulid), Some(good.clone()));
+        }
+        for bad in [
+            None,
+            Some("/dashboard/01BX5ZZKBKACTAV9WEVGEMMVRZ".to_string()),
+            Some(format!("/admin/{ulid}")),
+            Some(format!("/courses/async/admin/{ulid}")),
+            Some(format!("/dashboard/{ulid}//evil.com")),
+            Some(format!("/settings/{ulid}?notice=x")),
+            Some(format!("https://evil.com/dashboard/{ulid}")),
+        ] {
+            assert_eq!(safe_participant_next(bad.as_deref(), ulid), None, "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn participant_id_from_input_accepts_links_and_bare_ulids() {
+        let ulid = "01ARZ3NDEKTSV4RRFFQ69G5FAV";
+        for input in [
+            ulid.to_string(),
+            format!("  {} ", ulid.to_lowercase()),
+            format!("https://course.corrode.dev/dashboard/{ulid}"),
+            format!("/exercise/{ulid}/01_numbers?x=1"),
+        ] {
+            assert_eq!(
+                participant_id_from_input(&input).as_deref(),
+                Some(ulid),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            participant_id_from_input("https://course.corrode.dev/"),
+            None
+        );
+        assert_eq!(participant_id_from_input(""), None);
+    }
 
     #[test]
     fn resolve_register_next_accepts_simple_exercise_url() {
@@ -3450,6 +8365,7 @@ mod tests {
 
         assert!(valid_exercise_name("11_option/4_find_user"));
         assert!(valid_exercise_name("tour"));
+        assert!(valid_exercise_name("async:00_futures/1_poll"));
         assert!(!valid_exercise_name(""));
         assert!(!valid_exercise_name("chapter?participant=secret"));
         assert!(!valid_exercise_name("../chapter"));
@@ -3465,13 +8381,42 @@ mod tests {
     }
 
     #[test]
-    fn extracts_only_structured_rust_error_codes() {
+    fn counts_every_structured_diagnostic_code() {
+        let diagnostic = |level: &str, code: Option<&str>| Diagnostic {
+            level: level.to_string(),
+            code: code.map(str::to_string),
+            message: String::new(),
+            spans: Vec::new(),
+            children: Vec::new(),
+        };
+        let diagnostics = [
+            diagnostic("warning", Some("unused_variables")),
+            diagnostic("error", Some("E0308")),
+            diagnostic("error", None),
+            diagnostic("error", Some("E0308")),
+            diagnostic("error", Some("Hello, world!")),
+            diagnostic("warning", Some("clippy::needless_return")),
+        ];
         assert_eq!(
-            first_rust_error_code("error[E0308]: mismatched types"),
-            Some("E0308".to_string())
+            diagnostic_counts(&diagnostics),
+            [
+                DiagnosticCount {
+                    code: "unused_variables",
+                    level: "warning",
+                    occurrences: 1
+                },
+                DiagnosticCount {
+                    code: "E0308",
+                    level: "error",
+                    occurrences: 2
+                },
+                DiagnosticCount {
+                    code: "clippy::needless_return",
+                    level: "warning",
+                    occurrences: 1
+                },
+            ]
         );
-        assert_eq!(first_rust_error_code("error: expected expression"), None);
-        assert_eq!(first_rust_error_code("all tests passed"), None);
     }
 
     #[test]
@@ -3486,4 +8431,1016 @@ mod tests {
         assert_eq!(usable(Some("   ".into())), None);
         assert_eq!(usable(Some("unknown".into())), None);
     }
+
+    /// A throwaway, fully migrated database on one backend.
+    struct TestDb {
+        db: Db,
+        url: String,
+    }
+
+    impl TestDb {
+        async fn finish(self) {
+            self.db.close().await;
+            match self.db.backend() {
+                cargo_course::db::Backend::Sqlite => {
+                    let path = self.url.trim_start_matches("sqlite:");
+                    for suffix in ["", "-wal", "-shm"] {
+                        let _ = std::fs::remove_file(format!("{path}{suffix}"));
+                    }
+                }
+                cargo_course::db::Backend::Postgres => {
+                    use sqlx::migrate::MigrateDatabase;
+                    let _ = sqlx::Postgres::force_drop_database(&self.url).await;
+                }
+            }
+        }
+    }
+
+    /// One fresh database per backend under test: always SQLite, plus
+    /// Postgres when `CORRODE_TEST_POSTGRES_URL` names a server (e.g.
+    /// `postgres://postgres@localhost:5432`). Each call gets its own
+    /// database, so tests run in parallel.
+    async fn test_databases() -> Vec<TestDb> {
+        let name = format!("corrode_test_{}", Ulid::new().to_string().to_lowercase());
+        let mut urls = vec![format!(
+            "sqlite:{}",
+            std::env::temp_dir().join(format!("{name}.db")).display()
+        )];
+        match std::env::var("CORRODE_TEST_POSTGRES_URL") {
+            Ok(server) => urls.push(format!("{}/{name}", server.trim_end_matches('/'))),
+            Err(_) => eprintln!("CORRODE_TEST_POSTGRES_URL is unset; testing SQLite only"),
+        }
+        let migrations = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+        let mut databases = Vec::with_capacity(urls.len());
+        for url in urls {
+            let db = Db::connect(&url, 2).await.expect("connect test database");
+            db.migrate(&migrations)
+                .await
+                .expect("migrate test database");
+            databases.push(TestDb { db, url });
+        }
+        databases
+    }
+
+    async fn execute(db: &Db, sql: &str) -> Result<u64, sqlx::Error> {
+        with_pool!(db, |pool| sqlx::query(sql)
+            .execute(pool)
+            .await
+            .map(|done| done.rows_affected()))
+    }
+
+    async fn count(db: &Db, sql: &str) -> i64 {
+        with_pool!(db, |pool| sqlx::query_scalar(sql).fetch_one(pool).await).unwrap()
+    }
+
+    /// The real course, as a server without `CORRODE_COURSES` hosts it.
+    fn test_courses() -> &'static Courses {
+        static COURSES: std::sync::OnceLock<Courses> = std::sync::OnceLock::new();
+        COURSES.get_or_init(|| {
+            Courses::load(
+                vec![cargo_course::courses::CourseConfig {
+                    slug: cargo_course::courses::DEFAULT_SLUG.to_string(),
+                    title: cargo_course::courses::DEFAULT_TITLE.to_string(),
+                    root: ".".into(),
+                    version: None,
+                }],
+                COURSE_VERSION,
+            )
+            .expect("load the course")
+        })
+    }
+
+    fn ui_event(event_type: &str) -> CourseEvent<'_> {
+        CourseEvent {
+            course: test_courses().default_course(),
+            participant_id: Some("p1"),
+            session_id: "s1",
+            event_type,
+            exercise_name: Some("00_integers"),
+            result: None,
+            tests_passed: None,
+            tests_total: None,
+            duration_ms: None,
+            diagnostic_code: None,
+            diagnostics: &[],
+        }
+    }
+
+    #[tokio::test]
+    async fn course_events_dedupe_ui_events_but_keep_every_run() {
+        for test in test_databases().await {
+            let db = &test.db;
+            execute(
+                db,
+                "INSERT INTO participants (id, name) VALUES ('p1', 'Ferris')",
+            )
+            .await
+            .unwrap();
+            store_course_event(db, ui_event("chapter_view"))
+                .await
+                .unwrap();
+            store_course_event(db, ui_event("chapter_view"))
+                .await
+                .unwrap();
+            let diagnostics = [
+                DiagnosticCount {
+                    code: "E0308",
+                    level: "error",
+                    occurrences: 2,
+                },
+                DiagnosticCount {
+                    code: "unused_variables",
+                    level: "warning",
+                    occurrences: 1,
+                },
+            ];
+            for _ in 0..2 {
+                store_course_event(
+                    db,
+                    CourseEvent {
+                        result: Some("compile_failed"),
+                        diagnostic_code: Some("E0308"),
+                        diagnostics: &diagnostics,
+                        ..ui_event("exercise_run")
+                    },
+                )
+                .await
+                .unwrap();
+            }
+
+            let events = count(db, "SELECT COUNT(*) FROM course_events").await;
+            let rows = count(db, "SELECT COUNT(*) FROM course_event_diagnostics").await;
+            assert_eq!((events, rows), (3, 4), "{:?}", db.backend());
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn removed_participants_are_hidden_then_purged_after_the_grace_period() {
+        for test in test_databases().await {
+            let db = &test.db;
+            execute(
+                db,
+                "INSERT INTO participants (id, name) VALUES ('old', 'Old'), ('new', 'New')",
+            )
+            .await
+            .unwrap();
+            execute(
+                db,
+                "INSERT INTO submissions (id, participant_id, exercise_name, source_code, tests_passed)
+                 VALUES ('s1', 'old', '00_integers', 'fn main() {}', TRUE)",
+            )
+            .await
+            .unwrap();
+            execute(
+                db,
+                "INSERT INTO admin_audit (actor, actor_name, action, participant_id, before)
+                 VALUES ('admin', 'admin', 'participant.remove', 'old', '{\"name\":\"Old\"}'),
+                        ('admin', 'admin', 'participant.remove', 'new', '{\"name\":\"New\"}')",
+            )
+            .await
+            .unwrap();
+            let long_ago = (chrono::Utc::now()
+                - chrono::Duration::days(REMOVED_PARTICIPANT_GRACE_DAYS + 1))
+            .naive_utc();
+            with_pool!(db, |pool| sqlx::query(
+                "UPDATE participants SET deleted_at = $1 WHERE id = 'old'"
+            )
+            .bind(long_ago)
+            .execute(pool)
+            .await
+            .map(|done| done.rows_affected()))
+            .unwrap();
+            execute(
+                db,
+                "UPDATE participants SET deleted_at = CURRENT_TIMESTAMP WHERE id = 'new'",
+            )
+            .await
+            .unwrap();
+
+            assert!(!participant_exists(db, "old").await.unwrap());
+            assert!(!participant_exists(db, "new").await.unwrap());
+            assert_eq!(purge_removed_participants(db).await.unwrap(), 1);
+            let left: Vec<String> =
+                with_pool!(db, |pool| sqlx::query_scalar("SELECT id FROM participants")
+                    .fetch_all(pool)
+                    .await)
+                .unwrap();
+            assert_eq!(left, ["new"], "{:?}", db.backend());
+            assert_eq!(count(db, "SELECT COUNT(*) FROM submissions").await, 0);
+            // Only the purged participant's audit row loses its snapshot.
+            let redacted: Vec<String> = with_pool!(db, |pool| sqlx::query_scalar(
+                "SELECT participant_id FROM admin_audit WHERE before IS NULL"
+            )
+            .fetch_all(pool)
+            .await)
+            .unwrap();
+            assert_eq!(redacted, ["old"], "{:?}", db.backend());
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn account_export_and_deletion_cover_all_participant_data() {
+        for test in test_databases().await {
+            let db = &test.db;
+            for sql in [
+                "INSERT INTO participants (id, name, team_token) VALUES
+                     ('p1', 'Ferris', 'rustfest'), ('p2', 'Other', 'rustfest')",
+                "INSERT INTO submissions (id, participant_id, exercise_name, source_code, tests_passed)
+                 VALUES ('s1', 'p1', '00_integers', 'fn main() {}', TRUE),
+                        ('s2', 'p2', '00_integers', 'fn main() {}', TRUE)",
+                "INSERT INTO course_events
+                     (id, participant_id, session_id, event_type, exercise_name, result,
+                      course_version, git_hash)
+                 VALUES ('e1', 'p1', 'tab', 'exercise_run', '00_integers', 'compile_failed',
+                         '0.1.0', 'abc')",
+                "INSERT INTO course_event_diagnostics (event_id, code, level, occurrences)
+                 VALUES ('e1', 'E0308', 'error', 2)",
+                "INSERT INTO participant_api_tokens (id, participant_id, token_hash, label)
+                 VALUES ('t1', 'p1', 'hash', 'laptop')",
+                "INSERT INTO admin_audit (actor, actor_name, action, participant_id, before, after)
+                 VALUES ('admin', 'admin', 'participant.move', 'p1',
+                         '{\"team_token\":null}', '{\"team_token\":\"rustfest\"}')",
+            ] {
+                execute(db, sql).await.unwrap();
+            }
+
+            let export = load_account_export(db, "p1").await.unwrap().unwrap();
+            assert_eq!(export.participant.name, "Ferris");
+            assert!(!export.participant.has_passphrase);
+            assert_eq!(export.submissions.len(), 1);
+            assert_eq!(export.submissions[0].source_code, "fn main() {}");
+            assert_eq!(export.course_events.len(), 1);
+            assert_eq!(export.course_events[0].diagnostics[0].code, "E0308");
+            assert_eq!(export.api_tokens[0].label, "laptop");
+            assert_eq!(
+                export.admin_actions[0].after,
+                Some(serde_json::json!({ "team_token": "rustfest" }))
+            );
+            assert!(load_account_export(db, "nobody").await.unwrap().is_none());
+
+            assert!(delete_account(db, "p1").await.unwrap());
+            assert!(!delete_account(db, "p1").await.unwrap());
+            assert_eq!(
+                count(db, "SELECT COUNT(*) FROM participants WHERE id = 'p1'").await,
+                0
+            );
+            assert_eq!(
+                count(
+                    db,
+                    "SELECT COUNT(*) FROM submissions WHERE participant_id = 'p1'"
+                )
+                .await,
+                0
+            );
+            assert_eq!(count(db, "SELECT COUNT(*) FROM course_events").await, 0);
+            assert_eq!(
+                count(db, "SELECT COUNT(*) FROM course_event_diagnostics").await,
+                0
+            );
+            assert_eq!(
+                count(db, "SELECT COUNT(*) FROM participant_api_tokens").await,
+                0
+            );
+            assert_eq!(
+                count(
+                    db,
+                    "SELECT COUNT(*) FROM submissions WHERE participant_id = 'p2'"
+                )
+                .await,
+                1
+            );
+            // The audit row stays, minus its snapshots.
+            assert_eq!(
+                count(
+                    db,
+                    "SELECT COUNT(*) FROM admin_audit
+                     WHERE participant_id = 'p1' AND before IS NULL AND after IS NULL"
+                )
+                .await,
+                1
+            );
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn retention_keeps_the_submissions_progress_depends_on() {
+        for test in test_databases().await {
+            let db = &test.db;
+            for sql in [
+                "INSERT INTO participants (id, name) VALUES ('p1', 'Ferris')",
+                "INSERT INTO submissions
+                     (id, participant_id, exercise_name, source_code, tests_passed,
+                      clippy_passed, fmt_passed, submitted_at, verification)
+                 VALUES ('perfected', 'p1', '00_integers', '', TRUE, TRUE, TRUE,
+                         '2026-01-01 10:00:00', 'unverified'),
+                        ('passed', 'p1', '00_integers', '', TRUE, FALSE, FALSE,
+                         '2026-01-01 11:00:00', 'unverified'),
+                        ('failed-old', 'p1', '00_integers', '', FALSE, FALSE, FALSE,
+                         '2026-01-01 12:00:00', 'unverified'),
+                        ('failed-new', 'p1', '00_integers', '', FALSE, FALSE, FALSE,
+                         '2026-01-01 13:00:00', 'unverified'),
+                        ('pending', 'p1', '00_integers', '', TRUE, FALSE, FALSE,
+                         '2026-01-01 14:00:00', 'pending'),
+                        ('other-step', 'p1', '01_floats', '', FALSE, FALSE, FALSE,
+                         '2026-01-01 10:00:00', 'unverified')",
+            ] {
+                execute(db, sql).await.unwrap();
+            }
+            let policy = RetentionPolicy {
+                events_older_than_days: None,
+                max_submissions_per_step: Some(1),
+                inactive_for_days: None,
+            };
+            let expected = RetentionReport {
+                submissions: 1,
+                ..RetentionReport::default()
+            };
+
+            assert_eq!(apply_retention(db, &policy, true).await.unwrap(), expected);
+            assert_eq!(count(db, "SELECT COUNT(*) FROM submissions").await, 6);
+            assert_eq!(apply_retention(db, &policy, false).await.unwrap(), expected);
+            assert_eq!(
+                count(
+                    db,
+                    "SELECT COUNT(*) FROM submissions WHERE id = 'failed-old'"
+                )
+                .await,
+                0,
+                "{:?}",
+                db.backend()
+            );
+            assert_eq!(count(db, "SELECT COUNT(*) FROM submissions").await, 5);
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn retention_drops_inactive_participants_and_old_events() {
+        for test in test_databases().await {
+            let db = &test.db;
+            for sql in [
+                "INSERT INTO participants (id, name, created_at) VALUES
+                     ('idle', 'Idle', '2025-01-01 00:00:00'),
+                     ('browsing', 'Browsing', '2025-01-01 00:00:00'),
+                     ('cli', 'Cli', '2025-01-01 00:00:00')",
+                "INSERT INTO submissions
+                     (id, participant_id, exercise_name, source_code, tests_passed, submitted_at)
+                 VALUES ('s1', 'idle', '00_integers', '', TRUE, '2025-01-02 00:00:00')",
+                "INSERT INTO course_events
+                     (id, participant_id, session_id, event_type, exercise_name,
+                      course_version, git_hash, created_at)
+                 VALUES ('e1', 'idle', 's1', 'chapter_view', '00_integers', '0', 'x',
+                         '2025-01-03 00:00:00'),
+                        ('e2', 'browsing', 's2', 'chapter_view', '00_integers', '0', 'x',
+                         '2025-01-03 00:00:00')",
+                "INSERT INTO course_events
+                     (id, participant_id, session_id, event_type, exercise_name,
+                      course_version, git_hash)
+                 VALUES ('e3', 'browsing', 's3', 'chapter_view', '00_integers', '0', 'x')",
+                "INSERT INTO participant_api_tokens
+                     (id, participant_id, token_hash, label, last_used_at)
+                 VALUES ('t1', 'cli', 'hash', 'laptop', CURRENT_TIMESTAMP)",
+                "INSERT INTO admin_audit (actor, actor_name, action, participant_id, after)
+                 VALUES ('admin', 'admin', 'participant.move', 'idle', '{\"team_token\":\"x\"}')",
+            ] {
+                execute(db, sql).await.unwrap();
+            }
+            let policy = RetentionPolicy {
+                events_older_than_days: Some(30),
+                max_submissions_per_step: None,
+                inactive_for_days: Some(90),
+            };
+
+            assert_eq!(
+                apply_retention(db, &policy, false).await.unwrap(),
+                RetentionReport {
+                    participants: 1,
+                    submissions: 1,
+                    course_events: 2,
+                }
+            );
+            let left: Vec<String> = with_pool!(db, |pool| sqlx::query_scalar(
+                "SELECT id FROM participants ORDER BY id"
+            )
+            .fetch_all(pool)
+            .await)
+            .unwrap();
+            assert_eq!(left, ["browsing", "cli"], "{:?}", db.backend());
+            assert_eq!(count(db, "SELECT COUNT(*) FROM course_events").await, 1);
+            assert_eq!(
+                count(db, "SELECT COUNT(*) FROM admin_audit WHERE after IS NULL").await,
+                1
+            );
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn progress_and_enrollment_are_per_course() {
+        for test in test_databases().await {
+            let db = &test.db;
+            for sql in [
+                "INSERT INTO participants (id, name) VALUES ('p1', 'Ferris')",
+                "INSERT INTO submissions
+                     (id, participant_id, course, exercise_name, source_code,
+                      tests_passed, fmt_passed, clippy_passed)
+                 VALUES ('s1', 'p1', 'fundamentals', '00_integers', 'a', TRUE, TRUE, TRUE),
+                        ('s2', 'p1', 'async', '00_integers', 'b', FALSE, FALSE, FALSE)",
+            ] {
+                execute(db, sql).await.unwrap();
+            }
+
+            enroll(db, "p1", "async").await.unwrap();
+            enroll(db, "p1", "async").await.unwrap();
+            enroll(db, "p1", "fundamentals").await.unwrap();
+            assert_eq!(
+                enrolled_courses(db, "p1").await.unwrap(),
+                ["async", "fundamentals"],
+                "{:?}",
+                db.backend()
+            );
+
+            // The same exercise name in two courses is two exercises.
+            let fundamentals = load_step_progress(db, "p1", "fundamentals").await.unwrap();
+            assert!(fundamentals["00_integers"].completed);
+            assert_eq!(
+                fundamentals["00_integers"].submitted_code.as_deref(),
+                Some("a")
+            );
+            let async_rust = load_step_progress(db, "p1", "async").await.unwrap();
+            assert!(!async_rust["00_integers"].completed);
+
+            let stats = get_admin_stats(db, None, "async").await.unwrap();
+            assert_eq!((stats.participants, stats.submissions), (1, 0));
+            let stats = get_admin_stats(db, None, "python").await.unwrap();
+            assert_eq!((stats.participants, stats.submissions), (0, 0));
+
+            let export = load_account_export(db, "p1").await.unwrap().unwrap();
+            let courses: Vec<_> = export.enrollments.iter().map(|e| &e.course).collect();
+            assert_eq!(courses, ["async", "fundamentals"]);
+            assert_eq!(export.submissions[1].course, "async");
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn chapters_open_after_prerequisites_and_release() {
+        let mut catalog = test_courses().default_course().catalog.current().to_vec();
+        let word_count = catalog
+            .iter()
+            .position(|e| e.file_stem == "06_word_count")
+            .unwrap();
+        catalog[word_count].directives =
+            toml::from_str("requires = [\"05_borrowing_and_references\"]\nunlock = \"release\"\n")
+                .unwrap();
+        let borrowing = catalog[word_count - 1].title.clone();
+
+        for test in test_databases().await {
+            let db = &test.db;
+            execute(
+                db,
+                "INSERT INTO participants (id, name) VALUES ('p1', 'Ferris')",
+            )
+            .await
+            .unwrap();
+            let progress = |released| {
+                let catalog = &catalog;
+                async move {
+                    let mut exercises =
+                        get_exercise_progress(db, Some("p1"), "fundamentals", catalog)
+                            .await
+                            .unwrap();
+                    apply_locks(&mut exercises, catalog, &released);
+                    exercises
+                }
+            };
+
+            let exercises = progress(load_releases(db, "fundamentals").await.unwrap()).await;
+            assert_eq!(
+                exercises[word_count].locked.as_deref(),
+                Some(
+                    format!("Complete {borrowing} first. Opens when your instructor releases it")
+                        .as_str()
+                ),
+                "{:?}",
+                db.backend()
+            );
+            assert!(exercises[word_count - 1].locked.is_none());
+
+            // Releasing it in another course changes nothing.
+            execute(
+                db,
+                "INSERT INTO chapter_releases (course, chapter) VALUES
+                     ('async', '06_word_count'), ('fundamentals', '06_word_count')",
+            )
+            .await
+            .unwrap();
+            let released = load_releases(db, "fundamentals").await.unwrap();
+            assert_eq!(released.len(), 1);
+            let exercises = progress(released.clone()).await;
+            assert_eq!(
+                exercises[word_count].locked.as_deref(),
+                Some(format!("Complete {borrowing} first").as_str())
+            );
+
+            // Someone who already started the chapter keeps it.
+            execute(
+                db,
+                "INSERT INTO submissions
+                     (id, participant_id, exercise_name, source_code, tests_passed, fmt_passed, clippy_passed)
+                 VALUES ('s1', 'p1', '06_word_count/2_word_count', '', FALSE, FALSE, FALSE)",
+            )
+            .await
+            .unwrap();
+            let exercises = progress(std::collections::HashSet::new()).await;
+            assert!(exercises[word_count].locked.is_none());
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn chapter_tags_mirror_the_catalog_and_filter_the_dashboard() {
+        let catalog = test_courses().default_course().catalog.current().to_vec();
+        let tagged = |catalog: &[Exercise]| {
+            catalog
+                .iter()
+                .filter(|e| e.has_tag("error-handling"))
+                .count()
+        };
+        let expected = i64::try_from(tagged(&catalog)).unwrap();
+        assert!(expected > 0);
+
+        for test in test_databases().await {
+            let db = &test.db;
+            sync_chapter_tags(db, "fundamentals", &catalog).await;
+            let error_handling = "SELECT COUNT(*) FROM chapter_tags
+                                  WHERE course = 'fundamentals' AND tag = 'error-handling'";
+            assert_eq!(count(db, error_handling).await, expected);
+
+            // A reload rewrites the course's rows and leaves other courses alone.
+            execute(
+                db,
+                "INSERT INTO chapter_tags (course, chapter, tag) VALUES ('other', '00_x', 'result')",
+            )
+            .await
+            .unwrap();
+            let mut edited = catalog.clone();
+            for e in &mut edited {
+                e.directives.tags.retain(|tag| tag != "error-handling");
+            }
+            sync_chapter_tags(db, "fundamentals", &edited).await;
+            assert_eq!(count(db, error_handling).await, 0);
+            assert_eq!(
+                count(
+                    db,
+                    "SELECT COUNT(*) FROM chapter_tags WHERE course = 'other'"
+                )
+                .await,
+                1
+            );
+
+            // The dashboard lists only the tagged chapters, without the tour.
+            let exercises = get_exercise_progress(db, None, "fundamentals", &catalog)
+                .await
+                .unwrap();
+            let (tags, tag) = tag_filter(&catalog, Some("error-handling".to_string()));
+            assert!(tags.iter().any(|t| t == "error-handling"));
+            let dots = dots_from_exercises("", &exercises, tag.as_deref());
+            assert_eq!(dots.len(), tagged(&catalog));
+            assert!(dots.iter().all(|d| d.href.is_none()));
+            assert_eq!(tag_filter(&catalog, Some("nope".to_string())).1, None);
+            assert_eq!(
+                dots_from_exercises("", &exercises, None).len(),
+                catalog.len() + 1
+            );
+
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn status_lists_submitted_and_passed_steps_once() {
+        let catalog = test_courses().default_course().catalog.current().to_vec();
+        for test in test_databases().await {
+            let db = &test.db;
+            for sql in [
+                "INSERT INTO participants (id, name) VALUES ('p1', 'Ferris')",
+                "INSERT INTO submissions (id, participant_id, exercise_name, source_code, tests_passed)
+                 VALUES ('s1', 'p1', '00_integers/3_add_health', '', FALSE),
+                        ('s2', 'p1', '00_integers/3_add_health', '', TRUE),
+                        ('s3', 'p1', '00_integers/4_damage_with_bonus', '', FALSE)",
+            ] {
+                execute(db, sql).await.unwrap();
+            }
+
+            let exercises = get_exercise_progress(db, Some("p1"), "fundamentals", &catalog)
+                .await
+                .unwrap();
+            let integers = exercises.iter().find(|e| e.name == "00_integers").unwrap();
+            assert_eq!(
+                integers.submitted_steps(),
+                [
+                    "00_integers/3_add_health",
+                    "00_integers/4_damage_with_bonus"
+                ],
+                "{:?}",
+                db.backend()
+            );
+            assert_eq!(integers.passed_steps(), ["00_integers/3_add_health"]);
+            assert!(exercises[1].submitted_steps().is_empty());
+
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn queued_submissions_keep_their_time_within_the_participants_lifetime() {
+        let now = chrono::Utc::now();
+        let yesterday = now - chrono::Duration::days(1);
+        let tomorrow = now + chrono::Duration::days(1);
+        let last_year = now - chrono::Duration::days(365);
+        let last_week = now - chrono::Duration::days(7);
+        assert_eq!(submission_time(None, None, now), now.naive_utc());
+        assert_eq!(submission_time(None, Some(yesterday), now), now.naive_utc());
+        assert_eq!(
+            submission_time(Some(yesterday), None, now),
+            yesterday.naive_utc()
+        );
+        assert_eq!(
+            submission_time(Some(yesterday), Some(last_week), now),
+            yesterday.naive_utc()
+        );
+        assert_eq!(
+            submission_time(Some(last_year), Some(last_week), now),
+            last_week.naive_utc()
+        );
+        assert_eq!(submission_time(Some(tomorrow), None, now), now.naive_utc());
+        // A participant clock ahead of the server's still can't go past now.
+        assert_eq!(
+            submission_time(Some(last_year), Some(tomorrow), now),
+            now.naive_utc()
+        );
+
+        let catalog = test_courses().default_course().catalog.current().to_vec();
+        for test in test_databases().await {
+            let db = &test.db;
+            execute(
+                db,
+                "INSERT INTO participants (id, name) VALUES ('p1', 'Ferris')",
+            )
+            .await
+            .unwrap();
+            with_pool!(db, |pool| sqlx::query(
+                "INSERT INTO submissions (id, participant_id, exercise_name, source_code,
+                                          tests_passed, submitted_at)
+                 VALUES ('s1', 'p1', '00_integers/3_add_health', '', TRUE, $1)",
+            )
+            .bind(submission_time(Some(yesterday), None, now))
+            .execute(pool)
+            .await
+            .map(|done| done.rows_affected()))
+            .unwrap();
+
+            let exercises = get_exercise_progress(db, Some("p1"), "fundamentals", &catalog)
+                .await
+                .unwrap();
+            let stored = exercises[0].submissions[0].submitted_at;
+            assert_eq!(
+                stored.timestamp_micros(),
+                yesterday.timestamp_micros(),
+                "{:?}",
+                db.backend()
+            );
+
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn signing_out_bumps_only_that_admins_epoch() {
+        for test in test_databases().await {
+            let db = &test.db;
+            execute(
+                db,
+                "INSERT INTO instructors (id, name, token_hash)
+                 VALUES ('ida', 'Ida', 'h1'), ('ivo', 'Ivo', 'h2')",
+            )
+            .await
+            .unwrap();
+            assert_eq!(admin_epoch(db).await.unwrap(), 0);
+
+            bump_admin_epoch(db, None).await.unwrap();
+            bump_admin_epoch(db, Some("ida")).await.unwrap();
+            bump_admin_epoch(db, Some("ida")).await.unwrap();
+
+            assert_eq!(admin_epoch(db).await.unwrap(), 1, "{:?}", db.backend());
+            let epochs: Vec<(String, i64)> = with_pool!(db, |pool| {
+                sqlx::query_as("SELECT id, auth_epoch FROM instructors ORDER BY id")
+                    .fetch_all(pool)
+                    .await
+            })
+            .unwrap();
+            assert_eq!(
+                epochs,
+                [("ida".to_string(), 2), ("ivo".to_string(), 0)],
+                "{:?}",
+                db.backend()
+            );
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn duplicate_submissions_add_fmt_and_clippy_claims() {
+        for test in test_databases().await {
+            let db = &test.db;
+            for sql in [
+                "INSERT INTO participants (id, name) VALUES ('p1', 'Ferris')",
+                "INSERT INTO submissions
+                     (id, participant_id, exercise_name, source_code, tests_passed,
+                      clippy_passed, fmt_passed, claimed_tests_passed, claimed_clippy_passed,
+                      claimed_fmt_passed, verification)
+                 VALUES ('watched', 'p1', '00_integers', '', TRUE, FALSE, FALSE,
+                         TRUE, FALSE, FALSE, 'unverified'),
+                        ('verified', 'p1', '01_floats', '', TRUE, TRUE, FALSE,
+                         TRUE, FALSE, FALSE, 'verified')",
+            ] {
+                execute(db, sql).await.unwrap();
+            }
+
+            upgrade_claims(db, "watched", true, true).await.unwrap();
+            upgrade_claims(db, "watched", false, false).await.unwrap();
+            upgrade_claims(db, "verified", true, true).await.unwrap();
+
+            let rows: Vec<(String, bool, bool, bool, bool)> = with_pool!(db, |pool| {
+                sqlx::query_as(
+                    "SELECT id, clippy_passed, fmt_passed, claimed_clippy_passed,
+                            claimed_fmt_passed
+                     FROM submissions ORDER BY id",
+                )
+                .fetch_all(pool)
+                .await
+            })
+            .unwrap();
+            assert_eq!(
+                rows,
+                [
+                    ("verified".to_string(), true, false, true, true),
+                    ("watched".to_string(), true, true, true, true),
+                ],
+                "{:?}",
+                db.backend()
+            );
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn quizzes_with_a_pass_mark_count_toward_progress() {
+        let mut catalog = test_courses().default_course().catalog.current().to_vec();
+        let quiz_chapter = catalog.iter().position(|e| e.quiz().is_some()).unwrap();
+        let stem = catalog[quiz_chapter].file_stem.clone();
+        let total = catalog[quiz_chapter].quiz().unwrap().questions.len();
+        assert!(!catalog[quiz_chapter].is_completable());
+        for step in &mut catalog[quiz_chapter].steps {
+            if let Step::Quiz(quiz) = step {
+                quiz.pass_mark = Some(50);
+            }
+        }
+        assert!(catalog[quiz_chapter].is_completable());
+
+        for test in test_databases().await {
+            let db = &test.db;
+            execute(
+                db,
+                "INSERT INTO participants (id, name, team_token) VALUES
+                     ('p1', 'Ferris', 'rustfest'), ('p2', 'Corro', 'oxidize')",
+            )
+            .await
+            .unwrap();
+            let answer = |participant: &str, attempt: &str, question: usize, correct: bool| {
+                format!(
+                    "INSERT INTO quiz_answers
+                         (participant_id, course, chapter, attempt, question, answer, correct)
+                     VALUES ('{participant}', 'fundamentals', '{stem}', '{attempt}', {question},
+                             '0', {correct})"
+                )
+            };
+            let progress = async |participant| {
+                let exercises =
+                    get_exercise_progress(db, Some(participant), "fundamentals", &catalog)
+                        .await
+                        .unwrap();
+                let e = &exercises[quiz_chapter];
+                (e.attempted, e.completed, e.perfected)
+            };
+
+            // Half right across two attempts isn't half right in one.
+            let half = total.div_ceil(2);
+            for question in 0..half - 1 {
+                execute(db, &answer("p1", "a1", question, true))
+                    .await
+                    .unwrap();
+            }
+            execute(db, &answer("p1", "a2", half - 1, true))
+                .await
+                .unwrap();
+            assert_eq!(
+                progress("p1").await,
+                (true, false, false),
+                "{:?}",
+                db.backend()
+            );
+
+            for question in 0..total {
+                execute(db, &answer("p1", "a3", question, question < half))
+                    .await
+                    .unwrap();
+            }
+            assert_eq!(progress("p1").await, (true, true, false));
+            for question in 0..total {
+                execute(db, &answer("p2", "b1", question, true))
+                    .await
+                    .unwrap();
+            }
+            assert_eq!(progress("p2").await, (true, true, true));
+
+            let stats = load_quiz_question_stats(db, None, "fundamentals", &catalog)
+                .await
+                .unwrap();
+            assert_eq!(stats.len(), total);
+            assert_eq!((stats[0].answers, stats[0].correct_percent), (3, Some(100)));
+            assert_eq!(stats[total - 1].correct_percent, Some(50));
+
+            // An instructor only sees their teams' answers.
+            for sql in [
+                "INSERT INTO instructors (id, name, token_hash) VALUES ('i1', 'Ana', 'hash')",
+                "INSERT INTO instructor_teams (instructor_id, team_token) VALUES ('i1', 'oxidize')",
+            ] {
+                execute(db, sql).await.unwrap();
+            }
+            let scoped = load_quiz_question_stats(db, Some("i1"), "fundamentals", &catalog)
+                .await
+                .unwrap();
+            assert_eq!(
+                (scoped[0].answers, scoped[total - 1].correct_percent),
+                (1, Some(100))
+            );
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_stats_only_count_an_instructors_teams() {
+        for test in test_databases().await {
+            let db = &test.db;
+            for sql in [
+                "INSERT INTO instructors (id, name, token_hash) VALUES ('i1', 'Ana', 'hash')",
+                "INSERT INTO instructor_teams (instructor_id, team_token) VALUES ('i1', 'rustfest')",
+                "INSERT INTO participants (id, name, team_token) VALUES
+                     ('p1', 'One', 'rustfest'), ('p2', 'Two', 'oxidize'), ('p3', 'Three', NULL)",
+                "INSERT INTO submissions
+                     (id, participant_id, exercise_name, source_code, tests_passed, fmt_passed, clippy_passed)
+                 VALUES ('s1', 'p1', '00_integers', '', TRUE, TRUE, TRUE),
+                        ('s2', 'p2', '00_integers', '', TRUE, FALSE, TRUE),
+                        ('s3', 'p3', '00_integers', '', FALSE, FALSE, FALSE)",
+                "INSERT INTO course_enrollments (participant_id, course) VALUES
+                     ('p1', 'fundamentals'), ('p2', 'fundamentals'), ('p3', 'fundamentals')",
+            ] {
+                execute(db, sql).await.unwrap();
+            }
+
+            let all = get_admin_stats(db, None, "fundamentals").await.unwrap();
+            assert_eq!(
+                (all.participants, all.submissions, all.perfected),
+                (3, 2, 1)
+            );
+            let scoped = get_admin_stats(db, Some("i1"), "fundamentals")
+                .await
+                .unwrap();
+            assert_eq!(
+                (scoped.participants, scoped.submissions, scoped.perfected),
+                (1, 1, 1),
+                "{:?}",
+                db.backend()
+            );
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_audit_rejects_updates_and_deletes() {
+        for test in test_databases().await {
+            let db = &test.db;
+            execute(
+                db,
+                "INSERT INTO admin_audit (actor, actor_name, action) \
+                 VALUES ('admin', 'admin', 'participant.move')",
+            )
+            .await
+            .unwrap();
+            assert!(
+                execute(db, "UPDATE admin_audit SET action = 'participant.remove'")
+                    .await
+                    .is_err()
+            );
+            assert!(execute(db, "DELETE FROM admin_audit").await.is_err());
+            assert_eq!(count(db, "SELECT COUNT(*) FROM admin_audit").await, 1);
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn step_progress_seeds_the_editor_with_the_latest_submission() {
+        for test in test_databases().await {
+            let db = &test.db;
+            execute(
+                db,
+                "INSERT INTO participants (id, name) VALUES ('p1', 'Ferris')",
+            )
+            .await
+            .unwrap();
+            execute(
+                db,
+                "INSERT INTO submissions
+                     (id, participant_id, exercise_name, source_code, tests_passed, submitted_at)
+                 VALUES ('s1', 'p1', '04_functions/add', 'old', TRUE, '2026-01-01 10:00:00'),
+                        ('s2', 'p1', '04_functions/add', 'new', FALSE, '2026-01-01 11:00:00')",
+            )
+            .await
+            .unwrap();
+
+            let progress = load_step_progress(db, "p1", cargo_course::courses::DEFAULT_SLUG)
+                .await
+                .unwrap();
+            let step = &progress["04_functions/add"];
+            assert!(step.attempted && step.completed && !step.perfected);
+            assert_eq!(
+                step.submitted_code.as_deref(),
+                Some("new"),
+                "{:?}",
+                db.backend()
+            );
+            test.finish().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn step_history_lists_one_steps_attempts_newest_first() {
+        for test in test_databases().await {
+            let db = &test.db;
+            execute(
+                db,
+                "INSERT INTO participants (id, name) VALUES ('p1', 'Ferris'), ('p2', 'Corro')",
+            )
+            .await
+            .unwrap();
+            execute(
+                db,
+                "INSERT INTO submissions
+                     (id, participant_id, exercise_name, source_code, tests_passed, fmt_passed,
+                      verification, submitted_at)
+                 VALUES ('s1', 'p1', '04_functions/add', 'a', FALSE, FALSE, 'unverified',
+                         '2026-01-01 10:00:00'),
+                        ('s2', 'p1', '04_functions/add', 'b', TRUE, TRUE, 'pending',
+                         '2026-01-01 11:00:00'),
+                        ('s3', 'p1', '04_functions/sub', 'c', TRUE, TRUE, 'unverified',
+                         '2026-01-01 12:00:00'),
+                        ('s4', 'p2', '04_functions/add', 'd', TRUE, TRUE, 'unverified',
+                         '2026-01-01 13:00:00')",
+            )
+            .await
+            .unwrap();
+
+            let history = load_step_history(
+                db,
+                "p1",
+                cargo_course::courses::DEFAULT_SLUG,
+                "04_functions/add",
+            )
+            .await
+            .unwrap();
+            let rows: Vec<_> = history
+                .iter()
+                .map(|a| {
+                    (
+                        a.id.as_str(),
+                        a.number,
+                        a.tests_passed,
+                        a.verification_pending,
+                    )
+                })
+                .collect();
+            assert_eq!(
+                rows,
+                [("s2", 2, true, true), ("s1", 1, false, false)],
+                "{:?}",
+                db.backend()
+            );
+            test.finish().await;
+        }
+    }
 }
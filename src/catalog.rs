@@ -0,0 +1,312 @@
+//! The live course catalog, reloaded when `examples/` or `solutions/`
+//! change on disk.
+//!
+//! Fixing a typo in a chapter during a workshop used to mean restarting
+//! the server. [`Catalog::watch`] now polls both trees for changed
+//! paths, sizes or modification times and re-runs
+//! [`scan_chapters`](crate::exercises::scan_chapters) when they move.
+//! Polling rather than OS file events keeps this working on bind mounts
+//! and network filesystems, and a tree of a few hundred small files is
+//! cheap to stat every couple of seconds.
+//!
+//! A chapter that doesn't parse on reload (often a half-saved file)
+//! keeps its previous version, or stays out if it never parsed, while
+//! the rest of the scan goes live. The errors are kept for the admin
+//! page until a later scan comes back clean.
+//!
+//! Handlers take one [`Catalog::current`] snapshot per request, an
+//! `Arc` to an immutable `Vec`, so a reload that lands mid-request
+//! can't mix chapters from two scans.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::exercises::{self, Exercise};
+
+/// How often [`Catalog::watch`] looks for changes unless
+/// `CORRODE_CONTENT_POLL_SECONDS` says otherwise.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The poll interval from `CORRODE_CONTENT_POLL_SECONDS`, or `None`
+/// when it is `0` and content reloading is off. Anything that isn't a
+/// number is ignored with a warning.
+#[must_use]
+pub fn poll_interval_from_env() -> Option<Duration> {
+    let Ok(raw) = std::env::var("CORRODE_CONTENT_POLL_SECONDS") else {
+        return Some(DEFAULT_POLL_INTERVAL);
+    };
+    match raw.trim().parse::<u64>() {
+        Ok(0) => None,
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            log::warn!(
+                "ignoring CORRODE_CONTENT_POLL_SECONDS={raw:?}: expected a number of seconds"
+            );
+            Some(DEFAULT_POLL_INTERVAL)
+        }
+    }
+}
+
+/// The chapters being served, and how the last reload went.
+#[derive(Debug)]
+pub struct Catalog {
+    examples_dir: PathBuf,
+    current: RwLock<Arc<Vec<Exercise>>>,
+    status: RwLock<CatalogStatus>,
+}
+
+/// What the admin page shows about the catalog.
+#[derive(Debug, Clone)]
+pub struct CatalogStatus {
+    /// When the catalog being served was scanned.
+    pub loaded_at: DateTime<Utc>,
+    /// When the last scan ran, successful or not.
+    pub checked_at: DateTime<Utc>,
+    /// The chapters the last scan couldn't parse (one entry each), or
+    /// the scan's error when it couldn't run at all. Empty when the
+    /// catalog being served is the latest content.
+    pub errors: Vec<String>,
+}
+
+impl Catalog {
+    /// Scan `examples_dir` for the first time. Broken chapters are
+    /// skipped (and reported in [`Catalog::status`]) so one bad file
+    /// can't keep the server from starting; a missing directory is an
+    /// error.
+    pub fn load(examples_dir: &Path) -> Result<Self> {
+        let scan = exercises::scan_chapters(examples_dir)?;
+        for error in &scan.errors {
+            log::warn!("Skipping {error}");
+        }
+        let now = Utc::now();
+        Ok(Self {
+            examples_dir: examples_dir.to_path_buf(),
+            current: RwLock::new(Arc::new(scan.exercises)),
+            status: RwLock::new(CatalogStatus {
+                loaded_at: now,
+                checked_at: now,
+                errors: scan.errors.iter().map(ToString::to_string).collect(),
+            }),
+        })
+    }
+
+    /// The catalog as of now. Hold on to it for the rest of the request.
+    #[must_use]
+    pub fn current(&self) -> Arc<Vec<Exercise>> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    #[must_use]
+    pub fn status(&self) -> CatalogStatus {
+        self.status
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Re-scan and swap the new chapters in. A chapter that doesn't
+    /// parse keeps the version being served, if there is one. Returns
+    /// whether the catalog was replaced, which only fails to happen
+    /// when the examples directory can't be read.
+    pub fn reload(&self) -> bool {
+        let scan = exercises::scan_chapters(&self.examples_dir);
+        let now = Utc::now();
+        let mut status = self.status.write().unwrap_or_else(PoisonError::into_inner);
+        status.checked_at = now;
+        let scan = match scan {
+            Ok(scan) => scan,
+            Err(err) => {
+                status.errors = vec![format!("{err:#}")];
+                return false;
+            }
+        };
+        let live = self.current();
+        let mut chapters = scan.exercises;
+        chapters.extend(scan.errors.iter().filter_map(|broken| {
+            let name = broken.chapter.file_name()?.to_str()?;
+            live.iter().find(|ex| ex.file_stem == name).cloned()
+        }));
+        chapters.sort_by(|a, b| a.file_stem.cmp(&b.file_stem));
+        exercises::number_chapters(&mut chapters);
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(chapters);
+        status.loaded_at = now;
+        status.errors = scan.errors.iter().map(ToString::to_string).collect();
+        true
+    }
+
+    /// Poll `examples/` and its sibling `solutions/` every `interval`
+    /// and [`reload`](Self::reload) when anything in them changed.
+    /// `on_reload` runs with the new chapters after each reload that
+    /// replaced the catalog, and is awaited before the next poll.
+    pub async fn watch<F, Fut>(self: Arc<Self>, interval: Duration, on_reload: F)
+    where
+        F: Fn(Arc<Vec<Exercise>>) -> Fut + Send,
+        Fut: Future<Output = ()> + Send,
+    {
+        let roots = vec![
+            self.examples_dir.clone(),
+            self.examples_dir.with_file_name("solutions"),
+        ];
+        let mut last = fingerprint(&roots);
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let catalog = Arc::clone(&self);
+            let roots = roots.clone();
+            let outcome = tokio::task::spawn_blocking(move || {
+                let seen = fingerprint(&roots);
+                (seen != last).then(|| (seen, catalog.reload()))
+            })
+            .await;
+            match outcome {
+                Ok(Some((seen, reloaded))) => {
+                    last = seen;
+                    for error in self.status().errors {
+                        log::warn!("Course content reload: {error}");
+                    }
+                    if reloaded {
+                        let chapters = self.current();
+                        log::info!("Course content changed; loaded {} chapters", chapters.len());
+                        on_reload(chapters).await;
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => log::error!("Course content reload panicked: {err}"),
+            }
+        }
+    }
+}
+
+/// Hash of every entry's path, size and modification time under `roots`.
+/// Missing roots hash as empty, so creating `solutions/` counts as a
+/// change.
+fn fingerprint(roots: &[PathBuf]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for root in roots {
+        let mut files = Vec::new();
+        collect_files(root, &mut files);
+        files.sort();
+        for (path, len, modified) in files {
+            (path, len, modified).hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<(PathBuf, u64, Option<std::time::SystemTime>)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(std::result::Result::ok) {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let is_dir = meta.is_dir();
+        out.push((path.clone(), meta.len(), meta.modified().ok()));
+        if is_dir {
+            collect_files(&path, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A one-chapter course in a fresh temp dir.
+    fn course(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("corrode-catalog-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let chapter = root.join("examples/00_hello");
+        std::fs::create_dir_all(&chapter).unwrap();
+        std::fs::write(chapter.join("main.rs"), "//! # Hello\n\nfn main() {}\n").unwrap();
+        root
+    }
+
+    #[test]
+    fn reload_keeps_the_live_version_of_broken_chapters() {
+        let root = course("reload");
+        let examples = root.join("examples");
+        let catalog = Catalog::load(&examples).unwrap();
+        let before = catalog.current();
+        assert_eq!(before.len(), 1);
+        assert!(catalog.status().errors.is_empty());
+
+        // A chapter directory without any code step doesn't parse; it
+        // was never live, so it stays out.
+        std::fs::create_dir_all(examples.join("01_broken")).unwrap();
+        let changed = fingerprint(std::slice::from_ref(&examples));
+        assert!(catalog.reload());
+        assert_eq!(catalog.current().len(), 1);
+        let errors = catalog.status().errors;
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].contains("01_broken"), "{errors:?}");
+
+        // While it stays broken, fixes elsewhere still go live, and a
+        // chapter that breaks keeps the version being served.
+        std::fs::write(
+            examples.join("00_hello/main.rs"),
+            "//! # Hello again\n\nfn main() {}\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(examples.join("02_later")).unwrap();
+        std::fs::write(
+            examples.join("02_later/main.rs"),
+            "//! # Later\n\nfn main() {}\n",
+        )
+        .unwrap();
+        assert!(catalog.reload());
+        let titles = |catalog: &Catalog| -> Vec<(u8, String)> {
+            catalog
+                .current()
+                .iter()
+                .map(|ex| (ex.number, ex.title.clone()))
+                .collect()
+        };
+        assert_eq!(
+            titles(&catalog),
+            [(1, "Hello again".to_string()), (2, "Later".to_string())]
+        );
+        std::fs::write(
+            examples.join("00_hello/main.rs"),
+            "//! # Half\n\nfn main( {\n",
+        )
+        .unwrap();
+        assert!(catalog.reload());
+        assert_eq!(
+            titles(&catalog),
+            [(1, "Hello again".to_string()), (2, "Later".to_string())]
+        );
+        assert_eq!(catalog.status().errors.len(), 2);
+
+        std::fs::write(
+            examples.join("00_hello/main.rs"),
+            "//! # Hello\n\nfn main() {}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            examples.join("01_broken/main.rs"),
+            "//! # Fixed\n\nfn main() {}\n",
+        )
+        .unwrap();
+        assert_ne!(fingerprint(std::slice::from_ref(&examples)), changed);
+        assert!(catalog.reload());
+        assert_eq!(catalog.current().len(), 3);
+        assert!(catalog.status().errors.is_empty());
+        // Requests still holding the old snapshot keep a whole catalog.
+        assert_eq!(before.len(), 1);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
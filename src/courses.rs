@@ -0,0 +1,313 @@
+//! The courses one server hosts.
+//!
+//! A server used to serve exactly one `examples/` tree. Tracks such as
+//! "async Rust" or "Rust for Python developers" each have their own
+//! content root, so `CORRODE_COURSES` can name a registry file listing
+//! them:
+//!
+//! ```toml
+//! [[courses]]
+//! slug = "fundamentals"
+//! title = "Rust fundamentals"
+//! root = "."            # holds examples/ and solutions/
+//!
+//! [[courses]]
+//! slug = "async"
+//! title = "Async Rust"
+//! root = "courses/async"
+//! version = "2026.1"    # course_events.course_version; the server's by default
+//! ```
+//!
+//! Relative roots are resolved against the registry file's directory.
+//! Without `CORRODE_COURSES` the server hosts a single course,
+//! [`DEFAULT_SLUG`], from the working directory, as before.
+//!
+//! Every course is served under `/courses/<slug>/`. The first one is the
+//! *default course*: it is also served at the site root, where the
+//! single course used to live, and exercise names that don't say which
+//! course they belong to (what older CLIs send) are its. Names for the
+//! other courses are qualified as `<slug>:<exercise>`; see
+//! [`Course::qualify`] and [`Courses::resolve`].
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+
+use crate::catalog::Catalog;
+
+/// The course that submissions and events recorded before there were
+/// several courses belong to, and the one a server without a registry
+/// hosts.
+pub const DEFAULT_SLUG: &str = "fundamentals";
+
+/// Title of the course a server without a registry hosts.
+pub const DEFAULT_TITLE: &str = "Rust fundamentals";
+
+/// One `[[courses]]` entry of the registry file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CourseConfig {
+    /// URL segment and database key. Lowercase letters, digits and `-`.
+    pub slug: String,
+    pub title: String,
+    /// Directory holding the course's `examples/` and `solutions/`.
+    #[serde(default = "default_root")]
+    pub root: PathBuf,
+    /// Recorded as `course_events.course_version`.
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+fn default_root() -> PathBuf {
+    PathBuf::from(".")
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegistryFile {
+    courses: Vec<CourseConfig>,
+}
+
+/// A course being served.
+#[derive(Debug)]
+pub struct Course {
+    pub slug: String,
+    pub title: String,
+    pub version: String,
+    /// Its chapters, reloaded when its content changes.
+    pub catalog: Arc<Catalog>,
+    is_default: bool,
+}
+
+impl Course {
+    #[must_use]
+    pub const fn is_default(&self) -> bool {
+        self.is_default
+    }
+
+    /// Path prefix of the course's pages: empty for the default course,
+    /// which lives at the site root, and `/courses/<slug>` otherwise.
+    #[must_use]
+    pub fn base(&self) -> String {
+        if self.is_default {
+            String::new()
+        } else {
+            format!("/courses/{}", self.slug)
+        }
+    }
+
+    /// `exercise_name` as the browser and CLI send it: unchanged for the
+    /// default course, `<slug>:<exercise_name>` for the others.
+    #[must_use]
+    pub fn qualify(&self, exercise_name: &str) -> String {
+        if self.is_default {
+            exercise_name.to_string()
+        } else {
+            format!("{}:{exercise_name}", self.slug)
+        }
+    }
+}
+
+/// Every course this server hosts, default first.
+#[derive(Debug)]
+pub struct Courses {
+    courses: Vec<Arc<Course>>,
+}
+
+impl Courses {
+    /// The registry named by `CORRODE_COURSES`, or the single
+    /// [`DEFAULT_SLUG`] course from the working directory.
+    /// `server_version` is the course version of courses that don't set
+    /// their own.
+    pub fn from_env(server_version: &str) -> Result<Self> {
+        match std::env::var("CORRODE_COURSES") {
+            Ok(path) if !path.trim().is_empty() => {
+                Self::from_file(Path::new(&path), server_version)
+            }
+            _ => Self::load(
+                vec![CourseConfig {
+                    slug: DEFAULT_SLUG.to_string(),
+                    title: DEFAULT_TITLE.to_string(),
+                    root: default_root(),
+                    version: None,
+                }],
+                server_version,
+            ),
+        }
+    }
+
+    /// Read a registry file.
+    pub fn from_file(path: &Path, server_version: &str) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("reading course registry {}", path.display()))?;
+        let file: RegistryFile = toml::from_str(&raw)
+            .with_context(|| format!("parsing course registry {}", path.display()))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let configs = file
+            .courses
+            .into_iter()
+            .map(|config| CourseConfig {
+                root: dir.join(&config.root),
+                ..config
+            })
+            .collect();
+        Self::load(configs, server_version)
+    }
+
+    /// Validate `configs` and scan each course's content.
+    pub fn load(configs: Vec<CourseConfig>, server_version: &str) -> Result<Self> {
+        if configs.is_empty() {
+            bail!("the course registry lists no courses");
+        }
+        let mut courses: Vec<Arc<Course>> = Vec::with_capacity(configs.len());
+        for (i, config) in configs.into_iter().enumerate() {
+            if !valid_slug(&config.slug) {
+                bail!(
+                    "course slug `{}` must be lowercase letters, digits and `-`",
+                    config.slug
+                );
+            }
+            if courses.iter().any(|course| course.slug == config.slug) {
+                bail!("course `{}` is listed twice", config.slug);
+            }
+            if config.title.trim().is_empty() {
+                bail!("course `{}` has no title", config.slug);
+            }
+            let catalog = Catalog::load(&config.root.join("examples"))
+                .with_context(|| format!("loading course `{}`", config.slug))?;
+            courses.push(Arc::new(Course {
+                slug: config.slug,
+                title: config.title,
+                version: config.version.unwrap_or_else(|| server_version.to_string()),
+                catalog: Arc::new(catalog),
+                is_default: i == 0,
+            }));
+        }
+        if !courses.iter().any(|course| course.slug == DEFAULT_SLUG) {
+            log::warn!(
+                "No course is named `{DEFAULT_SLUG}`; progress recorded before the course registry won't show up"
+            );
+        }
+        Ok(Self { courses })
+    }
+
+    #[must_use]
+    pub fn default_course(&self) -> &Arc<Course> {
+        &self.courses[0]
+    }
+
+    #[must_use]
+    pub fn get(&self, slug: &str) -> Option<&Arc<Course>> {
+        self.courses.iter().find(|course| course.slug == slug)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<Course>> {
+        self.courses.iter()
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.courses.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.courses.is_empty()
+    }
+
+    /// Split a possibly qualified exercise name into its course and the
+    /// name within it. An unqualified name is the default course's.
+    ///
+    /// # Errors
+    ///
+    /// When the qualifier names a course this server doesn't host.
+    pub fn resolve<'a>(&self, exercise_name: &'a str) -> Result<(&Arc<Course>, &'a str)> {
+        match exercise_name.split_once(':') {
+            Some((slug, name)) => self
+                .get(slug)
+                .map(|course| (course, name))
+                .ok_or_else(|| anyhow!("no course named `{slug}`")),
+            None => Ok((self.default_course(), exercise_name)),
+        }
+    }
+}
+
+fn valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= 64
+        && slug
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A registry of two one-chapter courses in a fresh temp dir.
+    fn registry(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("corrode-courses-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (course, chapter) in [("fundamentals", "00_hello"), ("async", "00_futures")] {
+            let dir = root.join(course).join("examples").join(chapter);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("main.rs"), "//! # Chapter\n\nfn main() {}\n").unwrap();
+        }
+        std::fs::write(
+            root.join("courses.toml"),
+            "[[courses]]\nslug = \"fundamentals\"\ntitle = \"Rust fundamentals\"\n\
+             root = \"fundamentals\"\n\n[[courses]]\nslug = \"async\"\n\
+             title = \"Async Rust\"\nroot = \"async\"\nversion = \"2026.1\"\n",
+        )
+        .unwrap();
+        root
+    }
+
+    #[test]
+    fn registry_resolves_roots_and_qualified_exercise_names() {
+        let root = registry("resolve");
+        let courses = Courses::from_file(&root.join("courses.toml"), "0.1.0").unwrap();
+        assert_eq!(courses.len(), 2);
+
+        let default = courses.default_course();
+        assert_eq!(default.slug, "fundamentals");
+        assert_eq!(default.version, "0.1.0");
+        assert_eq!(default.base(), "");
+        assert_eq!(default.catalog.current()[0].file_stem, "00_hello");
+
+        let async_rust = courses.get("async").unwrap();
+        assert_eq!(async_rust.version, "2026.1");
+        assert_eq!(async_rust.base(), "/courses/async");
+        assert_eq!(async_rust.catalog.current()[0].file_stem, "00_futures");
+
+        let qualified = async_rust.qualify("00_futures");
+        assert_eq!(qualified, "async:00_futures");
+        let (course, name) = courses.resolve(&qualified).unwrap();
+        assert_eq!((course.slug.as_str(), name), ("async", "00_futures"));
+        let (course, name) = courses.resolve("00_hello").unwrap();
+        assert_eq!((course.slug.as_str(), name), ("fundamentals", "00_hello"));
+        assert!(courses.resolve("python:00_hello").is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn registry_rejects_bad_and_duplicate_slugs() {
+        let root = registry("invalid");
+        let config = |slug: &str| CourseConfig {
+            slug: slug.to_string(),
+            title: "T".to_string(),
+            root: root.join("fundamentals"),
+            version: None,
+        };
+        let err = Courses::load(vec![config("Async Rust")], "0.1.0").unwrap_err();
+        assert!(err.to_string().contains("lowercase"), "{err}");
+        let err = Courses::load(vec![config("a"), config("a")], "0.1.0").unwrap_err();
+        assert!(err.to_string().contains("twice"), "{err}");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
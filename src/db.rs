@@ -0,0 +1,488 @@
+//! Where course data lives: SQLite or PostgreSQL, picked by the scheme
+//! of `DATABASE_URL`.
+//!
+//! SQLite (`sqlite:…`) is the default and keeps a workshop to a single
+//! file next to the server. PostgreSQL (`postgres://…`) is for
+//! deployments that already run a managed database. Each backend has its
+//! own migration set under `migrations/<backend>/`.
+//!
+//! Queries are written once, in the SQL both engines accept, and run
+//! through [`with_pool!`](crate::with_pool) against whichever pool is
+//! live. In practice that means:
+//!
+//! - `$1`-style placeholders (SQLite reads them positionally too),
+//! - `TRUE` / `FALSE` for flags rather than `1` / `0`,
+//! - `lower(…)` for case-insensitive ordering rather than `COLLATE NOCASE`,
+//! - `ON CONFLICT DO NOTHING` rather than `INSERT OR IGNORE`,
+//! - date arithmetic done in Rust and bound as a parameter.
+//!
+//! [`backup`] and [`restore`] are the operator's side of the same split:
+//! SQLite copies itself with `VACUUM INTO`, PostgreSQL goes through
+//! `pg_dump` / `pg_restore`.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{Context, anyhow, bail};
+use sqlx::migrate::{MigrateDatabase, MigrateError, Migrator};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteConnection, SqliteLockingMode, SqlitePool, SqlitePoolOptions,
+};
+use sqlx::{Connection, Postgres, Sqlite};
+use tokio::process::Command;
+
+const UNSUPPORTED_URL: &str = "DATABASE_URL must start with sqlite:, postgres:// or postgresql://";
+
+/// Which database engine a [`Db`] talks to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+impl Backend {
+    /// The backend for a `DATABASE_URL`, or `None` for an unsupported
+    /// scheme.
+    ///
+    /// ```
+    /// use cargo_course::db::Backend;
+    ///
+    /// assert_eq!(Backend::from_url("sqlite:course.db"), Some(Backend::Sqlite));
+    /// assert_eq!(Backend::from_url("postgres://localhost/course"), Some(Backend::Postgres));
+    /// assert_eq!(Backend::from_url("mysql://localhost/course"), None);
+    /// ```
+    #[must_use]
+    pub fn from_url(url: &str) -> Option<Self> {
+        if url.starts_with("sqlite:") {
+            Some(Self::Sqlite)
+        } else if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            Some(Self::Postgres)
+        } else {
+            None
+        }
+    }
+
+    /// Short name, also the migrations subdirectory.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Sqlite => "sqlite",
+            Self::Postgres => "postgres",
+        }
+    }
+
+    /// This backend's migration set below `root` (usually `./migrations`).
+    #[must_use]
+    pub fn migrations_dir(self, root: &Path) -> PathBuf {
+        root.join(self.name())
+    }
+}
+
+/// A connection pool for one of the supported backends. Cloning is
+/// cheap; both variants are reference-counted pools.
+#[derive(Clone, Debug)]
+pub enum Db {
+    Sqlite(SqlitePool),
+    Postgres(PgPool),
+}
+
+impl Db {
+    /// Create the database named by `url` if it doesn't exist yet, then
+    /// open a pool of at most `max_connections`.
+    pub async fn connect(url: &str, max_connections: u32) -> Result<Self, sqlx::Error> {
+        match Backend::from_url(url) {
+            Some(Backend::Sqlite) => {
+                if !Sqlite::database_exists(url).await? {
+                    Sqlite::create_database(url).await?;
+                }
+                let pool = SqlitePoolOptions::new()
+                    .max_connections(max_connections)
+                    .connect(url)
+                    .await?;
+                Ok(Self::Sqlite(pool))
+            }
+            Some(Backend::Postgres) => {
+                if !Postgres::database_exists(url).await? {
+                    Postgres::create_database(url).await?;
+                }
+                let pool = PgPoolOptions::new()
+                    .max_connections(max_connections)
+                    .connect(url)
+                    .await?;
+                Ok(Self::Postgres(pool))
+            }
+            None => Err(sqlx::Error::Configuration(UNSUPPORTED_URL.into())),
+        }
+    }
+
+    #[must_use]
+    pub const fn backend(&self) -> Backend {
+        match self {
+            Self::Sqlite(_) => Backend::Sqlite,
+            Self::Postgres(_) => Backend::Postgres,
+        }
+    }
+
+    /// Apply this backend's migrations from below `root`.
+    pub async fn migrate(&self, root: &Path) -> Result<(), MigrateError> {
+        let migrator = Migrator::new(self.backend().migrations_dir(root)).await?;
+        crate::with_pool!(self, |pool| migrator.run(pool).await)
+    }
+
+    /// Close every connection in the pool.
+    pub async fn close(&self) {
+        crate::with_pool!(self, |pool| pool.close().await);
+    }
+}
+
+/// Evaluate `$body` with `$pool` bound to the concrete pool inside a
+/// [`Db`]. The body is expanded once per backend, so it is type-checked
+/// against both drivers and must produce the same type in each.
+///
+/// ```no_run
+/// # async fn demo(db: &cargo_course::db::Db) -> Result<(), sqlx::Error> {
+/// let participants: i64 = cargo_course::with_pool!(db, |pool| {
+///     sqlx::query_scalar("SELECT COUNT(*) FROM participants")
+///         .fetch_one(pool)
+///         .await
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! with_pool {
+    ($db:expr, |$pool:ident| $body:expr) => {
+        match $db {
+            $crate::db::Db::Sqlite($pool) => $body,
+            $crate::db::Db::Postgres($pool) => $body,
+        }
+    };
+}
+
+/// Write a consistent copy of the database at `url` to `output`.
+///
+/// Safe while the server keeps running: SQLite writes a compacted
+/// database file, PostgreSQL a custom-format `pg_dump` archive. `output`
+/// must not exist yet.
+pub async fn backup(url: &str, output: &Path) -> anyhow::Result<()> {
+    if output.exists() {
+        bail!("{} already exists", output.display());
+    }
+    if let Some(parent) = output.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating {}", parent.display()))?;
+    }
+    match Backend::from_url(url) {
+        Some(Backend::Sqlite) => {
+            let options = SqliteConnectOptions::from_str(url)?.create_if_missing(false);
+            if !options.get_filename().is_file() {
+                bail!("{} does not exist", options.get_filename().display());
+            }
+            let pool = SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect_with(options)
+                .await?;
+            let copied = vacuum_into(&pool, output).await;
+            pool.close().await;
+            copied
+        }
+        Some(Backend::Postgres) => {
+            run_pg_tool(
+                Command::new("pg_dump")
+                    .arg("--format=custom")
+                    .arg("--file")
+                    .arg(output)
+                    .arg("--dbname")
+                    .arg(url),
+            )
+            .await
+        }
+        None => bail!(UNSUPPORTED_URL),
+    }
+}
+
+/// Replace the database at `url` with a file [`backup`] wrote.
+///
+/// The server must be stopped. Whatever the database held before is
+/// first backed up next to `backup` (the returned path), so a restore
+/// can be undone the same way.
+///
+/// SQLite backups are refused when they fail `PRAGMA integrity_check`
+/// or come from a newer schema than the migrations below
+/// `migrations_root`; older ones are brought up to date by the next
+/// server start. Both backends refuse to restore while the database is
+/// in use: SQLite when it can't be locked exclusively, PostgreSQL
+/// while other sessions are connected.
+pub async fn restore(
+    url: &str,
+    backup: &Path,
+    migrations_root: &Path,
+) -> anyhow::Result<Option<PathBuf>> {
+    if !backup.is_file() {
+        bail!("{} not found", backup.display());
+    }
+    let backend = Backend::from_url(url).ok_or_else(|| anyhow!(UNSUPPORTED_URL))?;
+    let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+    let extension = match backend {
+        Backend::Sqlite => "db",
+        Backend::Postgres => "dump",
+    };
+    let safety = backup.with_file_name(format!("pre-restore-{stamp}.{extension}"));
+
+    match backend {
+        Backend::Sqlite => {
+            let source = SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect_with(SqliteConnectOptions::new().filename(backup).read_only(true))
+                .await
+                .with_context(|| format!("opening {}", backup.display()))?;
+            let restored = restore_sqlite(&source, url, backup, migrations_root, &safety).await;
+            source.close().await;
+            restored
+        }
+        Backend::Postgres => {
+            run_pg_tool(
+                Command::new("pg_restore")
+                    .arg("--list")
+                    .arg(backup)
+                    .stdout(Stdio::null()),
+            )
+            .await
+            .with_context(|| format!("{} is not a pg_dump archive", backup.display()))?;
+
+            let Db::Postgres(pool) = Db::connect(url, 1).await? else {
+                unreachable!("a postgres:// URL connects to Postgres");
+            };
+            let cleared = clear_postgres(&pool, url, &safety).await;
+            pool.close().await;
+            cleared?;
+            run_pg_tool(
+                Command::new("pg_restore")
+                    .arg("--no-owner")
+                    .arg("--single-transaction")
+                    .arg("--exit-on-error")
+                    .arg("--dbname")
+                    .arg(url)
+                    .arg(backup),
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "restoring {} failed; the previous contents are in {}",
+                    backup.display(),
+                    safety.display()
+                )
+            })?;
+            Ok(Some(safety))
+        }
+    }
+}
+
+async fn vacuum_into<'c>(
+    executor: impl sqlx::SqliteExecutor<'c>,
+    output: &Path,
+) -> anyhow::Result<()> {
+    let output = output
+        .to_str()
+        .ok_or_else(|| anyhow!("{} is not valid UTF-8", output.display()))?;
+    sqlx::query("VACUUM INTO $1")
+        .bind(output)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// The SQLite half of [`restore`]: check `source`, save the live
+/// database to `safety`, then swap the contents in.
+///
+/// The live database is locked exclusively first, so a running server
+/// (whose connections hold it shared) makes the restore bail instead of
+/// having its files deleted underneath it. The lock is held until the
+/// backup is in place. The backup is copied to a temporary file next to
+/// the live one and renamed over it, so the live path always holds a
+/// complete database.
+async fn restore_sqlite(
+    source: &SqlitePool,
+    url: &str,
+    backup: &Path,
+    migrations_root: &Path,
+    safety: &Path,
+) -> anyhow::Result<Option<PathBuf>> {
+    let integrity: String = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_one(source)
+        .await?;
+    if integrity != "ok" {
+        bail!("{} is damaged: {integrity}", backup.display());
+    }
+    let schema: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations")
+        .fetch_one(source)
+        .await
+        .with_context(|| format!("{} is not a course database", backup.display()))?;
+    let known = Migrator::new(Backend::Sqlite.migrations_dir(migrations_root))
+        .await?
+        .iter()
+        .map(|migration| migration.version)
+        .max();
+    if schema > known {
+        bail!(
+            "{} has schema version {}, newer than this server's migrations ({})",
+            backup.display(),
+            schema.unwrap_or_default(),
+            known.unwrap_or_default()
+        );
+    }
+
+    let options = SqliteConnectOptions::from_str(url)?;
+    let live = options.get_filename().to_path_buf();
+    let sibling = |suffix: &str| {
+        let mut path = live.clone().into_os_string();
+        path.push(suffix);
+        PathBuf::from(path)
+    };
+    let remove = |path: &Path| match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    };
+
+    let lock = if live.exists() {
+        if safety.exists() {
+            bail!("{} already exists", safety.display());
+        }
+        let mut lock = SqliteConnection::connect_with(
+            &options
+                .create_if_missing(false)
+                .locking_mode(SqliteLockingMode::Exclusive)
+                .busy_timeout(Duration::ZERO),
+        )
+        .await?;
+        sqlx::raw_sql("BEGIN EXCLUSIVE; COMMIT;")
+            .execute(&mut lock)
+            .await
+            .with_context(|| format!("{} is in use; stop the server first", live.display()))?;
+        vacuum_into(&mut lock, safety).await?;
+        // Fold the WAL back into the file we're about to replace, so no
+        // `-wal` is left behind for SQLite to replay onto the backup.
+        sqlx::query("PRAGMA journal_mode = DELETE")
+            .execute(&mut lock)
+            .await?;
+        Some(lock)
+    } else {
+        None
+    };
+
+    let staged = sibling("-restore");
+    remove(&staged)?;
+    vacuum_into(source, &staged).await?;
+    for suffix in ["-wal", "-shm"] {
+        remove(&sibling(suffix))?;
+    }
+    std::fs::rename(&staged, &live)
+        .with_context(|| format!("moving {} into place", staged.display()))?;
+    Ok(match lock {
+        Some(lock) => {
+            lock.close().await?;
+            Some(safety.to_path_buf())
+        }
+        None => None,
+    })
+}
+
+/// The PostgreSQL half of [`restore`] before `pg_restore` runs: refuse
+/// while the server is connected, save the database to `safety`, then
+/// empty it.
+async fn clear_postgres(pool: &PgPool, url: &str, safety: &Path) -> anyhow::Result<()> {
+    let others: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pg_stat_activity
+         WHERE datname = current_database() AND pid <> pg_backend_pid()",
+    )
+    .fetch_one(pool)
+    .await?;
+    if others > 0 {
+        bail!("{others} other session(s) are connected; stop the server first");
+    }
+    backup(url, safety).await?;
+    sqlx::raw_sql("DROP SCHEMA public CASCADE; CREATE SCHEMA public;")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Run `pg_dump` or `pg_restore`, failing on a non-zero exit.
+async fn run_pg_tool(command: &mut Command) -> anyhow::Result<()> {
+    let program = command
+        .as_std()
+        .get_program()
+        .to_string_lossy()
+        .into_owned();
+    let status = command
+        .status()
+        .await
+        .with_context(|| format!("running {program} (is the PostgreSQL client installed?)"))?;
+    if !status.success() {
+        bail!("{program} exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn names(url: &str) -> Vec<String> {
+        let db = Db::connect(url, 1).await.unwrap();
+        let names = crate::with_pool!(&db, |pool| sqlx::query_scalar(
+            "SELECT name FROM participants ORDER BY name"
+        )
+        .fetch_all(pool)
+        .await)
+        .unwrap();
+        db.close().await;
+        names
+    }
+
+    #[tokio::test]
+    async fn sqlite_restore_brings_back_the_backup_and_keeps_what_it_replaced() {
+        let dir = std::env::temp_dir().join(format!("corrode_backup_{}", ulid::Ulid::new()));
+        let url = format!("sqlite:{}", dir.join("course.db").display());
+        let migrations = Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Db::connect(&url, 1).await.unwrap();
+        db.migrate(&migrations).await.unwrap();
+        let insert = |id: &'static str| {
+            let db = db.clone();
+            async move {
+                crate::with_pool!(&db, |pool| sqlx::query(
+                    "INSERT INTO participants (id, name) VALUES ($1, $1)"
+                )
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|done| done.rows_affected()))
+                .unwrap();
+            }
+        };
+
+        insert("before").await;
+        let snapshot = dir.join("backups").join("course.db");
+        backup(&url, &snapshot).await.unwrap();
+        assert!(backup(&url, &snapshot).await.is_err(), "never overwrites");
+        insert("after").await;
+        let busy = restore(&url, &snapshot, &migrations).await.unwrap_err();
+        assert!(busy.to_string().contains("in use"), "{busy:#}");
+        db.close().await;
+        assert_eq!(names(&url).await, ["after", "before"]);
+
+        let saved = restore(&url, &snapshot, &migrations)
+            .await
+            .unwrap()
+            .expect("the replaced database is saved");
+        assert_eq!(names(&url).await, ["before"]);
+        let saved_url = format!("sqlite:{}", saved.display());
+        assert_eq!(names(&saved_url).await, ["after", "before"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
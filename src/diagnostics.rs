@@ -0,0 +1,530 @@
+//! Structured compiler and clippy diagnostics.
+//!
+//! The editor wants more than a blob of `stderr`: it underlines spans,
+//! lists lint names and offers fixes. [`Diagnostic`] is the shape we
+//! hand to the browser, a trimmed-down version of rustc's
+//! `--error-format=json` output (line/column spans, labels, child
+//! notes and suggested replacements).
+//!
+//! Two sources feed it:
+//!
+//! * [`parse_json`]: rustc/clippy JSON diagnostics, available whenever
+//!   the server runs the toolchain itself. Exact spans and suggestions.
+//! * [`parse_human`]: a best-effort parse of the human-readable output,
+//!   for backends that only give us rendered text (the Playground).
+//!   Levels, codes, messages, labelled spans and `= note:` / `help:`
+//!   children survive; suggested replacements don't.
+//!
+//! Spans pointing outside the learner's file (into std, or into macro
+//! definitions) are dropped, as are rustc's summary lines ("aborting due
+//! to 2 previous errors").
+
+use serde::{Deserialize, Serialize};
+
+/// One diagnostic (or child note/help) as the editor consumes it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// `error`, `warning`, `note`, `help` (rustc's level names).
+    pub level: String,
+    /// Error code (`E0382`) or lint name (`clippy::needless_return`,
+    /// `unused_variables`), when rustc reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    /// Spans in the learner's file. At most a few are primary; the rest
+    /// are secondary context ("value moved here").
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<Span>,
+    /// Attached notes and help messages, some carrying suggestions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Self>,
+}
+
+/// A region of the learner's file. Lines and columns are 1-based;
+/// columns count chars, and `column_end` is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub line_start: u32,
+    pub line_end: u32,
+    pub column_start: u32,
+    pub column_end: u32,
+    pub is_primary: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Text rustc suggests replacing the span with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggested_replacement: Option<String>,
+    /// rustc's confidence in the suggestion: `MachineApplicable`,
+    /// `MaybeIncorrect`, `HasPlaceholders` or `Unspecified`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applicability: Option<String>,
+}
+
+impl Diagnostic {
+    /// `true` for `error`-level diagnostics.
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+
+    /// The first primary span, if any.
+    #[must_use]
+    pub fn primary_span(&self) -> Option<&Span> {
+        self.spans.iter().find(|span| span.is_primary)
+    }
+
+    /// Suggested edits that rustc is confident can be applied as-is.
+    pub fn machine_applicable_fixes(&self) -> impl Iterator<Item = &Span> {
+        self.children
+            .iter()
+            .flat_map(|child| child.spans.iter())
+            .chain(self.spans.iter())
+            .filter(|span| {
+                span.suggested_replacement.is_some()
+                    && span.applicability.as_deref() == Some("MachineApplicable")
+            })
+    }
+
+    /// Move every span (including children's) down by `delta` lines.
+    /// Used when the code we compiled had lines prepended to it.
+    pub fn shift_lines(&mut self, delta: i64) {
+        let shift = |line: u32| u32::try_from((i64::from(line) + delta).max(1)).unwrap_or(u32::MAX);
+        for span in &mut self.spans {
+            span.line_start = shift(span.line_start);
+            span.line_end = shift(span.line_end);
+        }
+        for child in &mut self.children {
+            child.shift_lines(delta);
+        }
+    }
+}
+
+/// Is `file_name` the learner's source rather than std, a dependency or
+/// a macro definition?
+fn is_user_file(file_name: &str) -> bool {
+    !(file_name.starts_with('<')
+        || file_name.starts_with("/rustc/")
+        || file_name.contains("/.cargo/")
+        || file_name.contains("/lib/rustlib/"))
+}
+
+/// rustc's end-of-build summaries, which carry no information the
+/// individual diagnostics don't.
+fn is_summary(message: &str) -> bool {
+    let generated = message
+        .split_once(" generated ")
+        .is_some_and(|(_, rest)| rest.contains("warning"));
+    message.starts_with("aborting due to")
+        || message.starts_with("could not compile")
+        || message.ends_with("warning emitted")
+        || message.ends_with("warnings emitted")
+        || generated
+}
+
+#[derive(Deserialize)]
+struct RawDiagnostic {
+    message: String,
+    code: Option<RawCode>,
+    level: String,
+    #[serde(default)]
+    spans: Vec<RawSpan>,
+    #[serde(default)]
+    children: Vec<Self>,
+    rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct RawSpan {
+    file_name: String,
+    line_start: u32,
+    line_end: u32,
+    column_start: u32,
+    column_end: u32,
+    is_primary: bool,
+    label: Option<String>,
+    suggested_replacement: Option<String>,
+    suggestion_applicability: Option<String>,
+}
+
+impl RawDiagnostic {
+    fn into_diagnostic(self) -> Diagnostic {
+        Diagnostic {
+            level: self.level,
+            code: self.code.map(|code| code.code),
+            message: self.message,
+            spans: self
+                .spans
+                .into_iter()
+                .filter(|span| is_user_file(&span.file_name))
+                .map(|span| Span {
+                    line_start: span.line_start,
+                    line_end: span.line_end,
+                    column_start: span.column_start,
+                    column_end: span.column_end,
+                    is_primary: span.is_primary,
+                    label: span.label,
+                    suggested_replacement: span.suggested_replacement,
+                    applicability: span.suggestion_applicability,
+                })
+                .collect(),
+            children: self
+                .children
+                .into_iter()
+                .map(Self::into_diagnostic)
+                .collect(),
+        }
+    }
+}
+
+/// Diagnostics plus the human-readable rendering rustc produced for
+/// them, so callers can still show learners the familiar text.
+#[derive(Debug, Clone, Default)]
+pub struct Parsed {
+    pub diagnostics: Vec<Diagnostic>,
+    pub rendered: String,
+}
+
+/// Parse `--error-format=json` output (one JSON object per line).
+///
+/// Lines that aren't diagnostics (artifact notifications, panics from a
+/// crashed compiler, plain text) are passed through to `rendered`
+/// unchanged so nothing the compiler said is lost.
+#[must_use]
+pub fn parse_json(stderr: &str) -> Parsed {
+    let mut parsed = Parsed::default();
+    for line in stderr.lines() {
+        let Ok(raw) = serde_json::from_str::<RawDiagnostic>(line) else {
+            if !line.starts_with('{') {
+                parsed.rendered.push_str(line);
+                parsed.rendered.push('\n');
+            }
+            continue;
+        };
+        if let Some(rendered) = &raw.rendered {
+            parsed.rendered.push_str(rendered);
+        }
+        if is_summary(&raw.message) {
+            continue;
+        }
+        let diagnostic = raw.into_diagnostic();
+        if !diagnostic.spans.is_empty() || diagnostic.code.is_some() {
+            parsed.diagnostics.push(diagnostic);
+        }
+    }
+    parsed
+}
+
+/// Split `error[E0382]: message` into `("error", Some("E0382"),
+/// "message")`. Only matches headers starting at column 0.
+fn parse_header(line: &str) -> Option<(&str, Option<&str>, &str)> {
+    let (head, message) = line.split_once(": ")?;
+    let (level, code) = match head.split_once('[') {
+        Some((level, rest)) => (level, Some(rest.strip_suffix(']')?)),
+        None => (head, None),
+    };
+    matches!(level, "error" | "warning" | "note" | "help").then_some((level, code, message))
+}
+
+/// Parse ` --> src/main.rs:4:20` into `(file, line, column)`.
+fn parse_location(line: &str) -> Option<(&str, u32, u32)> {
+    let rest = line.trim_start();
+    let rest = rest
+        .strip_prefix("--> ")
+        .or_else(|| rest.strip_prefix("::: "))?;
+    let mut parts = rest.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    Some((parts.next()?, line, column))
+}
+
+/// Parse a gutter line: `12 |     code` gives `Some((Some(12), "    code"))`,
+/// `   |     ^^^ label` gives `Some((None, "    ^^^ label"))`.
+fn parse_gutter(line: &str) -> Option<(Option<u32>, &str)> {
+    let (gutter, rest) = line.split_once('|')?;
+    let gutter = gutter.trim();
+    let number = if gutter.is_empty() {
+        None
+    } else {
+        Some(gutter.parse().ok()?)
+    };
+    Some((number, rest.strip_prefix(' ').unwrap_or(rest)))
+}
+
+/// Turn a marker line (`    ^^^^ label`, `    ---   ^^^ label`) into
+/// spans on `line`. Only the last marker carries an inline label; the
+/// others get theirs from the lines below (see [`parse_human`]).
+/// Markers drawn with `_` / `|` for multi-line spans are skipped.
+fn parse_markers(markers: &str, line: u32) -> Vec<Span> {
+    let chars: Vec<char> = markers.chars().collect();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            ' ' => i += 1,
+            marker @ ('^' | '-') => {
+                let start = i;
+                while chars.get(i) == Some(&marker) {
+                    i += 1;
+                }
+                let (Ok(start), Ok(end)) = (u32::try_from(start), u32::try_from(i)) else {
+                    break;
+                };
+                spans.push(Span {
+                    line_start: line,
+                    line_end: line,
+                    column_start: start + 1,
+                    column_end: end + 1,
+                    is_primary: marker == '^',
+                    label: None,
+                    suggested_replacement: None,
+                    applicability: None,
+                });
+            }
+            _ => {
+                let label: String = chars[i..].iter().collect();
+                if let Some(last) = spans.last_mut() {
+                    last.label = Some(label.trim().to_string());
+                }
+                break;
+            }
+        }
+    }
+    spans
+}
+
+/// Pull `clippy::needless_return` out of
+/// ``= note: `#[warn(clippy::needless_return)]` on by default``.
+fn lint_from_note(message: &str) -> Option<&str> {
+    let start = message
+        .find("#[warn(")
+        .or_else(|| message.find("#[deny("))?
+        + "#[warn(".len();
+    let end = message[start..].find(")]")? + start;
+    Some(&message[start..end])
+}
+
+/// Best-effort parse of rustc's human-readable diagnostics.
+///
+/// Recovers levels, codes, messages, labelled single-line spans and
+/// `note:` / `help:` children. Lint names are recovered from the
+/// `#[warn(...)]` note. Suggested replacements aren't, because the
+/// rendered diff doesn't say which columns it replaces.
+#[must_use]
+pub fn parse_human(stderr: &str) -> Vec<Diagnostic> {
+    let mut out: Vec<Diagnostic> = Vec::new();
+    // `Some` while we're inside a diagnostic we intend to keep.
+    let mut current: Option<Diagnostic> = None;
+    // Whether snippets currently belong to the last child rather than
+    // the top-level diagnostic.
+    let mut in_child = false;
+    let mut in_user_file = false;
+    let mut last_line: Option<u32> = None;
+    // Spans on `last_line` still waiting for a label from a line below.
+    let mut unlabelled: Vec<usize> = Vec::new();
+
+    for line in stderr.lines() {
+        if let Some((level, code, message)) = parse_header(line) {
+            let top_level = matches!(level, "error" | "warning") || current.is_none();
+            if top_level {
+                out.extend(current.take());
+                in_child = false;
+                last_line = None;
+                if !is_summary(message) {
+                    current = Some(Diagnostic {
+                        level: level.to_string(),
+                        code: code.map(str::to_string),
+                        message: message.to_string(),
+                        spans: Vec::new(),
+                        children: Vec::new(),
+                    });
+                }
+            } else if let Some(diagnostic) = current.as_mut() {
+                diagnostic.children.push(Diagnostic {
+                    level: level.to_string(),
+                    code: None,
+                    message: message.to_string(),
+                    spans: Vec::new(),
+                    children: Vec::new(),
+                });
+                in_child = true;
+                last_line = None;
+            }
+            continue;
+        }
+        let Some(diagnostic) = current.as_mut() else {
+            continue;
+        };
+
+        if let Some((file, _, _)) = parse_location(line) {
+            in_user_file = is_user_file(file);
+            continue;
+        }
+
+        let trimmed = line.trim_start();
+        if let Some(note) = trimmed.strip_prefix("= ") {
+            if let Some((level, message)) = note.split_once(": ") {
+                if diagnostic.code.is_none() {
+                    diagnostic.code = lint_from_note(message).map(str::to_string);
+                }
+                diagnostic.children.push(Diagnostic {
+                    level: level.to_string(),
+                    code: None,
+                    message: message.to_string(),
+                    spans: Vec::new(),
+                    children: Vec::new(),
+                });
+            }
+            continue;
+        }
+
+        let Some((number, text)) = parse_gutter(line) else {
+            continue;
+        };
+        if let Some(number) = number {
+            last_line = Some(number);
+            unlabelled.clear();
+            continue;
+        }
+        // Child snippets render suggestions as diffs, not markers.
+        if in_child || !in_user_file {
+            continue;
+        }
+        let Some((offset, first)) = text.char_indices().find(|&(_, c)| c != ' ') else {
+            continue;
+        };
+        match first {
+            '^' | '-' => {
+                let Some(line) = last_line else {
+                    continue;
+                };
+                for span in parse_markers(text, line) {
+                    if span.label.is_none() {
+                        unlabelled.push(diagnostic.spans.len());
+                    }
+                    diagnostic.spans.push(span);
+                }
+            }
+            // `|` connectors lead from a marker down to its label.
+            '|' if text.trim().chars().all(|c| c == '|' || c == ' ') => {}
+            _ => {
+                // A label under an earlier marker on the same line starts
+                // in that marker's column.
+                let column = text[..offset].chars().count() + 1;
+                let hit = unlabelled.iter().position(|&index| {
+                    usize::try_from(diagnostic.spans[index].column_start) == Ok(column)
+                });
+                if let Some(position) = hit {
+                    let index = unlabelled.remove(position);
+                    diagnostic.spans[index].label = Some(text.trim().to_string());
+                }
+            }
+        }
+    }
+    out.extend(current);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BORROW_ERROR: &str = "\
+error[E0382]: borrow of moved value: `s`
+ --> src/main.rs:4:20
+  |
+2 |     let s = String::new();
+  |         - move occurs because `s` has type `String`, which does not implement the `Copy` trait
+3 |     let t = s;
+  |             - value moved here
+4 |     println!(\"{}\", s);
+  |                    ^ value borrowed here after move
+  |
+help: consider cloning the value if the performance cost is acceptable
+  |
+3 |     let t = s.clone();
+  |              ++++++++
+
+error[E0308]: mismatched types
+ --> src/main.rs:6:18
+  |
+6 |     let x: i32 = \"no\";
+  |            ---   ^^^^ expected `i32`, found `&str`
+  |            |
+  |            expected due to this
+
+warning: unneeded `return` statement
+ --> src/main.rs:2:5
+  |
+2 |     return x + 1;
+  |     ^^^^^^^^^^^^
+  |
+  = help: for further information visit https://rust-lang.github.io/rust-clippy/index.html#needless_return
+  = note: `#[warn(clippy::needless_return)]` on by default
+
+error: aborting due to 1 previous error; 1 warning emitted
+
+For more information about this error, try `rustc --explain E0382`.
+";
+
+    #[test]
+    fn human_output_recovers_codes_spans_and_children() {
+        let diagnostics = parse_human(BORROW_ERROR);
+        assert_eq!(diagnostics.len(), 3, "{diagnostics:#?}");
+
+        let borrow = &diagnostics[0];
+        assert_eq!(borrow.level, "error");
+        assert_eq!(borrow.code.as_deref(), Some("E0382"));
+        assert_eq!(borrow.spans.len(), 3);
+        let primary = borrow.primary_span().unwrap();
+        assert_eq!((primary.line_start, primary.column_start), (4, 20));
+        assert_eq!(
+            primary.label.as_deref(),
+            Some("value borrowed here after move")
+        );
+        assert_eq!(borrow.spans[1].label.as_deref(), Some("value moved here"));
+        assert!(!borrow.spans[1].is_primary);
+        assert_eq!(borrow.children.len(), 1);
+        assert_eq!(borrow.children[0].level, "help");
+
+        let mismatch = &diagnostics[1];
+        assert_eq!(mismatch.spans.len(), 2);
+        assert_eq!(
+            mismatch.spans[0].label.as_deref(),
+            Some("expected due to this")
+        );
+        assert_eq!(
+            (mismatch.spans[1].column_start, mismatch.spans[1].column_end),
+            (18, 22)
+        );
+        assert_eq!(
+            mismatch.spans[1].label.as_deref(),
+            Some("expected `i32`, found `&str`")
+        );
+
+        let lint = &diagnostics[2];
+        assert_eq!(lint.code.as_deref(), Some("clippy::needless_return"));
+        let span = lint.primary_span().unwrap();
+        assert_eq!((span.column_start, span.column_end), (5, 17));
+    }
+
+    #[test]
+    fn json_output_keeps_suggestions_and_drops_summaries() {
+        let stderr = concat!(
+            r#"{"$message_type":"diagnostic","message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"level":"warning","spans":[{"file_name":"main.rs","byte_start":26,"byte_end":38,"line_start":2,"line_end":2,"column_start":5,"column_end":17,"is_primary":true,"text":[],"label":null,"suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[{"message":"remove `return`","code":null,"level":"help","spans":[{"file_name":"main.rs","byte_start":26,"byte_end":38,"line_start":2,"line_end":2,"column_start":5,"column_end":17,"is_primary":true,"text":[],"label":null,"suggested_replacement":"x + 1","suggestion_applicability":"MachineApplicable","expansion":null}],"children":[],"rendered":null}],"rendered":"warning: unneeded `return` statement\n"}"#,
+            "\n",
+            r#"{"$message_type":"diagnostic","message":"1 warning emitted","code":null,"level":"warning","spans":[],"children":[],"rendered":"warning: 1 warning emitted\n"}"#,
+            "\n",
+        );
+        let parsed = parse_json(stderr);
+        assert_eq!(parsed.diagnostics.len(), 1);
+        let fixes: Vec<_> = parsed.diagnostics[0].machine_applicable_fixes().collect();
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(fixes[0].suggested_replacement.as_deref(), Some("x + 1"));
+        assert!(parsed.rendered.contains("1 warning emitted"));
+    }
+}
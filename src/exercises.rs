@@ -1,4 +1,5 @@
-//! Startup-time scan and parse of `examples/NN_slug/` chapter directories.
+//! Scan and parse of `examples/NN_slug/` chapter directories, at startup
+//! and again whenever [`crate::catalog`] sees the content change.
 //!
 //! Each chapter is a directory `examples/NN_slug/` containing one or more
 //! exercise files (`.rs`) and zero or more notes (`.md`), interleaved by a
@@ -23,37 +24,97 @@
 use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
 
-/// Multiple-choice quiz loaded from a chapter's `quiz.toml`.
+/// Quiz loaded from a chapter's `quiz.toml`.
 ///
 /// The quiz file format is intentionally tiny: a list of `[[questions]]`
-/// tables, each with a prompt, an optional hint, and an inline-table
-/// `answers` array. Exactly one answer per question must be marked
-/// `correct = true`; this is enforced when the chapter is parsed so a
-/// malformed quiz fails fast at startup rather than at render time.
+/// tables, each with a prompt, an optional hint, and whatever its
+/// [`QuestionKind`] (`type`, `single` by default) asks for. Every
+/// question is checked when the chapter is parsed (see
+/// `load_chapter_quiz`) so a malformed quiz fails fast at startup
+/// rather than at render time.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Quiz {
+    /// Percentage of questions (1-100) a participant must get right in
+    /// one attempt for the chapter to count as completed; a perfect
+    /// attempt perfects it. Without one the quiz is practice: answers
+    /// are still recorded, but the chapter stays out of progress.
+    #[serde(default)]
+    pub pass_mark: Option<u8>,
     pub questions: Vec<Question>,
 }
 
+/// What a question asks the learner to do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionKind {
+    /// Pick the one correct entry of `answers`.
+    #[default]
+    Single,
+    /// Pick every correct entry of `answers` (at least one), then check.
+    Multiple,
+    /// Type what `code` prints; compared with `accept` after
+    /// [`normalize_output`].
+    Output,
+    /// Put `items` (listed in the right order) back in order.
+    Order,
+    /// Click the line of `code` that fails to compile (`line`, 1-based).
+    CompileError,
+}
+
+impl QuestionKind {
+    /// The `type` value in `quiz.toml`, also emitted as
+    /// `data-quiz-type` for `static/js/quiz.js`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Single => "single",
+            Self::Multiple => "multiple",
+            Self::Output => "output",
+            Self::Order => "order",
+            Self::CompileError => "compile_error",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Question {
+    #[serde(rename = "type", default)]
+    pub kind: QuestionKind,
     /// The question itself, in markdown (rendered server-side, so
     /// inline code, `**bold**`, and fenced code blocks all work).
     pub prompt: String,
     /// Optional nudge shown above the answers.
     #[serde(default)]
     pub hint: Option<String>,
-    /// 2–6 answer choices. Order is preserved; we do not shuffle.
+    /// `single` and `multiple`: 2–6 answer choices, shuffled per render.
+    #[serde(default)]
     pub answers: Vec<Answer>,
+    /// `output` and `compile_error`: the program, shown read-only.
+    #[serde(default)]
+    pub code: Option<String>,
+    /// `output`: every output that counts as right.
+    #[serde(default)]
+    pub accept: Vec<String>,
+    /// `order`: the items, in the right order.
+    #[serde(default)]
+    pub items: Vec<String>,
+    /// `compile_error`: the 1-based line of `code` that fails.
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// `output`, `order` and `compile_error`: markdown revealed once the
+    /// learner has answered. Choice questions explain per answer.
+    #[serde(default)]
+    pub explanation: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Answer {
     /// Answer text, in markdown.
     pub text: String,
-    /// True for exactly one answer per question.
+    /// Exactly one answer of a `single` question is correct; any
+    /// number (but at least one) of a `multiple` one.
     pub correct: bool,
     /// Per-answer commentary revealed after the visitor picks an
     /// answer, not just for the right answer, but for every
@@ -62,11 +123,87 @@ pub struct Answer {
     pub explanation: String,
 }
 
+/// The form `output` answers are compared in.
+///
+/// `\r\n` becomes `\n`, trailing whitespace goes from every line, and
+/// blank lines go from both ends. Spacing within a line still counts.
+/// `static/js/quiz.js` applies the same rules to what the learner types.
+#[must_use]
+pub fn normalize_output(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let first = lines.iter().position(|line| !line.is_empty());
+    let last = lines.iter().rposition(|line| !line.is_empty());
+    match (first, last) {
+        (Some(first), Some(last)) => lines[first..=last].join("\n"),
+        _ => String::new(),
+    }
+}
+
+impl Question {
+    /// Whether `answer`, as `static/js/quiz.js` posts it, is right.
+    /// `None` when it isn't an answer to this question at all.
+    ///
+    /// Choices are indices into `answers` as listed in `quiz.toml` (one
+    /// for `single`, comma-separated for `multiple`), `output` is the
+    /// text typed, `order` the items' `data-position`s as placed, and
+    /// `compile_error` the 1-based line clicked.
+    #[must_use]
+    pub fn grade(&self, answer: &str) -> Option<bool> {
+        // Distinct indices below `limit`, in the order given.
+        let indices = |limit: usize| -> Option<Vec<usize>> {
+            let listed: Vec<usize> = answer
+                .split(',')
+                .map(|n| n.trim().parse().ok().filter(|&n| n < limit))
+                .collect::<Option<_>>()?;
+            let mut distinct = listed.clone();
+            distinct.sort_unstable();
+            distinct.dedup();
+            (distinct.len() == listed.len()).then_some(listed)
+        };
+        match self.kind {
+            QuestionKind::Single => {
+                let [picked] = indices(self.answers.len())?[..] else {
+                    return None;
+                };
+                Some(self.answers[picked].correct)
+            }
+            QuestionKind::Multiple => {
+                let picked = indices(self.answers.len())?;
+                Some(
+                    self.answers
+                        .iter()
+                        .enumerate()
+                        .all(|(i, a)| a.correct == picked.contains(&i)),
+                )
+            }
+            QuestionKind::Output => {
+                let typed = normalize_output(answer);
+                Some(self.accept.iter().any(|a| normalize_output(a) == typed))
+            }
+            QuestionKind::Order => {
+                let placed = indices(self.items.len())?;
+                (placed.len() == self.items.len())
+                    .then(|| placed.iter().enumerate().all(|(i, &p)| i == p))
+            }
+            QuestionKind::CompileError => {
+                let line: usize = answer.trim().parse().ok()?;
+                let code = self.code.as_deref().unwrap_or_default();
+                (1..=code.lines().count())
+                    .contains(&line)
+                    .then_some(self.line == Some(line))
+            }
+        }
+    }
+}
+
 impl Quiz {
     /// Render the whole quiz to HTML. One outer `<section data-quiz>`
-    /// wraps an `<ol>` of question cards; each card carries the
-    /// per-answer `data-correct` flag so `static/js/quiz.js` can
-    /// evaluate clicks without an extra round trip.
+    /// wraps an `<ol>` of question cards. Each card says its type in
+    /// `data-quiz-type` and carries what `static/js/quiz.js` needs to
+    /// check an answer without a round trip: `data-correct` per
+    /// answer, the accepted outputs, each item's place, the failing
+    /// line. `data-question` and the answers' `data-index` are what it
+    /// posts back (see [`Question::grade`]).
     #[must_use]
     pub fn render_html(&self) -> String {
         use std::fmt::Write;
@@ -91,7 +228,8 @@ impl Quiz {
         for (i, q) in self.questions.iter().enumerate() {
             let _ = write!(
                 out,
-                "<li class=\"quiz-card\" data-quiz-card>\
+                "<li class=\"quiz-card\" data-quiz-card data-quiz-type=\"{kind}\" \
+                     data-question=\"{i}\">\
                    <div class=\"quiz-card-head\">\
                      <span class=\"quiz-card-num\">Question {n}\
                        <span class=\"quiz-card-num-total\">\
@@ -100,6 +238,7 @@ impl Quiz {
                            aria-live=\"polite\"></span>\
                    </div>\
                    <div class=\"quiz-prompt\">{prompt}</div>",
+                kind = q.kind.as_str(),
                 n = i + 1,
                 total = self.questions.len(),
                 prompt = render_markdown(&q.prompt),
@@ -113,36 +252,21 @@ impl Quiz {
                     body = render_inline_markdown(hint),
                 );
             }
-            out.push_str("<ol class=\"quiz-answers\">");
-            // Shuffle answers per render so the correct option doesn't
-            // sit in the same slot every time. Re-rendered on every
-            // request, so each page load gets a fresh order.
-            let mut shuffled: Vec<&Answer> = q.answers.iter().collect();
-            {
-                use rand::seq::SliceRandom;
-                shuffled.shuffle(&mut rand::rng());
+            match q.kind {
+                QuestionKind::Single | QuestionKind::Multiple => render_choices(&mut out, q),
+                QuestionKind::Output => render_output(&mut out, q),
+                QuestionKind::Order => render_order(&mut out, q),
+                QuestionKind::CompileError => render_compile_error(&mut out, q),
             }
-            for a in &shuffled {
+            if let Some(explanation) = &q.explanation {
                 let _ = write!(
                     out,
-                    "<li class=\"quiz-answer-wrap\">\
-                       <button type=\"button\" class=\"quiz-answer\" \
-                               data-quiz-answer \
-                               data-correct=\"{correct}\">\
-                         <span class=\"quiz-answer-marker\" aria-hidden=\"true\"></span>\
-                         <span class=\"quiz-answer-text\">{text}</span>\
-                       </button>\
-                       <div class=\"quiz-explanation\" \
-                            data-quiz-explanation hidden>\
-                         {explanation}\
-                       </div>\
-                     </li>",
-                    correct = a.correct,
-                    text = render_inline_markdown(&a.text),
-                    explanation = render_inline_markdown(&a.explanation),
+                    "<div class=\"quiz-explanation quiz-card-explanation\" \
+                          data-quiz-explanation hidden>{}</div>",
+                    render_markdown(explanation),
                 );
             }
-            out.push_str("</ol></li>");
+            out.push_str("</li>");
         }
         out.push_str("</ol>");
         out.push_str(
@@ -157,6 +281,155 @@ impl Quiz {
     }
 }
 
+/// The "Check" button of the question types that don't answer on the
+/// first click.
+const CHECK_BUTTON: &str = "<button type=\"button\" class=\"btn btn-secondary quiz-check\" \
+     data-quiz-check>Check</button>";
+
+/// Answer buttons of a `single` or `multiple` question. A `multiple`
+/// question's buttons toggle and get a "Check" button.
+fn render_choices(out: &mut String, q: &Question) {
+    use std::fmt::Write;
+    out.push_str("<ol class=\"quiz-answers\">");
+    // Shuffle answers per render so the correct option doesn't
+    // sit in the same slot every time. Re-rendered on every
+    // request, so each page load gets a fresh order.
+    let mut shuffled: Vec<(usize, &Answer)> = q.answers.iter().enumerate().collect();
+    {
+        use rand::seq::SliceRandom;
+        shuffled.shuffle(&mut rand::rng());
+    }
+    let pressed = if q.kind == QuestionKind::Multiple {
+        " aria-pressed=\"false\""
+    } else {
+        ""
+    };
+    for (index, a) in shuffled {
+        let _ = write!(
+            out,
+            "<li class=\"quiz-answer-wrap\">\
+               <button type=\"button\" class=\"quiz-answer\" \
+                       data-quiz-answer{pressed} data-index=\"{index}\" \
+                       data-correct=\"{correct}\">\
+                 <span class=\"quiz-answer-marker\" aria-hidden=\"true\"></span>\
+                 <span class=\"quiz-answer-text\">{text}</span>\
+               </button>\
+               <div class=\"quiz-explanation\" \
+                    data-quiz-explanation hidden>\
+                 {explanation}\
+               </div>\
+             </li>",
+            correct = a.correct,
+            text = render_inline_markdown(&a.text),
+            explanation = render_inline_markdown(&a.explanation),
+        );
+    }
+    out.push_str("</ol>");
+    if q.kind == QuestionKind::Multiple {
+        let _ = write!(
+            out,
+            "<p class=\"quiz-actions\">\
+               <span class=\"quiz-actions-note\">Pick every answer that applies.</span>\
+               {CHECK_BUTTON}</p>"
+        );
+    }
+}
+
+/// The program and a box for what it prints. The accepted outputs,
+/// normalised, travel as a JSON array in `data-accept`; the first one
+/// is shown once the learner has answered.
+fn render_output(out: &mut String, q: &Question) {
+    use std::fmt::Write;
+    let accept: Vec<String> = q.accept.iter().map(|a| normalize_output(a)).collect();
+    let _ = write!(
+        out,
+        "<pre class=\"quiz-code\"><code class=\"language-rust\">{code}</code></pre>\
+         <label class=\"quiz-output\">\
+           <span class=\"quiz-output-label\">What does it print?</span>\
+           <textarea class=\"quiz-output-input\" rows=\"3\" spellcheck=\"false\" \
+                     data-quiz-output data-accept=\"{accept}\"></textarea>\
+         </label>\
+         <p class=\"quiz-actions\">{CHECK_BUTTON}</p>\
+         <div class=\"quiz-expected\" data-quiz-expected hidden>\
+           <span class=\"quiz-output-label\">It prints</span>\
+           <pre class=\"quiz-code\"><code>{expected}</code></pre>\
+         </div>",
+        code = escape_attr(q.code.as_deref().unwrap_or_default().trim_end()),
+        accept = escape_attr(&serde_json::to_string(&accept).unwrap_or_else(|_| "[]".into())),
+        expected = escape_attr(accept.first().map_or("", String::as_str)),
+    );
+}
+
+/// The items, shuffled out of order, each with buttons to move it.
+/// `data-position` is an item's place in the right order.
+fn render_order(out: &mut String, q: &Question) {
+    use std::fmt::Write;
+    let mut order: Vec<usize> = (0..q.items.len()).collect();
+    {
+        use rand::seq::SliceRandom;
+        // Two distinct items always have a wrong order to start from.
+        while order.windows(2).all(|pair| pair[0] < pair[1]) {
+            order.shuffle(&mut rand::rng());
+        }
+    }
+    out.push_str("<ol class=\"quiz-order\" data-quiz-order>");
+    for position in order {
+        let _ = write!(
+            out,
+            "<li class=\"quiz-order-item\" data-quiz-item data-position=\"{position}\">\
+               <span class=\"quiz-order-text\">{text}</span>\
+               <span class=\"quiz-order-moves\">\
+                 <button type=\"button\" class=\"quiz-order-move\" data-quiz-move=\"-1\" \
+                         aria-label=\"Move up\">↑</button>\
+                 <button type=\"button\" class=\"quiz-order-move\" data-quiz-move=\"1\" \
+                         aria-label=\"Move down\">↓</button>\
+               </span>\
+             </li>",
+            text = render_inline_markdown(&q.items[position]),
+        );
+    }
+    out.push_str("</ol>");
+    let _ = write!(out, "<p class=\"quiz-actions\">{CHECK_BUTTON}</p>");
+}
+
+/// The program with one button per line; `data-correct` marks the line
+/// that fails. Blank lines aren't clickable.
+fn render_compile_error(out: &mut String, q: &Question) {
+    use std::fmt::Write;
+    let failing = q.line.unwrap_or_default();
+    out.push_str(
+        "<p class=\"quiz-actions-note\">Click the line that doesn't compile.</p>\
+         <ol class=\"quiz-code quiz-code-lines\">",
+    );
+    for (i, line) in q
+        .code
+        .as_deref()
+        .unwrap_or_default()
+        .trim_end()
+        .lines()
+        .enumerate()
+    {
+        let n = i + 1;
+        if line.trim().is_empty() {
+            let _ = write!(
+                out,
+                "<li class=\"quiz-code-line is-blank\"><span class=\"quiz-code-num\">{n}</span></li>"
+            );
+            continue;
+        }
+        let _ = write!(
+            out,
+            "<li><button type=\"button\" class=\"quiz-code-line\" data-quiz-line=\"{n}\" \
+                         data-correct=\"{correct}\">\
+               <span class=\"quiz-code-num\">{n}</span><code>{text}</code>\
+             </button></li>",
+            correct = n == failing,
+            text = escape_attr(line),
+        );
+    }
+    out.push_str("</ol>");
+}
+
 /// Render a short markdown snippet (one paragraph) and strip the
 /// outer `<p>…</p>` so the result can be spliced inline.
 fn render_inline_markdown(md: &str) -> String {
@@ -186,6 +459,8 @@ pub struct Note {
     pub title: String,
     /// Rendered HTML body (everything after the H1).
     pub html: String,
+    /// Markdown source of the same body, for `cargo course read`.
+    pub markdown: String,
 }
 
 /// An exercise file: prose + starter code + tests, rendered as one
@@ -213,6 +488,9 @@ pub struct CodeStep {
     /// either the chapter has no hints at all, or the hints file has no
     /// section matching this step's slug."
     pub hints_html: Option<String>,
+    /// Markdown source of `hints_html`, which `cargo course hint` splits
+    /// with [`hint_items`] to reveal one hint at a time.
+    pub hints_markdown: Option<String>,
     /// Full source of the reference solution for this step, loaded from
     /// the sibling `solutions/<chapter>/<file>` tree (the `//!` inner-doc
     /// block stripped, mirroring `starter_code`). `None` when no solution
@@ -287,7 +565,7 @@ pub struct ChapterDirectives {
     /// the default for any chapter without a `.chapter.toml`. An explicit
     /// list (including the empty list) hides any button not named in
     /// it. See the `Buttons` map in `exercise.html` for valid names
-    /// (`run`, `submit`, `format`, `reset`, `copy`, `vscode`).
+    /// (`run`, `submit`, `format`, `clippy`, `reset`, `copy`, `vscode`).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub show: Option<Vec<String>>,
     /// Whether the page-wide table of contents (the chapter list at
@@ -316,6 +594,87 @@ pub struct ChapterDirectives {
     /// default.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bonus: Option<bool>,
+    /// rustc error codes (`E0382`) whose concept this chapter teaches.
+    /// `/explain/{code}` links back to every chapter listing the code,
+    /// so a learner reading the compiler's explanation can jump to the
+    /// lesson. Empty by default.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub error_codes: Vec<String>,
+    /// Chapters (directory names, e.g. `05_borrowing_and_references`)
+    /// a participant completes before this one opens. Names that match
+    /// no chapter are ignored rather than locking the chapter for good;
+    /// `lint-content` reports them. Empty by default.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+    /// When the chapter opens; see [`Unlock`]. `None` is
+    /// [`Unlock::Prerequisites`], which without `requires` means it is
+    /// always open.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unlock: Option<Unlock>,
+    /// How hard the chapter is, shown as a badge on the dashboard and
+    /// the chapter page. `None` shows no badge.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<Difficulty>,
+    /// Topics the chapter covers, in lowercase kebab-case
+    /// (`error-handling`). Shown as chips, and the dashboard and
+    /// `cargo course status --tag` filter on them. Empty by default.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// The `unlock` policy of a chapter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unlock {
+    /// Always open, whatever `requires` says.
+    Open,
+    /// Open once every chapter in `requires` is completed.
+    Prerequisites,
+    /// Like `Prerequisites`, and an instructor has also released the
+    /// chapter on `/admin`.
+    Release,
+}
+
+/// The `difficulty` of a chapter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Advanced,
+}
+
+impl Difficulty {
+    /// The name as written in `.chapter.toml`, also used as a CSS class.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Beginner => "beginner",
+            Self::Intermediate => "intermediate",
+            Self::Advanced => "advanced",
+        }
+    }
+
+    /// The name as the badge shows it.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Beginner => "Beginner",
+            Self::Intermediate => "Intermediate",
+            Self::Advanced => "Advanced",
+        }
+    }
+}
+
+/// What still stands between a participant and a locked chapter; see
+/// [`Exercise::lock`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lock {
+    /// `file_stem`s of the required chapters not completed yet, in the
+    /// order `requires` lists them.
+    pub missing: Vec<String>,
+    /// The chapter waits for an instructor to release it.
+    pub awaiting_release: bool,
 }
 
 /// A single chapter, parsed from one `examples/NN_slug/` directory.
@@ -356,6 +715,35 @@ impl Exercise {
         self.slug.contains("quiz")
     }
 
+    /// The chapter's `quiz.toml`, if it has one.
+    #[must_use]
+    pub fn quiz(&self) -> Option<&Quiz> {
+        self.steps.iter().find_map(|step| match step {
+            Step::Quiz(quiz) => Some(quiz),
+            _ => None,
+        })
+    }
+
+    /// Can a participant complete this chapter? Chapters with code steps
+    /// can, and so can a quiz with a [`Quiz::pass_mark`]; notes-only
+    /// chapters and practice quizzes can't, and stay out of progress.
+    #[must_use]
+    pub fn is_completable(&self) -> bool {
+        !self.code_steps().is_empty() || self.quiz().is_some_and(|quiz| quiz.pass_mark.is_some())
+    }
+
+    /// Does `.chapter.toml` list `code` (canonical, e.g. `E0382`) under
+    /// `error_codes`? Entries are compared after
+    /// [`normalize_code`](crate::explain::normalize_code), so `e382` in
+    /// the file still matches.
+    #[must_use]
+    pub fn teaches(&self, code: &str) -> bool {
+        self.directives
+            .error_codes
+            .iter()
+            .any(|listed| crate::explain::normalize_code(listed).as_deref() == Some(code))
+    }
+
     /// True for optional "bonus" chapters (`bonus = true` in
     /// `.chapter.toml`). Bonus chapters are hidden from the TOC, the
     /// chapter picker, progress totals, and the next-chapter flow, but
@@ -365,6 +753,61 @@ impl Exercise {
         self.directives.bonus.unwrap_or(false)
     }
 
+    /// The chapter's [`ChapterDirectives::difficulty`].
+    #[must_use]
+    pub const fn difficulty(&self) -> Option<Difficulty> {
+        self.directives.difficulty
+    }
+
+    /// The chapter's [`ChapterDirectives::tags`].
+    #[must_use]
+    pub fn tags(&self) -> &[String] {
+        &self.directives.tags
+    }
+
+    /// Does `.chapter.toml` list `tag` under `tags`?
+    #[must_use]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.directives.tags.iter().any(|listed| listed == tag)
+    }
+
+    /// The chapter's [`Unlock`] policy, defaulted.
+    #[must_use]
+    pub fn unlock(&self) -> Unlock {
+        self.directives.unlock.unwrap_or(Unlock::Prerequisites)
+    }
+
+    /// Whether this chapter is locked for a participant who has
+    /// completed the chapters for which `completed(file_stem)` holds,
+    /// and what they still need if so. `released` says whether an
+    /// instructor has released the chapter. Requirements that name no
+    /// chapter in `catalog` (the chapter's course) don't count.
+    #[must_use]
+    pub fn lock(
+        &self,
+        catalog: &[Self],
+        completed: impl Fn(&str) -> bool,
+        released: bool,
+    ) -> Option<Lock> {
+        let unlock = self.unlock();
+        if unlock == Unlock::Open {
+            return None;
+        }
+        let missing: Vec<String> = self
+            .directives
+            .requires
+            .iter()
+            .filter(|name| catalog.iter().any(|e| &e.file_stem == *name))
+            .filter(|name| !completed(name))
+            .cloned()
+            .collect();
+        let awaiting_release = unlock == Unlock::Release && !released;
+        (!missing.is_empty() || awaiting_release).then_some(Lock {
+            missing,
+            awaiting_release,
+        })
+    }
+
     /// All code steps in render order. Useful for progress calculations.
     #[must_use]
     pub fn code_steps(&self) -> Vec<&CodeStep> {
@@ -431,10 +874,10 @@ impl Exercise {
     }
 
     /// Convenience for templates: should the named editor button
-    /// (`run`, `submit`, `format`, `reset`, `copy`, `vim`, `vscode`)
-    /// render in this chapter? When `.chapter.toml` doesn't set
-    /// `show`, every button is visible (the default). When `show` is
-    /// an explicit allow-list, only the names in it survive.
+    /// (`run`, `submit`, `format`, `clippy`, `reset`, `copy`, `vim`,
+    /// `vscode`) render in this chapter? When `.chapter.toml` doesn't
+    /// set `show`, every button is visible (the default). When `show`
+    /// is an explicit allow-list, only the names in it survive.
     /// Mirrored on the server so the welcome chapter doesn't flash a
     /// full toolbar and then strip it down to just Run after JS boots.
     #[must_use]
@@ -521,12 +964,73 @@ pub enum RenderKind {
     Quiz { html: String },
 }
 
+/// Chapters parsed by [`scan_chapters`], plus the chapter directories
+/// that failed to parse.
+#[derive(Debug)]
+pub struct Scan {
+    pub exercises: Vec<Exercise>,
+    pub errors: Vec<ScanError>,
+}
+
+/// A chapter directory [`scan_chapters`] had to skip.
+#[derive(Debug)]
+pub struct ScanError {
+    pub chapter: PathBuf,
+    /// Why. A [`ContentError`] somewhere in the chain says which file
+    /// and line.
+    pub error: anyhow::Error,
+}
+
+impl std::fmt::Display for ScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {:#}", self.chapter.display(), self.error)
+    }
+}
+
+/// A problem pinned to a file, and to a line when we know it. Raised
+/// by the parser where the position is cheap to recover (quiz files,
+/// `.rs` files syn can't parse) and collected by [`crate::lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentError {
+    pub path: PathBuf,
+    /// 1-based.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for ContentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{line}: {}", self.path.display(), self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+impl std::error::Error for ContentError {}
+
+/// 1-based line number of byte `offset` in `src`.
+#[must_use]
+pub fn line_at(src: &str, offset: usize) -> usize {
+    src.get(..offset).unwrap_or(src).matches('\n').count() + 1
+}
+
 /// Scan a directory for `NN_slug/` chapter dirs and parse each one.
 ///
 /// Directories whose name does not match `^\d+_` are skipped. Chapters
 /// missing a `main.rs` are logged and skipped, so a single malformed
 /// chapter does not take down the whole server.
 pub fn scan_dir(dir: &Path) -> Result<Vec<Exercise>> {
+    let scan = scan_chapters(dir)?;
+    for error in &scan.errors {
+        log::warn!("Skipping {error}");
+    }
+    Ok(scan.exercises)
+}
+
+/// [`scan_dir`], but hand back the chapters that failed instead of
+/// logging them, so a reload can keep the live version of those.
+pub fn scan_chapters(dir: &Path) -> Result<Scan> {
     if !dir.exists() {
         return Err(anyhow!("Examples directory not found: {}", dir.display()));
     }
@@ -553,21 +1057,32 @@ pub fn scan_dir(dir: &Path) -> Result<Vec<Exercise>> {
     let solutions_root = solutions_root.is_dir().then_some(solutions_root);
 
     let mut out = Vec::with_capacity(chapter_dirs.len());
+    let mut errors = Vec::new();
     for chapter_dir in chapter_dirs {
         match parse_chapter(&chapter_dir, solutions_root.as_deref()) {
             Ok(ex) => out.push(ex),
-            Err(e) => {
-                log::warn!("Skipping {}: {e:#}", chapter_dir.display());
-            }
+            Err(error) => errors.push(ScanError {
+                chapter: chapter_dir,
+                error,
+            }),
         }
     }
 
-    // Assign human-facing chapter numbers as a running ordinal over the
-    // non-bonus chapters (in disk order), so hiding a bonus chapter leaves
-    // no gap. Bonus chapters get `0`, which templates render as "Bonus".
-    // This overrides the provisional `prefix + 1` set in `parse_chapter`.
+    number_chapters(&mut out);
+
+    Ok(Scan {
+        exercises: out,
+        errors,
+    })
+}
+
+/// Assign human-facing chapter numbers as a running ordinal over the
+/// non-bonus chapters (in disk order), so hiding a bonus chapter leaves
+/// no gap. Bonus chapters get `0`, which templates render as "Bonus".
+/// This overrides the provisional `prefix + 1` set in `parse_chapter`.
+pub(crate) fn number_chapters(chapters: &mut [Exercise]) {
     let mut display = 0u8;
-    for ex in &mut out {
+    for ex in chapters {
         if ex.is_bonus() {
             ex.number = 0;
         } else {
@@ -575,13 +1090,45 @@ pub fn scan_dir(dir: &Path) -> Result<Vec<Exercise>> {
             ex.number = display;
         }
     }
+}
 
-    Ok(out)
+/// Look up the code step a `submissions.exercise_name` refers to:
+/// `<chapter_file_stem>` for legacy single-step chapters, or
+/// `<chapter_file_stem>/<step_key>` for multi-step ones.
+#[must_use]
+pub fn find_code_step<'a>(catalog: &'a [Exercise], exercise_name: &str) -> Option<&'a CodeStep> {
+    let (chapter, step_key) = exercise_name.split_once('/').unwrap_or((exercise_name, ""));
+    catalog
+        .iter()
+        .find(|ex| ex.file_stem == chapter)?
+        .code_steps()
+        .into_iter()
+        .find(|step| step.key() == step_key)
 }
 
-/// Convenience: scan and wrap in `Arc` for `AppState`.
-pub fn load(dir: &Path) -> Result<Arc<Vec<Exercise>>> {
-    Ok(Arc::new(scan_dir(dir)?))
+/// Every tag used in `catalog`, sorted and without duplicates.
+#[must_use]
+pub fn catalog_tags(catalog: &[Exercise]) -> Vec<&str> {
+    let mut tags: Vec<&str> = catalog
+        .iter()
+        .flat_map(|ex| ex.tags().iter().map(String::as_str))
+        .collect();
+    tags.sort_unstable();
+    tags.dedup();
+    tags
+}
+
+/// Is `tag` lowercase kebab-case (`error-handling`), the shape it needs
+/// to sit in a URL and on the command line unquoted?
+#[must_use]
+pub fn valid_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.split('-').all(|word| {
+            !word.is_empty()
+                && word
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        })
 }
 
 fn parse_chapter(dir: &Path, solutions_root: Option<&Path>) -> Result<Exercise> {
@@ -723,31 +1270,146 @@ fn load_chapter_quiz(dir: &Path) -> Result<Option<Quiz>> {
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
         Err(e) => return Err(anyhow!("reading {}: {e}", path.display())),
     };
-    let quiz: Quiz = toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?;
+    let located = |line: Option<usize>, message: String| ContentError {
+        path: path.clone(),
+        line,
+        message,
+    };
+    let quiz: Quiz = toml::from_str(&raw).map_err(|e| {
+        located(
+            e.span().map(|span| line_at(&raw, span.start)),
+            e.message().to_string(),
+        )
+    })?;
     if quiz.questions.is_empty() {
-        return Err(anyhow!("{} has no questions", path.display()));
+        return Err(located(None, "no questions".to_string()).into());
     }
+    if quiz
+        .pass_mark
+        .is_some_and(|mark| !(1..=100).contains(&mark))
+    {
+        let line = raw
+            .lines()
+            .position(|line| line.trim_start().starts_with("pass_mark"))
+            .map(|i| i + 1);
+        return Err(located(
+            line,
+            "`pass_mark` must be a percentage from 1 to 100".to_string(),
+        )
+        .into());
+    }
+    // `[[questions]]` headers in file order, to point at the question.
+    let question_lines: Vec<usize> = raw
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.trim() == "[[questions]]")
+        .map(|(i, _)| i + 1)
+        .collect();
     for (i, q) in quiz.questions.iter().enumerate() {
-        let correct = q.answers.iter().filter(|a| a.correct).count();
-        if correct != 1 {
-            return Err(anyhow!(
-                "{}: question {} has {correct} correct answers (need exactly 1)",
-                path.display(),
-                i + 1,
-            ));
-        }
-        if q.answers.len() < 2 {
-            return Err(anyhow!(
-                "{}: question {} has only {} answer(s); need at least 2",
-                path.display(),
-                i + 1,
-                q.answers.len(),
-            ));
+        if let Some(problem) = question_problem(q) {
+            return Err(located(
+                question_lines.get(i).copied(),
+                format!("question {} {problem}", i + 1),
+            )
+            .into());
         }
     }
     Ok(Some(quiz))
 }
 
+/// `load_chapter_quiz`'s checks for one question, by type: what's
+/// wrong with it, phrased to follow "question N ", or `None` if it can
+/// be rendered.
+fn question_problem(q: &Question) -> Option<String> {
+    let kind = q.kind.as_str();
+    let unused = [
+        (
+            "answers",
+            !q.answers.is_empty(),
+            matches!(q.kind, QuestionKind::Single | QuestionKind::Multiple),
+        ),
+        (
+            "code",
+            q.code.is_some(),
+            matches!(q.kind, QuestionKind::Output | QuestionKind::CompileError),
+        ),
+        (
+            "accept",
+            !q.accept.is_empty(),
+            q.kind == QuestionKind::Output,
+        ),
+        ("items", !q.items.is_empty(), q.kind == QuestionKind::Order),
+        (
+            "line",
+            q.line.is_some(),
+            q.kind == QuestionKind::CompileError,
+        ),
+        (
+            "explanation",
+            q.explanation.is_some(),
+            !matches!(q.kind, QuestionKind::Single | QuestionKind::Multiple),
+        ),
+    ];
+    if let Some((field, _, _)) = unused.iter().find(|(_, set, applies)| *set && !applies) {
+        return Some(format!("sets `{field}`, which {kind} questions don't use"));
+    }
+    let code = q.code.as_deref().unwrap_or_default();
+    match q.kind {
+        QuestionKind::Single | QuestionKind::Multiple => {
+            let correct = q.answers.iter().filter(|a| a.correct).count();
+            if q.kind == QuestionKind::Single && correct != 1 {
+                return Some(format!("has {correct} correct answers (need exactly 1)"));
+            }
+            if correct == 0 {
+                return Some("has no correct answers (need at least 1)".to_string());
+            }
+            (q.answers.len() < 2)
+                .then(|| format!("has only {} answer(s); need at least 2", q.answers.len()))
+        }
+        QuestionKind::Output => {
+            if code.trim().is_empty() {
+                Some("has no `code` to run".to_string())
+            } else if !q.accept.iter().any(|a| !normalize_output(a).is_empty()) {
+                Some("has no `accept`ed output".to_string())
+            } else {
+                None
+            }
+        }
+        QuestionKind::Order => {
+            let distinct: std::collections::HashSet<&str> =
+                q.items.iter().map(|item| item.trim()).collect();
+            if q.items.len() < 2 {
+                Some(format!(
+                    "has only {} item(s) to order; need at least 2",
+                    q.items.len()
+                ))
+            } else if distinct.len() != q.items.len() {
+                Some("lists the same item twice".to_string())
+            } else {
+                None
+            }
+        }
+        QuestionKind::CompileError => {
+            let lines = code.lines().count();
+            match q.line {
+                _ if code.trim().is_empty() => Some("has no `code` to show".to_string()),
+                None => Some("doesn't say which `line` fails".to_string()),
+                Some(line)
+                    if code
+                        .lines()
+                        .nth(line.wrapping_sub(1))
+                        .is_none_or(|text| text.trim().is_empty()) =>
+                {
+                    Some(format!(
+                        "points at line {line}, which isn't a line of code (1-{lines})"
+                    ))
+                }
+                Some(_) => None,
+            }
+        }
+    }
+}
+
 /// Read `.chapter.toml` from a chapter directory if present.
 ///
 /// A missing file is not an error; it just yields the default
@@ -764,7 +1426,22 @@ fn load_chapter_directives(dir: &Path) -> ChapterDirectives {
         }
     };
     match toml::from_str::<ChapterDirectives>(&raw) {
-        Ok(d) => d,
+        Ok(d) => {
+            for code in &d.error_codes {
+                if crate::explain::normalize_code(code).is_none() {
+                    log::warn!("{}: `{code}` is not an error code", path.display());
+                }
+            }
+            for tag in &d.tags {
+                if !valid_tag(tag) {
+                    log::warn!(
+                        "{}: tag `{tag}` is not lowercase kebab-case",
+                        path.display()
+                    );
+                }
+            }
+            d
+        }
         Err(e) => {
             log::warn!("parsing {}: {e}", path.display());
             ChapterDirectives::default()
@@ -820,6 +1497,7 @@ fn apply_hints(
             .find(|(i, (heading, _))| !consumed[*i] && heading_matches_slug(heading, &code.slug))
         {
             code.hints_html = Some(render_markdown(body));
+            code.hints_markdown = Some(body.clone());
             consumed[sec_idx] = true;
         }
     }
@@ -845,12 +1523,13 @@ fn apply_hints(
         (steps, None)
     } else {
         note.html = render_markdown(&leftover);
+        note.markdown = leftover;
         (steps, Some(note))
     }
 }
 
 /// Locate the `<n>_hints.md` file in a chapter dir, if present.
-fn find_hints_path(chapter_dir: &Path) -> Option<PathBuf> {
+pub(crate) fn find_hints_path(chapter_dir: &Path) -> Option<PathBuf> {
     std::fs::read_dir(chapter_dir)
         .ok()?
         .filter_map(std::result::Result::ok)
@@ -902,7 +1581,7 @@ fn split_hints_markdown(md: &str) -> (String, Vec<(String, String)>) {
 /// whole heading text is used. This lets authors write descriptive
 /// headings like `` `quoted_line`, the state machine `` while still
 /// keying off the file slug.
-fn heading_matches_slug(heading: &str, step_slug: &str) -> bool {
+pub(crate) fn heading_matches_slug(heading: &str, step_slug: &str) -> bool {
     let key = heading.find('`').map_or_else(
         || heading.trim(),
         |start| {
@@ -926,7 +1605,11 @@ fn parse_code_file(
 ) -> Result<(CodeStep, String)> {
     let starter_code_full =
         std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
-    let intro_md = extract_inner_doc(&starter_code_full)?;
+    let intro_md = extract_inner_doc(&starter_code_full).map_err(|e| ContentError {
+        path: path.to_path_buf(),
+        line: Some(e.span().start().line),
+        message: format!("parsing file with syn: {e}"),
+    })?;
     let (title_opt, _body_md) = split_title(&intro_md);
     let title = title_opt.unwrap_or_else(|| fallback_title.to_string());
     let starter_code = trim_trailing_blank_lines(&strip_inner_doc(&starter_code_full));
@@ -938,6 +1621,7 @@ fn parse_code_file(
             title: title.clone(),
             starter_code,
             hints_html: None,
+            hints_markdown: None,
             solution_code: None,
         },
         title,
@@ -1027,6 +1711,7 @@ fn parse_note(path: &Path) -> Result<Note> {
         slug,
         title,
         html,
+        markdown: body_md,
     })
 }
 
@@ -1070,7 +1755,7 @@ fn strip_inner_doc(source: &str) -> String {
 }
 
 /// Split `02_strings_and_chars` into `(2, "strings_and_chars")`.
-fn split_numeric_prefix(stem: &str) -> Option<(u8, String)> {
+pub(crate) fn split_numeric_prefix(stem: &str) -> Option<(u8, String)> {
     let (num, rest) = stem.split_once('_')?;
     let n: u8 = num.parse().ok()?;
     Some((n, rest.to_string()))
@@ -1078,8 +1763,8 @@ fn split_numeric_prefix(stem: &str) -> Option<(u8, String)> {
 
 /// Use `syn` to extract the file's inner doc attributes (`//!` and
 /// `/*! */`) joined into a single Markdown string.
-fn extract_inner_doc(source: &str) -> Result<String> {
-    let file: syn::File = syn::parse_file(source).context("parsing file with syn")?;
+fn extract_inner_doc(source: &str) -> syn::Result<String> {
+    let file: syn::File = syn::parse_file(source)?;
 
     let mut lines: Vec<String> = Vec::new();
     for attr in &file.attrs {
@@ -1174,6 +1859,219 @@ pub fn render_markdown(md: &str) -> String {
     wrap_alert_sections(&out)
 }
 
+/// Split a step's hints markdown into the hints `cargo course hint`
+/// reveals one at a time.
+///
+/// Each item of a top-level list is one hint, and any other top-level
+/// block (a paragraph, a code block) is a hint of its own. List markers
+/// are dropped, so the CLI can number the hints itself.
+#[must_use]
+pub fn hint_items(md: &str) -> Vec<String> {
+    use pulldown_cmark::{Event, Options, Parser, Tag};
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    for (event, range) in Parser::new_ext(md, Options::ENABLE_GFM).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Item) if depth == 1 => {
+                items.push(strip_list_marker(&md[range]));
+            }
+            Event::Start(Tag::List(_)) if depth == 0 => {}
+            Event::Start(_) if depth == 0 => items.push(md[range].trim().to_string()),
+            _ => {}
+        }
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+    }
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+/// Drop a list item's `1. ` / `- ` marker and the matching indentation
+/// of its continuation lines.
+fn strip_list_marker(item: &str) -> String {
+    let item = item.trim_end();
+    let body = item.trim_start_matches(|c: char| c.is_ascii_digit());
+    let body = body
+        .strip_prefix(['.', ')', '-', '*', '+'])
+        .unwrap_or(body)
+        .trim_start_matches(' ');
+    let width = item.len() - body.len();
+    let indent = " ".repeat(width);
+    body.lines()
+        .map(|line| line.strip_prefix(indent.as_str()).unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render markdown as plain terminal text for the CLI.
+///
+/// Headings are underlined, list items get bullets or numbers, code
+/// blocks are indented and quotes are prefixed with a bar. Inline code
+/// keeps its backticks; links are followed by their URL when it differs
+/// from the link text.
+#[must_use]
+pub fn render_terminal(md: &str) -> String {
+    use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+    let mut opts = Options::empty();
+    opts.insert(Options::ENABLE_TABLES);
+    opts.insert(Options::ENABLE_GFM);
+
+    let mut out = TerminalText::default();
+    // `Some(n)` for an ordered list's next number, `None` for bullets.
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut heading: Option<(HeadingLevel, usize)> = None;
+    let mut links: Vec<(String, usize)> = Vec::new();
+    for event in Parser::new_ext(md, opts) {
+        match event {
+            Event::Start(Tag::Paragraph | Tag::Table(_)) => out.gap(),
+            Event::Start(Tag::Heading { level, .. }) => {
+                out.gap();
+                heading = Some((level, out.text.len()));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, start)) = heading.take() {
+                    let width = out.text[start..].chars().count();
+                    let rule = if level == HeadingLevel::H1 { "=" } else { "-" };
+                    out.end_line();
+                    out.write(&rule.repeat(width));
+                }
+                out.end_line();
+            }
+            Event::Start(Tag::BlockQuote(kind)) => {
+                out.gap();
+                out.prefix.push("│ ".to_string());
+                if let Some(kind) = kind {
+                    out.write(&format!("{kind:?}"));
+                    out.end_line();
+                    out.blank = true;
+                }
+            }
+            Event::End(TagEnd::BlockQuote(_) | TagEnd::Item) => {
+                out.end_line();
+                out.prefix.pop();
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                out.gap();
+                out.prefix.push("    ".to_string());
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                out.prefix.pop();
+            }
+            Event::Start(Tag::List(first)) => {
+                if lists.is_empty() {
+                    out.gap();
+                } else {
+                    out.end_line();
+                }
+                lists.push(first);
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                out.end_line();
+            }
+            Event::Start(Tag::Item) => {
+                out.end_line();
+                let marker = match lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                out.write(&marker);
+                out.prefix.push(" ".repeat(marker.chars().count()));
+                out.fresh_item = true;
+            }
+            Event::Start(Tag::TableCell) if !out.at_line_start => out.write(" | "),
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                links.push((dest_url.to_string(), out.text.len()));
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some((url, start)) = links.pop()
+                    && out.text[start..] != *url
+                {
+                    out.write(&format!(" ({url})"));
+                }
+            }
+            Event::Text(text) => out.write(&text),
+            Event::Code(code) => out.write(&format!("`{code}`")),
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(TagEnd::TableHead | TagEnd::TableRow) => out.end_line(),
+            Event::Rule => {
+                out.gap();
+                out.write("────────");
+                out.end_line();
+            }
+            Event::TaskListMarker(done) => out.write(if done { "[x] " } else { "[ ] " }),
+            _ => {}
+        }
+    }
+    out.end_line();
+    out.text
+}
+
+/// Output buffer for [`render_terminal`]: writes each new line's prefix
+/// (quote bars, list and code indentation) before its first character.
+#[derive(Default)]
+struct TerminalText {
+    text: String,
+    prefix: Vec<String>,
+    at_line_start: bool,
+    /// The last line written was blank.
+    blank: bool,
+    /// Just wrote a list marker; the item's first block joins its line.
+    fresh_item: bool,
+}
+
+impl TerminalText {
+    fn write(&mut self, text: &str) {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 && self.at_line_start {
+                // A blank line inside a code block.
+                self.text.push_str(self.prefix.concat().trim_end());
+                self.text.push('\n');
+                self.blank = true;
+            } else if i > 0 {
+                self.end_line();
+            }
+            if line.is_empty() {
+                continue;
+            }
+            if self.at_line_start || self.text.is_empty() {
+                self.text.push_str(&self.prefix.concat());
+                self.at_line_start = false;
+            }
+            self.text.push_str(line);
+            self.blank = false;
+            self.fresh_item = false;
+        }
+    }
+
+    fn end_line(&mut self) {
+        if !self.at_line_start && !self.text.is_empty() {
+            self.text.push('\n');
+            self.at_line_start = true;
+        }
+    }
+
+    /// Separate the next block from the previous one by a blank line.
+    fn gap(&mut self) {
+        if self.fresh_item {
+            return;
+        }
+        self.end_line();
+        if !self.text.is_empty() && !self.blank {
+            self.text.push_str(self.prefix.concat().trim_end());
+            self.text.push('\n');
+            self.blank = true;
+        }
+    }
+}
+
 /// Headings whose body (the immediately-following `<ul>...</ul>`) should
 /// be wrapped in a NOTE-style blockquote. The H2 stays inside the
 /// blockquote and acts as the label, so all three render with the same
@@ -1232,7 +2130,7 @@ fn wrap_one_alert_section(html: &str, heading: &str) -> String {
     out
 }
 
-fn is_external(url: &str) -> bool {
+pub(crate) fn is_external(url: &str) -> bool {
     url.starts_with("http://") || url.starts_with("https://")
 }
 
@@ -1302,6 +2200,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn chapter_error_codes_are_valid_and_link_back() {
+        let exercises =
+            scan_dir(Path::new("examples")).expect("examples dir should exist when running tests");
+
+        for ex in &exercises {
+            for code in &ex.directives.error_codes {
+                assert!(
+                    crate::explain::normalize_code(code).is_some(),
+                    "{}: `{code}` is not an error code",
+                    ex.file_stem
+                );
+            }
+        }
+        let teaching: Vec<&str> = crate::explain::chapters_teaching(&exercises, "E0382")
+            .map(|ex| ex.slug.as_str())
+            .collect();
+        assert_eq!(teaching, ["moves_and_copy"]);
+    }
+
     #[test]
     fn bonus_chapters_are_unnumbered_and_excluded_from_the_sequence() {
         let exercises =
@@ -1331,6 +2249,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn chapters_carry_difficulty_and_tags() {
+        let exercises =
+            scan_dir(Path::new("examples")).expect("examples dir should exist when running tests");
+        let chapter = exercises
+            .iter()
+            .find(|e| e.file_stem == "13_question_mark_operator")
+            .expect("the `?` chapter should be present");
+        assert_eq!(chapter.difficulty(), Some(Difficulty::Beginner));
+        assert!(chapter.has_tag("error-handling"));
+        assert!(!chapter.has_tag("error"));
+
+        let tags = catalog_tags(&exercises);
+        assert!(tags.windows(2).all(|pair| pair[0] < pair[1]), "{tags:?}");
+        assert!(tags.contains(&"error-handling"));
+
+        for tag in ["result", "error-handling", "utf8"] {
+            assert!(valid_tag(tag), "{tag}");
+        }
+        for tag in [
+            "",
+            "Error",
+            "error handling",
+            "error--handling",
+            "-error",
+            "error_handling",
+        ] {
+            assert!(!valid_tag(tag), "{tag}");
+        }
+    }
+
+    #[test]
+    fn chapters_lock_until_prerequisites_and_release() {
+        let mut exercises =
+            scan_dir(Path::new("examples")).expect("examples dir should exist when running tests");
+        let target = exercises
+            .iter()
+            .position(|e| e.file_stem == "06_word_count")
+            .expect("expected 06_word_count to be present");
+        exercises[target].directives =
+            toml::from_str(r#"requires = ["05_borrowing_and_references", "99_no_such_chapter"]"#)
+                .expect("directives should parse");
+        let chapter = &exercises[target];
+        let nothing_done = |_: &str| false;
+        let everything_done = |_: &str| true;
+
+        // The unknown requirement is ignored; the real one is missing.
+        assert_eq!(
+            chapter.lock(&exercises, nothing_done, false),
+            Some(Lock {
+                missing: vec!["05_borrowing_and_references".to_string()],
+                awaiting_release: false,
+            })
+        );
+        assert_eq!(chapter.lock(&exercises, everything_done, false), None);
+
+        let mut released_only = chapter.clone();
+        released_only.directives.unlock = Some(Unlock::Release);
+        assert_eq!(
+            released_only.lock(&exercises, everything_done, false),
+            Some(Lock {
+                missing: Vec::new(),
+                awaiting_release: true,
+            })
+        );
+        assert_eq!(released_only.lock(&exercises, everything_done, true), None);
+
+        let mut open = chapter.clone();
+        open.directives.unlock = Some(Unlock::Open);
+        assert_eq!(open.lock(&exercises, nothing_done, false), None);
+
+        // Chapters without directives are always open.
+        assert!(
+            exercises
+                .iter()
+                .filter(|e| e.directives.requires.is_empty() && e.directives.unlock.is_none())
+                .all(|e| e.lock(&exercises, nothing_done, false).is_none())
+        );
+    }
+
     #[test]
     fn attaches_reference_solutions_from_sibling_tree() {
         // `scan_dir` derives the `solutions/` tree as a sibling of the
@@ -1363,6 +2361,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn finds_code_steps_by_submission_key() {
+        let exercises =
+            scan_dir(Path::new("examples")).expect("examples dir should exist when running tests");
+        let step = find_code_step(&exercises, "00_integers/3_add_health")
+            .expect("expected 00_integers/3_add_health to resolve");
+        assert_eq!(step.slug, "add_health");
+        assert!(find_code_step(&exercises, "00_integers/9_missing").is_none());
+        assert!(find_code_step(&exercises, "99_nope").is_none());
+    }
+
     #[test]
     fn discovers_chapter_notes() {
         // The first chapter (`00_integers`) ships with an introductory
@@ -1440,14 +2449,106 @@ mod tests {
             "quiz should have a reasonable number of questions"
         );
         for (i, q) in quiz.questions.iter().enumerate() {
-            let correct = q.answers.iter().filter(|a| a.correct).count();
             assert_eq!(
-                correct,
-                1,
-                "question {} should have exactly one correct answer",
+                question_problem(q),
+                None,
+                "question {} should be valid",
                 i + 1
             );
         }
+        let html = quiz.render_html();
+        for kind in ["single", "multiple", "output", "order", "compile_error"] {
+            assert!(
+                html.contains(&format!("data-quiz-type=\"{kind}\"")),
+                "the sample quiz should show a {kind} question"
+            );
+        }
+    }
+
+    fn question(toml_src: &str) -> Question {
+        toml::from_str(toml_src).expect("question should parse")
+    }
+
+    #[test]
+    fn normalize_output_ignores_trailing_whitespace_and_blank_edges() {
+        assert_eq!(normalize_output("\n\n6  \r\nSome(3)\t\n\n"), "6\nSome(3)");
+        assert_eq!(normalize_output("a\n\n  b"), "a\n\n  b");
+        assert_eq!(normalize_output(" \n \n"), "");
+    }
+
+    #[test]
+    fn question_problem_checks_each_type() {
+        let valid = [
+            r#"answers = [{ explanation = "e", text = "a", correct = true }, { explanation = "e", text = "b", correct = false }]"#,
+            r#"type = "multiple"
+               answers = [{ explanation = "e", text = "a", correct = true }, { explanation = "e", text = "b", correct = true }]"#,
+            r#"type = "output"
+               code = "fn main() { println!(\"hi\"); }"
+               accept = ["hi"]"#,
+            r#"type = "order"
+               items = ["first", "second"]"#,
+            r#"type = "compile_error"
+               code = "fn main() {\n\n    let x: i32 = \"no\";\n}"
+               line = 3"#,
+        ];
+        for src in valid {
+            let q = question(&format!("prompt = \"p\"\n{src}"));
+            assert_eq!(question_problem(&q), None, "{src}");
+        }
+
+        let invalid = [
+            (
+                r#"answers = [{ explanation = "e", text = "a", correct = true }, { explanation = "e", text = "b", correct = true }]"#,
+                "has 2 correct answers (need exactly 1)",
+            ),
+            (
+                r#"type = "multiple"
+                   answers = [{ explanation = "e", text = "a", correct = false }, { explanation = "e", text = "b", correct = false }]"#,
+                "has no correct answers (need at least 1)",
+            ),
+            (
+                r#"type = "multiple"
+                   answers = [{ explanation = "e", text = "a", correct = true }]"#,
+                "has only 1 answer(s); need at least 2",
+            ),
+            (
+                r#"type = "output"
+                   code = "fn main() {}"
+                   accept = ["  ", ""]"#,
+                "has no `accept`ed output",
+            ),
+            (
+                r#"type = "output"
+                   accept = ["hi"]"#,
+                "has no `code` to run",
+            ),
+            (
+                r#"type = "order"
+                   items = ["a", "b", " a "]"#,
+                "lists the same item twice",
+            ),
+            (
+                r#"type = "compile_error"
+                   code = "fn main() {\n\n}"
+                   line = 2"#,
+                "points at line 2, which isn't a line of code (1-3)",
+            ),
+            (
+                r#"type = "compile_error"
+                   code = "fn main() {}""#,
+                "doesn't say which `line` fails",
+            ),
+            (
+                r#"type = "order"
+                   items = ["a", "b"]
+                   answers = [{ explanation = "e", text = "a", correct = true }]"#,
+                "sets `answers`, which order questions don't use",
+            ),
+        ];
+        for (src, expected) in invalid {
+            let q = question(&format!("prompt = \"p\"\n{src}"));
+            assert_eq!(question_problem(&q).as_deref(), Some(expected), "{src}");
+        }
     }
 
     #[test]
@@ -1544,6 +2645,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn hint_items_split_top_level_list_items() {
+        let md = "1. First, with `code`.\n2. Second\n   continues here.\n   - nested\n\nA closing paragraph.\n";
+        assert_eq!(
+            hint_items(md),
+            [
+                "First, with `code`.",
+                "Second\ncontinues here.\n- nested",
+                "A closing paragraph."
+            ]
+        );
+
+        let exercises = scan_dir(Path::new("examples")).unwrap();
+        let step = find_code_step(&exercises, "00_integers/4_damage_with_bonus").unwrap();
+        let hints = hint_items(step.hints_markdown.as_deref().unwrap());
+        assert_eq!(hints.len(), 3);
+        assert!(hints[0].starts_with("The maths is"));
+    }
+
+    #[test]
+    fn render_terminal_formats_blocks_as_plain_text() {
+        let md = "Intro with [docs](https://doc.rust-lang.org).\n\n## Steps\n\n1. one\n2. two\n\n```rust\nlet x = 1;\n\nlet y = 2;\n```\n\n> [!TIP]\n> Be brief.\n";
+        assert_eq!(
+            render_terminal(md),
+            "Intro with docs (https://doc.rust-lang.org).\n\nSteps\n-----\n\n1. one\n2. two\n\n    let x = 1;\n\n    let y = 2;\n\n│ Tip\n│ Be brief.\n"
+        );
+    }
+
     #[test]
     fn renamed_chapters_distribute_hints_per_step() {
         // 11_iterators and 17_csv_parser were renamed so each hints H2
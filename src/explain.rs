@@ -0,0 +1,124 @@
+//! Offline `rustc --explain` texts, served at `/explain/{code}`.
+//!
+//! Learners hitting `E0382` used to paste it into a search engine. The
+//! server instead ships the compiler's own long-form explanations: one
+//! Markdown file per code (`E0382.md`), dumped from the toolchain by
+//! `scripts/dump-error-codes.sh` (the Docker build runs it) and loaded
+//! once at startup. Nothing is fetched at request time.
+//!
+//! Chapters that teach the concept behind a code list it under
+//! `error_codes` in their `.chapter.toml` (see
+//! [`ChapterDirectives::error_codes`](crate::exercises::ChapterDirectives::error_codes)),
+//! so an explanation can link back into the course.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::exercises::Exercise;
+
+/// Directory the explanations are read from unless
+/// `CORRODE_ERROR_CODES_DIR` says otherwise.
+pub const DEFAULT_DIR: &str = "error_codes";
+
+/// Every explanation we have, keyed by canonical code (`E0382`).
+#[derive(Debug, Clone, Default)]
+pub struct Explanations {
+    texts: HashMap<String, String>,
+}
+
+impl Explanations {
+    /// Read every `Exxxx.md` in `dir`. A missing directory is not an
+    /// error (the server runs fine without explanations, `/explain`
+    /// just 404s); an unreadable one is.
+    pub fn load(dir: &Path) -> std::io::Result<Self> {
+        let mut texts = HashMap::new();
+        if !dir.is_dir() {
+            return Ok(Self { texts });
+        }
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "md") {
+                continue;
+            }
+            let Some(code) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(normalize_code)
+            else {
+                continue;
+            };
+            texts.insert(code, std::fs::read_to_string(&path)?);
+        }
+        Ok(Self { texts })
+    }
+
+    /// The Markdown explanation for a canonical `code`.
+    #[must_use]
+    pub fn get(&self, code: &str) -> Option<&str> {
+        self.texts.get(code).map(String::as_str)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+}
+
+/// Canonicalise a user-supplied code: `E0382`, `e0382` and `E382` all
+/// become `E0382`. Anything else is `None`.
+#[must_use]
+pub fn normalize_code(raw: &str) -> Option<String> {
+    let digits = raw.trim().strip_prefix(['E', 'e'])?;
+    if digits.is_empty() || digits.len() > 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("E{digits:0>4}"))
+}
+
+/// Chapters whose `.chapter.toml` lists `code` under `error_codes`, in
+/// course order.
+pub fn chapters_teaching<'a>(
+    catalog: &'a [Exercise],
+    code: &'a str,
+) -> impl Iterator<Item = &'a Exercise> {
+    catalog
+        .iter()
+        .filter(move |exercise| exercise.teaches(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_error_codes() {
+        assert_eq!(normalize_code("E0382").as_deref(), Some("E0382"));
+        assert_eq!(normalize_code(" e382 ").as_deref(), Some("E0382"));
+        assert_eq!(normalize_code("E5").as_deref(), Some("E0005"));
+        assert_eq!(normalize_code("E03820"), None);
+        assert_eq!(normalize_code("E"), None);
+        assert_eq!(normalize_code("0382"), None);
+        assert_eq!(normalize_code("E03x2"), None);
+    }
+
+    #[test]
+    fn loads_markdown_files_by_code() {
+        let dir = std::env::temp_dir().join(format!("corrode-explain-{}", ulid::Ulid::new()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("E0382.md"), "A value was used after move.\n").unwrap();
+        std::fs::write(dir.join("README.md"), "not a code").unwrap();
+        std::fs::write(dir.join("E0499.txt"), "wrong extension").unwrap();
+
+        let explanations = Explanations::load(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(explanations.len(), 1);
+        assert!(explanations.get("E0382").unwrap().contains("after move"));
+        assert!(Explanations::load(&dir.join("missing")).unwrap().is_empty());
+    }
+}
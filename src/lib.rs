@@ -1,2 +1,11 @@
+pub mod catalog;
+pub mod courses;
+pub mod db;
+pub mod diagnostics;
 pub mod exercises;
+pub mod explain;
+pub mod lint;
+pub mod runner;
+pub mod session;
 pub mod types;
+pub mod verify;
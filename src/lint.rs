@@ -0,0 +1,537 @@
+//! Content checks behind `cargo course lint-content`.
+//!
+//! [`scan_chapters`] skips a chapter it can't parse and otherwise stays
+//! quiet about content that parses but is wrong: a hints section that
+//! never reaches its step, a solution nobody can reveal, a link to a
+//! chapter that was renamed. [`lint`] collects all of those as
+//! [`ContentError`]s with a file and, where there is one, a line, so
+//! content PRs can be gated on an empty list.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use pulldown_cmark::{Event, Parser, Tag};
+
+use crate::exercises::{
+    self, ChapterDirectives, CodeStep, ContentError, Exercise, ScanError, Unlock,
+    heading_matches_slug, line_at, scan_chapters,
+};
+use crate::explain;
+
+/// Site pages a note may link to besides `/exercise/…` and `/explain/…`.
+const COURSE_PAGES: &[&str] = &["/", "/cheatsheet", "/playground", "/tour", "/signup"];
+
+/// Every problem in the course under `examples_dir` (and its sibling
+/// `solutions/`), ordered by file and line. Only a missing or
+/// unreadable `examples_dir` is an `Err`.
+pub fn lint(examples_dir: &Path) -> Result<Vec<ContentError>> {
+    let scan = scan_chapters(examples_dir)?;
+    let mut problems = Vec::new();
+
+    // Chapter directories on disk, parsed or not, so a skipped chapter
+    // doesn't also make its solutions look orphaned.
+    let mut chapter_names: HashSet<String> = HashSet::new();
+    for skipped in &scan.errors {
+        chapter_names.extend(dir_name(&skipped.chapter));
+        problems.push(skipped_chapter(skipped));
+    }
+    chapter_names.extend(scan.exercises.iter().map(|ex| ex.file_stem.clone()));
+
+    let solutions_root = examples_dir.with_file_name("solutions");
+    let solutions_root = solutions_root.is_dir().then_some(solutions_root);
+    for exercise in &scan.exercises {
+        let dir = examples_dir.join(&exercise.file_stem);
+        lint_hints(&dir, exercise, &mut problems);
+        lint_directives(
+            &dir,
+            exercise,
+            &scan.exercises,
+            &chapter_names,
+            &mut problems,
+        );
+        lint_links(&dir, &scan.exercises, &chapter_names, &mut problems);
+        if let Some(root) = &solutions_root {
+            lint_solutions(
+                &dir,
+                &root.join(&exercise.file_stem),
+                exercise,
+                &mut problems,
+            );
+        }
+    }
+    if let Some(root) = &solutions_root {
+        for dir in sorted_entries(root)
+            .into_iter()
+            .filter(|path| path.is_dir())
+        {
+            if dir_name(&dir).is_some_and(|name| !chapter_names.contains(&name)) {
+                problems.push(ContentError {
+                    path: dir,
+                    line: None,
+                    message: "solutions for a chapter that doesn't exist in examples/".to_string(),
+                });
+            }
+        }
+    }
+
+    problems.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    Ok(problems)
+}
+
+/// Where a skipped chapter went wrong: the file and line the parser
+/// pinned the error to, or the chapter directory when it didn't.
+fn skipped_chapter(skipped: &ScanError) -> ContentError {
+    skipped
+        .error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<ContentError>())
+        .map_or_else(
+            || ContentError {
+                path: skipped.chapter.clone(),
+                line: None,
+                message: format!("chapter skipped: {:#}", skipped.error),
+            },
+            |located| ContentError {
+                message: format!("chapter skipped: {}", located.message),
+                ..located.clone()
+            },
+        )
+}
+
+/// `## ` headings in the hints note that are keyed to a step that
+/// doesn't exist.
+///
+/// Unmatched sections are allowed: they render in the chapter-wide
+/// hints block. A heading whose first backticked token looks like a
+/// slug (`` ## `validate` ``) was meant for a step, though, so when no
+/// step has that slug it's a typo or a renamed file.
+fn lint_hints(dir: &Path, exercise: &Exercise, problems: &mut Vec<ContentError>) {
+    let Some(path) = exercises::find_hints_path(dir) else {
+        return;
+    };
+    let Some(md) = read(&path, problems) else {
+        return;
+    };
+    let steps = exercise.code_steps();
+    for (i, line) in md.lines().enumerate() {
+        let Some(heading) = line.strip_prefix("## ").map(str::trim_end) else {
+            continue;
+        };
+        let keyed = heading
+            .split('`')
+            .nth(1)
+            .is_some_and(|token| !token.is_empty() && token.chars().all(is_slug_char));
+        if keyed
+            && !steps
+                .iter()
+                .any(|step| heading_matches_slug(heading, &step.slug))
+        {
+            let slugs: Vec<&str> = steps.iter().map(|step| step.slug.as_str()).collect();
+            problems.push(ContentError {
+                path: path.clone(),
+                line: Some(i + 1),
+                message: format!(
+                    "hints heading \"{heading}\" matches no step (slugs: {})",
+                    slugs.join(", ")
+                ),
+            });
+        }
+    }
+}
+
+const fn is_slug_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'
+}
+
+/// `.chapter.toml` keys the server would reject, error codes that
+/// aren't codes, and `requires` entries the server ignores: unknown
+/// chapters, and requirements that come back round to the chapter, so
+/// it could never open. The server logs the first two and carries on
+/// with defaults.
+fn lint_directives(
+    dir: &Path,
+    exercise: &Exercise,
+    catalog: &[Exercise],
+    chapter_names: &HashSet<String>,
+    problems: &mut Vec<ContentError>,
+) {
+    let path = dir.join(".chapter.toml");
+    if !path.exists() {
+        return;
+    }
+    let Some(raw) = read(&path, problems) else {
+        return;
+    };
+    match toml::from_str::<ChapterDirectives>(&raw) {
+        Ok(directives) => {
+            for code in &directives.error_codes {
+                if explain::normalize_code(code).is_none() {
+                    problems.push(ContentError {
+                        line: raw.find(code.as_str()).map(|at| line_at(&raw, at)),
+                        path: path.clone(),
+                        message: format!("`{code}` is not an error code"),
+                    });
+                }
+            }
+            let line_of = |name: &str| raw.find(name).map(|at| line_at(&raw, at));
+            for tag in &directives.tags {
+                if !exercises::valid_tag(tag) {
+                    problems.push(ContentError {
+                        line: line_of(tag),
+                        path: path.clone(),
+                        message: format!("tag `{tag}` is not lowercase kebab-case"),
+                    });
+                }
+            }
+            for name in &directives.requires {
+                let message = if !chapter_names.contains(name) {
+                    format!("`requires` names unknown chapter `{name}`")
+                } else if exercise.unlock() != Unlock::Open
+                    && requirement_cycle(&exercise.file_stem, name, catalog)
+                {
+                    format!("`requires` cycle: `{name}` needs this chapter first")
+                } else {
+                    continue;
+                };
+                problems.push(ContentError {
+                    line: line_of(name),
+                    path: path.clone(),
+                    message,
+                });
+            }
+            if directives.unlock == Some(Unlock::Open) && !directives.requires.is_empty() {
+                problems.push(ContentError {
+                    line: line_of("unlock"),
+                    path: path.clone(),
+                    message: "`requires` has no effect with `unlock = \"open\"`".to_string(),
+                });
+            }
+        }
+        Err(err) => problems.push(ContentError {
+            line: err.span().map(|span| line_at(&raw, span.start)),
+            path,
+            message: err.message().to_string(),
+        }),
+    }
+}
+
+/// Does `name`, directly or through its own `requires`, require
+/// `chapter`? Open chapters and chapters that don't parse have no
+/// requirements.
+fn requirement_cycle(chapter: &str, name: &str, catalog: &[Exercise]) -> bool {
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut pending = vec![name];
+    while let Some(next) = pending.pop() {
+        if next == chapter {
+            return true;
+        }
+        if !seen.insert(next) {
+            continue;
+        }
+        if let Some(ex) = catalog
+            .iter()
+            .find(|ex| ex.file_stem == next && ex.unlock() != Unlock::Open)
+        {
+            pending.extend(ex.directives.requires.iter().map(String::as_str));
+        }
+    }
+    false
+}
+
+/// Links in the chapter's notes that point inside the course but at
+/// nothing: `/exercise/<slug>` for a chapter that doesn't exist, an
+/// `/explain/<code>` that isn't a code, or a relative path (notes
+/// render under `/exercise/…`, where relative links don't resolve).
+fn lint_links(
+    dir: &Path,
+    catalog: &[Exercise],
+    chapter_names: &HashSet<String>,
+    problems: &mut Vec<ContentError>,
+) {
+    for path in sorted_entries(dir) {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+            continue;
+        }
+        let Some(md) = read(&path, problems) else {
+            continue;
+        };
+        for (event, range) in Parser::new(&md).into_offset_iter() {
+            let Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) = event
+            else {
+                continue;
+            };
+            if let Some(message) = broken_link(&dest_url, catalog, chapter_names) {
+                problems.push(ContentError {
+                    path: path.clone(),
+                    line: Some(line_at(&md, range.start)),
+                    message,
+                });
+            }
+        }
+    }
+}
+
+/// Why `url` doesn't lead anywhere in the course, or `None` if it does
+/// (or leaves the course, which we don't check).
+fn broken_link(url: &str, catalog: &[Exercise], chapter_names: &HashSet<String>) -> Option<String> {
+    if url.starts_with('#') || exercises::is_external(url) || url.contains(':') {
+        return None;
+    }
+    let path = url.split(['#', '?']).next().unwrap_or_default();
+    if !path.starts_with('/') {
+        return Some(format!(
+            "relative link `{url}` doesn't resolve on the course site; use `/exercise/<slug>`"
+        ));
+    }
+    let path = path.trim_end_matches('/');
+    if COURSE_PAGES.contains(&path) || path.is_empty() {
+        return None;
+    }
+    if let Some(chapter) = path.strip_prefix("/exercise/") {
+        let known = catalog
+            .iter()
+            .any(|ex| ex.slug == chapter || ex.file_stem == chapter)
+            || chapter_names.contains(chapter);
+        return (!known).then(|| format!("link to unknown chapter `{chapter}`"));
+    }
+    if let Some(code) = path.strip_prefix("/explain/") {
+        return explain::normalize_code(code)
+            .is_none()
+            .then(|| format!("`{code}` in `{url}` is not an error code"));
+    }
+    Some(format!("link `{url}` doesn't match a course page"))
+}
+
+/// Steps without a reference solution, and solution files without a step.
+fn lint_solutions(
+    dir: &Path,
+    solutions_dir: &Path,
+    exercise: &Exercise,
+    problems: &mut Vec<ContentError>,
+) {
+    let steps = exercise.code_steps();
+    let step_files: Vec<String> = steps.iter().map(|step| step_file(step)).collect();
+    for (step, file) in steps.iter().zip(&step_files) {
+        if step.solution_code.is_none() {
+            problems.push(ContentError {
+                path: dir.join(file),
+                line: None,
+                message: format!(
+                    "step has no reference solution at {}",
+                    solutions_dir.join(file).display()
+                ),
+            });
+        }
+    }
+    for path in sorted_entries(solutions_dir) {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("rs") {
+            continue;
+        }
+        if dir_name(&path).is_some_and(|name| !step_files.contains(&name)) {
+            problems.push(ContentError {
+                path,
+                line: None,
+                message: format!("solution matches no step in {}", dir.display()),
+            });
+        }
+    }
+}
+
+/// File a code step is read from: `main.rs` for a single-step chapter,
+/// `<key>.rs` otherwise. Solutions mirror the same name.
+fn step_file(step: &CodeStep) -> String {
+    let key = step.key();
+    if key.is_empty() {
+        "main.rs".to_string()
+    } else {
+        format!("{key}.rs")
+    }
+}
+
+fn read(path: &Path, problems: &mut Vec<ContentError>) -> Option<String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Some(text),
+        Err(err) => {
+            problems.push(ContentError {
+                path: path.to_path_buf(),
+                line: None,
+                message: format!("can't read: {err}"),
+            });
+            None
+        }
+    }
+}
+
+/// Entries of `dir`, sorted; empty when it can't be read.
+fn sorted_entries(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(std::result::Result::ok)
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    entries
+}
+
+fn dir_name(path: &Path) -> Option<String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A course with one chapter of two steps, one hints file and one
+    /// solution, in a fresh temp dir.
+    fn course(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("corrode-lint-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let chapter = root.join("examples/00_hello");
+        std::fs::create_dir_all(&chapter).unwrap();
+        std::fs::create_dir_all(root.join("solutions/00_hello")).unwrap();
+        for file in ["1_greet.rs", "2_wave.rs"] {
+            std::fs::write(chapter.join(file), "//! # Step\n\nfn main() {}\n").unwrap();
+        }
+        std::fs::write(root.join("solutions/00_hello/1_greet.rs"), "fn main() {}\n").unwrap();
+        root
+    }
+
+    /// Problems as printed, with paths relative to `root`.
+    fn messages(root: &Path) -> Vec<String> {
+        lint(&root.join("examples"))
+            .unwrap()
+            .into_iter()
+            .map(|problem| {
+                ContentError {
+                    path: problem.path.strip_prefix(root).unwrap().to_path_buf(),
+                    ..problem
+                }
+                .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_each_kind_of_problem_with_its_location() {
+        let root = course("kinds");
+        let chapter = root.join("examples/00_hello");
+        std::fs::write(
+            chapter.join("3_hints.md"),
+            "# Hints\n\n## `greet`\n\nSay hi.\n\n## `wav`\n\nTypo.\n\n## Going further\n\nChapter-wide.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            chapter.join("0_intro.md"),
+            "# Hello\n\nSee [the next chapter](/exercise/goodbye), \
+             [E0382](/explain/E0382) and [a file](notes.md).\n",
+        )
+        .unwrap();
+        std::fs::write(
+            chapter.join(".chapter.toml"),
+            "show_toc = true\nbonnus = true\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("solutions/00_hello/9_extra.rs"), "").unwrap();
+        let quiz = root.join("examples/01_quiz");
+        std::fs::create_dir_all(&quiz).unwrap();
+        std::fs::write(quiz.join("1_intro.md"), "# Quiz\n").unwrap();
+        std::fs::write(
+            quiz.join("quiz.toml"),
+            "[[questions]]\nprompt = \"?\"\nanswers = [\n  \
+             { text = \"a\", correct = true, explanation = \"\" },\n  \
+             { text = \"b\", correct = true, explanation = \"\" },\n]\n",
+        )
+        .unwrap();
+        let tags = root.join("examples/02_tags");
+        std::fs::create_dir_all(&tags).unwrap();
+        std::fs::write(tags.join("1_intro.md"), "# Tags\n").unwrap();
+        std::fs::write(
+            tags.join(".chapter.toml"),
+            "difficulty = \"beginner\"\ntags = [\"strings\", \"Error Handling\"]\n",
+        )
+        .unwrap();
+        let broken = root.join("examples/03_broken");
+        std::fs::create_dir_all(&broken).unwrap();
+        std::fs::write(broken.join("1_intro.md"), "# Broken\n").unwrap();
+        std::fs::write(
+            broken.join("2_oops.rs"),
+            "//! # Oops\n\nfn main() {\n    let x = ;\n}\n",
+        )
+        .unwrap();
+
+        let found = messages(&root);
+        let expected = [
+            "examples/00_hello/.chapter.toml:2: unknown field `bonnus`",
+            "examples/00_hello/0_intro.md:3: link to unknown chapter `goodbye`",
+            "examples/00_hello/0_intro.md:3: relative link `notes.md`",
+            "examples/00_hello/2_wave.rs: step has no reference solution",
+            "examples/00_hello/3_hints.md:7: hints heading \"`wav`\" matches no step",
+            "examples/01_quiz/quiz.toml:1: chapter skipped: question 1 has 2 correct answers",
+            "examples/02_tags/.chapter.toml:2: tag `Error Handling` is not lowercase kebab-case",
+            "examples/03_broken/2_oops.rs:4: chapter skipped: parsing file with syn: expected an expression",
+            "solutions/00_hello/9_extra.rs: solution matches no step",
+        ];
+        assert_eq!(found.len(), expected.len(), "{found:#?}");
+        for (found, expected) in found.iter().zip(expected) {
+            assert!(
+                found.starts_with(expected),
+                "{found} should start with {expected}"
+            );
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn reports_requirements_that_cannot_be_met() {
+        let root = course("requires");
+        let hello = root.join("examples/00_hello");
+        std::fs::write(
+            hello.join(".chapter.toml"),
+            "requires = [\"01_bye\", \"07_nope\"]\n",
+        )
+        .unwrap();
+        let bye = root.join("examples/01_bye");
+        std::fs::create_dir_all(&bye).unwrap();
+        std::fs::write(bye.join("1_intro.md"), "# Bye\n").unwrap();
+        std::fs::write(bye.join(".chapter.toml"), "requires = [\"00_hello\"]\n").unwrap();
+        // Open chapters don't enforce `requires`, so this one closes no cycle.
+        let extra = root.join("examples/02_extra");
+        std::fs::create_dir_all(&extra).unwrap();
+        std::fs::write(extra.join("1_intro.md"), "# Extra\n").unwrap();
+        std::fs::write(
+            extra.join(".chapter.toml"),
+            "unlock = \"open\"\nrequires = [\"00_hello\"]\n",
+        )
+        .unwrap();
+
+        let found = messages(&root);
+        let expected = [
+            "examples/00_hello/.chapter.toml:1: `requires` cycle: `01_bye` needs this chapter first",
+            "examples/00_hello/.chapter.toml:1: `requires` names unknown chapter `07_nope`",
+            "examples/00_hello/2_wave.rs: step has no reference solution",
+            "examples/01_bye/.chapter.toml:1: `requires` cycle: `00_hello` needs this chapter first",
+            "examples/02_extra/.chapter.toml:1: `requires` has no effect with `unlock = \"open\"`",
+        ];
+        assert_eq!(found.len(), expected.len(), "{found:#?}");
+        for (found, expected) in found.iter().zip(expected) {
+            assert!(
+                found.starts_with(expected),
+                "{found} should start with {expected}"
+            );
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn the_real_course_is_clean() {
+        let problems = lint(Path::new("examples")).unwrap();
+        let listed: Vec<String> = problems.iter().map(ToString::to_string).collect();
+        assert!(problems.is_empty(), "{listed:#?}");
+    }
+}
@@ -0,0 +1,1357 @@
+//! Pluggable backends for compiling, running and formatting learner code.
+//!
+//! `/api/run` and `/api/format` used to post every request straight to
+//! play.rust-lang.org. That is fine for self-study, but on-site workshops
+//! fall over when the venue network drops or the Playground rate-limits a
+//! room full of people sharing one IP. The server therefore dispatches
+//! through the [`RunBackend`] and [`FormatBackend`] traits, and picks an
+//! implementation for each at startup from `CORRODE_RUN_BACKEND` and
+//! `CORRODE_FORMAT_BACKEND` (see [`BackendKind`]):
+//!
+//! * [`PlaygroundBackend`] (the default): forwards the code to
+//!   play.rust-lang.org, exactly like the old hard-coded proxy.
+//! * [`LocalBackend`]: uses the toolchain installed on the server. Runs
+//!   compile in a throwaway directory under CPU, memory, wall-clock and
+//!   output-size [`Limits`]; formatting pipes the code through `rustfmt`
+//!   with the repository's `rustfmt.toml`, if there is one.
+//!
+//! Both return the same [`RunOutput`] / [`FormatOutput`] shapes (the
+//! subset of the Playground responses we use), so the handlers can parse
+//! test results and record analytics without caring where the code ran.
+//! `/api/clippy` works the same way through [`LintBackend`]
+//! (`CORRODE_CLIPPY_BACKEND`), returning structured [`LintOutput`].
+//!
+//! Without a [`Sandbox`] the local backend is only a resource fence: the
+//! learner's program runs as the server's user with the server's
+//! filesystem and network access, so the server refuses it unless
+//! `/api/run` is limited to known participants. With one
+//! (`CORRODE_RUN_USER`), every process runs as a dedicated user without
+//! network, in namespaces of its own, with its scratch dir as the only
+//! writable directory that outlives it. Either way spawned processes see
+//! none of the server's secrets in their environment, and a run that
+//! overstays its wall-clock limit is killed along with everything it
+//! started.
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+use crate::diagnostics::{self, Diagnostic};
+
+/// Edition every learner snippet is compiled with. Matches the crate
+/// edition and the Playground request we send.
+pub const EDITION: &str = "2024";
+
+/// One compile-and-run request.
+#[derive(Debug, Clone, Copy)]
+pub struct RunJob<'a> {
+    /// Complete source of a single-file program.
+    pub code: &'a str,
+    /// Compile with `--test` and run the test harness instead of `main()`.
+    /// Locally the tests run one at a time, in name order.
+    pub tests: bool,
+}
+
+/// Result of a run, mirroring the Playground `/execute` response.
+///
+/// `success` is `true` only when the code compiled *and* the resulting
+/// binary exited successfully. `stderr` carries compiler output (plus any
+/// runtime stderr); `stdout` carries the program or test-harness output.
+/// `diagnostics` holds the compiler's errors and warnings in structured
+/// form, from rustc's JSON output locally and from a best-effort parse
+/// of `stderr` for the Playground.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct RunOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Why a backend couldn't produce a [`RunOutput`] at all.
+///
+/// A compile error or a failing test is *not* a `RunError`: those are
+/// normal outcomes reported through `RunOutput::success`.
+#[derive(Debug)]
+pub enum RunError {
+    /// The upstream service asked us to back off (HTTP 429).
+    RateLimited,
+    /// The upstream service was unreachable or returned garbage.
+    Upstream(String),
+    /// The local toolchain could not be started (missing `rustc`, I/O
+    /// error creating the scratch directory, ...).
+    Toolchain(String),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RateLimited => write!(f, "rate limited by upstream"),
+            Self::Upstream(msg) => write!(f, "upstream failure: {msg}"),
+            Self::Toolchain(msg) => write!(f, "local toolchain failure: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+/// Boxed future returned by [`RunBackend::run`]. Spelled out so the trait
+/// stays object-safe (`Arc<dyn RunBackend>` lives in the server's state).
+pub type RunFuture<'a> = Pin<Box<dyn Future<Output = Result<RunOutput, RunError>> + Send + 'a>>;
+
+/// Something that can compile and run a single-file Rust program.
+pub trait RunBackend: Send + Sync {
+    /// Short identifier for logs (`playground`, `local`).
+    fn name(&self) -> &'static str;
+
+    /// Compile and run `job`, returning its output.
+    fn run<'a>(&'a self, job: RunJob<'a>) -> RunFuture<'a>;
+}
+
+/// Result of formatting, mirroring the Playground `/format` response.
+///
+/// When the formatter rejects the input (almost always a parse error),
+/// `success` is `false`, `code` is the original input unchanged and
+/// `stderr` carries rustfmt's complaint.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct FormatOutput {
+    pub success: bool,
+    pub code: String,
+    pub stderr: String,
+}
+
+/// Boxed future returned by [`FormatBackend::format`].
+pub type FormatFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<FormatOutput, RunError>> + Send + 'a>>;
+
+/// Something that can run rustfmt over a single-file Rust program.
+pub trait FormatBackend: Send + Sync {
+    /// Short identifier for logs (`playground`, `local`).
+    fn name(&self) -> &'static str;
+
+    /// Format `code` with edition 2024 rules.
+    fn format<'a>(&'a self, code: &'a str) -> FormatFuture<'a>;
+}
+
+/// Result of linting: clippy's findings as structured [`Diagnostic`]s
+/// plus the human-readable rendering of the same output.
+///
+/// `success` is `false` when the code doesn't compile; lint warnings
+/// alone don't fail a run.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct LintOutput {
+    pub success: bool,
+    pub diagnostics: Vec<Diagnostic>,
+    pub stderr: String,
+}
+
+/// Boxed future returned by [`LintBackend::clippy`].
+pub type LintFuture<'a> = Pin<Box<dyn Future<Output = Result<LintOutput, RunError>> + Send + 'a>>;
+
+/// Something that can run clippy over a single-file Rust program.
+pub trait LintBackend: Send + Sync {
+    /// Short identifier for logs (`playground`, `local`).
+    fn name(&self) -> &'static str;
+
+    /// Lint `code` at clippy's default lint levels.
+    fn clippy<'a>(&'a self, code: &'a str) -> LintFuture<'a>;
+}
+
+/// Which backend the server should use for runs, formatting or lints,
+/// parsed from [`Self::RUN_ENV_VAR`], [`Self::FORMAT_ENV_VAR`] or
+/// [`Self::CLIPPY_ENV_VAR`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackendKind {
+    /// Forward to play.rust-lang.org.
+    #[default]
+    Playground,
+    /// Use the locally installed toolchain.
+    Local,
+}
+
+impl BackendKind {
+    /// Environment variable choosing the `/api/run` backend.
+    pub const RUN_ENV_VAR: &'static str = "CORRODE_RUN_BACKEND";
+    /// Environment variable choosing the `/api/format` backend.
+    pub const FORMAT_ENV_VAR: &'static str = "CORRODE_FORMAT_BACKEND";
+    /// Environment variable choosing the `/api/clippy` backend.
+    pub const CLIPPY_ENV_VAR: &'static str = "CORRODE_CLIPPY_BACKEND";
+
+    /// Read a backend choice from the environment variable `key`. Unset
+    /// means the default ([`Self::Playground`]); an unrecognised value is
+    /// an error so a typo doesn't silently send a workshop to the
+    /// Playground.
+    pub fn from_env(key: &str) -> anyhow::Result<Self> {
+        std::env::var(key).map_or_else(
+            |_| Ok(Self::default()),
+            |raw| raw.parse().map_err(|e| anyhow::anyhow!("{key}: {e}")),
+        )
+    }
+
+    /// Build the run backend this kind names.
+    pub fn run_backend(self) -> anyhow::Result<Arc<dyn RunBackend>> {
+        Ok(match self {
+            Self::Playground => Arc::new(PlaygroundBackend::new()),
+            Self::Local => Arc::new(LocalBackend::from_env()?),
+        })
+    }
+
+    /// Build the format backend this kind names.
+    pub fn format_backend(self) -> anyhow::Result<Arc<dyn FormatBackend>> {
+        Ok(match self {
+            Self::Playground => Arc::new(PlaygroundBackend::new()),
+            Self::Local => Arc::new(LocalBackend::from_env()?),
+        })
+    }
+
+    /// Build the lint backend this kind names.
+    pub fn lint_backend(self) -> anyhow::Result<Arc<dyn LintBackend>> {
+        Ok(match self {
+            Self::Playground => Arc::new(PlaygroundBackend::new()),
+            Self::Local => Arc::new(LocalBackend::from_env()?),
+        })
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" | "playground" | "remote" => Ok(Self::Playground),
+            "local" => Ok(Self::Local),
+            other => Err(format!(
+                "unknown backend `{other}` (expected `playground` or `local`)"
+            )),
+        }
+    }
+}
+
+/// Forwards runs to play.rust-lang.org's `/execute` endpoint and
+/// formatting to its `/format` endpoint.
+///
+/// The upstream already runs untrusted code in a sandbox and enforces its
+/// own rate limits, so this stays thin: status codes are mapped onto
+/// [`RunError`] and the JSON body is passed back as-is.
+pub struct PlaygroundBackend {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl PlaygroundBackend {
+    /// Backend pointed at the public Playground.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_base_url("https://play.rust-lang.org")
+    }
+
+    /// Backend pointed at a different Playground-compatible host (e.g. a
+    /// self-hosted instance). `base_url` has no trailing slash.
+    #[must_use]
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.to_string(),
+        }
+    }
+
+    /// POST `body` to `<base_url>/<endpoint>` and decode the JSON reply.
+    async fn post<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &serde_json::Value,
+        timeout: Duration,
+    ) -> Result<T, RunError> {
+        let resp = self
+            .client
+            .post(format!("{}/{endpoint}", self.base_url))
+            .timeout(timeout)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| RunError::Upstream(format!("{e:?}")))?;
+
+        let status = resp.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(RunError::RateLimited);
+        }
+        if !status.is_success() {
+            return Err(RunError::Upstream(format!(
+                "Playground /{endpoint} returned {status}"
+            )));
+        }
+
+        resp.json::<T>().await.map_err(|e| {
+            RunError::Upstream(format!("unparseable Playground /{endpoint} response: {e}"))
+        })
+    }
+}
+
+impl Default for PlaygroundBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunBackend for PlaygroundBackend {
+    fn name(&self) -> &'static str {
+        "playground"
+    }
+
+    fn run<'a>(&'a self, job: RunJob<'a>) -> RunFuture<'a> {
+        Box::pin(async move {
+            let body = serde_json::json!({
+                "channel": "stable",
+                "mode": "debug",
+                "edition": EDITION,
+                "crateType": "bin",
+                "tests": job.tests,
+                "backtrace": false,
+                "code": job.code,
+            });
+            let mut output: RunOutput =
+                self.post("execute", &body, Duration::from_secs(20)).await?;
+            output.diagnostics = diagnostics::parse_human(&output.stderr);
+            Ok(output)
+        })
+    }
+}
+
+impl FormatBackend for PlaygroundBackend {
+    fn name(&self) -> &'static str {
+        "playground"
+    }
+
+    fn format<'a>(&'a self, code: &'a str) -> FormatFuture<'a> {
+        Box::pin(async move {
+            let body = serde_json::json!({
+                "channel": "stable",
+                "edition": EDITION,
+                "code": code,
+            });
+            self.post("format", &body, Duration::from_secs(15)).await
+        })
+    }
+}
+
+/// Prepended to code sent to the Playground's `/clippy`, which only
+/// lints whole crates: as a library crate nothing calls the learner's
+/// functions, so without this every helper would be reported as dead.
+const PLAYGROUND_CLIPPY_HEADER: &str = "#![allow(dead_code)]\n";
+
+#[derive(serde::Deserialize)]
+struct PlaygroundClippyResp {
+    success: bool,
+    stderr: String,
+}
+
+impl LintBackend for PlaygroundBackend {
+    fn name(&self) -> &'static str {
+        "playground"
+    }
+
+    fn clippy<'a>(&'a self, code: &'a str) -> LintFuture<'a> {
+        Box::pin(async move {
+            let body = serde_json::json!({
+                "channel": "stable",
+                "edition": EDITION,
+                "crateType": "lib",
+                "code": format!("{PLAYGROUND_CLIPPY_HEADER}{code}"),
+            });
+            let resp: PlaygroundClippyResp =
+                self.post("clippy", &body, Duration::from_secs(20)).await?;
+            // The Playground only gives us rendered text.
+            let mut diagnostics = diagnostics::parse_human(&resp.stderr);
+            for diagnostic in &mut diagnostics {
+                diagnostic.shift_lines(-1);
+            }
+            Ok(LintOutput {
+                success: resp.success,
+                diagnostics,
+                stderr: resp.stderr,
+            })
+        })
+    }
+}
+
+/// Resource limits applied to every process the [`LocalBackend`] spawns
+/// (the compiler and the compiled program alike).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// CPU time per process, in seconds (`ulimit -t`).
+    pub cpu_seconds: u64,
+    /// Address-space cap per process, in bytes (`ulimit -v`).
+    pub memory_bytes: u64,
+    /// Wall-clock budget per process. The process is killed when it
+    /// runs out, which also catches programs blocked on `stdin` or
+    /// sleeping, which CPU limits don't.
+    pub wall_clock: Duration,
+    /// Maximum bytes kept from each of stdout and stderr. Anything past
+    /// the cap is drained and discarded so a `loop { println!() }` can't
+    /// exhaust server memory.
+    pub max_output_bytes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            cpu_seconds: 10,
+            memory_bytes: 1024 * 1024 * 1024,
+            wall_clock: Duration::from_secs(20),
+            max_output_bytes: 64 * 1024,
+        }
+    }
+}
+
+impl Limits {
+    /// Defaults, overridden by any of `CORRODE_RUN_CPU_SECONDS`,
+    /// `CORRODE_RUN_MEMORY_MB`, `CORRODE_RUN_TIMEOUT_SECONDS` and
+    /// `CORRODE_RUN_MAX_OUTPUT_KB` that are set to a positive integer.
+    /// Unparseable values are ignored with a warning.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let mut limits = Self::default();
+        if let Some(v) = env_u64("CORRODE_RUN_CPU_SECONDS") {
+            limits.cpu_seconds = v;
+        }
+        if let Some(v) = env_u64("CORRODE_RUN_MEMORY_MB") {
+            limits.memory_bytes = v.saturating_mul(1024 * 1024);
+        }
+        if let Some(v) = env_u64("CORRODE_RUN_TIMEOUT_SECONDS") {
+            limits.wall_clock = Duration::from_secs(v);
+        }
+        if let Some(v) = env_u64("CORRODE_RUN_MAX_OUTPUT_KB") {
+            limits.max_output_bytes = usize::try_from(v.saturating_mul(1024)).unwrap_or(usize::MAX);
+        }
+        limits
+    }
+}
+
+fn env_u64(key: &str) -> Option<u64> {
+    let raw = std::env::var(key).ok()?;
+    match raw.trim().parse::<u64>() {
+        Ok(v) if v > 0 => Some(v),
+        _ => {
+            log::warn!("ignoring {key}={raw:?}: expected a positive integer");
+            None
+        }
+    }
+}
+
+/// Compiles with `rustc` and runs the result on the server itself.
+///
+/// Every run gets a fresh [`Scratch`] directory that is removed when the
+/// run finishes. The compiler invocation mirrors `scripts/check-solutions.sh`
+/// (`rustc --test --edition 2024`): exercises are std-only single files,
+/// so no Cargo project is needed.
+pub struct LocalBackend {
+    limits: Limits,
+    sandbox: Option<Sandbox>,
+    rustc: PathBuf,
+    rustfmt_config: Option<PathBuf>,
+}
+
+impl LocalBackend {
+    /// Backend using the toolchain found on `PATH` and rustfmt's default
+    /// settings.
+    #[must_use]
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            sandbox: None,
+            rustc: PathBuf::from("rustc"),
+            rustfmt_config: None,
+        }
+    }
+
+    /// Backend configured from the environment: [`Limits::from_env`],
+    /// [`Sandbox::from_env`] and the rustfmt config from
+    /// [`discover_rustfmt_config`].
+    pub fn from_env() -> anyhow::Result<Self> {
+        Ok(Self::new(Limits::from_env())
+            .with_sandbox(Sandbox::from_env()?)
+            .with_rustfmt_config(discover_rustfmt_config()))
+    }
+
+    /// Run every process in `sandbox` instead of as the server's user.
+    #[must_use]
+    pub const fn with_sandbox(mut self, sandbox: Option<Sandbox>) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Format with the given `rustfmt.toml` (passed as `--config-path`)
+    /// instead of rustfmt's defaults. A relative path is resolved against
+    /// the current directory here, since rustfmt runs in a scratch dir.
+    /// With a [`Sandbox`], the file must be readable by its user and
+    /// outside the temp dirs it replaces.
+    #[must_use]
+    pub fn with_rustfmt_config(mut self, path: Option<PathBuf>) -> Self {
+        self.rustfmt_config = path.map(|path| std::path::absolute(&path).unwrap_or(path));
+        self
+    }
+
+    /// The limits every spawned process runs under.
+    #[must_use]
+    pub const fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Fail unless `<tool> --version` runs the way this backend runs
+    /// tools (in its sandbox, if it has one); `setting` names the
+    /// configuration that needs the tool in the error.
+    pub fn require_tool(&self, tool: &str, setting: &str) -> anyhow::Result<()> {
+        let scratch = Scratch::new("probe")?;
+        if let Some(sandbox) = &self.sandbox {
+            sandbox.adopt(scratch.path())?;
+        }
+        let version = [OsStr::new("--version")];
+        let mut cmd = limited_command(
+            OsStr::new(tool),
+            &version,
+            scratch.path(),
+            &self.limits,
+            self.sandbox.as_ref(),
+        );
+        match cmd.output() {
+            Ok(out) if out.status.success() => Ok(()),
+            Ok(out) => anyhow::bail!(
+                "{setting} needs `{tool}`, but `{tool} --version` failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            ),
+            Err(err) => anyhow::bail!("{setting} needs `{tool}` on PATH: {err}"),
+        }
+    }
+
+    async fn run_local(&self, job: RunJob<'_>) -> Result<RunOutput, RunError> {
+        let scratch = Scratch::new("run").map_err(|e| RunError::Toolchain(e.to_string()))?;
+        scratch.write("main.rs", job.code)?;
+        let bin = scratch.path().join("main");
+
+        let mut args: Vec<&OsStr> = vec![
+            OsStr::new("--edition"),
+            OsStr::new(EDITION),
+            OsStr::new("--crate-name"),
+            OsStr::new("main"),
+            OsStr::new("-C"),
+            OsStr::new("debuginfo=0"),
+            OsStr::new("--error-format=json"),
+        ];
+        if job.tests {
+            args.push(OsStr::new("--test"));
+        }
+        // `main.rs` relative to the scratch dir, as in `clippy_with`.
+        args.extend([OsStr::new("-o"), bin.as_os_str(), OsStr::new("main.rs")]);
+
+        let mut compile = run_limited(
+            self.rustc.as_os_str(),
+            &args,
+            scratch.path(),
+            &self.limits,
+            self.sandbox.as_ref(),
+            None,
+        )
+        .await?;
+        let parsed = diagnostics::parse_json(&compile.stderr);
+        compile.stderr = parsed.rendered;
+        let mut stderr = compile.stderr_with_notes(&self.limits);
+        if !compile.success() {
+            return Ok(RunOutput {
+                success: false,
+                stdout: String::new(),
+                stderr,
+                diagnostics: parsed.diagnostics,
+            });
+        }
+
+        // One test at a time: `verify` relies on libtest's name order.
+        let run_args: &[&OsStr] = if job.tests {
+            &[OsStr::new("--test-threads=1")]
+        } else {
+            &[]
+        };
+        let run = run_limited(
+            bin.as_os_str(),
+            run_args,
+            scratch.path(),
+            &self.limits,
+            self.sandbox.as_ref(),
+            None,
+        )
+        .await?;
+        stderr.push_str(&run.stderr_with_notes(&self.limits));
+        Ok(RunOutput {
+            success: run.success(),
+            stdout: run.stdout,
+            stderr,
+            diagnostics: parsed.diagnostics,
+        })
+    }
+
+    /// Pipe `code` through `rustfmt --edition 2024` (plus
+    /// `--config-path` when a `rustfmt.toml` is configured). On success
+    /// the formatted source is in `stdout`; parse errors land in `stderr`
+    /// with a non-zero exit.
+    pub async fn rustfmt(&self, code: &str) -> Result<Captured, RunError> {
+        let scratch = Scratch::new("fmt").map_err(|e| RunError::Toolchain(e.to_string()))?;
+        let mut args = vec![OsStr::new("--edition"), OsStr::new(EDITION)];
+        if let Some(config) = &self.rustfmt_config {
+            args.extend([OsStr::new("--config-path"), config.as_os_str()]);
+        }
+        run_limited(
+            OsStr::new("rustfmt"),
+            &args,
+            scratch.path(),
+            &self.limits,
+            self.sandbox.as_ref(),
+            Some(code.as_bytes()),
+        )
+        .await
+    }
+
+    /// Lint `code` with `clippy-driver` at its default lint levels with
+    /// warnings denied, i.e. what `cargo clippy -- -D warnings` does in
+    /// a learner's checkout. The file is checked in `--test` mode so
+    /// functions only exercised by `#[test]`s don't count as dead code.
+    pub async fn clippy(&self, code: &str) -> Result<Captured, RunError> {
+        self.clippy_with(code, &["-D", "warnings"]).await
+    }
+
+    /// Run `clippy-driver` over `code` as in [`Self::clippy`], with
+    /// `extra` appended to the default flags.
+    async fn clippy_with(&self, code: &str, extra: &[&str]) -> Result<Captured, RunError> {
+        let scratch = Scratch::new("clippy").map_err(|e| RunError::Toolchain(e.to_string()))?;
+        scratch.write("main.rs", code)?;
+        let mut args = vec![
+            OsStr::new("--edition"),
+            OsStr::new(EDITION),
+            OsStr::new("--crate-name"),
+            OsStr::new("main"),
+            OsStr::new("--test"),
+            OsStr::new("--emit=metadata"),
+        ];
+        args.extend(extra.iter().map(OsStr::new));
+        // Relative to the scratch dir, so output says `main.rs` rather
+        // than a temp path.
+        args.push(OsStr::new("main.rs"));
+        run_limited(
+            OsStr::new("clippy-driver"),
+            &args,
+            scratch.path(),
+            &self.limits,
+            self.sandbox.as_ref(),
+            None,
+        )
+        .await
+    }
+}
+
+impl RunBackend for LocalBackend {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn run<'a>(&'a self, job: RunJob<'a>) -> RunFuture<'a> {
+        Box::pin(self.run_local(job))
+    }
+}
+
+impl FormatBackend for LocalBackend {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn format<'a>(&'a self, code: &'a str) -> FormatFuture<'a> {
+        Box::pin(async move {
+            let out = self.rustfmt(code).await?;
+            // A truncated stdout is a cut-off copy of the source, never
+            // something to hand back as the formatted file.
+            Ok(if out.success() && !out.truncated {
+                FormatOutput {
+                    success: true,
+                    code: out.stdout,
+                    stderr: out.stderr,
+                }
+            } else {
+                FormatOutput {
+                    success: false,
+                    code: code.to_string(),
+                    stderr: out.stderr_with_notes(&self.limits),
+                }
+            })
+        })
+    }
+}
+
+impl LintBackend for LocalBackend {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn clippy<'a>(&'a self, code: &'a str) -> LintFuture<'a> {
+        Box::pin(async move {
+            let mut out = self.clippy_with(code, &["--error-format=json"]).await?;
+            let parsed = diagnostics::parse_json(&out.stderr);
+            out.stderr = parsed.rendered;
+            let stderr = out.stderr_with_notes(&self.limits);
+            Ok(LintOutput {
+                success: out.success(),
+                diagnostics: parsed.diagnostics,
+                stderr,
+            })
+        })
+    }
+}
+
+/// Locate the `rustfmt.toml` the local formatter should honour.
+///
+/// `CORRODE_RUSTFMT_CONFIG` wins if set; otherwise `rustfmt.toml` or
+/// `.rustfmt.toml` in the server's working directory (the repository
+/// root in every deployment we ship). `None` means rustfmt's defaults.
+/// The path may be relative; [`LocalBackend::with_rustfmt_config`]
+/// makes it absolute.
+#[must_use]
+pub fn discover_rustfmt_config() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("CORRODE_RUSTFMT_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    ["rustfmt.toml", ".rustfmt.toml"]
+        .into_iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+}
+
+/// A throwaway working directory under the system temp dir, removed
+/// (recursively) on drop.
+pub struct Scratch {
+    path: PathBuf,
+}
+
+impl Scratch {
+    /// Create `$TMPDIR/corrode-<purpose>-<ulid>/`.
+    pub fn new(purpose: &str) -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("corrode-{purpose}-{}", ulid::Ulid::new()));
+        std::fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write `contents` to `name` inside the scratch dir and return the
+    /// full path.
+    pub fn write(&self, name: &str, contents: &str) -> Result<PathBuf, RunError> {
+        let path = self.path.join(name);
+        std::fs::write(&path, contents)
+            .map_err(|e| RunError::Toolchain(format!("writing {}: {e}", path.display())))?;
+        Ok(path)
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.path) {
+            log::warn!("removing scratch dir {} failed: {e}", self.path.display());
+        }
+    }
+}
+
+/// Captured result of one [`run_limited`] process.
+#[derive(Debug, Default)]
+pub struct Captured {
+    /// Exit code, or `None` if the process was killed by a signal
+    /// (including our own wall-clock kill).
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// The wall-clock budget ran out and the process was killed.
+    pub timed_out: bool,
+    /// At least one stream went over `max_output_bytes`.
+    pub truncated: bool,
+}
+
+impl Captured {
+    /// Exited normally with status 0.
+    #[must_use]
+    pub const fn success(&self) -> bool {
+        matches!(self.exit_code, Some(0)) && !self.timed_out
+    }
+
+    /// `stderr`, followed by a one-line explanation for every limit the
+    /// process hit, so learners see *why* their run stopped.
+    #[must_use]
+    pub fn stderr_with_notes(&self, limits: &Limits) -> String {
+        let mut out = self.stderr.clone();
+        let mut note = |line: String| {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str(&line);
+            out.push('\n');
+        };
+        if self.timed_out {
+            note(format!(
+                "note: killed after {}s wall-clock time limit",
+                limits.wall_clock.as_secs()
+            ));
+        } else if self.exit_code.is_none() {
+            note(format!(
+                "note: killed by a signal (CPU limit is {}s, memory limit is {} MiB)",
+                limits.cpu_seconds,
+                limits.memory_bytes / (1024 * 1024)
+            ));
+        }
+        if self.truncated {
+            note(format!(
+                "note: output truncated to {} KiB",
+                limits.max_output_bytes / 1024
+            ));
+        }
+        out
+    }
+}
+
+/// Environment variables spawned processes inherit. Everything else the
+/// server was started with (`CORRODE_ADMIN_TOKEN`, `DATABASE_URL`, ...)
+/// is withheld from learner code.
+const PASSED_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "LANG",
+    "LC_ALL",
+    "RUSTUP_HOME",
+    "RUSTUP_TOOLCHAIN",
+    "CARGO_HOME",
+];
+
+/// Applies the CPU and memory caps, then execs the tool.
+/// `$1`, `$2`: `ulimit -t` and `ulimit -v`; the rest is the command.
+const LIMITS_SCRIPT: &str = r#"ulimit -t "$1" && ulimit -v "$2" && shift 2 && exec "$@""#;
+
+/// Runs as root inside [`Sandbox`]'s fresh namespaces: remounts the root
+/// read-only, gives `/tmp` and friends private, size-capped tmpfs
+/// mounts, binds the working directory (the scratch dir) back at its
+/// own path as the one writable place, applies the limits and drops to
+/// the sandbox user. `$1`, `$2`: uid and gid; `$3`, `$4`: as for
+/// [`LIMITS_SCRIPT`]; the rest is the command.
+const SANDBOX_SCRIPT: &str = r#"set -e
+scratch=$PWD
+mount -o remount,bind,ro /
+for dir in /tmp /var/tmp /dev/shm /run/lock; do
+    if [ -d "$dir" ]; then mount -t tmpfs -o size=16m,mode=1777 tmpfs "$dir"; fi
+done
+mkdir -p "$scratch"
+mount --no-canonicalize --bind /proc/self/cwd "$scratch"
+mount -o remount,bind,rw "$scratch"
+cd "$scratch"
+uid=$1 gid=$2
+ulimit -t "$3"
+ulimit -v "$4"
+shift 4
+exec setpriv --reuid "$uid" --regid "$gid" --clear-groups --no-new-privs \
+    --inh-caps=-all --bounding-set=-all -- "$@""#;
+
+/// Isolation for every process a [`LocalBackend`] spawns, configured
+/// with [`Self::ENV_VAR`].
+///
+/// Each process starts in new network, PID, IPC, UTS and mount
+/// namespaces (`unshare`), so it has no network but loopback and sees
+/// no other process. The root filesystem is remounted read-only and
+/// `/tmp`, `/var/tmp`, `/dev/shm` and `/run/lock` get empty tmpfs
+/// mounts of their own, leaving the scratch dir (owned by the sandbox
+/// user, mode 0700) as the only writable directory that outlives the
+/// run. The tool then runs as `uid`:`gid` with no supplementary
+/// groups, no capabilities and `no_new_privs` (`setpriv`).
+///
+/// Needs util-linux, a server running as root (in a container: with
+/// `CAP_SYS_ADMIN`), and a toolchain the sandbox user can read, e.g.
+/// one installed under `/usr/local` rather than `/root/.rustup`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sandbox {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl Sandbox {
+    /// Environment variable holding the sandbox user as `uid` or
+    /// `uid:gid` (numeric; the gid defaults to the uid). Unset means no
+    /// sandbox.
+    pub const ENV_VAR: &'static str = "CORRODE_RUN_USER";
+
+    /// Read [`Self::ENV_VAR`]. Unlike the [`Limits`] settings, a value
+    /// that doesn't parse is an error: running unsandboxed by accident
+    /// is not a safe fallback.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        std::env::var(Self::ENV_VAR).map_or(Ok(None), |raw| {
+            raw.parse()
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("{}: {e}", Self::ENV_VAR))
+        })
+    }
+
+    /// Hand `dir` to the sandbox user, readable by no one else.
+    fn adopt(self, dir: &Path) -> std::io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::os::unix::fs::chown(dir, Some(self.uid), Some(self.gid))?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+    }
+}
+
+impl FromStr for Sandbox {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |id: &str| {
+            id.trim()
+                .parse::<u32>()
+                .map_err(|_| format!("expected `uid` or `uid:gid`, got `{s}`"))
+        };
+        let (uid, gid) = match s.split_once(':') {
+            Some((uid, gid)) => (parse(uid)?, parse(gid)?),
+            None => (parse(s)?, parse(s)?),
+        };
+        if uid == 0 {
+            return Err("refusing to run learner code as root (uid 0)".to_string());
+        }
+        Ok(Self { uid, gid })
+    }
+}
+
+/// The command [`run_limited`] spawns for `program args...`: the tool
+/// wrapped in [`LIMITS_SCRIPT`], or in `unshare` and
+/// [`SANDBOX_SCRIPT`] when `sandbox` is set, with only [`PASSED_ENV`]
+/// in its environment.
+fn limited_command(
+    program: &OsStr,
+    args: &[&OsStr],
+    dir: &Path,
+    limits: &Limits,
+    sandbox: Option<&Sandbox>,
+) -> std::process::Command {
+    let cpu = limits.cpu_seconds.to_string();
+    let memory = (limits.memory_bytes / 1024).to_string();
+    let mut cmd = sandbox.map_or_else(
+        || {
+            let mut cmd = std::process::Command::new("sh");
+            cmd.args(["-c", LIMITS_SCRIPT, "sh"]);
+            cmd
+        },
+        |sandbox| {
+            let mut cmd = std::process::Command::new("unshare");
+            cmd.args([
+                "--net",
+                "--ipc",
+                "--uts",
+                "--pid",
+                "--fork",
+                "--kill-child",
+                "--mount",
+                "--mount-proc",
+                "--propagation",
+                "private",
+                "sh",
+                "-c",
+                SANDBOX_SCRIPT,
+                "sh",
+            ])
+            .args([sandbox.uid.to_string(), sandbox.gid.to_string()]);
+            cmd
+        },
+    );
+    cmd.args([cpu, memory])
+        .arg(program)
+        .args(args)
+        .current_dir(dir)
+        .env_clear()
+        .envs(
+            PASSED_ENV
+                .iter()
+                .filter_map(|key| Some((key, std::env::var_os(key)?))),
+        );
+    cmd
+}
+
+/// How long a killed run's output may take to reach EOF before we give
+/// up on it. Descendants that left the process group can hold the pipes
+/// open indefinitely.
+const KILL_GRACE: Duration = Duration::from_secs(1);
+
+/// Spawn `program args...` in `dir` under `limits` and capture its output.
+///
+/// CPU and memory caps are applied through the shell's `ulimit` so they
+/// cover the exec'd process itself; the wall-clock cap is enforced here by
+/// killing the child's whole process group, and whatever it printed
+/// until then is kept. With a `sandbox`, `dir` is handed to the sandbox
+/// user first and the process runs isolated as described on
+/// [`Sandbox`]. `stdin` is fed to the process when `Some`, otherwise it
+/// reads from `/dev/null`.
+pub async fn run_limited(
+    program: &OsStr,
+    args: &[&OsStr],
+    dir: &Path,
+    limits: &Limits,
+    sandbox: Option<&Sandbox>,
+    stdin: Option<&[u8]>,
+) -> Result<Captured, RunError> {
+    if let Some(sandbox) = sandbox {
+        sandbox
+            .adopt(dir)
+            .map_err(|e| RunError::Toolchain(format!("preparing {}: {e}", dir.display())))?;
+    }
+    let mut cmd =
+        tokio::process::Command::from(limited_command(program, args, dir, limits, sandbox));
+    cmd.stdin(if stdin.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    })
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .process_group(0)
+    .kill_on_drop(true);
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| RunError::Toolchain(format!("spawning {}: {e}", program.to_string_lossy())))?;
+
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        // Ignore write errors: a process that exits without reading its
+        // input (e.g. on a parse error) closes the pipe early.
+        let _ = pipe.write_all(input).await;
+    }
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let max = limits.max_output_bytes;
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let (status, out_cut, err_cut) = {
+        let mut reading = std::pin::pin!(async {
+            tokio::join!(
+                read_capped(stdout, max, &mut out),
+                read_capped(stderr, max, &mut err)
+            )
+        });
+        let work = async { tokio::join!(&mut reading, child.wait()) };
+        if let Ok(((out_cut, err_cut), status)) =
+            tokio::time::timeout(limits.wall_clock, work).await
+        {
+            (Some(status), out_cut, err_cut)
+        } else {
+            kill_process_group(&child);
+            let _ = child.wait().await;
+            let (out_cut, err_cut) = tokio::time::timeout(KILL_GRACE, &mut reading)
+                .await
+                .unwrap_or_default();
+            (None, out_cut, err_cut)
+        }
+    };
+    let timed_out = status.is_none();
+    let exit_code = match status {
+        Some(status) => status
+            .map_err(|e| RunError::Toolchain(format!("waiting: {e}")))?
+            .code(),
+        None => None,
+    };
+    Ok(Captured {
+        exit_code,
+        stdout: String::from_utf8_lossy(&out).into_owned(),
+        stderr: String::from_utf8_lossy(&err).into_owned(),
+        timed_out,
+        truncated: out_cut || err_cut,
+    })
+}
+
+/// SIGKILL the process group `child` leads (it was spawned with
+/// `process_group(0)`), taking along everything it forked that stayed
+/// in the group. In a [`Sandbox`] that is all of them: killing `unshare`
+/// kills its PID namespace.
+fn kill_process_group(child: &tokio::process::Child) {
+    if let Some(pid) = child.id().and_then(|pid| i32::try_from(pid).ok()) {
+        // SAFETY: kill(2) takes no pointers; a negative pid names the
+        // process group, which can't be reused while `child` is unreaped.
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+}
+
+/// Read up to `max` bytes from `reader` into `buf`, then drain (and drop)
+/// the rest so the child never blocks on a full pipe. Returns whether
+/// anything was dropped. Bytes land in `buf` as they arrive, so they
+/// survive this future being cancelled part-way.
+async fn read_capped<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    max: usize,
+    buf: &mut Vec<u8>,
+) -> bool {
+    let Some(mut reader) = reader else {
+        return false;
+    };
+    buf.reserve(max.min(8 * 1024));
+    let limit = u64::try_from(max).unwrap_or(u64::MAX);
+    let mut kept = (&mut reader).take(limit);
+    while matches!(kept.read_buf(buf).await, Ok(read) if read > 0) {}
+    let dropped = tokio::io::copy(&mut reader, &mut tokio::io::sink())
+        .await
+        .unwrap_or(0);
+    dropped > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend_kind_parses_known_names() {
+        assert_eq!("local".parse(), Ok(BackendKind::Local));
+        assert_eq!(" Local ".parse(), Ok(BackendKind::Local));
+        assert_eq!("playground".parse(), Ok(BackendKind::Playground));
+        assert_eq!("".parse(), Ok(BackendKind::Playground));
+        assert!("docker".parse::<BackendKind>().is_err());
+    }
+
+    #[test]
+    fn sandbox_parses_a_numeric_user() {
+        assert_eq!(
+            "65534".parse(),
+            Ok(Sandbox {
+                uid: 65534,
+                gid: 65534
+            })
+        );
+        assert_eq!(
+            "1000:100".parse(),
+            Ok(Sandbox {
+                uid: 1000,
+                gid: 100
+            })
+        );
+        assert!("nobody".parse::<Sandbox>().is_err());
+        assert!("0:0".parse::<Sandbox>().is_err(), "root is no sandbox");
+    }
+
+    /// Run `sh -c script` in a fresh scratch dir, returning the dir too.
+    async fn run_script(
+        script: &str,
+        limits: &Limits,
+        sandbox: Option<&Sandbox>,
+    ) -> (Captured, Scratch) {
+        let scratch = Scratch::new("run-test").unwrap();
+        let args = [OsStr::new("-c"), OsStr::new(script)];
+        let out = run_limited(
+            OsStr::new("sh"),
+            &args,
+            scratch.path(),
+            limits,
+            sandbox,
+            None,
+        )
+        .await
+        .unwrap();
+        (out, scratch)
+    }
+
+    #[tokio::test]
+    async fn spawned_processes_get_a_scrubbed_environment() {
+        // Cargo sets this for the test binary; learner code must not see
+        // the server's environment.
+        assert!(std::env::var_os("CARGO_MANIFEST_DIR").is_some());
+        let (out, _scratch) = run_script("env", &Limits::default(), None).await;
+        assert!(out.success(), "{}", out.stderr);
+        assert!(out.stdout.contains("PATH="), "{}", out.stdout);
+        assert!(!out.stdout.contains("CARGO_MANIFEST_DIR"), "{}", out.stdout);
+    }
+
+    #[tokio::test]
+    async fn timed_out_runs_keep_their_output_and_lose_their_children() {
+        let limits = Limits {
+            wall_clock: Duration::from_secs(1),
+            ..Limits::default()
+        };
+        let (out, scratch) = run_script(
+            "echo started; echo oops >&2; (sleep 2; touch survived) & wait",
+            &limits,
+            None,
+        )
+        .await;
+        assert!(out.timed_out);
+        assert_eq!(out.stdout, "started\n");
+        assert_eq!(out.stderr, "oops\n");
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(
+            !scratch.path().join("survived").exists(),
+            "the background job outlived the run"
+        );
+    }
+
+    #[tokio::test]
+    async fn sandboxed_runs_have_no_network_and_one_writable_dir() {
+        use std::os::unix::fs::MetadataExt;
+
+        if std::fs::metadata("/proc/self").map(|meta| meta.uid()).ok() != Some(0) {
+            eprintln!("skipping: the sandbox needs root");
+            return;
+        }
+        let leak = format!("corrode-leak-{}", ulid::Ulid::new());
+        let script = format!(
+            "id -u; cat /proc/net/dev; echo hi > kept; touch /tmp/{leak}; \
+             touch /var/{leak} 2>/dev/null || echo read-only"
+        );
+        let sandbox = Sandbox {
+            uid: 65534,
+            gid: 65534,
+        };
+        let (out, scratch) = run_script(&script, &Limits::default(), Some(&sandbox)).await;
+        assert!(out.success(), "{}", out.stderr);
+        let mut lines = out.stdout.lines();
+        assert_eq!(lines.next(), Some("65534"));
+        let interfaces: Vec<_> = lines
+            .clone()
+            .filter_map(|line| Some(line.split_once(':')?.0.trim()))
+            .collect();
+        assert_eq!(interfaces, ["lo"], "{}", out.stdout);
+        assert_eq!(lines.last(), Some("read-only"));
+        assert!(scratch.path().join("kept").is_file());
+        assert!(!std::env::temp_dir().join(&leak).exists());
+        assert!(!Path::new("/var").join(&leak).exists());
+    }
+
+    #[tokio::test]
+    async fn local_formatter_honours_config_and_keeps_code_on_parse_errors() {
+        let scratch = Scratch::new("fmt-test").unwrap();
+        let config = scratch.write("rustfmt.toml", "hard_tabs = true\n").unwrap();
+        let backend = LocalBackend::new(Limits::default()).with_rustfmt_config(Some(config));
+
+        let out = backend.format("fn main(){let x=1;}").await.unwrap();
+        assert!(out.success, "{}", out.stderr);
+        assert_eq!(out.code, "fn main() {\n\tlet x = 1;\n}\n");
+
+        let broken = "fn main( {";
+        let out = backend.format(broken).await.unwrap();
+        assert!(!out.success);
+        assert_eq!(out.code, broken);
+        assert!(out.stderr.contains("error"), "{}", out.stderr);
+    }
+
+    #[tokio::test]
+    async fn local_formatter_resolves_a_relative_config_path() {
+        let scratch = Scratch::new("fmt-test").unwrap();
+        let config = scratch.write("rustfmt.toml", "hard_tabs = true\n").unwrap();
+        // The same file, reached from the crate root (the test's working
+        // directory) through `src/`, which the scratch dir rustfmt runs
+        // in doesn't have.
+        let cwd = std::env::current_dir().unwrap();
+        let relative: PathBuf = std::iter::once(Path::new("src"))
+            .chain(cwd.components().map(|_| Path::new("..")))
+            .chain(config.strip_prefix("/").ok())
+            .collect();
+        assert!(relative.is_relative());
+        let backend = LocalBackend::new(Limits::default()).with_rustfmt_config(Some(relative));
+
+        let out = backend.format("fn main(){let x=1;}").await.unwrap();
+        assert!(out.success, "{}", out.stderr);
+        assert_eq!(out.code, "fn main() {\n\tlet x = 1;\n}\n");
+    }
+
+    #[tokio::test]
+    async fn local_formatter_keeps_code_when_output_is_truncated() {
+        let backend = LocalBackend::new(Limits {
+            max_output_bytes: 16,
+            ..Limits::default()
+        });
+        let code = "fn main(){let greeting=\"hello\";println!(\"{greeting}\");}";
+        let out = backend.format(code).await.unwrap();
+        assert!(!out.success);
+        assert_eq!(out.code, code);
+        assert!(out.stderr.contains("output truncated"), "{}", out.stderr);
+    }
+
+    #[tokio::test]
+    async fn local_backend_runs_tests_and_reports_failures() {
+        let backend = LocalBackend::new(Limits::default());
+        let code = "fn add(a: i32, b: i32) -> i32 { a + b }\n\
+                    #[test] fn adds() { assert_eq!(add(1, 2), 3); }\n\
+                    #[test] fn fails() { assert_eq!(add(1, 1), 3); }\n";
+        let out = backend
+            .run(RunJob { code, tests: true })
+            .await
+            .expect("rustc should be installed where tests run");
+        assert!(!out.success, "a failing test fails the run");
+        assert!(out.stdout.contains("test adds ... ok"), "{}", out.stdout);
+        assert!(
+            out.stdout.contains("test fails ... FAILED"),
+            "{}",
+            out.stdout
+        );
+    }
+
+    #[tokio::test]
+    async fn local_backend_reports_compile_errors_on_stderr() {
+        let backend = LocalBackend::new(Limits::default());
+        let out = backend
+            .run(RunJob {
+                code: "fn main() { let x: i32 = \"no\"; }",
+                tests: false,
+            })
+            .await
+            .expect("rustc should be installed where tests run");
+        assert!(!out.success);
+        assert!(out.stderr.contains("E0308"), "{}", out.stderr);
+        let error = &out.diagnostics[0];
+        assert_eq!(error.code.as_deref(), Some("E0308"));
+        let span = error.primary_span().expect("a primary span");
+        assert_eq!((span.line_start, span.column_start), (1, 26));
+    }
+
+    #[tokio::test]
+    async fn local_linter_returns_structured_lints_with_fixes() {
+        let backend = LocalBackend::new(Limits::default());
+        let out = LintBackend::clippy(
+            &backend,
+            "fn main() {\n    println!(\"{}\", one());\n}\n\nfn one() -> i32 {\n    return 1;\n}\n",
+        )
+        .await
+        .expect("clippy should be installed where tests run");
+        assert!(out.success, "{}", out.stderr);
+        let lint = out
+            .diagnostics
+            .iter()
+            .find(|d| d.code.as_deref() == Some("clippy::needless_return"))
+            .unwrap_or_else(|| panic!("{:#?}", out.diagnostics));
+        assert_eq!(lint.primary_span().map(|span| span.line_start), Some(6));
+        let fix = lint.machine_applicable_fixes().next().expect("a fix");
+        assert_eq!(fix.suggested_replacement.as_deref(), Some("1"));
+        assert!(out.stderr.contains("needless_return"), "{}", out.stderr);
+    }
+
+    #[tokio::test]
+    async fn local_backend_enforces_wall_clock_and_output_limits() {
+        let backend = LocalBackend::new(Limits {
+            wall_clock: Duration::from_secs(3),
+            max_output_bytes: 1024,
+            ..Limits::default()
+        });
+        let out = backend
+            .run(RunJob {
+                code: "fn main() { loop { println!(\"spam\"); } }",
+                tests: false,
+            })
+            .await
+            .expect("rustc should be installed where tests run");
+        assert!(!out.success);
+        assert!(out.stdout.len() <= 1024);
+        assert!(out.stderr.contains("note: killed"), "{}", out.stderr);
+    }
+}
@@ -0,0 +1,689 @@
+//! Signed, expiring sessions, and the credentials that start them.
+//!
+//! The admin UI used to authenticate every request with `?token=` in the
+//! URL, which leaked the admin token into browser history, proxy logs
+//! and screenshots. The operator now signs in once at `/admin/login`
+//! and gets a cookie instead:
+//!
+//! ```text
+//! <epoch>.<expires-unix-seconds>.<nonce-hex>.<hmac-hex>
+//! ```
+//!
+//! The HMAC-SHA256 covers the epoch, the expiry and the nonce, keyed by
+//! a [`SessionKey`] derived from the admin token, so rotating
+//! `CORRODE_ADMIN_TOKEN` signs everybody out. The epoch is the admin's
+//! `auth_epoch` at sign-in; signing out bumps it in the database, which
+//! ends every session issued before.
+//!
+//! Instructors sign in on the same form with their own token
+//! ([`new_instructor_token`]) and get the same cookie with their id in
+//! front, `<instructor-id>.<epoch>.<expires>.<nonce>.<hmac>`, carrying
+//! their own epoch. Which teams they may see is looked up per request,
+//! so deleting an instructor ends their sessions too.
+//!
+//! Failed sign-ins are counted by a [`LoginThrottle`], which makes
+//! each client address (and, for participants, each account) wait
+//! longer after a few wrong tries.
+//!
+//! Mutating admin requests also carry a CSRF token (a hidden form field
+//! or the `X-CSRF-Token` header). It is a second MAC over the session
+//! nonce, so it is tied to one login and can't be computed without the
+//! key.
+//!
+//! Participants are identified by the ULID in their login link. Those
+//! who opt into a stronger mode (by setting a passphrase, or when an
+//! instructor hands them a one-time login code) get a
+//! [`ParticipantSession`] cookie the same way, prefixed with their ULID
+//! and their `auth_epoch`. Bumping the epoch in the database signs out
+//! every browser at once. The CLI authenticates with API tokens
+//! ([`new_api_token`]) that are stored only as [`secret_hash`]es.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::sync::{Mutex, PoisonError};
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Name of the admin session cookie.
+pub const ADMIN_COOKIE: &str = "corrode_admin";
+
+/// How long a login lasts, in seconds (12 hours: one workshop day).
+pub const ADMIN_SESSION_TTL: i64 = 12 * 60 * 60;
+
+/// Name of the participant session cookie.
+pub const PARTICIPANT_COOKIE: &str = "corrode_session";
+
+/// How long a participant stays signed in on one browser (30 days).
+pub const PARTICIPANT_SESSION_TTL: i64 = 30 * 24 * 60 * 60;
+
+/// How long an instructor-issued login code stays valid (24 hours).
+pub const LOGIN_CODE_TTL: i64 = 24 * 60 * 60;
+
+/// Failed sign-ins an address or account gets before [`LoginThrottle`]
+/// makes it wait.
+pub const FREE_LOGIN_ATTEMPTS: u32 = 3;
+
+/// Longest wait [`LoginThrottle`] imposes, in seconds (5 minutes).
+pub const MAX_LOGIN_BACKOFF: i64 = 5 * 60;
+
+/// Shortest passphrase [`check_passphrase`] accepts.
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Longest passphrase we hash; keeps Argon2 input bounded.
+pub const MAX_PASSPHRASE_LEN: usize = 256;
+
+/// Key that signs session cookies and CSRF tokens.
+#[derive(Clone)]
+pub struct SessionKey {
+    key: [u8; 32],
+}
+
+impl std::fmt::Debug for SessionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SessionKey(..)")
+    }
+}
+
+/// A verified admin session, recovered from its cookie. The caller
+/// still has to compare `epoch` with the admin's current `auth_epoch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminSession {
+    /// `None` for the super-admin (signed in with the admin token),
+    /// else the instructor's id.
+    pub instructor_id: Option<String>,
+    pub epoch: i64,
+    /// Unix timestamp after which the cookie is no longer accepted.
+    pub expires_at: i64,
+    nonce: String,
+}
+
+/// A verified participant session. The caller still has to compare
+/// `epoch` with the participant's current `auth_epoch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParticipantSession {
+    pub participant_id: String,
+    pub epoch: i64,
+    /// Unix timestamp after which the cookie is no longer accepted.
+    pub expires_at: i64,
+    nonce: String,
+}
+
+impl SessionKey {
+    /// Derive the signing key from the admin token. The domain prefix
+    /// keeps the key distinct from anything else hashed from the token.
+    #[must_use]
+    pub fn from_admin_token(admin_token: &str) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(b"corrode admin session v1\0");
+        hasher.update(admin_token.as_bytes());
+        Self {
+            key: hasher.finalize().into(),
+        }
+    }
+
+    fn mac(&self, parts: &[&[u8]]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC takes any key length");
+        for part in parts {
+            mac.update(part);
+            mac.update(b"|");
+        }
+        mac
+    }
+
+    /// Start a super-admin session at `now` (unix seconds), valid while
+    /// the admin's `auth_epoch` stays `epoch`. Returns the session and
+    /// the cookie value to hand to the browser.
+    #[must_use]
+    pub fn issue(&self, epoch: i64, now: i64) -> (AdminSession, String) {
+        self.issue_admin(None, epoch, now)
+    }
+
+    /// Start a session for instructor `instructor_id` at `now`, valid
+    /// while their `auth_epoch` stays `epoch`.
+    #[must_use]
+    pub fn issue_instructor(
+        &self,
+        instructor_id: &str,
+        epoch: i64,
+        now: i64,
+    ) -> (AdminSession, String) {
+        self.issue_admin(Some(instructor_id.to_string()), epoch, now)
+    }
+
+    fn issue_admin(
+        &self,
+        instructor_id: Option<String>,
+        epoch: i64,
+        now: i64,
+    ) -> (AdminSession, String) {
+        let session = AdminSession {
+            instructor_id,
+            epoch,
+            expires_at: now + ADMIN_SESSION_TTL,
+            nonce: to_hex(&rand::random::<[u8; 16]>()),
+        };
+        let epoch = session.epoch.to_string();
+        let expires = session.expires_at.to_string();
+        let tag = to_hex(
+            &self
+                .admin_mac(
+                    session.instructor_id.as_deref(),
+                    &epoch,
+                    &expires,
+                    &session.nonce,
+                )
+                .finalize()
+                .into_bytes(),
+        );
+        let cookie = match &session.instructor_id {
+            None => format!("{epoch}.{expires}.{}.{tag}", session.nonce),
+            Some(id) => format!("{id}.{epoch}.{expires}.{}.{tag}", session.nonce),
+        };
+        (session, cookie)
+    }
+
+    fn admin_mac(
+        &self,
+        instructor_id: Option<&str>,
+        epoch: &str,
+        expires: &str,
+        nonce: &str,
+    ) -> HmacSha256 {
+        instructor_id.map_or_else(
+            || {
+                self.mac(&[
+                    b"admin",
+                    epoch.as_bytes(),
+                    expires.as_bytes(),
+                    nonce.as_bytes(),
+                ])
+            },
+            |id| {
+                self.mac(&[
+                    b"instructor",
+                    id.as_bytes(),
+                    epoch.as_bytes(),
+                    expires.as_bytes(),
+                    nonce.as_bytes(),
+                ])
+            },
+        )
+    }
+
+    /// Check a cookie value's signature and expiry. `None` for anything
+    /// malformed, tampered with or expired.
+    #[must_use]
+    pub fn verify(&self, cookie: &str, now: i64) -> Option<AdminSession> {
+        let parts: Vec<&str> = cookie.trim().split('.').collect();
+        let (instructor_id, epoch, expires, nonce, tag) = match parts.as_slice() {
+            [epoch, expires, nonce, tag] => (None, *epoch, *expires, *nonce, *tag),
+            [id, epoch, expires, nonce, tag] => (Some(*id), *epoch, *expires, *nonce, *tag),
+            _ => return None,
+        };
+        self.admin_mac(instructor_id, epoch, expires, nonce)
+            .verify_slice(&from_hex(tag)?)
+            .ok()?;
+        let expires_at: i64 = expires.parse().ok()?;
+        let epoch: i64 = epoch.parse().ok()?;
+        (now < expires_at).then(|| AdminSession {
+            instructor_id: instructor_id.map(str::to_string),
+            epoch,
+            expires_at,
+            nonce: nonce.to_string(),
+        })
+    }
+
+    /// The CSRF token for `session`, to embed in admin forms.
+    #[must_use]
+    pub fn csrf_token(&self, session: &AdminSession) -> String {
+        let tag = self
+            .mac(&[b"csrf", session.nonce.as_bytes()])
+            .finalize()
+            .into_bytes();
+        to_hex(&tag)
+    }
+
+    /// Whether `presented` is the CSRF token of `session`. Constant-time.
+    #[must_use]
+    pub fn check_csrf(&self, session: &AdminSession, presented: &str) -> bool {
+        from_hex(presented).is_some_and(|tag| {
+            self.mac(&[b"csrf", session.nonce.as_bytes()])
+                .verify_slice(&tag)
+                .is_ok()
+        })
+    }
+
+    /// Start a session for `participant_id` at `now`, valid while the
+    /// participant's `auth_epoch` stays `epoch`.
+    #[must_use]
+    pub fn issue_participant(
+        &self,
+        participant_id: &str,
+        epoch: i64,
+        now: i64,
+    ) -> (ParticipantSession, String) {
+        let session = ParticipantSession {
+            participant_id: participant_id.to_string(),
+            epoch,
+            expires_at: now + PARTICIPANT_SESSION_TTL,
+            nonce: to_hex(&rand::random::<[u8; 16]>()),
+        };
+        let epoch = session.epoch.to_string();
+        let expires = session.expires_at.to_string();
+        let tag = self
+            .mac(&[
+                b"participant",
+                session.participant_id.as_bytes(),
+                epoch.as_bytes(),
+                expires.as_bytes(),
+                session.nonce.as_bytes(),
+            ])
+            .finalize()
+            .into_bytes();
+        let cookie = format!(
+            "{}.{epoch}.{expires}.{}.{}",
+            session.participant_id,
+            session.nonce,
+            to_hex(&tag)
+        );
+        (session, cookie)
+    }
+
+    /// Check a participant cookie's signature and expiry.
+    #[must_use]
+    pub fn verify_participant(&self, cookie: &str, now: i64) -> Option<ParticipantSession> {
+        let mut parts = cookie.trim().splitn(5, '.');
+        let participant_id = parts.next()?;
+        let epoch = parts.next()?;
+        let expires = parts.next()?;
+        let nonce = parts.next()?;
+        let tag = from_hex(parts.next()?)?;
+        self.mac(&[
+            b"participant",
+            participant_id.as_bytes(),
+            epoch.as_bytes(),
+            expires.as_bytes(),
+            nonce.as_bytes(),
+        ])
+        .verify_slice(&tag)
+        .ok()?;
+        let expires_at: i64 = expires.parse().ok()?;
+        let epoch: i64 = epoch.parse().ok()?;
+        (now < expires_at).then(|| ParticipantSession {
+            participant_id: participant_id.to_string(),
+            epoch,
+            expires_at,
+            nonce: nonce.to_string(),
+        })
+    }
+
+    /// CSRF token for the account forms on `participant_id`'s settings
+    /// page. Bound to the browser's session when there is one.
+    #[must_use]
+    pub fn participant_csrf_token(
+        &self,
+        participant_id: &str,
+        session: Option<&ParticipantSession>,
+    ) -> String {
+        let nonce = session.map_or("", |session| session.nonce.as_str());
+        let tag = self
+            .mac(&[
+                b"participant-csrf",
+                participant_id.as_bytes(),
+                nonce.as_bytes(),
+            ])
+            .finalize()
+            .into_bytes();
+        to_hex(&tag)
+    }
+
+    /// Whether `presented` is [`Self::participant_csrf_token`] for the
+    /// same participant and session. Constant-time.
+    #[must_use]
+    pub fn check_participant_csrf(
+        &self,
+        participant_id: &str,
+        session: Option<&ParticipantSession>,
+        presented: &str,
+    ) -> bool {
+        let nonce = session.map_or("", |session| session.nonce.as_str());
+        from_hex(presented).is_some_and(|tag| {
+            self.mac(&[
+                b"participant-csrf",
+                participant_id.as_bytes(),
+                nonce.as_bytes(),
+            ])
+            .verify_slice(&tag)
+            .is_ok()
+        })
+    }
+
+    /// Compare a typed-in admin token against the configured one
+    /// without leaking the position of the first mismatch.
+    #[must_use]
+    pub fn admin_token_matches(admin_token: &str, presented: &str) -> bool {
+        // Comparing MACs of both sides keeps the comparison fixed-length.
+        let expected = Self::from_admin_token(admin_token);
+        let tag = expected.mac(&[b"login", admin_token.as_bytes()]).finalize();
+        expected
+            .mac(&[b"login", presented.as_bytes()])
+            .verify_slice(&tag.into_bytes())
+            .is_ok()
+    }
+}
+
+/// Failed sign-ins per `K`: a client address for the admin form.
+///
+/// The first [`FREE_LOGIN_ATTEMPTS`] are free, then each one doubles the
+/// wait before the next attempt, up to [`MAX_LOGIN_BACKOFF`]. Kept in
+/// memory; a restart forgets it.
+#[derive(Debug)]
+pub struct LoginThrottle<K = IpAddr> {
+    /// Failures and the time of the latest one, per key.
+    failures: Mutex<HashMap<K, (u32, i64)>>,
+}
+
+impl<K> Default for LoginThrottle<K> {
+    fn default() -> Self {
+        Self {
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash> LoginThrottle<K> {
+    /// Seconds `key` still has to wait at `now` before it may try again;
+    /// 0 when it may go ahead.
+    #[must_use]
+    pub fn wait(&self, key: &K, now: i64) -> i64 {
+        let failures = self.failures.lock().unwrap_or_else(PoisonError::into_inner);
+        failures.get(key).map_or(0, |&(count, last)| {
+            (last + login_backoff(count) - now).max(0)
+        })
+    }
+
+    /// Count a failed sign-in by `key` at `now`. Keys that have waited
+    /// out the longest backoff are forgotten on the way, so the map
+    /// can't grow without bound.
+    pub fn failed(&self, key: K, now: i64) {
+        let mut failures = self.failures.lock().unwrap_or_else(PoisonError::into_inner);
+        failures.retain(|_, &mut (_, last)| now - last < MAX_LOGIN_BACKOFF);
+        let entry = failures.entry(key).or_insert((0, now));
+        *entry = (entry.0.saturating_add(1), now);
+        drop(failures);
+    }
+
+    /// Forget `key`'s failures after it signed in.
+    pub fn succeeded(&self, key: &K) {
+        self.failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(key);
+    }
+}
+
+/// How long to wait after `failures` failed sign-ins in a row.
+fn login_backoff(failures: u32) -> i64 {
+    failures
+        .checked_sub(FREE_LOGIN_ATTEMPTS)
+        .map_or(0, |extra| (1_i64 << extra.min(16)).min(MAX_LOGIN_BACKOFF))
+}
+
+/// Why [`check_passphrase`] rejected a passphrase, phrased for the form.
+pub fn check_passphrase(passphrase: &str) -> Result<(), &'static str> {
+    let len = passphrase.chars().count();
+    if len < MIN_PASSPHRASE_LEN {
+        Err("Use at least 8 characters.")
+    } else if len > MAX_PASSPHRASE_LEN {
+        Err("Use at most 256 characters.")
+    } else {
+        Ok(())
+    }
+}
+
+/// Argon2id hash of `passphrase` in PHC string format, with a fresh salt.
+pub fn hash_passphrase(passphrase: &str) -> Result<String, argon2::password_hash::Error> {
+    use argon2::password_hash::{PasswordHasher, SaltString};
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())?;
+    Ok(argon2::Argon2::default()
+        .hash_password(passphrase.as_bytes(), &salt)?
+        .to_string())
+}
+
+/// Whether `passphrase` matches a hash from [`hash_passphrase`].
+#[must_use]
+pub fn verify_passphrase(passphrase: &str, hash: &str) -> bool {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+    PasswordHash::new(hash).is_ok_and(|parsed| {
+        argon2::Argon2::default()
+            .verify_password(passphrase.as_bytes(), &parsed)
+            .is_ok()
+    })
+}
+
+/// Letters and digits a login code is drawn from; no `0`/`O`, `1`/`I`/`L`
+/// or `U` so a code read aloud in a workshop can't be mistyped.
+const LOGIN_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTVWXYZ23456789";
+
+/// A fresh one-time login code, e.g. `K7QX-3MPA`.
+#[must_use]
+pub fn new_login_code() -> String {
+    let mut code = String::with_capacity(9);
+    for i in 0..8 {
+        if i == 4 {
+            code.push('-');
+        }
+        let index = rand::random_range(0..LOGIN_CODE_ALPHABET.len());
+        code.push(char::from(LOGIN_CODE_ALPHABET[index]));
+    }
+    code
+}
+
+/// Canonical form of a typed-in login code: upper case, without the
+/// dash or spaces.
+#[must_use]
+pub fn normalize_login_code(raw: &str) -> String {
+    raw.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// A fresh API token for the CLI. The `cc_` prefix tells it apart from
+/// a bare participant ULID, which older CLI installs send instead.
+#[must_use]
+pub fn new_api_token() -> String {
+    format!("cc_{}", to_hex(&rand::random::<[u8; 20]>()))
+}
+
+/// A fresh sign-in token for an instructor account, shown once to the
+/// super-admin who creates it. The `ci_` prefix tells the admin login
+/// form to look it up instead of comparing it with the admin token.
+#[must_use]
+pub fn new_instructor_token() -> String {
+    format!("ci_{}", to_hex(&rand::random::<[u8; 20]>()))
+}
+
+/// What we store for a login code, API token or instructor token: its
+/// SHA-256, hex. All three are random enough that a plain hash doesn't
+/// need salting.
+#[must_use]
+pub fn secret_hash(secret: &str) -> String {
+    to_hex(&Sha256::digest(secret.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cookies_verify_until_they_expire() {
+        let key = SessionKey::from_admin_token("secret");
+        let (session, cookie) = key.issue(2, 1_000);
+        assert_eq!(session.expires_at, 1_000 + ADMIN_SESSION_TTL);
+
+        assert_eq!(key.verify(&cookie, 1_001), Some(session.clone()));
+        assert_eq!(session.epoch, 2);
+        assert_eq!(key.verify(&cookie, session.expires_at), None);
+
+        // A different admin token (e.g. after rotation) rejects it.
+        assert_eq!(
+            SessionKey::from_admin_token("other").verify(&cookie, 1_001),
+            None
+        );
+
+        // Stretching the expiry or rolling back the epoch breaks the
+        // signature.
+        let forged = cookie.replacen(
+            &session.expires_at.to_string(),
+            &(session.expires_at + 3_600).to_string(),
+            1,
+        );
+        assert_eq!(key.verify(&forged, 1_001), None);
+        let (_, rest) = cookie.split_once('.').unwrap();
+        assert_eq!(key.verify(&format!("1.{rest}"), 1_001), None);
+        assert_eq!(key.verify("", 1_001), None);
+        assert_eq!(key.verify("1.2.zz", 1_001), None);
+    }
+
+    #[test]
+    fn instructor_cookies_carry_the_instructor_id() {
+        let key = SessionKey::from_admin_token("secret");
+        let id = "01ARZ3NDEKTSV4RRFFQ69G5FAV";
+        let (session, cookie) = key.issue_instructor(id, 0, 1_000);
+        assert!(cookie.starts_with(id));
+
+        let verified = key.verify(&cookie, 1_001).unwrap();
+        assert_eq!(verified, session);
+        assert_eq!(verified.instructor_id.as_deref(), Some(id));
+
+        // Dropping the id doesn't turn the cookie into a super-admin one.
+        let (_, rest) = cookie.split_once('.').unwrap();
+        assert_eq!(key.verify(rest, 1_001), None);
+        let swapped = cookie.replacen(id, "01BX5ZZKBKACTAV9WEVGEMMVRZ", 1);
+        assert_eq!(key.verify(&swapped, 1_001), None);
+
+        let (admin, _) = key.issue(0, 1_000);
+        assert_eq!(admin.instructor_id, None);
+    }
+
+    #[test]
+    fn csrf_tokens_are_bound_to_the_session() {
+        let key = SessionKey::from_admin_token("secret");
+        let (first, _) = key.issue(0, 0);
+        let (second, _) = key.issue(0, 0);
+
+        let token = key.csrf_token(&first);
+        assert!(key.check_csrf(&first, &token));
+        assert!(!key.check_csrf(&second, &token));
+        assert!(!key.check_csrf(&first, ""));
+        assert!(!key.check_csrf(&first, "not hex"));
+    }
+
+    #[test]
+    fn participant_cookies_carry_id_and_epoch() {
+        let key = SessionKey::from_admin_token("secret");
+        let ulid = "01ARZ3NDEKTSV4RRFFQ69G5FAV";
+        let (session, cookie) = key.issue_participant(ulid, 3, 1_000);
+
+        let verified = key.verify_participant(&cookie, 1_001).unwrap();
+        assert_eq!(verified, session);
+        assert_eq!(verified.participant_id, ulid);
+        assert_eq!(verified.epoch, 3);
+        assert_eq!(key.verify_participant(&cookie, session.expires_at), None);
+
+        // Another participant's id (or an admin cookie) doesn't verify.
+        let swapped = cookie.replacen(ulid, "01BX5ZZKBKACTAV9WEVGEMMVRZ", 1);
+        assert_eq!(key.verify_participant(&swapped, 1_001), None);
+        let (_, admin) = key.issue(0, 1_000);
+        assert_eq!(key.verify_participant(&admin, 1_001), None);
+        assert_eq!(key.verify(&cookie, 1_001), None);
+
+        let token = key.participant_csrf_token(ulid, Some(&session));
+        assert!(key.check_participant_csrf(ulid, Some(&session), &token));
+        assert!(!key.check_participant_csrf(ulid, None, &token));
+        let anonymous = key.participant_csrf_token(ulid, None);
+        assert!(key.check_participant_csrf(ulid, None, &anonymous));
+        assert!(!key.check_participant_csrf("other", None, &anonymous));
+    }
+
+    #[test]
+    fn passphrases_hash_and_verify() {
+        assert!(check_passphrase("short").is_err());
+        assert!(check_passphrase("correct horse").is_ok());
+
+        let hash = hash_passphrase("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify_passphrase("correct horse", &hash));
+        assert!(!verify_passphrase("correct horse!", &hash));
+        assert!(!verify_passphrase("correct horse", "not a hash"));
+    }
+
+    #[test]
+    fn login_codes_and_api_tokens() {
+        let code = new_login_code();
+        assert_eq!(code.len(), 9);
+        assert_eq!(code.as_bytes()[4], b'-');
+        assert_eq!(normalize_login_code(&code.to_lowercase()).len(), 8);
+        assert_eq!(normalize_login_code(" k7qx-3mpa "), "K7QX3MPA");
+
+        assert!(new_instructor_token().starts_with("ci_"));
+        let token = new_api_token();
+        assert!(token.starts_with("cc_"));
+        assert_ne!(token, new_api_token());
+        assert_eq!(secret_hash(&token).len(), 64);
+        assert_eq!(secret_hash(&token), secret_hash(&token));
+    }
+
+    #[test]
+    fn login_throttle_backs_off_per_address() {
+        let throttle: LoginThrottle = LoginThrottle::default();
+        let attacker: IpAddr = [192, 0, 2, 1].into();
+        let admin: IpAddr = [192, 0, 2, 2].into();
+        for _ in 0..FREE_LOGIN_ATTEMPTS {
+            assert_eq!(throttle.wait(&attacker, 100), 0);
+            throttle.failed(attacker, 100);
+        }
+        assert_eq!(throttle.wait(&attacker, 100), 1);
+        throttle.failed(attacker, 101);
+        assert_eq!(throttle.wait(&attacker, 101), 2);
+        assert_eq!(throttle.wait(&attacker, 103), 0);
+        assert_eq!(throttle.wait(&admin, 101), 0);
+
+        for _ in 0..32 {
+            throttle.failed(attacker, 200);
+        }
+        assert_eq!(throttle.wait(&attacker, 200), MAX_LOGIN_BACKOFF);
+        throttle.succeeded(&attacker);
+        assert_eq!(throttle.wait(&attacker, 200), 0);
+    }
+
+    #[test]
+    fn admin_token_comparison() {
+        assert!(SessionKey::admin_token_matches("secret", "secret"));
+        assert!(!SessionKey::admin_token_matches("secret", "secre"));
+        assert!(!SessionKey::admin_token_matches("secret", ""));
+    }
+}
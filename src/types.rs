@@ -2,7 +2,8 @@
 //!
 //! This module provides the core data types used for communication between
 //! the corrode course CLI tool and server. These types handle participant
-//! registration, exercise submission, and progress tracking.
+//! registration, exercise submission, progress tracking, and the export
+//! and deletion of a participant's data.
 //!
 //! The types are designed to be:
 //! - Validated at construction time to prevent invalid states
@@ -10,10 +11,13 @@
 //! - Ergonomic to use with appropriate conversion traits
 
 use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
 
+use crate::exercises::Difficulty;
+
 /// A validated team token.
 ///
 /// Stored on `participants.team_token`. Populated server-side from
@@ -290,6 +294,20 @@ impl Token {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// `true` for an API token created on the settings page (`cc_…`),
+    /// `false` for a participant ULID.
+    ///
+    /// ```
+    /// use cargo_course::types::Token;
+    ///
+    /// assert!(Token::new("cc_0123abcd".to_string()).is_api_token());
+    /// assert!(!Token::new("01ARZ3NDEKTSV4RRFFQ69G5FAV".to_string()).is_api_token());
+    /// ```
+    #[must_use]
+    pub fn is_api_token(&self) -> bool {
+        self.0.starts_with("cc_")
+    }
 }
 
 impl FromStr for Token {
@@ -318,6 +336,10 @@ impl fmt::Display for Token {
 pub struct RegistrationRequest {
     /// The participant's chosen name (already validated)
     pub name: Name,
+    /// Slug of the course to enroll in; the server's default course
+    /// when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub course: Option<String>,
 }
 
 /// Response payload from successful participant registration.
@@ -336,7 +358,8 @@ pub struct RegistrationResponse {
 /// testing and linting checks performed by the CLI.
 #[derive(Serialize, Deserialize)]
 pub struct SubmissionRequest {
-    /// The participant's ULID token for identification
+    /// The participant's ULID, or an API token (`cc_…`) for accounts
+    /// that require sign-in
     pub ulid: String,
     /// Name of the exercise (e.g., "`01_strings`")
     pub exercise_name: String,
@@ -348,6 +371,10 @@ pub struct SubmissionRequest {
     pub clippy_passed: bool,
     /// Whether `cargo fmt --check` passed (for pedantic submissions)
     pub fmt_passed: bool,
+    /// When the participant submitted, for submissions the CLI queued
+    /// while the server was unreachable. `None` means now.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submitted_at: Option<DateTime<Utc>>,
 }
 
 /// Status information for a single exercise.
@@ -362,6 +389,23 @@ pub struct ExerciseStatus {
     pub completed: bool,
     /// Whether the solution passed all pedantic checks (fmt + clippy)
     pub perfected: bool,
+    /// What the participant still needs before the chapter opens, when
+    /// it is locked (see `.chapter.toml`'s `requires` and `unlock`)
+    #[serde(default)]
+    pub locked: Option<String>,
+    /// Steps with at least one submission, as `<chapter>/<step_key>`
+    /// (or just `<chapter>` for single-step chapters)
+    #[serde(default)]
+    pub submitted_steps: Vec<String>,
+    /// The subset of `submitted_steps` with a submission that passed
+    #[serde(default)]
+    pub passed_steps: Vec<String>,
+    /// The chapter's difficulty from `.chapter.toml`, if it has one
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    /// The chapter's tags from `.chapter.toml`
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 /// Response payload containing a participant's overall progress.
@@ -373,3 +417,145 @@ pub struct ProgressResponse {
     /// Status for each exercise in the course
     pub exercises: Vec<ExerciseStatus>,
 }
+
+/// Everything the server stores about one participant: the data export
+/// behind "Download my data" on the settings page and
+/// `cargo course export`.
+///
+/// The rows derive `sqlx::FromRow` so the server can load them straight
+/// into this shape; the field names are the column names.
+#[derive(Serialize, Deserialize)]
+pub struct AccountExport {
+    /// When the server assembled this export
+    pub exported_at: DateTime<Utc>,
+    pub participant: ExportedParticipant,
+    /// The courses the participant is enrolled in
+    #[serde(default)]
+    pub enrollments: Vec<ExportedEnrollment>,
+    /// Every submission, oldest first, with the full source code
+    pub submissions: Vec<ExportedSubmission>,
+    /// Analytics events from the course pages, oldest first
+    pub course_events: Vec<ExportedCourseEvent>,
+    /// Answers to chapter quizzes, oldest first
+    #[serde(default)]
+    pub quiz_answers: Vec<ExportedQuizAnswer>,
+    /// API tokens created on the settings page (never the secrets)
+    pub api_tokens: Vec<ExportedApiToken>,
+    /// Admin and instructor actions that touched this account
+    pub admin_actions: Vec<ExportedAdminAction>,
+}
+
+/// The participant row.
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct ExportedParticipant {
+    pub id: String,
+    pub name: String,
+    pub team_token: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub auth_required: bool,
+    /// Whether a passphrase is set; the hash itself is not exported
+    pub has_passphrase: bool,
+}
+
+/// A course the participant is enrolled in.
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct ExportedEnrollment {
+    /// The course's slug
+    pub course: String,
+    pub enrolled_at: DateTime<Utc>,
+}
+
+/// One stored submission.
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct ExportedSubmission {
+    pub id: String,
+    /// Slug of the course the exercise belongs to
+    #[serde(default = "default_course")]
+    pub course: String,
+    pub exercise_name: String,
+    pub source_code: String,
+    pub tests_passed: bool,
+    pub clippy_passed: bool,
+    pub fmt_passed: bool,
+    pub submitted_at: DateTime<Utc>,
+    /// `unverified`, `pending`, `verified` or `failed`
+    pub verification: String,
+    pub verification_log: Option<String>,
+}
+
+/// One analytics event, with the compiler diagnostics it carried.
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct ExportedCourseEvent {
+    pub id: String,
+    #[serde(default = "default_course")]
+    pub course: String,
+    pub session_id: String,
+    pub event_type: String,
+    pub exercise_name: Option<String>,
+    pub result: Option<String>,
+    pub tests_passed: Option<i64>,
+    pub tests_total: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub diagnostic_code: Option<String>,
+    pub course_version: String,
+    pub git_hash: String,
+    pub created_at: DateTime<Utc>,
+    #[sqlx(skip)]
+    pub diagnostics: Vec<ExportedDiagnostic>,
+}
+
+/// One answer to a chapter quiz question.
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct ExportedQuizAnswer {
+    pub course: String,
+    pub chapter: String,
+    /// Groups the answers given in one go at the quiz
+    pub attempt: String,
+    /// 0-based position of the question in the quiz
+    pub question: i64,
+    pub answer: String,
+    pub correct: bool,
+    pub answered_at: DateTime<Utc>,
+}
+
+/// Exports from servers that predate several courses only had the one.
+fn default_course() -> String {
+    crate::courses::DEFAULT_SLUG.to_string()
+}
+
+/// A diagnostic code counted on an `exercise_run` event.
+#[derive(Serialize, Deserialize)]
+pub struct ExportedDiagnostic {
+    pub code: String,
+    /// `error` or `warning`
+    pub level: String,
+    pub occurrences: i64,
+}
+
+/// An API token's metadata.
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct ExportedApiToken {
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// An entry from the admin audit log about this participant. Who
+/// performed it is left out.
+#[derive(Serialize, Deserialize)]
+pub struct ExportedAdminAction {
+    pub created_at: DateTime<Utc>,
+    /// e.g. `participant.move`
+    pub action: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+/// Request payload for deleting a participant's account and all of
+/// their data (`cargo course delete-account`).
+#[derive(Serialize, Deserialize)]
+pub struct AccountDeletionRequest {
+    /// The participant's ULID, or an API token (`cc_…`)
+    pub ulid: String,
+}
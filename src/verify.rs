@@ -0,0 +1,396 @@
+//! Server-side re-verification of submissions.
+//!
+//! Clients report `tests_passed`, `clippy_passed` and `fmt_passed`
+//! alongside the source they submit, but nothing stops someone from
+//! `curl`ing `true` for every step. When verification is enabled the
+//! server keeps those booleans as the *claim* and recomputes the real
+//! verdict itself, using the [`LocalBackend`] toolchain:
+//!
+//! * **tests**: every `#[test]` fn and `#[cfg(test)]` module is stripped
+//!   from the submission and replaced with the ones from the chapter's
+//!   own step file ([`with_reference_tests`]), so editing or deleting a
+//!   test doesn't help. The reference assertions call `::std::assert*!`
+//!   so a submitted `macro_rules! assert_eq` can't silence them. The
+//!   result is compiled with `--test` and run, and only counts when
+//!   libtest itself reports every reference test passing: a program
+//!   that exits early with status 0 has run no tests. The submission
+//!   shares stdout with libtest and can print a summary of its own, so
+//!   each reference test gets a random suffix it can't predict, and an
+//!   empty sentinel test sorts after all of them. Tests run one at a
+//!   time in name order, so an `ok` for the sentinel means every other
+//!   test finished before it. Code under test can forge the result of
+//!   the test calling it (libtest has printed its name already, and it
+//!   is the thread's name too), but not the sentinel's.
+//! * **fmt**: the submission must already be `rustfmt`-clean.
+//! * **clippy**: the submission (with its own tests) must pass clippy
+//!   with warnings denied.
+//!
+//! fmt and clippy are only checked once the tests pass, since a
+//! submission that doesn't pass its tests can't be "perfected" anyway.
+
+use proc_macro2::{Group, Ident, Punct, Spacing, TokenStream, TokenTree};
+use quote::{ToTokens, format_ident};
+
+use crate::runner::{LocalBackend, RunBackend, RunError, RunJob};
+
+/// How many bytes of tool output to keep in [`Verdict::log`].
+const LOG_LIMIT: usize = 4 * 1024;
+
+/// What the server found when it re-ran a submission.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Verdict {
+    pub tests_passed: bool,
+    pub clippy_passed: bool,
+    pub fmt_passed: bool,
+    /// Output of the first failing check (truncated), for instructors
+    /// looking into a claim the server disagreed with. Empty when every
+    /// check passed.
+    pub log: String,
+}
+
+/// Is `item` test-only code: a `#[test]` fn or a `#[cfg(test)]` item?
+fn is_test_item(item: &syn::Item) -> bool {
+    let attrs = match item {
+        syn::Item::Fn(f) => &f.attrs,
+        syn::Item::Mod(m) => &m.attrs,
+        syn::Item::Impl(i) => &i.attrs,
+        syn::Item::Use(u) => &u.attrs,
+        _ => return false,
+    };
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("test")
+            || (attr.path().is_ident("cfg")
+                && attr
+                    .parse_args::<syn::Ident>()
+                    .is_ok_and(|ident| ident == "test"))
+    })
+}
+
+/// Std macros the reference tests may call, which a submission could
+/// otherwise shadow with its own `macro_rules!`.
+const STD_MACROS: &[&str] = &[
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+    "matches",
+    "panic",
+    "format",
+    "vec",
+];
+
+/// Rewrite every bare `assert_eq!` (and the rest of [`STD_MACROS`]) in
+/// `tokens` to `::std::assert_eq!`, including inside other macros'
+/// arguments.
+fn pin_std_macros(tokens: TokenStream) -> TokenStream {
+    let mut out = Vec::new();
+    let mut iter = tokens.into_iter().peekable();
+    // Whether the previous token was `::`, so the path is already qualified.
+    let mut after_path_sep = false;
+    while let Some(token) = iter.next() {
+        match token {
+            TokenTree::Ident(ident)
+                if !after_path_sep
+                    && STD_MACROS.iter().any(|name| ident == name)
+                    && matches!(iter.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '!') =>
+            {
+                let span = ident.span();
+                for segment in ["std", &ident.to_string()] {
+                    out.push(TokenTree::Punct(Punct::new(':', Spacing::Joint)));
+                    out.push(TokenTree::Punct(Punct::new(':', Spacing::Alone)));
+                    out.push(TokenTree::Ident(Ident::new(segment, span)));
+                }
+                after_path_sep = false;
+            }
+            TokenTree::Group(group) => {
+                let mut pinned = Group::new(group.delimiter(), pin_std_macros(group.stream()));
+                pinned.set_span(group.span());
+                out.push(TokenTree::Group(pinned));
+                after_path_sep = false;
+            }
+            TokenTree::Punct(ref p) => {
+                after_path_sep = p.as_char() == ':' && p.spacing() == Spacing::Alone;
+                out.push(token);
+            }
+            other => {
+                after_path_sep = false;
+                out.push(other);
+            }
+        }
+    }
+    out.into_iter().collect()
+}
+
+/// Give every `#[test]` fn in `items` a random suffix, and collect the
+/// names libtest reports them under (`prefix` is the module path).
+fn rename_tests(items: &mut [syn::Item], prefix: &str, names: &mut Vec<String>) {
+    for item in items {
+        match item {
+            syn::Item::Fn(f) if f.attrs.iter().any(|attr| attr.path().is_ident("test")) => {
+                f.sig.ident = format_ident!("{}_{:016x}", f.sig.ident, rand::random::<u64>());
+                names.push(format!("{prefix}{}", f.sig.ident));
+            }
+            syn::Item::Mod(m) => {
+                if let Some((_, items)) = &mut m.content {
+                    rename_tests(items, &format!("{prefix}{}::", m.ident), names);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A submission with the reference tests spliced in.
+#[derive(Debug)]
+pub struct Harness {
+    /// Source for `rustc --test`: a token stream rendered back to text,
+    /// so only meant for the compiler, not for humans.
+    pub source: String,
+    /// The names libtest reports the reference tests under.
+    pub tests: Vec<String>,
+    /// An empty test whose name sorts after every one in `tests`.
+    pub sentinel: String,
+}
+
+/// Combine the non-test items of `submission` with the test items of
+/// `reference` (the chapter's step file) into one compilable source,
+/// with the tests renamed as described in the module docs.
+///
+/// Returns the parse error when the submission isn't valid Rust.
+pub fn with_reference_tests(submission: &str, reference: &str) -> Result<Harness, String> {
+    let mut file: syn::File =
+        syn::parse_file(submission).map_err(|e| format!("submission doesn't parse: {e}"))?;
+    let reference: syn::File =
+        syn::parse_file(reference).map_err(|e| format!("reference doesn't parse: {e}"))?;
+
+    file.items.retain(|item| !is_test_item(item));
+    let mut tests: Vec<syn::Item> = reference.items.into_iter().filter(is_test_item).collect();
+    let mut names = Vec::new();
+    rename_tests(&mut tests, "", &mut names);
+    // Only `z`s, and longer than any other name, so it sorts last.
+    let longest = names.iter().map(String::len).max().unwrap_or(0);
+    let sentinel = format_ident!("{}", "z".repeat(longest + 1));
+
+    let mut combined = file.into_token_stream();
+    for item in tests {
+        combined.extend(pin_std_macros(item.into_token_stream()));
+    }
+    combined.extend(quote::quote! {
+        #[test]
+        fn #sentinel() {}
+    });
+    Ok(Harness {
+        source: combined.to_string(),
+        tests: names,
+        sentinel: sentinel.to_string(),
+    })
+}
+
+/// Did libtest run the `harness` to the end and report every reference
+/// test passing? Exit status 0 alone is also what `process::exit(0)` in
+/// the code under test produces, before any test has run, and a summary
+/// line could have been printed by the submission.
+fn all_tests_passed(stdout: &str, harness: &Harness) -> bool {
+    let reported = |name: &str| {
+        let lines: Vec<&str> = stdout
+            .lines()
+            .filter(|line| {
+                line.strip_prefix("test ")
+                    .and_then(|rest| rest.strip_prefix(name))
+                    .is_some_and(|rest| rest.starts_with(" ... "))
+            })
+            .collect();
+        lines == [format!("test {name} ... ok")]
+    };
+    reported(&harness.sentinel) && harness.tests.iter().all(|name| reported(name))
+}
+
+/// Keep at most [`LOG_LIMIT`] bytes of `text`, cut on a char boundary.
+fn truncate_log(text: &str) -> String {
+    if text.len() <= LOG_LIMIT {
+        return text.to_string();
+    }
+    let mut end = LOG_LIMIT;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n… (truncated)", &text[..end])
+}
+
+/// Re-verify `submission` against the tests in `reference`.
+///
+/// A submission that fails to parse or compile is a normal failing
+/// verdict; `Err` is reserved for the toolchain itself being unusable
+/// (missing binary, scratch dir not writable), in which case the caller
+/// should retry later rather than record a verdict.
+pub async fn verify(
+    backend: &LocalBackend,
+    submission: &str,
+    reference: &str,
+) -> Result<Verdict, RunError> {
+    let harness = match with_reference_tests(submission, reference) {
+        Ok(harness) => harness,
+        Err(log) => {
+            return Ok(Verdict {
+                log,
+                ..Verdict::default()
+            });
+        }
+    };
+
+    let tests = backend
+        .run(RunJob {
+            code: &harness.source,
+            tests: true,
+        })
+        .await?;
+    if !tests.success || !all_tests_passed(&tests.stdout, &harness) {
+        let log = if tests.success {
+            format!(
+                "{}{}\nthe test harness didn't report {} passing test(s)\n",
+                tests.stderr,
+                tests.stdout,
+                harness.tests.len()
+            )
+        } else {
+            format!("{}{}", tests.stderr, tests.stdout)
+        };
+        return Ok(Verdict {
+            log: truncate_log(&log),
+            ..Verdict::default()
+        });
+    }
+
+    let mut log = String::new();
+    let fmt = backend.rustfmt(submission).await?;
+    let fmt_passed = fmt.success() && fmt.stdout == submission;
+    if !fmt_passed {
+        log.push_str(if fmt.success() {
+            "rustfmt would reformat this submission\n"
+        } else {
+            &fmt.stderr
+        });
+    }
+
+    let clippy = backend.clippy(submission).await?;
+    let clippy_passed = clippy.success();
+    if !clippy_passed {
+        log.push_str(&clippy.stderr);
+    }
+
+    Ok(Verdict {
+        tests_passed: true,
+        clippy_passed,
+        fmt_passed,
+        log: truncate_log(&log),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::Limits;
+
+    const REFERENCE: &str = "fn double(x: i32) -> i32 {\n    todo!()\n}\n\n\
+                             #[test]\nfn doubles() {\n    assert_eq!(double(2), 4);\n}\n";
+
+    #[test]
+    fn reference_tests_replace_submitted_ones() {
+        let submission = "fn double(x: i32) -> i32 {\n    x\n}\n\n\
+                          #[test]\nfn doubles() {}\n\n\
+                          #[cfg(test)]\nmod extra {}\n";
+        let harness = with_reference_tests(submission, REFERENCE).unwrap();
+        let combined = &harness.source;
+        assert_eq!(harness.tests.len(), 1);
+        assert!(harness.tests[0].starts_with("doubles_"), "{harness:?}");
+        assert!(combined.contains(&format!("fn {} ()", harness.tests[0])));
+        assert!(harness.sentinel > harness.tests[0], "{harness:?}");
+        assert!(
+            combined.contains(":: std :: assert_eq ! (double (2) , 4)"),
+            "{combined}"
+        );
+        assert!(!combined.contains("mod extra"), "{combined}");
+        assert_eq!(combined.matches("fn double (").count(), 1, "{combined}");
+
+        // A fresh suffix every time.
+        let again = with_reference_tests(submission, REFERENCE).unwrap();
+        assert_ne!(again.tests, harness.tests);
+    }
+
+    #[tokio::test]
+    async fn verify_rejects_submissions_that_gutted_the_tests() {
+        let backend = LocalBackend::new(Limits::default());
+        let cheat = "fn double(x: i32) -> i32 {\n    x\n}\n\n#[test]\nfn doubles() {}\n";
+        let verdict = verify(&backend, cheat, REFERENCE).await.unwrap();
+        assert!(!verdict.tests_passed);
+        assert!(verdict.log.contains("doubles"), "{}", verdict.log);
+    }
+
+    #[tokio::test]
+    async fn verify_rejects_submissions_that_exit_before_the_tests_run() {
+        let backend = LocalBackend::new(Limits::default());
+        let cheat = "fn double(_: i32) -> i32 {\n    std::process::exit(0)\n}\n";
+        let verdict = verify(&backend, cheat, REFERENCE).await.unwrap();
+        assert!(!verdict.tests_passed);
+        assert!(
+            verdict.log.contains("didn't report 1 passing test"),
+            "{}",
+            verdict.log
+        );
+    }
+
+    #[tokio::test]
+    async fn verify_rejects_submissions_that_forge_the_test_summary() {
+        let backend = LocalBackend::new(Limits::default());
+        let forged_summary = "use std::io::Write;\n\n\
+                              fn double(_x: i32) -> i32 {\n    \
+                              let _ = std::io::stdout()\n        \
+                              .write_all(b\"\\ntest result: ok. 1 passed; 0 failed; 0 ignored\\n\");\n    \
+                              std::process::exit(0)\n}\n";
+        // libtest has already printed `test <name> ... ` for the calling
+        // test, so finishing that line forges its result.
+        let forged_test = "use std::io::Write;\n\n\
+                           fn double(_x: i32) -> i32 {\n    \
+                           let _ = std::io::stdout()\n        \
+                           .write_all(b\"ok\\n\\ntest result: ok. 2 passed; 0 failed; 0 ignored\\n\");\n    \
+                           std::process::exit(0)\n}\n";
+        for cheat in [forged_summary, forged_test] {
+            let verdict = verify(&backend, cheat, REFERENCE).await.unwrap();
+            assert!(!verdict.tests_passed, "{cheat}");
+            assert!(
+                verdict.log.contains("didn't report 1 passing test"),
+                "{}",
+                verdict.log
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_rejects_submissions_that_shadow_the_assert_macros() {
+        let backend = LocalBackend::new(Limits::default());
+        let cheat = "macro_rules! assert_eq {\n    ($($t:tt)*) => {};\n}\n\n\
+                     fn double(x: i32) -> i32 {\n    x\n}\n";
+        let verdict = verify(&backend, cheat, REFERENCE).await.unwrap();
+        assert!(!verdict.tests_passed);
+        assert!(verdict.log.contains("doubles"), "{}", verdict.log);
+    }
+
+    #[tokio::test]
+    async fn verify_accepts_a_clean_solution() {
+        let backend = LocalBackend::new(Limits::default());
+        let solution = "fn double(x: i32) -> i32 {\n    x * 2\n}\n\n\
+                        #[test]\nfn doubles() {\n    assert_eq!(double(2), 4);\n}\n";
+        let verdict = verify(&backend, solution, REFERENCE).await.unwrap();
+        assert_eq!(
+            verdict,
+            Verdict {
+                tests_passed: true,
+                clippy_passed: true,
+                fmt_passed: true,
+                log: String::new(),
+            }
+        );
+    }
+}
@@ -0,0 +1,329 @@
+// Compiler / clippy diagnostics overlay for the inline editor.
+//
+// Takes the `diagnostics` array returned by `/api/run` and
+// `/api/clippy` (see `src/diagnostics.rs` for the shape: level, code,
+// message, 1-based line/column spans, child notes with suggested
+// replacements) and
+//   - underlines every span in the editor (wavy for primary spans,
+//     dotted for secondary "value moved here" context),
+//   - puts a marker in the gutter next to every line with a primary
+//     span (errors win over warnings; the title lists the messages),
+//   - shows the lint name, message and labels in a hover tooltip,
+//   - offers an "Apply fix" button for every suggestion rustc marks as
+//     `MachineApplicable`.
+//
+// Marks follow edits (positions are mapped through every change) so
+// they stay on the right token while the learner types; the caller
+// replaces them wholesale on the next lint run.
+//
+// Used by static/js/inline-editor.js.
+
+import { RangeSet, StateEffect, StateField } from "@codemirror/state";
+import {
+  Decoration,
+  EditorView,
+  GutterMarker,
+  gutter,
+  hoverTooltip,
+} from "@codemirror/view";
+
+const setDiagnosticsEffect = StateEffect.define();
+
+const EMPTY = {
+  items: [],
+  decorations: Decoration.none,
+  markers: RangeSet.empty,
+};
+
+class DiagnosticMarker extends GutterMarker {
+  constructor(tone, title) {
+    super();
+    this.tone = tone;
+    this.title = title;
+  }
+
+  eq(other) {
+    return other.tone === this.tone && other.title === this.title;
+  }
+
+  toDOM() {
+    const el = document.createElement("span");
+    el.className = `cm-diag-marker is-${this.tone}`;
+    el.textContent = this.tone === "error" ? "●" : "▲";
+    el.title = this.title;
+    return el;
+  }
+}
+
+// rustc columns count chars; CodeMirror positions count UTF-16 units.
+function toPos(doc, line, column) {
+  if (line < 1 || line > doc.lines) return null;
+  const { from, text } = doc.line(line);
+  let offset = 0;
+  let chars = 1;
+  for (const ch of text) {
+    if (chars >= column) break;
+    offset += ch.length;
+    chars += 1;
+  }
+  return from + offset;
+}
+
+function spanRange(doc, span) {
+  const from = toPos(doc, span.line_start, span.column_start);
+  const to = toPos(doc, span.line_end, span.column_end);
+  if (from == null || to == null) return null;
+  return { from, to: Math.max(from, to) };
+}
+
+// One fix per child suggestion; multi-part suggestions (several spans
+// in one child) are applied together.
+function collectFixes(doc, diagnostic) {
+  const fixes = [];
+  for (const child of diagnostic.children || []) {
+    const changes = [];
+    for (const span of child.spans || []) {
+      if (span.applicability !== "MachineApplicable") continue;
+      if (span.suggested_replacement == null) continue;
+      const range = spanRange(doc, span);
+      if (range) {
+        changes.push({ ...range, insert: span.suggested_replacement });
+      }
+    }
+    if (changes.length) fixes.push({ title: child.message, changes });
+  }
+  return fixes;
+}
+
+function buildItems(doc, diagnostics) {
+  const items = [];
+  for (const diagnostic of diagnostics) {
+    const fixes = collectFixes(doc, diagnostic);
+    for (const span of diagnostic.spans || []) {
+      const range = spanRange(doc, span);
+      if (!range) continue;
+      items.push({
+        ...range,
+        primary: span.is_primary,
+        level: diagnostic.level,
+        code: diagnostic.code || null,
+        message: span.is_primary
+          ? diagnostic.message
+          : span.label || diagnostic.message,
+        label: span.is_primary ? span.label || null : null,
+        fixes: span.is_primary ? fixes : [],
+      });
+    }
+  }
+  return items;
+}
+
+function decorationsFor(doc, items) {
+  const ranges = [];
+  for (const item of items) {
+    const tone = item.level === "error" ? "error" : "warning";
+    const cls = item.primary
+      ? `cm-diag cm-diag-${tone}`
+      : "cm-diag cm-diag-secondary";
+    // Zero-width spans (e.g. "expected `;` here") still need a mark.
+    const to =
+      item.to > item.from ? item.to : Math.min(item.from + 1, doc.length);
+    if (to > item.from) {
+      ranges.push(Decoration.mark({ class: cls }).range(item.from, to));
+    }
+  }
+  return Decoration.set(ranges, true);
+}
+
+function markersFor(doc, items) {
+  const lines = new Map();
+  for (const item of items) {
+    if (!item.primary) continue;
+    const line = doc.lineAt(item.from);
+    const entry = lines.get(line.number) || {
+      from: line.from,
+      tone: "warning",
+      messages: [],
+    };
+    if (item.level === "error") entry.tone = "error";
+    entry.messages.push(
+      item.code ? `${item.code}: ${item.message}` : item.message,
+    );
+    lines.set(line.number, entry);
+  }
+  const ranges = [...lines.values()].map((entry) =>
+    new DiagnosticMarker(entry.tone, entry.messages.join("\n")).range(
+      entry.from,
+    ),
+  );
+  return RangeSet.of(ranges, true);
+}
+
+function mapItems(items, changes) {
+  return items.map((item) => ({
+    ...item,
+    from: changes.mapPos(item.from),
+    to: changes.mapPos(item.to, 1),
+    fixes: item.fixes.map((fix) => ({
+      ...fix,
+      changes: fix.changes.map((change) => ({
+        ...change,
+        from: changes.mapPos(change.from),
+        to: changes.mapPos(change.to, 1),
+      })),
+    })),
+  }));
+}
+
+const diagnosticsField = StateField.define({
+  create: () => EMPTY,
+  update(value, tr) {
+    for (const effect of tr.effects) {
+      if (effect.is(setDiagnosticsEffect)) return effect.value;
+    }
+    if (!tr.docChanged || value.items.length === 0) return value;
+    return {
+      items: mapItems(value.items, tr.changes),
+      decorations: value.decorations.map(tr.changes),
+      markers: value.markers.map(tr.changes),
+    };
+  },
+  provide: (field) => EditorView.decorations.from(field, (v) => v.decorations),
+});
+
+function renderTooltip(view, items) {
+  const dom = document.createElement("div");
+  dom.className = "cm-diag-tooltip";
+  for (const item of items) {
+    const row = document.createElement("div");
+    const tone = item.primary ? item.level : "note";
+    row.className = `cm-diag-tooltip-item is-${tone}`;
+    if (item.code) {
+      const code = document.createElement("code");
+      code.className = "cm-diag-tooltip-code";
+      code.textContent = item.code;
+      // rustc error codes link to the offline `/explain` page.
+      if (/^E\d{4}$/.test(item.code)) {
+        const link = document.createElement("a");
+        link.href = `/explain/${item.code}`;
+        link.target = "_blank";
+        link.rel = "noopener";
+        link.appendChild(code);
+        row.append(link, " ");
+      } else {
+        row.append(code, " ");
+      }
+    }
+    row.append(item.message);
+    if (item.label) {
+      const label = document.createElement("div");
+      label.className = "cm-diag-tooltip-label";
+      label.textContent = item.label;
+      row.append(label);
+    }
+    for (const fix of item.fixes) {
+      const btn = document.createElement("button");
+      btn.type = "button";
+      btn.className = "cm-diag-fix";
+      btn.textContent = `Apply fix: ${fix.title}`;
+      btn.addEventListener("click", () => {
+        view.dispatch({ changes: fix.changes, userEvent: "input.fix" });
+        view.focus();
+      });
+      row.append(btn);
+    }
+    dom.append(row);
+  }
+  return { dom };
+}
+
+const diagnosticsTooltip = hoverTooltip(
+  (view, pos) => {
+    const { items } = view.state.field(diagnosticsField);
+    const hits = items.filter((item) => item.from <= pos && pos <= item.to);
+    if (!hits.length) return null;
+    // Primary spans first so the lint message leads.
+    hits.sort((a, b) => Number(b.primary) - Number(a.primary));
+    return {
+      pos: Math.min(...hits.map((item) => item.from)),
+      end: Math.max(...hits.map((item) => item.to)),
+      above: true,
+      create: (v) => renderTooltip(v, hits),
+    };
+  },
+  { hideOnChange: true },
+);
+
+const diagnosticsGutter = gutter({
+  class: "cm-diag-gutter",
+  markers: (view) => view.state.field(diagnosticsField).markers,
+});
+
+const diagnosticsTheme = EditorView.theme({
+  ".cm-diag-gutter .cm-gutterElement": {
+    padding: "0 2px",
+    fontSize: "0.7rem",
+  },
+  ".cm-diag-marker": { cursor: "default" },
+  ".cm-diag-marker.is-error": { color: "var(--color-error)" },
+  ".cm-diag-marker.is-warning": { color: "var(--color-warning)" },
+  ".cm-diag-error": {
+    textDecoration: "underline wavy var(--color-error)",
+    textUnderlineOffset: "3px",
+  },
+  ".cm-diag-warning": {
+    textDecoration: "underline wavy var(--color-warning)",
+    textUnderlineOffset: "3px",
+  },
+  ".cm-diag-secondary": {
+    textDecoration: "underline dotted var(--color-text-muted)",
+    textUnderlineOffset: "3px",
+  },
+  ".cm-diag-tooltip": {
+    maxWidth: "32rem",
+    padding: "0.4rem 0.6rem",
+    fontSize: "0.8rem",
+  },
+  ".cm-diag-tooltip-item + .cm-diag-tooltip-item": {
+    marginTop: "0.4rem",
+  },
+  ".cm-diag-tooltip-item.is-error": {
+    borderLeft: "3px solid var(--color-error)",
+    paddingLeft: "0.4rem",
+  },
+  ".cm-diag-tooltip-item.is-warning": {
+    borderLeft: "3px solid var(--color-warning)",
+    paddingLeft: "0.4rem",
+  },
+  ".cm-diag-tooltip-code": { fontWeight: "600" },
+  ".cm-diag-tooltip-label": { color: "var(--color-text-muted)" },
+  ".cm-diag-fix": {
+    display: "block",
+    marginTop: "0.3rem",
+    fontSize: "0.75rem",
+    cursor: "pointer",
+  },
+});
+
+// Extension to add to the editor once; feed it with `setDiagnostics`.
+export const diagnosticsExtension = [
+  diagnosticsField,
+  diagnosticsGutter,
+  diagnosticsTooltip,
+  diagnosticsTheme,
+];
+
+// Replace the editor's diagnostics (pass `[]` to clear them). Returns
+// the number of marks placed, i.e. diagnostics that had a span in the
+// learner's code.
+export function setDiagnostics(view, diagnostics) {
+  const items = buildItems(view.state.doc, diagnostics || []);
+  view.dispatch({
+    effects: setDiagnosticsEffect.of({
+      items,
+      decorations: decorationsFor(view.state.doc, items),
+      markers: markersFor(view.state.doc, items),
+    }),
+  });
+  return items.length;
+}
@@ -0,0 +1,107 @@
+// "Explain E0382" links under the run output.
+//
+// For every rustc error code among a run's `diagnostics`, renders a
+// small button; clicking it fetches `/explain/<code>/fragment` (the
+// offline `rustc --explain` text plus links to the chapters that teach
+// it) and shows it inline, so learners don't have to leave the page to
+// look the error up. Clicking the same button again collapses it.
+//
+// Used by static/js/inline-editor.js.
+
+import { highlightRust } from "./rust-highlight.js";
+
+const ERROR_CODE_RE = /^E\d{4}$/;
+
+// Fragments don't change while the page is open.
+const fragmentCache = new Map();
+
+async function fetchFragment(code) {
+  if (!fragmentCache.has(code)) {
+    const resp = await fetch(`/explain/${code}/fragment`);
+    if (!resp.ok) {
+      throw new Error(
+        resp.status === 404
+          ? `No explanation for ${code} on this server.`
+          : `HTTP ${resp.status}`,
+      );
+    }
+    fragmentCache.set(code, await resp.text());
+  }
+  return fragmentCache.get(code);
+}
+
+// Distinct error codes in first-seen order.
+export function errorCodes(diagnostics) {
+  const codes = [];
+  for (const d of diagnostics || []) {
+    if (d.code && ERROR_CODE_RE.test(d.code) && !codes.includes(d.code)) {
+      codes.push(d.code);
+    }
+  }
+  return codes;
+}
+
+// Replace the contents of `container` with one button per error code
+// in `diagnostics` and an (initially hidden) panel for the text.
+export function renderExplainLinks(container, diagnostics) {
+  container.innerHTML = "";
+  const codes = errorCodes(diagnostics);
+  container.style.display = codes.length ? "" : "none";
+  if (!codes.length) return;
+
+  const row = document.createElement("div");
+  row.className = "explain-links";
+  const label = document.createElement("span");
+  label.className = "explain-links-label";
+  label.textContent = "What does this error mean?";
+  row.appendChild(label);
+
+  const panel = document.createElement("div");
+  panel.className = "explain-panel";
+  panel.style.display = "none";
+  let openCode = null;
+
+  for (const code of codes) {
+    const btn = document.createElement("button");
+    btn.type = "button";
+    btn.className = "explain-chip";
+    btn.textContent = code;
+    btn.setAttribute("aria-expanded", "false");
+    btn.addEventListener("click", async () => {
+      for (const other of row.querySelectorAll(".explain-chip")) {
+        other.setAttribute("aria-expanded", "false");
+      }
+      if (openCode === code) {
+        openCode = null;
+        panel.style.display = "none";
+        return;
+      }
+      openCode = code;
+      btn.setAttribute("aria-expanded", "true");
+      panel.style.display = "";
+      panel.textContent = "Loading…";
+      try {
+        const html = await fetchFragment(code);
+        if (openCode !== code) return;
+        panel.innerHTML = html;
+        panel.querySelectorAll("pre > code").forEach((el) => {
+          const cls = el.className || "";
+          if (cls && !cls.includes("language-rust")) return;
+          el.innerHTML = highlightRust(el.textContent);
+        });
+        const full = document.createElement("a");
+        full.className = "explain-open";
+        full.href = `/explain/${code}`;
+        full.target = "_blank";
+        full.rel = "noopener";
+        full.textContent = `Open ${code} in a new tab`;
+        panel.prepend(full);
+      } catch (err) {
+        if (openCode === code) panel.textContent = err.message;
+      }
+    });
+    row.appendChild(btn);
+  }
+
+  container.append(row, panel);
+}
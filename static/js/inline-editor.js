@@ -16,6 +16,7 @@
 //         <button data-role="reset-btn">Reset</button>           (optional)
 //         <a    data-role="vscode-btn" href="...">Open</a>       (optional)
 //         <button data-role="format-btn">Format</button>         (optional)
+//         <button data-role="clippy-btn">Clippy</button>         (optional)
 //         <button data-role="run-btn">▶ Run</button>
 //         <button data-role="submit-btn" data-ulid="...">Submit</button>
 //                                                                (optional)
@@ -34,6 +35,8 @@
 //         <details data-role="output-details">
 //           <pre data-role="output-stderr"></pre>
 //         </details>
+//         <div data-role="explain-list"></div>                   (optional;
+//                                                  created if missing)
 //       </div>
 //     </section>
 //
@@ -67,6 +70,8 @@
 // handle so call sites don't need to branch.
 
 import { proseHighlightStyle, proseEditorTheme } from "./cm-theme.js";
+import { diagnosticsExtension, setDiagnostics } from "./editor-diagnostics.js";
+import { renderExplainLinks } from "./error-explain.js";
 
 const VIM_PREF_KEY = "corrode:editor:vim";
 /** @type {Set<{setVim: (on:boolean)=>void}>} */
@@ -295,6 +300,7 @@ export async function mountInlineEditor(section, opts = {}) {
   const submitBtn = $("submit-btn");
   const actionStatus = $("action-status");
   const formatBtn = $("format-btn");
+  const clippyBtn = $("clippy-btn");
   const resetBtn = $("reset-btn");
   const copyBtn = $("copy-btn");
 
@@ -343,6 +349,7 @@ export async function mountInlineEditor(section, opts = {}) {
       persistDraft(text);
     },
     focus: () => fallback && fallback.focus(),
+    setDiagnostics: (_diagnostics) => 0,
     setVim: (_on) => {},
     destroy: () => {},
   };
@@ -537,6 +544,7 @@ export async function mountInlineEditor(section, opts = {}) {
       EditorView.lineWrapping,
       ...(urlPlugin ? [urlPlugin, urlTheme] : []),
       themeCompartment.of(proseEditorTheme),
+      diagnosticsExtension,
       persistExt,
       // Page-specific extras (e.g. the tour's hover-explanation
       // tooltips). Built here so callers reuse the exact CM module
@@ -576,6 +584,7 @@ export async function mountInlineEditor(section, opts = {}) {
         persistDraft(text);
       },
       focus: () => editor.focus(),
+      setDiagnostics: (diagnostics) => setDiagnostics(editor, diagnostics),
       setVim: (on) => {
         if (!wantsVim) return;
         editor.dispatch({
@@ -611,6 +620,7 @@ export async function mountInlineEditor(section, opts = {}) {
       // Clear stale output/status from the previous run.
       if (outputPanel) outputPanel.style.display = "none";
       if (runStatus) runStatus.textContent = "";
+      api.setDiagnostics([]);
       setActionStatus("", "neutral");
       api.focus();
     });
@@ -735,9 +745,19 @@ export async function mountInlineEditor(section, opts = {}) {
   }
 
   // ---- Result rendering ---------------------------------------------
+  // "Explain E0382" buttons live at the bottom of the output panel;
+  // created on demand so every page embedding the editor gets them.
+  let explainList = $("explain-list");
+  if (!explainList && outputPanel) {
+    explainList = document.createElement("div");
+    explainList.dataset.role = "explain-list";
+    outputPanel.appendChild(explainList);
+  }
+
   function renderResults(data) {
     if (!outputPanel) return;
     outputPanel.style.display = "block";
+    if (explainList) renderExplainLinks(explainList, data.diagnostics);
     const rawOutput = (data.stderr || "") + "\n" + (data.stdout || "");
 
     if (wantsTestResults) {
@@ -913,6 +933,7 @@ export async function mountInlineEditor(section, opts = {}) {
         return null;
       }
       const data = await resp.json();
+      api.setDiagnostics(data.diagnostics || []);
       renderResults(data);
       return data;
     } catch (err) {
@@ -1082,6 +1103,75 @@ export async function mountInlineEditor(section, opts = {}) {
     });
   }
 
+  // ---- Clippy via /api/clippy ---------------------------------------
+  // Lints are drawn as underlines in the editor (see
+  // editor-diagnostics.js); hovering one shows the lint and, where
+  // rustc is sure of it, an "Apply fix" button.
+  if (clippyBtn) {
+    let clippyInFlight = false;
+    clippyBtn.addEventListener("click", async () => {
+      if (clippyInFlight) return;
+      clippyInFlight = true;
+      clippyBtn.disabled = true;
+      if (runStatus) {
+        runStatus.textContent = "Running clippy…";
+        runStatus.style.color = "var(--color-text-muted)";
+      }
+      try {
+        const resp = await fetch("/api/clippy", {
+          method: "POST",
+          headers: { "Content-Type": "application/json" },
+          body: JSON.stringify({
+            code: api.getValue(),
+            slug: exerciseKey,
+          }),
+        });
+        if (!resp.ok) {
+          if (runStatus) {
+            runStatus.textContent =
+              resp.status === 429
+                ? "Clippy rate-limited; try again in a moment."
+                : resp.status === 502
+                  ? "Clippy unreachable."
+                  : `Clippy failed (HTTP ${resp.status}).`;
+            runStatus.style.color = "var(--color-error, #c62828)";
+          }
+          return;
+        }
+        const data = await resp.json();
+        const diagnostics = data.diagnostics || [];
+        api.setDiagnostics(diagnostics);
+        if (runStatus) {
+          const lints = diagnostics.filter((d) => d.level !== "error").length;
+          if (!data.success) {
+            runStatus.textContent =
+              "Did not compile. Hover the underlined code for details.";
+            runStatus.style.color = "var(--color-error, #c62828)";
+          } else if (lints === 0) {
+            runStatus.textContent = "Clippy found nothing to complain about.";
+            runStatus.style.color = "var(--color-success, #2e7d32)";
+          } else {
+            runStatus.textContent = `Clippy: ${lints} ${
+              lints === 1 ? "lint" : "lints"
+            }. Hover the underlined code for details.`;
+            runStatus.style.color = "var(--color-warning, #9a6700)";
+          }
+        }
+      } catch (e) {
+        console.error(e);
+        if (runStatus) {
+          runStatus.textContent = "Clippy failed: " + e.message;
+          runStatus.style.color = "var(--color-error, #c62828)";
+        }
+      } finally {
+        clippyInFlight = false;
+        setTimeout(() => {
+          clippyBtn.disabled = false;
+        }, 250);
+      }
+    });
+  }
+
   // ---- Submit -------------------------------------------------------
   function markAsCompleted() {
     const meta = document.getElementById("exercise-meta");
@@ -5,13 +5,18 @@
 // module: no exports, no globals, no framework. Initialises every quiz
 // section it can find on the page.
 //
-// The server emits per-answer `data-correct` already, so the runtime
-// just needs to:
-//   1. lock the card once an answer is clicked,
-//   2. paint correct / wrong-pick verdicts on every answer,
-//   3. reveal each answer's explanation,
-//   4. tick the "answered" counter and, on the last answer, the score.
-// Reset wipes every card back to its initial state.
+// The server (`Quiz::render_html`) emits everything needed to check an
+// answer, so the runtime just needs to:
+//   1. take the learner's answer the way the card's `data-quiz-type`
+//      asks for it (one click, toggles + Check, typed output, reordered
+//      items, a clicked line),
+//   2. lock the card and paint correct / wrong verdicts,
+//   3. reveal the explanations,
+//   4. tick the "answered" counter and, on the last answer, the score,
+//   5. for a signed-in participant, post the answer to /api/quiz-answer,
+//      which grades it again and records it against the chapter.
+// Reset wipes every card back to its initial state and starts a new
+// attempt, so the server scores each run through the quiz on its own.
 
 (function () {
   "use strict";
@@ -28,12 +33,43 @@
     const headline = quiz.querySelector("[data-quiz-headline]");
     const resetBtn = quiz.querySelector("[data-quiz-reset]");
     const total = cards.length;
+    const page = quiz.closest("[data-course-chapter]");
+    const participant = page && page.dataset.participantId;
+    const chapter = page && page.dataset.courseChapter;
+    let attempt = newAttemptId();
 
-    cards.forEach((card) => wireCard(card, onAnswered));
+    cards.forEach((card) =>
+      wireCard(card, (answer) => {
+        record(card, answer);
+        onAnswered();
+      }),
+    );
     if (resetBtn) {
       resetBtn.addEventListener("click", resetAll);
     }
 
+    // Fire and forget: the quiz works the same when the post fails, the
+    // answer just doesn't count toward progress.
+    function record(card, answer) {
+      if (!participant || !chapter) return;
+      fetch("/api/quiz-answer", {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({
+          ulid: participant,
+          chapter,
+          attempt,
+          question: Number(card.dataset.question),
+          answer,
+        }),
+      })
+        .then((resp) => (resp.ok ? resp.json() : null))
+        .then((data) => {
+          if (data && data.chapter_completed) showCompleted(data);
+        })
+        .catch((err) => console.warn("[corrode] quiz answer not saved:", err));
+    }
+
     function onAnswered() {
       const answered = cards.filter((c) =>
         c.classList.contains("is-answered"),
@@ -67,6 +103,7 @@
     }
 
     function resetAll() {
+      attempt = newAttemptId();
       cards.forEach(resetCard);
       if (answeredEl) answeredEl.textContent = "0";
       if (scoreEl) scoreEl.textContent = "0";
@@ -77,63 +114,264 @@
     }
   }
 
+  function newAttemptId() {
+    if (typeof globalThis.crypto?.randomUUID === "function") {
+      return globalThis.crypto.randomUUID();
+    }
+    return `attempt-${Date.now().toString(36)}-${Math.random().toString(36).slice(2)}`;
+  }
+
+  // The chapter just reached its pass mark: reveal the next-chapter
+  // button and tick the topbar's chapter count, as a passing
+  // submission does on exercise chapters.
+  function showCompleted(data) {
+    document
+      .querySelector(".next-chapter-cta.is-locked")
+      ?.classList.remove("is-locked");
+    const count = document.querySelector(".topbar-progress-count strong");
+    if (count && typeof data.progress_done === "number") {
+      count.textContent = String(data.progress_done);
+    }
+  }
+
+  // Per-type wiring. Each one calls `finish(card, correct)` once the
+  // learner has committed to an answer, then `onAnswered(answer)` with
+  // the answer in the form `Question::grade` reads: answer indices for
+  // choices, the typed text, the items' positions as placed, or the
+  // clicked line.
+  const CardTypes = {
+    single: wireSingle,
+    multiple: wireMultiple,
+    output: wireOutput,
+    order: wireOrder,
+    compile_error: wireCompileError,
+  };
+
   function wireCard(card, onAnswered) {
-    const answers = Array.from(card.querySelectorAll("[data-quiz-answer]"));
+    const wire = CardTypes[card.dataset.quizType] || wireSingle;
+    wire(card, onAnswered);
+  }
+
+  function isLocked(card) {
+    return card.classList.contains("is-answered");
+  }
+
+  // Mark the card answered, set its verdict and reveal the card-wide
+  // explanation (the types without per-answer explanations have one).
+  function finish(card, correct) {
+    card.classList.add("is-answered");
+    card.classList.add(correct ? "is-correct" : "is-wrong");
     const status = card.querySelector("[data-quiz-status]");
+    if (status) {
+      status.textContent = correct ? "Correct" : "Incorrect";
+    }
+    const exp = card.querySelector(":scope > [data-quiz-explanation]");
+    if (exp) exp.hidden = false;
+    const check = card.querySelector("[data-quiz-check]");
+    if (check) check.disabled = true;
+  }
+
+  function answersOf(card) {
+    return Array.from(card.querySelectorAll("[data-quiz-answer]"));
+  }
+
+  function wireSingle(card, onAnswered) {
+    const answers = answersOf(card);
     answers.forEach((btn) => {
       btn.addEventListener("click", () => {
-        if (card.classList.contains("is-answered")) return;
-        revealCard(card, answers, btn, status);
-        onAnswered();
+        if (isLocked(card)) return;
+        const pickedCorrect = btn.dataset.correct === "true";
+        finish(card, pickedCorrect);
+        revealAnswers(answers, (b) => b === btn, !pickedCorrect);
+        onAnswered(btn.dataset.index);
       });
     });
   }
 
-  function revealCard(card, answers, picked, status) {
-    const pickedCorrect = picked.dataset.correct === "true";
-    card.classList.add("is-answered");
-    card.classList.add(pickedCorrect ? "is-correct" : "is-wrong");
-    if (status) {
-      status.textContent = pickedCorrect ? "Correct" : "Incorrect";
-    }
+  function wireMultiple(card, onAnswered) {
+    const answers = answersOf(card);
+    answers.forEach((btn) => {
+      btn.addEventListener("click", () => {
+        if (isLocked(card)) return;
+        const pressed = btn.getAttribute("aria-pressed") === "true";
+        btn.setAttribute("aria-pressed", pressed ? "false" : "true");
+        btn.classList.toggle("is-selected", !pressed);
+      });
+    });
+    const check = card.querySelector("[data-quiz-check]");
+    if (!check) return;
+    check.addEventListener("click", () => {
+      if (isLocked(card)) return;
+      const picked = (b) => b.getAttribute("aria-pressed") === "true";
+      if (!answers.some(picked)) return;
+      const correct = answers.every(
+        (b) => picked(b) === (b.dataset.correct === "true"),
+      );
+      finish(card, correct);
+      revealAnswers(answers, picked, true);
+      onAnswered(
+        answers
+          .filter(picked)
+          .map((b) => b.dataset.index)
+          .join(","),
+      );
+    });
+  }
+
+  // Paint the verdict on every answer button. The picked answers'
+  // explanations are revealed; with `showCorrect`, so are the correct
+  // answers', so the visitor sees what they should have chosen. Other
+  // distractors stay hidden to keep the card uncluttered.
+  function revealAnswers(answers, isPicked, showCorrect) {
     answers.forEach((btn) => {
       btn.disabled = true;
       const wrap = btn.closest(".quiz-answer-wrap");
       const isCorrect = btn.dataset.correct === "true";
-      const isPicked = btn === picked;
+      const picked = isPicked(btn);
       if (isCorrect) {
         btn.classList.add("is-correct");
         wrap && wrap.classList.add("is-correct");
       }
-      if (isPicked && !isCorrect) {
+      if (picked && !isCorrect) {
         btn.classList.add("is-wrong-pick");
         wrap && wrap.classList.add("is-wrong-pick");
       }
-      if (isPicked) {
+      if (picked) {
         btn.classList.add("is-chosen");
       }
-      // Reveal the picked answer's explanation. If the pick was
-      // wrong, also reveal the correct answer's explanation so
-      // the visitor sees what they should have chosen. Other
-      // distractors stay hidden to keep the card uncluttered.
-      const showExplanation = isPicked || (!pickedCorrect && isCorrect);
+      const showExplanation = picked || (showCorrect && isCorrect);
       const exp = wrap && wrap.querySelector("[data-quiz-explanation]");
       if (exp && showExplanation) exp.hidden = false;
     });
   }
 
+  // Mirrors `normalize_output` in src/exercises.rs: the server sends
+  // the accepted outputs already normalised.
+  function normalizeOutput(text) {
+    const lines = text.split("\n").map((l) => l.replace(/\s+$/, ""));
+    while (lines.length && lines[0] === "") lines.shift();
+    while (lines.length && lines[lines.length - 1] === "") lines.pop();
+    return lines.join("\n");
+  }
+
+  function wireOutput(card, onAnswered) {
+    const input = card.querySelector("[data-quiz-output]");
+    const check = card.querySelector("[data-quiz-check]");
+    if (!input || !check) return;
+    let accept = [];
+    try {
+      accept = JSON.parse(input.dataset.accept || "[]");
+    } catch (_) {
+      accept = [];
+    }
+    check.addEventListener("click", () => {
+      if (isLocked(card)) return;
+      const answer = normalizeOutput(input.value);
+      if (answer === "") return;
+      const correct = accept.includes(answer);
+      input.readOnly = true;
+      input.classList.add(correct ? "is-correct" : "is-wrong-pick");
+      finish(card, correct);
+      const expected = card.querySelector("[data-quiz-expected]");
+      if (expected && !correct) expected.hidden = false;
+      onAnswered(input.value);
+    });
+  }
+
+  function wireOrder(card, onAnswered) {
+    const list = card.querySelector("[data-quiz-order]");
+    const check = card.querySelector("[data-quiz-check]");
+    if (!list || !check) return;
+    // Remember the shuffled order the server sent so reset can put
+    // it back.
+    card._quizInitialOrder = Array.from(list.children);
+    list.addEventListener("click", (e) => {
+      const btn = e.target.closest("[data-quiz-move]");
+      if (!btn || isLocked(card)) return;
+      const item = btn.closest("[data-quiz-item]");
+      if (btn.dataset.quizMove === "-1" && item.previousElementSibling) {
+        list.insertBefore(item, item.previousElementSibling);
+      } else if (btn.dataset.quizMove === "1" && item.nextElementSibling) {
+        list.insertBefore(item.nextElementSibling, item);
+      }
+      btn.focus();
+    });
+    check.addEventListener("click", () => {
+      if (isLocked(card)) return;
+      const items = Array.from(list.children);
+      const placed = items.map((item) => item.dataset.position).join(",");
+      let correct = true;
+      items.forEach((item, i) => {
+        const inPlace = Number(item.dataset.position) === i;
+        if (!inPlace) correct = false;
+        item.classList.add(inPlace ? "is-correct" : "is-wrong-pick");
+      });
+      // Show the right order, keeping the verdict on each item.
+      items
+        .slice()
+        .sort((a, b) => Number(a.dataset.position) - Number(b.dataset.position))
+        .forEach((item) => list.appendChild(item));
+      list
+        .querySelectorAll("[data-quiz-move]")
+        .forEach((b) => (b.disabled = true));
+      finish(card, correct);
+      onAnswered(placed);
+    });
+  }
+
+  function wireCompileError(card, onAnswered) {
+    const lines = Array.from(card.querySelectorAll("[data-quiz-line]"));
+    lines.forEach((btn) => {
+      btn.addEventListener("click", () => {
+        if (isLocked(card)) return;
+        const pickedCorrect = btn.dataset.correct === "true";
+        lines.forEach((b) => {
+          b.disabled = true;
+          if (b.dataset.correct === "true") b.classList.add("is-correct");
+        });
+        btn.classList.add("is-chosen");
+        if (!pickedCorrect) btn.classList.add("is-wrong-pick");
+        finish(card, pickedCorrect);
+        onAnswered(btn.dataset.quizLine);
+      });
+    });
+  }
+
   function resetCard(card) {
     card.classList.remove("is-answered", "is-correct", "is-wrong");
     const status = card.querySelector("[data-quiz-status]");
     if (status) status.textContent = "";
-    const answers = card.querySelectorAll("[data-quiz-answer]");
-    answers.forEach((btn) => {
-      btn.disabled = false;
-      btn.classList.remove("is-correct", "is-wrong-pick", "is-chosen");
-      const wrap = btn.closest(".quiz-answer-wrap");
-      wrap && wrap.classList.remove("is-correct", "is-wrong-pick");
-      const exp = wrap && wrap.querySelector("[data-quiz-explanation]");
-      if (exp) exp.hidden = true;
-    });
+    card
+      .querySelectorAll("[data-quiz-explanation], [data-quiz-expected]")
+      .forEach((el) => (el.hidden = true));
+    card
+      .querySelectorAll(
+        "[data-quiz-answer], [data-quiz-line], [data-quiz-move], [data-quiz-check]",
+      )
+      .forEach((btn) => (btn.disabled = false));
+    card
+      .querySelectorAll(
+        "[data-quiz-answer], [data-quiz-line], [data-quiz-item], [data-quiz-output], .quiz-answer-wrap",
+      )
+      .forEach((el) =>
+        el.classList.remove(
+          "is-correct",
+          "is-wrong-pick",
+          "is-chosen",
+          "is-selected",
+        ),
+      );
+    card
+      .querySelectorAll("[data-quiz-answer][aria-pressed]")
+      .forEach((btn) => btn.setAttribute("aria-pressed", "false"));
+    const input = card.querySelector("[data-quiz-output]");
+    if (input) {
+      input.value = "";
+      input.readOnly = false;
+    }
+    const list = card.querySelector("[data-quiz-order]");
+    if (list && card._quizInitialOrder) {
+      card._quizInitialOrder.forEach((item) => list.appendChild(item));
+    }
   }
 })();